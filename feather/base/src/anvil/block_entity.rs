@@ -140,6 +140,20 @@ pub enum BlockEntityKind {
         #[serde(default)]
         record_item: InventorySlot,
     },
+    #[serde(rename = "minecraft:mob_spawner")]
+    #[serde(rename_all = "PascalCase")]
+    MobSpawner {
+        /// The entity spawned next.
+        spawn_data: SpawnData,
+        /// Ticks until the next spawn.
+        delay: i16,
+        min_spawn_delay: i16,
+        max_spawn_delay: i16,
+        spawn_count: i16,
+        max_nearby_entities: i16,
+        required_player_range: i16,
+        spawn_range: i16,
+    },
     // TODO: a few more
     /// Fallback type for unknown block entities
     #[serde(other, serialize_with = "BlockEntityKind::serialize_unknown")]
@@ -171,9 +185,34 @@ impl BlockEntityKind {
             BlockEntityKind::Hopper { .. } => BlockEntityVariant::Hopper,
             BlockEntityKind::Jigsaw { .. } => BlockEntityVariant::Jigsaw,
             BlockEntityKind::Jukebox { .. } => BlockEntityVariant::Jukebox,
+            BlockEntityKind::MobSpawner { .. } => BlockEntityVariant::MobSpawner,
             BlockEntityKind::Unknown { .. } => BlockEntityVariant::Unknown,
         }
     }
+
+    /// Creates a spawner spawning the given entity,
+    /// with the settings of vanilla's spawners.
+    pub fn mob_spawner(entity_id: impl Into<String>) -> Self {
+        BlockEntityKind::MobSpawner {
+            spawn_data: SpawnData {
+                id: entity_id.into(),
+            },
+            delay: 20,
+            min_spawn_delay: 200,
+            max_spawn_delay: 800,
+            spawn_count: 4,
+            max_nearby_entities: 6,
+            required_player_range: 16,
+            spawn_range: 4,
+        }
+    }
+}
+
+/// The entity a spawner spawns next.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnData {
+    /// The namespaced entity ID, e.g. `minecraft:zombie`.
+    pub id: String,
 }
 
 /// Variant of a `BlockEntityKind`.
@@ -197,5 +236,6 @@ pub enum BlockEntityVariant {
     Hopper,
     Jigsaw,
     Jukebox,
+    MobSpawner,
     Unknown,
 }
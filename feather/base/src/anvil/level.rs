@@ -1,10 +1,13 @@
 //! Implements level.dat file loading.
 
+use crate::BlockId;
 use libcraft_core::Biome;
-use libcraft_items::Item;
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
 use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
 use std::{collections::HashMap, fs::File};
+use thiserror::Error;
 
 /// Root level tag
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +77,7 @@ pub struct LevelData {
     #[serde(rename = "generatorName")]
     pub generator_name: String,
     #[serde(rename = "generatorOptions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub generator_options: Option<SuperflatGeneratorOptions>,
 }
 
@@ -105,6 +109,17 @@ pub struct LevelVersion {
     name: String,
 }
 
+/// The height of the world, which superflat layers may not exceed.
+const WORLD_HEIGHT: u32 = 256;
+
+/// Options of the superflat generator.
+///
+/// These are stored in level.dat and can be converted from and to
+/// the preset strings shown by the vanilla client, such as
+/// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains;village`:
+/// the layers from the bottom up, the biome, and optionally the structures
+/// with their parameters (e.g. `village(size=1 distance=32)`).
+/// Presets without a structure part contain a village, like in vanilla.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuperflatGeneratorOptions {
     pub structures: HashMap<String, nbt::Value>,
@@ -125,29 +140,255 @@ impl Default for SuperflatGeneratorOptions {
             structures: default_structures,
             layers: vec![
                 SuperflatLayer {
-                    block: Item::Bedrock.name().to_string(),
+                    block: BlockId::bedrock().identifier().to_owned(),
                     height: 1,
                 },
                 SuperflatLayer {
-                    block: Item::Dirt.name().to_string(),
+                    block: BlockId::dirt().identifier().to_owned(),
                     height: 2,
                 },
                 SuperflatLayer {
-                    block: Item::GrassBlock.name().to_string(),
+                    block: BlockId::grass_block().identifier().to_owned(),
                     height: 1,
                 },
             ],
-            biome: Biome::Plains.name().to_string(),
+            biome: namespaced(Biome::Plains.name()),
+        }
+    }
+}
+
+impl SuperflatGeneratorOptions {
+    /// Gets the biome of the world, if it is known.
+    pub fn biome(&self) -> Option<Biome> {
+        Biome::from_name(strip_namespace(&self.biome))
+    }
+
+    /// Checks that all layer blocks and the biome are known
+    /// and that the layers fit in the world.
+    pub fn validate(&self) -> Result<(), SuperflatError> {
+        for layer in &self.layers {
+            if layer.block_id().is_none() {
+                return Err(SuperflatError::UnknownBlock(layer.block.clone()));
+            }
+        }
+        let height: u32 = self.layers.iter().map(|layer| layer.height as u32).sum();
+        if height > WORLD_HEIGHT {
+            return Err(SuperflatError::TooHigh(height));
+        }
+        if self.biome().is_none() {
+            return Err(SuperflatError::UnknownBiome(self.biome.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SuperflatGeneratorOptions {
+    type Err = SuperflatError;
+
+    /// Parses and validates a superflat preset string.
+    fn from_str(preset: &str) -> Result<Self, Self::Err> {
+        let mut parts = preset.trim().split(';');
+
+        let layers = parts
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<SuperflatLayer>, _>>()?;
+
+        let biome = match parts.next() {
+            Some(biome) => namespaced(biome.trim()),
+            None => SuperflatGeneratorOptions::default().biome,
+        };
+
+        let structures = match parts.next() {
+            Some(structures) => parse_structures(structures)?,
+            None => SuperflatGeneratorOptions::default().structures,
+        };
+
+        if let Some(extra) = parts.next() {
+            return Err(SuperflatError::InvalidPreset(format!(
+                "unexpected part {:?} after the structures",
+                extra
+            )));
+        }
+
+        let options = Self {
+            structures,
+            layers,
+            biome,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+impl Display for SuperflatGeneratorOptions {
+    /// Formats these options as a preset string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, layer) in self.layers.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", layer)?;
+        }
+        write!(f, ";{};", namespaced(&self.biome))?;
+
+        // Sort structures so that the same options
+        // always produce the same preset.
+        let mut structures: Vec<_> = self.structures.iter().collect();
+        structures.sort_by_key(|(name, _)| *name);
+        for (i, (name, parameters)) in structures.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(name)?;
+            if let nbt::Value::Compound(parameters) = parameters {
+                let mut parameters: Vec<_> = parameters
+                    .iter()
+                    .filter_map(|(key, value)| Some((key, nbt_value_to_string(value)?)))
+                    .collect();
+                parameters.sort();
+                if !parameters.is_empty() {
+                    let parameters: Vec<String> = parameters
+                        .into_iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect();
+                    write!(f, "({})", parameters.join(" "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the structure part of a preset, e.g. `village(size=1 distance=32),lake`.
+fn parse_structures(structures: &str) -> Result<HashMap<String, nbt::Value>, SuperflatError> {
+    let mut parsed = HashMap::new();
+    for structure in structures.split(',').map(str::trim) {
+        if structure.is_empty() {
+            continue;
         }
+        let (name, parameters) = match structure.find('(') {
+            Some(start) => {
+                let parameters = structure[start + 1..].strip_suffix(')').ok_or_else(|| {
+                    SuperflatError::InvalidPreset(format!(
+                        "missing ')' after the parameters of structure {:?}",
+                        structure
+                    ))
+                })?;
+                (&structure[..start], parameters)
+            }
+            None => (structure, ""),
+        };
+
+        let mut values = HashMap::new();
+        for parameter in parameters.split_whitespace() {
+            let (key, value) = parameter.split_once('=').ok_or_else(|| {
+                SuperflatError::InvalidPreset(format!(
+                    "structure parameter {:?} is not of the form key=value",
+                    parameter
+                ))
+            })?;
+            values.insert(key.to_owned(), nbt::Value::String(value.to_owned()));
+        }
+        parsed.insert(name.to_owned(), nbt::Value::Compound(values));
+    }
+    Ok(parsed)
+}
+
+fn nbt_value_to_string(value: &nbt::Value) -> Option<String> {
+    match value {
+        nbt::Value::String(value) => Some(value.clone()),
+        nbt::Value::Byte(value) => Some(value.to_string()),
+        nbt::Value::Short(value) => Some(value.to_string()),
+        nbt::Value::Int(value) => Some(value.to_string()),
+        nbt::Value::Long(value) => Some(value.to_string()),
+        _ => None,
     }
 }
 
+fn namespaced(identifier: &str) -> String {
+    if identifier.contains(':') {
+        identifier.to_owned()
+    } else {
+        format!("minecraft:{}", identifier)
+    }
+}
+
+fn strip_namespace(identifier: &str) -> &str {
+    identifier.strip_prefix("minecraft:").unwrap_or(identifier)
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct SuperflatLayer {
-    pub block: String, // TODO: Use "Block" enum and implement (de)serialization
+    /// The block identifier. The `minecraft:` namespace may be omitted.
+    pub block: String,
     pub height: u8,
 }
 
+impl SuperflatLayer {
+    /// Gets the block of this layer, if it is known.
+    pub fn block_id(&self) -> Option<BlockId> {
+        BlockId::from_identifier(&namespaced(&self.block))
+    }
+}
+
+impl FromStr for SuperflatLayer {
+    type Err = SuperflatError;
+
+    /// Parses a layer of a preset, e.g. `2*minecraft:dirt`.
+    fn from_str(layer: &str) -> Result<Self, Self::Err> {
+        let layer = layer.trim();
+        let (height, block) = match layer.split_once('*') {
+            Some((height, block)) => {
+                let height = height
+                    .trim()
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|&height| height > 0)
+                    .ok_or_else(|| SuperflatError::InvalidHeight(layer.to_owned()))?;
+                (height, block.trim())
+            }
+            None => (1, layer),
+        };
+        if block.is_empty() {
+            return Err(SuperflatError::InvalidPreset(format!(
+                "layer {:?} has no block",
+                layer
+            )));
+        }
+        Ok(Self {
+            block: namespaced(block),
+            height,
+        })
+    }
+}
+
+impl Display for SuperflatLayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.height != 1 {
+            write!(f, "{}*", self.height)?;
+        }
+        f.write_str(&namespaced(&self.block))
+    }
+}
+
+/// An error in superflat generator options.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SuperflatError {
+    #[error("invalid superflat preset: {0}")]
+    InvalidPreset(String),
+    #[error("invalid height in superflat layer {0:?}: expected a number from 1 to 255")]
+    InvalidHeight(String),
+    #[error("unknown block {0:?} in superflat layers")]
+    UnknownBlock(String),
+    #[error("unknown superflat biome {0:?}")]
+    UnknownBiome(String),
+    #[error("superflat layers are {0} blocks high, but worlds are only 256 blocks high")]
+    TooHigh(u32),
+}
+
 /// The type of world generator for a level.
 #[derive(Debug, PartialEq)]
 pub enum LevelGeneratorType {
@@ -204,4 +445,64 @@ mod tests {
         assert_eq!(level.generator_name, "default");
         assert!(level.generator_options.is_none());
     }
+
+    #[test]
+    fn superflat_presets_round_trip() {
+        let preset = "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains;lake,village(distance=32 size=1)";
+        let options: SuperflatGeneratorOptions = preset.parse().unwrap();
+        assert_eq!(options.layers.len(), 3);
+        assert_eq!(options.layers[1].height, 2);
+        assert_eq!(options.layers[1].block_id(), Some(BlockId::dirt()));
+        assert_eq!(options.biome(), Some(Biome::Plains));
+        assert_eq!(options.structures.len(), 2);
+        assert_eq!(options.to_string(), preset);
+
+        let default = SuperflatGeneratorOptions::default();
+        let parsed: SuperflatGeneratorOptions = default.to_string().parse().unwrap();
+        assert_eq!(parsed.to_string(), default.to_string());
+    }
+
+    #[test]
+    fn superflat_preset_defaults() {
+        // Namespaces may be omitted, and presets
+        // without structures contain a village.
+        let options: SuperflatGeneratorOptions = "stone,3*sand;desert".parse().unwrap();
+        assert_eq!(options.layers[0].block, "minecraft:stone");
+        assert_eq!(options.biome(), Some(Biome::Desert));
+        assert!(options.structures.contains_key("village"));
+
+        let options: SuperflatGeneratorOptions =
+            "minecraft:stone;minecraft:plains;".parse().unwrap();
+        assert!(options.structures.is_empty());
+    }
+
+    #[test]
+    fn invalid_superflat_presets() {
+        let error = |preset: &str| preset.parse::<SuperflatGeneratorOptions>().unwrap_err();
+        assert_eq!(
+            error("minecraft:bedrock,2*minecraft:dirtt"),
+            SuperflatError::UnknownBlock("minecraft:dirtt".to_owned())
+        );
+        assert_eq!(
+            error("0*minecraft:stone"),
+            SuperflatError::InvalidHeight("0*minecraft:stone".to_owned())
+        );
+        assert_eq!(
+            error("x*minecraft:stone"),
+            SuperflatError::InvalidHeight("x*minecraft:stone".to_owned())
+        );
+        assert_eq!(
+            error("200*minecraft:stone,100*minecraft:dirt"),
+            SuperflatError::TooHigh(300)
+        );
+        assert_eq!(
+            error("minecraft:stone;minecraft:nowhere"),
+            SuperflatError::UnknownBiome("minecraft:nowhere".to_owned())
+        );
+        assert!(matches!(
+            error("minecraft:stone;minecraft:plains;village(size=1"),
+            SuperflatError::InvalidPreset(_)
+        ));
+        assert!(matches!(error(""), SuperflatError::InvalidPreset(_)));
+    }
 }
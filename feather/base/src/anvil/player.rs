@@ -16,6 +16,7 @@ use quill_common::components::{
 };
 
 use crate::inventory::*;
+use crate::Dimension;
 
 use super::entity::{AnimalData, ItemNbt};
 
@@ -26,6 +27,14 @@ pub struct PlayerData {
     #[serde(flatten)]
     pub animal: AnimalData,
 
+    #[serde(rename = "Dimension")]
+    #[serde(default)]
+    pub dimension: Dimension,
+    /// The name of the world the player is in.
+    /// Not part of the vanilla format.
+    #[serde(rename = "World")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub world: Option<String>,
     #[serde(rename = "playerGameType")]
     pub gamemode: i32,
     #[serde(rename = "previousPlayerGameType")]
@@ -203,6 +212,7 @@ mod tests {
         let mut cursor = Cursor::new(include_bytes!("player.dat").to_vec());
 
         let player: PlayerData = nbt::from_gzip_reader(&mut cursor).unwrap();
+        assert_eq!(player.dimension, Dimension::Overworld);
         assert_eq!(player.gamemode, Gamemode::Creative.to_i32().unwrap());
         assert_eq!(
             player.previous_gamemode,
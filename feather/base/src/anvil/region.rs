@@ -2,7 +2,9 @@
 //! of Anvil region files.
 
 use crate::{
-    chunk::{BlockStore, LightStore, PackedArray, Palette},
+    chunk::{
+        BlockStore, BoundingBox, ChunkStatus, ChunkStructures, LightStore, PackedArray, Palette,
+    },
     Chunk, ChunkPosition, ChunkSection,
 };
 
@@ -11,7 +13,7 @@ use bitvec::{bitvec, vec::BitVec};
 use blocks::BlockId;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use libcraft_core::Biome;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
@@ -77,6 +79,55 @@ pub struct ChunkLevel {
     #[serde(rename = "Status")]
     #[serde(default)]
     worldgen_status: Cow<'static, str>,
+    #[serde(default)]
+    structures: LevelStructures,
+}
+
+/// Represents the structures starting in or
+/// passing through a chunk in a region file.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct LevelStructures {
+    #[serde(default)]
+    starts: BTreeMap<String, LevelStructureStart>,
+    /// Start chunk positions, packed like
+    /// vanilla's `ChunkPos.asLong`.
+    #[serde(serialize_with = "long_array_map")]
+    #[serde(default)]
+    references: BTreeMap<String, Vec<i64>>,
+}
+
+/// Represents a structure start in a region file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LevelStructureStart {
+    /// The structure name, or `INVALID` if no
+    /// structure of this kind starts in the chunk.
+    id: Cow<'static, str>,
+    #[serde(rename = "ChunkX")]
+    #[serde(default)]
+    chunk_x: i32,
+    #[serde(rename = "ChunkZ")]
+    #[serde(default)]
+    chunk_z: i32,
+    #[serde(rename = "BB", serialize_with = "nbt::i32_array")]
+    #[serde(default)]
+    bounding_box: Vec<i32>,
+}
+
+/// Serializes the values of a map as NBT long arrays.
+fn long_array_map<S: Serializer>(
+    map: &BTreeMap<String, Vec<i64>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    struct LongArray<'a>(&'a [i64]);
+
+    impl Serialize for LongArray<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            nbt::i64_array(self.0, serializer)
+        }
+    }
+
+    serializer.collect_map(map.iter().map(|(name, array)| (name, LongArray(array))))
 }
 
 /// Represents a chunk section in a region file.
@@ -238,6 +289,9 @@ impl RegionHandle {
                 Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
         }
 
+        chunk.set_status(status_from_identifier(&level.worldgen_status));
+        *chunk.structures_mut() = structures_from_level(&level.structures);
+
         // chunk.recalculate_heightmap();
 
         Ok((chunk, level.entities.clone(), level.block_entities.clone()))
@@ -411,7 +465,11 @@ fn chunk_to_chunk_root(
             z_pos: chunk.position().z,
             last_update: 0,    // TODO
             inhabited_time: 0, // TODO
-            block_entities: block_entities.into(),
+            block_entities: block_entities
+                .iter()
+                .chain(chunk.block_entities())
+                .cloned()
+                .collect(),
             sections: chunk
                 .sections()
                 .iter()
@@ -448,12 +506,89 @@ fn chunk_to_chunk_root(
             scheduled_block_updates: vec![],          // TODO
             scheduled_liquid_updates: vec![],
             post_processing: vec![vec![]; 16],
-            worldgen_status: "postprocessed".into(),
+            worldgen_status: status_identifier(chunk.status()).into(),
+            structures: level_structures(chunk.position(), chunk.structures()),
         },
         data_version: DATA_VERSION,
     }
 }
 
+/// Returns the vanilla identifier of the status
+/// a chunk is saved with.
+fn status_identifier(status: ChunkStatus) -> &'static str {
+    match status {
+        ChunkStatus::Proto => "liquid_carvers",
+        ChunkStatus::Decorated => "features",
+        ChunkStatus::Full => "full",
+    }
+}
+
+/// Parses the status of a saved chunk. Unknown statuses,
+/// including the `postprocessed` status written by older
+/// versions, are treated as fully generated.
+fn status_from_identifier(identifier: &str) -> ChunkStatus {
+    match identifier {
+        "liquid_carvers" => ChunkStatus::Proto,
+        "features" => ChunkStatus::Decorated,
+        _ => ChunkStatus::Full,
+    }
+}
+
+fn level_structures(pos: ChunkPosition, structures: &ChunkStructures) -> LevelStructures {
+    LevelStructures {
+        starts: structures
+            .starts
+            .iter()
+            .map(|(name, bounding_box)| {
+                (
+                    name.clone(),
+                    LevelStructureStart {
+                        id: name.clone().into(),
+                        chunk_x: pos.x,
+                        chunk_z: pos.z,
+                        bounding_box: bounding_box.to_array().to_vec(),
+                    },
+                )
+            })
+            .collect(),
+        references: structures
+            .references
+            .iter()
+            .map(|(name, starts)| {
+                let starts = starts
+                    .iter()
+                    .map(|pos| (pos.x as u32 as i64) | ((pos.z as u32 as i64) << 32))
+                    .collect();
+                (name.clone(), starts)
+            })
+            .collect(),
+    }
+}
+
+fn structures_from_level(level: &LevelStructures) -> ChunkStructures {
+    ChunkStructures {
+        starts: level
+            .starts
+            .iter()
+            .filter(|(_, start)| start.id != "INVALID")
+            .filter_map(|(name, start)| {
+                Some((name.clone(), BoundingBox::from_slice(&start.bounding_box)?))
+            })
+            .collect(),
+        references: level
+            .references
+            .iter()
+            .map(|(name, starts)| {
+                let starts = starts
+                    .iter()
+                    .map(|&packed| ChunkPosition::new(packed as i32, (packed >> 32) as i32))
+                    .collect();
+                (name.clone(), starts)
+            })
+            .collect(),
+    }
+}
+
 fn convert_palette(section: &mut ChunkSection) -> Vec<LevelPaletteEntry> {
     raw_palette_to_palette_entries(section.blocks().palette().unwrap().as_slice())
 }
@@ -921,4 +1056,35 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn chunk_status_identifiers() {
+        for &status in &[
+            ChunkStatus::Proto,
+            ChunkStatus::Decorated,
+            ChunkStatus::Full,
+        ] {
+            assert_eq!(status_from_identifier(status_identifier(status)), status);
+        }
+        assert_eq!(status_from_identifier("postprocessed"), ChunkStatus::Full);
+        assert_eq!(status_from_identifier(""), ChunkStatus::Full);
+    }
+
+    #[test]
+    fn structures_round_trip() {
+        let mut structures = ChunkStructures::default();
+        structures.starts.insert(
+            "village".to_owned(),
+            BoundingBox::new((-40, 0, 10), (-10, 255, 30)),
+        );
+        structures.references.insert(
+            "village".to_owned(),
+            vec![ChunkPosition::new(-3, 1), ChunkPosition::new(5, -7)],
+        );
+
+        let level = level_structures(ChunkPosition::new(-2, 1), &structures);
+        assert_eq!(level.starts["village"].chunk_x, -2);
+        assert_eq!(level.starts["village"].chunk_z, 1);
+        assert_eq!(structures_from_level(&level), structures);
+    }
 }
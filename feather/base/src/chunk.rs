@@ -3,6 +3,7 @@ use std::usize;
 use ::blocks::BlockId;
 use libcraft_core::Biome;
 
+use crate::anvil::block_entity::BlockEntityData;
 use crate::ChunkPosition;
 
 /// The number of bits used for each block
@@ -44,6 +45,7 @@ mod heightmap;
 mod light;
 mod packed_array;
 mod palette;
+mod structures;
 
 pub use self::blocks::BlockStore;
 pub use biome_store::BiomeStore;
@@ -51,6 +53,20 @@ pub use heightmap::{Heightmap, HeightmapFunction, HeightmapStore};
 pub use light::LightStore;
 pub use packed_array::PackedArray;
 pub use palette::Palette;
+pub use structures::{BoundingBox, ChunkStructures};
+
+/// How far a chunk has progressed through world generation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChunkStatus {
+    /// The terrain has been generated, but the chunk
+    /// has not been decorated (trees, etc.) yet.
+    Proto,
+    /// The chunk has been decorated. Decorating
+    /// its neighbours may still write into it.
+    Decorated,
+    /// Generation is complete.
+    Full,
+}
 
 /// A 16x256x16 chunk of blocks plus associated
 /// light, biome, and heightmap data.
@@ -64,6 +80,12 @@ pub struct Chunk {
     heightmaps: HeightmapStore,
 
     position: ChunkPosition,
+
+    status: ChunkStatus,
+
+    structures: ChunkStructures,
+
+    block_entities: Vec<BlockEntityData>,
 }
 
 impl Default for Chunk {
@@ -77,6 +99,9 @@ impl Default for Chunk {
             biomes: BiomeStore::default(),
             position: ChunkPosition::new(0, 0),
             heightmaps: HeightmapStore::new(),
+            status: ChunkStatus::Full,
+            structures: ChunkStructures::default(),
+            block_entities: Vec::new(),
         }
     }
 }
@@ -112,6 +137,39 @@ impl Chunk {
         self.position = pos;
     }
 
+    /// Gets the world generation status of this chunk.
+    /// Chunks are [`ChunkStatus::Full`] unless set otherwise.
+    pub fn status(&self) -> ChunkStatus {
+        self.status
+    }
+
+    /// Sets the world generation status of this chunk.
+    pub fn set_status(&mut self, status: ChunkStatus) {
+        self.status = status;
+    }
+
+    /// Gets the structures starting in or passing through this chunk.
+    pub fn structures(&self) -> &ChunkStructures {
+        &self.structures
+    }
+
+    /// Mutably gets the structures starting in or passing through this chunk.
+    pub fn structures_mut(&mut self) -> &mut ChunkStructures {
+        &mut self.structures
+    }
+
+    /// Gets the block entities stored with this chunk, such as
+    /// the chests placed by world generation. They are saved
+    /// along with the chunk.
+    pub fn block_entities(&self) -> &[BlockEntityData] {
+        &self.block_entities
+    }
+
+    /// Mutably gets the block entities stored with this chunk.
+    pub fn block_entities_mut(&mut self) -> &mut Vec<BlockEntityData> {
+        &mut self.block_entities
+    }
+
     /// Gets the block at the given position within this chunk.
     ///
     /// Returns `None` if the coordinates are out of bounds.
@@ -190,12 +190,11 @@ where
     pub fn recalculate(&mut self, get_block: impl Fn(usize, usize, usize) -> BlockId) {
         for x in 0..CHUNK_WIDTH {
             for z in 0..CHUNK_WIDTH {
-                for y in (0..CHUNK_HEIGHT).rev() {
-                    if F::is_solid(get_block(x, y, z)) {
-                        self.set_height(x, z, y + 1);
-                        break;
-                    }
-                }
+                let height = (0..CHUNK_HEIGHT)
+                    .rev()
+                    .find(|&y| F::is_solid(get_block(x, y, z)))
+                    .map_or(0, |y| y + 1);
+                self.set_height(x, z, height);
             }
         }
     }
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use crate::ChunkPosition;
+
+/// An axis-aligned box of blocks. Both corners are inclusive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BoundingBox {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+    pub max_z: i32,
+}
+
+impl BoundingBox {
+    /// Creates a bounding box spanning the two corners.
+    pub fn new(a: (i32, i32, i32), b: (i32, i32, i32)) -> Self {
+        Self {
+            min_x: a.0.min(b.0),
+            min_y: a.1.min(b.1),
+            min_z: a.2.min(b.2),
+            max_x: a.0.max(b.0),
+            max_y: a.1.max(b.1),
+            max_z: a.2.max(b.2),
+        }
+    }
+
+    /// Returns the smallest bounding box containing both boxes.
+    pub fn union(self, other: BoundingBox) -> Self {
+        Self {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            min_z: self.min_z.min(other.min_z),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+            max_z: self.max_z.max(other.max_z),
+        }
+    }
+
+    /// Determines whether the given block is within this box.
+    pub fn contains(&self, x: i32, y: i32, z: i32) -> bool {
+        (self.min_x..=self.max_x).contains(&x)
+            && (self.min_y..=self.max_y).contains(&y)
+            && (self.min_z..=self.max_z).contains(&z)
+    }
+
+    /// Determines whether this box overlaps the given chunk column.
+    pub fn intersects_chunk(&self, chunk: ChunkPosition) -> bool {
+        let (min_x, min_z) = (chunk.x * 16, chunk.z * 16);
+        self.min_x <= min_x + 15
+            && self.max_x >= min_x
+            && self.min_z <= min_z + 15
+            && self.max_z >= min_z
+    }
+
+    /// Returns the horizontal center of this box.
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.min_x + (self.max_x - self.min_x) / 2,
+            self.min_z + (self.max_z - self.min_z) / 2,
+        )
+    }
+
+    /// Returns the corners in the order used by
+    /// the vanilla `BB` tag.
+    pub fn to_array(self) -> [i32; 6] {
+        [
+            self.min_x, self.min_y, self.min_z, self.max_x, self.max_y, self.max_z,
+        ]
+    }
+
+    /// Parses the corners in the order used by the vanilla `BB` tag.
+    pub fn from_slice(corners: &[i32]) -> Option<Self> {
+        match *corners {
+            [min_x, min_y, min_z, max_x, max_y, max_z] => {
+                Some(Self::new((min_x, min_y, min_z), (max_x, max_y, max_z)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The structures starting in or passing through a chunk.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChunkStructures {
+    /// The bounding boxes of the structures
+    /// starting in this chunk, by structure name.
+    pub starts: BTreeMap<String, BoundingBox>,
+    /// The start chunks of the structures overlapping
+    /// this chunk, by structure name.
+    pub references: BTreeMap<String, Vec<ChunkPosition>>,
+}
+
+impl ChunkStructures {
+    /// Determines whether no structures start
+    /// in or pass through this chunk.
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty() && self.references.is_empty()
+    }
+}
@@ -0,0 +1,441 @@
+//! The whitelist, ban lists and operator list.
+//!
+//! The lists are stored in the vanilla `whitelist.json`, `ops.json`,
+//! `banned-players.json` and `banned-ips.json` files, so files
+//! from a vanilla server can be reused.
+
+use std::{
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+use anyhow::Context;
+use parking_lot::RwLock;
+use quill_common::access::{IpBan, OpEntry, PlayerBan, WhitelistEntry};
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+pub use quill_common::access::{AccessUpdate, DEFAULT_BAN_REASON, DEFAULT_BAN_SOURCE};
+
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const OPS_FILE: &str = "ops.json";
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+/// Why a player may not join.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginDenied {
+    Banned(PlayerBan),
+    IpBanned(IpBan),
+    NotWhitelisted,
+}
+
+impl LoginDenied {
+    /// Gets the message shown to the player when they are disconnected.
+    pub fn disconnect_message(&self) -> String {
+        match self {
+            LoginDenied::Banned(ban) => ban.disconnect_message(),
+            LoginDenied::IpBanned(ban) => ban.disconnect_message(),
+            LoginDenied::NotWhitelisted => "You are not white-listed on this server!".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Lists {
+    whitelist: Vec<WhitelistEntry>,
+    ops: Vec<OpEntry>,
+    banned_players: Vec<PlayerBan>,
+    banned_ips: Vec<IpBan>,
+}
+
+/// The whitelist, ban lists and operator list.
+///
+/// Shared between the main thread and connection workers
+/// through an `Arc`; the server stores an `Arc<AccessLists>`
+/// as a resource in the `Game`.
+///
+/// Changes are saved to disk immediately. Expired bans
+/// are ignored and dropped the next time the ban list is saved.
+#[derive(Debug)]
+pub struct AccessLists {
+    /// Directory containing the files, or `None`
+    /// if the lists are only kept in memory.
+    dir: Option<PathBuf>,
+    whitelist_enabled: AtomicBool,
+    lists: RwLock<Lists>,
+    revision: AtomicU64,
+}
+
+impl AccessLists {
+    /// Loads the lists from the files in `dir`,
+    /// creating empty files for lists that don't exist.
+    pub fn load(dir: impl Into<PathBuf>, whitelist_enabled: bool) -> anyhow::Result<Self> {
+        let access_lists = Self {
+            dir: Some(dir.into()),
+            whitelist_enabled: AtomicBool::new(whitelist_enabled),
+            lists: RwLock::new(Lists::default()),
+            revision: AtomicU64::new(0),
+        };
+        access_lists.reload()?;
+        Ok(access_lists)
+    }
+
+    /// Creates empty lists that are never saved to disk.
+    pub fn in_memory(whitelist_enabled: bool) -> Self {
+        Self {
+            dir: None,
+            whitelist_enabled: AtomicBool::new(whitelist_enabled),
+            lists: RwLock::new(Lists::default()),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Reloads all lists from disk, discarding
+    /// the lists in memory.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let lists = Lists {
+            whitelist: load_list(&dir.join(WHITELIST_FILE))?,
+            ops: load_list(&dir.join(OPS_FILE))?,
+            banned_players: load_list(&dir.join(BANNED_PLAYERS_FILE))?,
+            banned_ips: load_list(&dir.join(BANNED_IPS_FILE))?,
+        };
+        *self.lists.write() = lists;
+        self.changed();
+        Ok(())
+    }
+
+    /// Gets a counter incremented whenever the lists change.
+    ///
+    /// Used to find out whether online players need to be checked again.
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Acquire)
+    }
+
+    /// Determines whether the player with the given UUID and IP
+    /// address may join. Bans take precedence over the whitelist.
+    pub fn check_login(&self, uuid: Uuid, ip: IpAddr) -> Result<(), LoginDenied> {
+        if let Some(ban) = self.player_ban(uuid) {
+            return Err(LoginDenied::Banned(ban));
+        }
+        if let Some(ban) = self.ip_ban(ip) {
+            return Err(LoginDenied::IpBanned(ban));
+        }
+        // Like vanilla, operators don't need to be whitelisted.
+        if self.is_whitelist_enabled() && !self.is_whitelisted(uuid) && self.op(uuid).is_none() {
+            return Err(LoginDenied::NotWhitelisted);
+        }
+        Ok(())
+    }
+
+    /// Determines whether the player may join when the server is full.
+    pub fn bypasses_player_limit(&self, uuid: Uuid) -> bool {
+        self.op(uuid)
+            .map(|op| op.bypasses_player_limit)
+            .unwrap_or(false)
+    }
+
+    /// Applies an update from a plugin. Returns whether the lists changed.
+    pub fn apply(&self, update: AccessUpdate) -> anyhow::Result<bool> {
+        match update {
+            AccessUpdate::SetWhitelistEnabled(enabled) => {
+                let changed = self.is_whitelist_enabled() != enabled;
+                self.set_whitelist_enabled(enabled);
+                Ok(changed)
+            }
+            AccessUpdate::AddToWhitelist(entry) => self.add_to_whitelist(entry),
+            AccessUpdate::RemoveFromWhitelist(uuid) => self.remove_from_whitelist(uuid),
+            AccessUpdate::AddOp(entry) => self.add_op(entry).map(|()| true),
+            AccessUpdate::RemoveOp(uuid) => self.remove_op(uuid),
+            AccessUpdate::BanPlayer(ban) => self.ban_player(ban).map(|()| true),
+            AccessUpdate::PardonPlayer(uuid) => self.pardon_player(uuid),
+            AccessUpdate::BanIp(ban) => self.ban_ip(ban).map(|()| true),
+            AccessUpdate::PardonIp(ip) => self.pardon_ip(ip),
+        }
+    }
+
+    fn changed(&self) {
+        self.revision.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn save<T: Serialize>(&self, file: &str, list: &[T]) -> anyhow::Result<()> {
+        self.changed();
+        match &self.dir {
+            Some(dir) => save_list(&dir.join(file), list),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whitelist
+impl AccessLists {
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.whitelist_enabled.load(Ordering::Acquire)
+    }
+
+    /// Enables or disables the whitelist. This setting
+    /// comes from the server config and is not saved.
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        if self.whitelist_enabled.swap(enabled, Ordering::AcqRel) != enabled {
+            self.changed();
+        }
+    }
+
+    pub fn whitelist(&self) -> Vec<WhitelistEntry> {
+        self.lists.read().whitelist.clone()
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.lists
+            .read()
+            .whitelist
+            .iter()
+            .any(|entry| entry.uuid == uuid)
+    }
+
+    /// Adds a player to the whitelist. Returns `false`
+    /// if they were already whitelisted.
+    pub fn add_to_whitelist(&self, entry: WhitelistEntry) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write();
+        if lists.whitelist.iter().any(|e| e.uuid == entry.uuid) {
+            return Ok(false);
+        }
+        lists.whitelist.push(entry);
+        self.save(WHITELIST_FILE, &lists.whitelist)?;
+        Ok(true)
+    }
+
+    /// Removes a player from the whitelist. Returns `false`
+    /// if they were not whitelisted.
+    pub fn remove_from_whitelist(&self, uuid: Uuid) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write();
+        let len = lists.whitelist.len();
+        lists.whitelist.retain(|e| e.uuid != uuid);
+        if lists.whitelist.len() == len {
+            return Ok(false);
+        }
+        self.save(WHITELIST_FILE, &lists.whitelist)?;
+        Ok(true)
+    }
+}
+
+/// Operators
+impl AccessLists {
+    pub fn ops(&self) -> Vec<OpEntry> {
+        self.lists.read().ops.clone()
+    }
+
+    pub fn op(&self, uuid: Uuid) -> Option<OpEntry> {
+        self.lists
+            .read()
+            .ops
+            .iter()
+            .find(|op| op.uuid == uuid)
+            .cloned()
+    }
+
+    /// Makes a player an operator, replacing
+    /// their existing entry if there is one.
+    pub fn add_op(&self, entry: OpEntry) -> anyhow::Result<()> {
+        let mut lists = self.lists.write();
+        lists.ops.retain(|op| op.uuid != entry.uuid);
+        lists.ops.push(entry);
+        self.save(OPS_FILE, &lists.ops)
+    }
+
+    /// Removes a player's operator status. Returns `false`
+    /// if they were not an operator.
+    pub fn remove_op(&self, uuid: Uuid) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write();
+        let len = lists.ops.len();
+        lists.ops.retain(|op| op.uuid != uuid);
+        if lists.ops.len() == len {
+            return Ok(false);
+        }
+        self.save(OPS_FILE, &lists.ops)?;
+        Ok(true)
+    }
+}
+
+/// Bans
+impl AccessLists {
+    /// Gets all bans that have not expired.
+    pub fn banned_players(&self) -> Vec<PlayerBan> {
+        self.lists
+            .read()
+            .banned_players
+            .iter()
+            .filter(|ban| !ban.is_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Gets the player's ban if they are banned.
+    pub fn player_ban(&self, uuid: Uuid) -> Option<PlayerBan> {
+        self.lists
+            .read()
+            .banned_players
+            .iter()
+            .find(|ban| ban.uuid == uuid && !ban.is_expired())
+            .cloned()
+    }
+
+    /// Bans a player, replacing their existing ban if there is one.
+    pub fn ban_player(&self, ban: PlayerBan) -> anyhow::Result<()> {
+        let mut lists = self.lists.write();
+        lists.banned_players.retain(|b| b.uuid != ban.uuid);
+        lists.banned_players.push(ban);
+        lists.banned_players.retain(|b| !b.is_expired());
+        self.save(BANNED_PLAYERS_FILE, &lists.banned_players)
+    }
+
+    /// Removes a player's ban. Returns `false` if they were not banned.
+    pub fn pardon_player(&self, uuid: Uuid) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write();
+        let was_banned = lists
+            .banned_players
+            .iter()
+            .any(|b| b.uuid == uuid && !b.is_expired());
+        lists
+            .banned_players
+            .retain(|b| b.uuid != uuid && !b.is_expired());
+        self.save(BANNED_PLAYERS_FILE, &lists.banned_players)?;
+        Ok(was_banned)
+    }
+
+    /// Gets all IP bans that have not expired.
+    pub fn banned_ips(&self) -> Vec<IpBan> {
+        self.lists
+            .read()
+            .banned_ips
+            .iter()
+            .filter(|ban| !ban.is_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Gets the ban of an IP address if it is banned.
+    pub fn ip_ban(&self, ip: IpAddr) -> Option<IpBan> {
+        self.lists
+            .read()
+            .banned_ips
+            .iter()
+            .find(|ban| ban.ip == ip && !ban.is_expired())
+            .cloned()
+    }
+
+    /// Bans an IP address, replacing its existing ban if there is one.
+    pub fn ban_ip(&self, ban: IpBan) -> anyhow::Result<()> {
+        let mut lists = self.lists.write();
+        lists.banned_ips.retain(|b| b.ip != ban.ip);
+        lists.banned_ips.push(ban);
+        lists.banned_ips.retain(|b| !b.is_expired());
+        self.save(BANNED_IPS_FILE, &lists.banned_ips)
+    }
+
+    /// Removes the ban of an IP address. Returns `false`
+    /// if it was not banned.
+    pub fn pardon_ip(&self, ip: IpAddr) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write();
+        let was_banned = lists
+            .banned_ips
+            .iter()
+            .any(|b| b.ip == ip && !b.is_expired());
+        lists.banned_ips.retain(|b| b.ip != ip && !b.is_expired());
+        self.save(BANNED_IPS_FILE, &lists.banned_ips)?;
+        Ok(was_banned)
+    }
+}
+
+fn load_list<T: Serialize + DeserializeOwned>(path: &Path) -> anyhow::Result<Vec<T>> {
+    if !path.exists() {
+        save_list::<T>(path, &[])?;
+        return Ok(Vec::new());
+    }
+    let json =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("malformed {}", path.display()))
+}
+
+fn save_list<T: Serialize>(path: &Path, list: &[T]) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(list)?;
+    fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Uuid {
+        Uuid::from_u128(0x905c7e4fb96b45139645d123225575e2)
+    }
+
+    fn localhost() -> IpAddr {
+        IpAddr::from([127, 0, 0, 1])
+    }
+
+    #[test]
+    fn whitelist_and_ops() {
+        let lists = AccessLists::in_memory(true);
+        assert_eq!(
+            lists.check_login(player(), localhost()),
+            Err(LoginDenied::NotWhitelisted)
+        );
+
+        lists
+            .add_op(OpEntry {
+                uuid: player(),
+                name: String::from("caelunshun"),
+                level: 4,
+                bypasses_player_limit: true,
+            })
+            .unwrap();
+        assert_eq!(lists.check_login(player(), localhost()), Ok(()));
+        assert!(lists.bypasses_player_limit(player()));
+
+        lists.remove_op(player()).unwrap();
+        lists
+            .add_to_whitelist(WhitelistEntry {
+                uuid: player(),
+                name: String::from("caelunshun"),
+            })
+            .unwrap();
+        assert_eq!(lists.check_login(player(), localhost()), Ok(()));
+        assert!(!lists.bypasses_player_limit(player()));
+    }
+
+    #[test]
+    fn bans() {
+        let lists = AccessLists::in_memory(false);
+        let revision = lists.revision();
+
+        lists
+            .ban_player(PlayerBan::new(player(), "caelunshun"))
+            .unwrap();
+        assert!(lists.revision() > revision);
+        assert!(matches!(
+            lists.check_login(player(), localhost()),
+            Err(LoginDenied::Banned(_))
+        ));
+        assert!(lists.pardon_player(player()).unwrap());
+        assert!(!lists.pardon_player(player()).unwrap());
+
+        lists.ban_ip(IpBan::new(localhost())).unwrap();
+        assert!(matches!(
+            lists.check_login(player(), localhost()),
+            Err(LoginDenied::IpBanned(_))
+        ));
+
+        let mut expired = IpBan::new(localhost());
+        expired.expires = Some(expired.created);
+        lists.ban_ip(expired).unwrap();
+        assert_eq!(lists.check_login(player(), localhost()), Ok(()));
+        assert!(lists.banned_ips().is_empty());
+    }
+}
@@ -1,7 +1,10 @@
 use ahash::AHashMap;
 use base::{ChunkPosition, Position};
 use ecs::{Entity, SysResult, SystemExecutor};
-use quill_common::events::{EntityCreateEvent, EntityRemoveEvent};
+use quill_common::{
+    components::WorldId,
+    events::{EntityCreateEvent, EntityRemoveEvent},
+};
 use utils::vec_remove_item;
 
 use crate::{events::ChunkCrossEvent, Game};
@@ -13,57 +16,77 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 /// A spatial index to look up entities within a given chunk.
 #[derive(Default)]
 pub struct ChunkEntities {
-    entities: AHashMap<ChunkPosition, Vec<Entity>>,
+    entities: AHashMap<(WorldId, ChunkPosition), Vec<Entity>>,
 }
 
 impl ChunkEntities {
-    /// Returns the entities in the given chunk.
-    pub fn entities_in_chunk(&self, chunk: ChunkPosition) -> &[Entity] {
+    /// Returns the entities in the given chunk of `world`.
+    pub fn entities_in_chunk(&self, world: WorldId, chunk: ChunkPosition) -> &[Entity] {
         self.entities
-            .get(&chunk)
+            .get(&(world, chunk))
             .map(Vec::as_slice)
             .unwrap_or_default()
     }
 
-    fn update(
-        &mut self,
-        entity: Entity,
-        old_chunk: Option<ChunkPosition>,
-        new_chunk: ChunkPosition,
-    ) {
+    fn update(&mut self, entity: Entity, old_chunk: Option<IndexedChunk>, new_chunk: IndexedChunk) {
         if let Some(old_chunk) = old_chunk {
-            if let Some(vec) = self.entities.get_mut(&old_chunk) {
+            if let Some(vec) = self.entities.get_mut(&old_chunk.key()) {
                 vec_remove_item(vec, &entity);
             }
         }
 
-        self.entities.entry(new_chunk).or_default().push(entity);
+        self.entities
+            .entry(new_chunk.key())
+            .or_default()
+            .push(entity);
     }
 
-    fn remove_entity(&mut self, entity: Entity, chunk: ChunkPosition) {
-        if let Some(vec) = self.entities.get_mut(&chunk) {
+    fn remove_entity(&mut self, entity: Entity, chunk: IndexedChunk) {
+        if let Some(vec) = self.entities.get_mut(&chunk.key()) {
             vec_remove_item(vec, &entity);
         }
     }
 }
 
+/// Component storing the chunk under which
+/// an entity is stored in the `ChunkEntities`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct IndexedChunk {
+    world: WorldId,
+    chunk: ChunkPosition,
+}
+
+impl IndexedChunk {
+    fn key(self) -> (WorldId, ChunkPosition) {
+        (self.world, self.chunk)
+    }
+}
+
 fn update_chunk_entities(game: &mut Game) -> SysResult {
-    // Entities that have crossed chunks
+    // Entities that have crossed chunks or changed worlds
     let mut events = Vec::new();
-    for (entity, (old_chunk, &position)) in
-        game.ecs.query::<(&mut ChunkPosition, &Position)>().iter()
+    for (entity, (old_chunk, &position, &world)) in game
+        .ecs
+        .query::<(&mut IndexedChunk, &Position, &WorldId)>()
+        .iter()
     {
-        let new_chunk = position.chunk();
-        if position.chunk() != *old_chunk {
+        let new_chunk = IndexedChunk {
+            world,
+            chunk: position.chunk(),
+        };
+        if new_chunk != *old_chunk {
             game.chunk_entities
                 .update(entity, Some(*old_chunk), new_chunk);
-            events.push((
-                entity,
-                ChunkCrossEvent {
-                    old_chunk: *old_chunk,
-                    new_chunk,
-                },
-            ));
+            // Changing worlds triggers a `WorldChangeEvent` instead.
+            if old_chunk.world == world {
+                events.push((
+                    entity,
+                    ChunkCrossEvent {
+                        old_chunk: old_chunk.chunk,
+                        new_chunk: new_chunk.chunk,
+                    },
+                ));
+            }
 
             *old_chunk = new_chunk;
         }
@@ -74,13 +97,19 @@ fn update_chunk_entities(game: &mut Game) -> SysResult {
 
     // Entities that have been created
     let mut insertions = Vec::new();
-    for (entity, (_event, &position)) in game.ecs.query::<(&EntityCreateEvent, &Position)>().iter()
+    for (entity, (_event, &position, &world)) in game
+        .ecs
+        .query::<(&EntityCreateEvent, &Position, &WorldId)>()
+        .iter()
     {
-        let chunk = position.chunk();
+        let chunk = IndexedChunk {
+            world,
+            chunk: position.chunk(),
+        };
         game.chunk_entities.update(entity, None, chunk);
         insertions.push((entity, chunk));
     }
-    // Add ChunkPosition component to new entities
+    // Add IndexedChunk component to new entities
     for (entity, chunk) in insertions {
         game.ecs.insert(entity, chunk)?;
     }
@@ -88,7 +117,7 @@ fn update_chunk_entities(game: &mut Game) -> SysResult {
     // Entities that have been destroyed
     for (entity, (_event, &chunk)) in game
         .ecs
-        .query::<(&EntityRemoveEvent, &ChunkPosition)>()
+        .query::<(&EntityRemoveEvent, &IndexedChunk)>()
         .iter()
     {
         game.chunk_entities.remove_entity(entity, chunk);
@@ -0,0 +1,411 @@
+//! Tracks chunks through the stages of world generation.
+//!
+//! Generated chunks start out as [`ChunkStatus::Proto`]. A proto
+//! chunk is decorated once its eight neighbours have been generated,
+//! and decorating it may write into those neighbours. A decorated
+//! chunk becomes [`ChunkStatus::Full`] (and is handed to the game)
+//! once all its neighbours have been decorated too, since only then
+//! no more writes into it can happen.
+//!
+//! Full chunks are never written to by decoration.
+//!
+//! Chunks are saved as soon as decoration modifies them and again
+//! once they are full, so that chunks dropped from memory can be
+//! loaded from the world save in the same state.
+
+use std::collections::VecDeque;
+
+use ahash::{AHashMap, AHashSet};
+use base::{Chunk, ChunkPosition, ChunkStatus};
+use worldgen::DecorationRegion;
+
+/// Number of incomplete chunks kept in memory. Chunks needed
+/// to finish a requested chunk are kept even above this limit.
+const MAX_CACHED_CHUNKS: usize = 512;
+
+/// Number of finished chunks remembered without loading them again.
+const MAX_FINISHED_CHUNKS: usize = 4096;
+
+/// Something the [`GenerationPipeline`] needs done.
+pub enum Action {
+    /// Load the chunk from the world save.
+    Load(ChunkPosition),
+    /// Generate the chunk.
+    Generate(ChunkPosition),
+    /// Decorate the center chunk of the region.
+    Decorate(DecorationRegion),
+    /// Save a chunk to the world save.
+    Save(Chunk),
+    /// Hand a requested chunk to the game.
+    Deliver(Chunk),
+}
+
+enum Slot {
+    /// Waiting for the world save or the generator.
+    Pending,
+    /// Held in memory.
+    Present(Chunk),
+    /// Moved into a decoration task.
+    Busy(ChunkStatus),
+}
+
+/// State machine deciding when chunks are
+/// loaded, generated, decorated and delivered.
+#[derive(Default)]
+pub struct GenerationPipeline {
+    slots: AHashMap<ChunkPosition, Slot>,
+    /// Chunks requested by the game.
+    wanted: AHashSet<ChunkPosition>,
+    /// Chunks known to be fully generated. These are not kept in memory.
+    finished: AHashSet<ChunkPosition>,
+    /// The order in which chunks were inserted into `finished`.
+    finished_order: VecDeque<ChunkPosition>,
+    /// The time each chunk in memory was last modified.
+    last_modified: AHashMap<ChunkPosition, u64>,
+    /// Incremented whenever a chunk is modified.
+    clock: u64,
+    actions: VecDeque<Action>,
+}
+
+impl GenerationPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pops the next action to perform.
+    pub fn next_action(&mut self) -> Option<Action> {
+        self.actions.pop_front()
+    }
+
+    /// Requests a chunk for the game. It will
+    /// be delivered once it is fully generated.
+    pub fn request(&mut self, pos: ChunkPosition) {
+        self.wanted.insert(pos);
+        if !self.slots.contains_key(&pos) {
+            // Finished chunks are loaded again from the world save.
+            self.finished.remove(&pos);
+            self.slots.insert(pos, Slot::Pending);
+            self.actions.push_back(Action::Load(pos));
+        }
+        self.advance();
+    }
+
+    /// Handles the result of a [`Action::Load`].
+    /// `chunk` is `None` if the chunk is not in the world save.
+    pub fn on_loaded(&mut self, pos: ChunkPosition, chunk: Option<Chunk>) {
+        match chunk {
+            Some(chunk) => self.insert(chunk),
+            None => self.actions.push_back(Action::Generate(pos)),
+        }
+        self.advance();
+    }
+
+    /// Handles a chunk which failed to load. It is treated as
+    /// finished so that decoration never overwrites it.
+    ///
+    /// Returns whether the game requested the chunk.
+    pub fn on_load_failed(&mut self, pos: ChunkPosition) -> bool {
+        self.slots.remove(&pos);
+        self.mark_finished(pos);
+        let wanted = self.wanted.remove(&pos);
+        self.advance();
+        wanted
+    }
+
+    /// Handles the result of a [`Action::Generate`].
+    pub fn on_generated(&mut self, chunk: Chunk) {
+        self.insert(chunk);
+        self.advance();
+    }
+
+    /// Handles the result of a [`Action::Decorate`].
+    pub fn on_decorated(&mut self, region: DecorationRegion) {
+        let center = region.center_position();
+        for mut chunk in region.into_chunks() {
+            if chunk.position() == center {
+                chunk.set_status(ChunkStatus::Decorated);
+            }
+            self.actions.push_back(Action::Save(chunk.clone()));
+            self.insert(chunk);
+        }
+        self.advance();
+    }
+
+    fn insert(&mut self, chunk: Chunk) {
+        let pos = chunk.position();
+        if chunk.status() == ChunkStatus::Full {
+            self.slots.remove(&pos);
+            self.last_modified.remove(&pos);
+            self.mark_finished(pos);
+            if self.wanted.remove(&pos) {
+                self.actions.push_back(Action::Deliver(chunk));
+            }
+        } else {
+            self.clock += 1;
+            self.last_modified.insert(pos, self.clock);
+            self.slots.insert(pos, Slot::Present(chunk));
+        }
+    }
+
+    /// Remembers that a chunk is finished, forgetting the oldest
+    /// finished chunks if too many are remembered. Forgotten
+    /// chunks are loaded again when needed, and are still
+    /// recognized as finished by their status.
+    fn mark_finished(&mut self, pos: ChunkPosition) {
+        if self.finished.insert(pos) {
+            self.finished_order.push_back(pos);
+        }
+        while self.finished_order.len() > MAX_FINISHED_CHUNKS {
+            if let Some(oldest) = self.finished_order.pop_front() {
+                self.finished.remove(&oldest);
+            }
+        }
+    }
+
+    fn status(&self, pos: ChunkPosition) -> Option<ChunkStatus> {
+        match self.slots.get(&pos) {
+            Some(Slot::Pending) => None,
+            Some(Slot::Present(chunk)) => Some(chunk.status()),
+            Some(Slot::Busy(status)) => Some(*status),
+            None if self.finished.contains(&pos) => Some(ChunkStatus::Full),
+            None => None,
+        }
+    }
+
+    fn is_busy(&self, pos: ChunkPosition) -> bool {
+        matches!(self.slots.get(&pos), Some(Slot::Busy(_)))
+    }
+
+    /// Makes sure the chunk is in memory or on its way.
+    fn ensure(&mut self, pos: ChunkPosition) {
+        if !self.slots.contains_key(&pos) && !self.finished.contains(&pos) {
+            self.slots.insert(pos, Slot::Pending);
+            self.actions.push_back(Action::Load(pos));
+        }
+    }
+
+    fn advance(&mut self) {
+        let wanted: Vec<ChunkPosition> = self.wanted.iter().copied().collect();
+        for pos in wanted {
+            self.try_finish(pos);
+        }
+
+        self.evict();
+    }
+
+    fn try_finish(&mut self, pos: ChunkPosition) {
+        if self.is_busy(pos) {
+            return;
+        }
+        match self.status(pos) {
+            None => self.ensure(pos),
+            Some(ChunkStatus::Proto) => self.try_decorate(pos),
+            Some(ChunkStatus::Decorated) => {
+                let mut ready = true;
+                for neighbour in neighbours(pos) {
+                    if self.is_busy(neighbour) {
+                        ready = false;
+                        continue;
+                    }
+                    match self.status(neighbour) {
+                        None => {
+                            self.ensure(neighbour);
+                            ready = false;
+                        }
+                        Some(ChunkStatus::Proto) => {
+                            self.try_decorate(neighbour);
+                            ready = false;
+                        }
+                        Some(_) => (),
+                    }
+                }
+                if ready {
+                    if let Some(Slot::Present(mut chunk)) = self.slots.remove(&pos) {
+                        chunk.set_status(ChunkStatus::Full);
+                        self.actions.push_back(Action::Save(chunk.clone()));
+                        self.insert(chunk);
+                    }
+                }
+            }
+            Some(ChunkStatus::Full) => {
+                // Only reached for chunks delivered before, which
+                // `request` loads again; nothing to do here.
+            }
+        }
+    }
+
+    fn try_decorate(&mut self, pos: ChunkPosition) {
+        let mut ready = true;
+        for neighbour in neighbours(pos) {
+            if self.is_busy(neighbour) || self.status(neighbour).is_none() {
+                self.ensure(neighbour);
+                ready = false;
+            }
+        }
+        if !ready {
+            return;
+        }
+
+        let mut region = DecorationRegion::new(pos);
+        for member in neighbours(pos).chain(std::iter::once(pos)) {
+            let status = match self.status(member) {
+                Some(ChunkStatus::Full) | None => continue,
+                Some(status) => status,
+            };
+            if let Some(Slot::Present(chunk)) = self.slots.insert(member, Slot::Busy(status)) {
+                region.insert(chunk);
+            }
+        }
+        self.actions.push_back(Action::Decorate(region));
+    }
+
+    /// Drops the least recently modified chunks from memory if too
+    /// many are kept. Modified chunks have been saved already,
+    /// so they are loaded again from the world save when needed.
+    fn evict(&mut self) {
+        if self.slots.len() <= MAX_CACHED_CHUNKS {
+            return;
+        }
+
+        // Finishing a chunk needs the chunks up to two chunks
+        // away, since its neighbours must be decorated.
+        let needed: AHashSet<ChunkPosition> =
+            self.wanted.iter().flat_map(|&pos| area(pos, 2)).collect();
+        let mut evictable: Vec<(u64, ChunkPosition)> = self
+            .slots
+            .iter()
+            .filter(|&(pos, slot)| matches!(slot, Slot::Present(_)) && !needed.contains(pos))
+            .map(|(&pos, _)| (self.last_modified.get(&pos).copied().unwrap_or(0), pos))
+            .collect();
+        evictable.sort_unstable();
+
+        let excess = self.slots.len() - MAX_CACHED_CHUNKS;
+        for &(_, pos) in evictable.iter().take(excess) {
+            self.slots.remove(&pos);
+            self.last_modified.remove(&pos);
+        }
+    }
+}
+
+fn area(center: ChunkPosition, radius: i32) -> impl Iterator<Item = ChunkPosition> {
+    (-radius..=radius).flat_map(move |z| {
+        (-radius..=radius).map(move |x| ChunkPosition::new(center.x + x, center.z + z))
+    })
+}
+
+fn neighbours(pos: ChunkPosition) -> impl Iterator<Item = ChunkPosition> {
+    area(pos, 1).filter(move |&neighbour| neighbour != pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Performs actions until the pipeline has nothing left to do,
+    /// acting as a world save containing `saved` chunks.
+    /// Returns the delivered chunks and the number of decorations.
+    fn run(
+        pipeline: &mut GenerationPipeline,
+        saved: &mut AHashMap<ChunkPosition, Chunk>,
+    ) -> (Vec<Chunk>, usize) {
+        let mut delivered = Vec::new();
+        let mut decorations = 0;
+        while let Some(action) = pipeline.next_action() {
+            match action {
+                Action::Load(pos) => pipeline.on_loaded(pos, saved.get(&pos).cloned()),
+                Action::Generate(pos) => {
+                    let mut chunk = Chunk::new(pos);
+                    chunk.set_status(ChunkStatus::Proto);
+                    pipeline.on_generated(chunk);
+                }
+                Action::Decorate(region) => {
+                    decorations += 1;
+                    pipeline.on_decorated(region);
+                }
+                Action::Save(chunk) => {
+                    saved.insert(chunk.position(), chunk);
+                }
+                Action::Deliver(chunk) => delivered.push(chunk),
+            }
+        }
+        (delivered, decorations)
+    }
+
+    #[test]
+    fn chunks_are_delivered_after_neighbours_are_decorated() {
+        let mut pipeline = GenerationPipeline::new();
+        let mut saved = AHashMap::new();
+
+        pipeline.request(ChunkPosition::new(0, 0));
+        let (delivered, decorations) = run(&mut pipeline, &mut saved);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].position(), ChunkPosition::new(0, 0));
+        assert_eq!(delivered[0].status(), ChunkStatus::Full);
+        assert_eq!(decorations, 9);
+
+        // Decorated chunks were saved, and the delivered chunk once it was full.
+        assert_eq!(saved.len(), 5 * 5);
+        assert_eq!(saved[&ChunkPosition::new(0, 0)].status(), ChunkStatus::Full);
+        assert_eq!(
+            saved[&ChunkPosition::new(1, 1)].status(),
+            ChunkStatus::Decorated
+        );
+        assert_eq!(
+            saved[&ChunkPosition::new(2, 2)].status(),
+            ChunkStatus::Proto
+        );
+
+        // Neighbouring chunks reuse the work already done.
+        pipeline.request(ChunkPosition::new(1, 0));
+        let (delivered, decorations) = run(&mut pipeline, &mut saved);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(decorations, 3);
+    }
+
+    #[test]
+    fn finished_chunks_are_not_decorated() {
+        let mut pipeline = GenerationPipeline::new();
+        let mut saved = AHashMap::new();
+        saved.insert(
+            ChunkPosition::new(0, 0),
+            Chunk::new(ChunkPosition::new(0, 0)),
+        );
+
+        pipeline.request(ChunkPosition::new(0, 0));
+        let (delivered, decorations) = run(&mut pipeline, &mut saved);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(decorations, 0);
+
+        pipeline.request(ChunkPosition::new(1, 0));
+        let (delivered, _) = run(&mut pipeline, &mut saved);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(saved[&ChunkPosition::new(0, 0)].status(), ChunkStatus::Full);
+    }
+
+    #[test]
+    fn memory_is_bounded_while_chunks_are_requested() {
+        let mut pipeline = GenerationPipeline::new();
+        let mut saved = AHashMap::new();
+
+        // A request that never completes keeps the pipeline busy.
+        pipeline.request(ChunkPosition::new(-1000, -1000));
+        assert!(matches!(pipeline.next_action(), Some(Action::Load(_))));
+
+        for i in 0..40 {
+            pipeline.request(ChunkPosition::new(i * 10, 0));
+            let (delivered, _) = run(&mut pipeline, &mut saved);
+            assert_eq!(delivered.len(), 1);
+        }
+        assert!(pipeline.slots.len() <= MAX_CACHED_CHUNKS);
+        assert_eq!(
+            saved[&ChunkPosition::new(391, 1)].status(),
+            ChunkStatus::Decorated
+        );
+
+        // Evicted chunks are loaded again in the state they were saved in.
+        pipeline.request(ChunkPosition::new(1, 0));
+        let (delivered, decorations) = run(&mut pipeline, &mut saved);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(decorations, 3);
+    }
+}
@@ -1,4 +1,9 @@
-//! Chunk loading and unloading based on player `View`s.
+//! Chunk loading and unloading based on player `View`s
+//! and forced chunk loads.
+//!
+//! Chunks are identified by their world and position,
+//! so each world of the `Game` loads its own chunks.
+//! Chunks outside the world border are never loaded.
 
 use std::{
     collections::VecDeque,
@@ -9,10 +14,14 @@ use std::{
 use ahash::AHashMap;
 use base::ChunkPosition;
 use ecs::{Entity, SysResult, SystemExecutor};
-use quill_common::events::EntityRemoveEvent;
+use quill_common::{components::WorldId, events::EntityRemoveEvent};
 use utils::vec_remove_item;
 
-use crate::{chunk::worker::LoadRequest, events::ViewUpdateEvent, Game};
+use crate::{
+    chunk::worker::LoadRequest,
+    events::{ViewUpdateEvent, WorldBorderChangeEvent},
+    Game, World,
+};
 
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     game.insert_resource(ChunkLoadState::default());
@@ -20,6 +29,7 @@ pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
         .group::<ChunkLoadState>()
         .add_system(remove_dead_entities)
         .add_system(update_tickets_for_players)
+        .add_system(load_chunks_inside_borders)
         .add_system(unload_chunks)
         .add_system(load_chunks);
 }
@@ -29,15 +39,18 @@ pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
 const UNLOAD_DELAY: Duration = Duration::from_secs(10);
 
 #[derive(Default)]
-struct ChunkLoadState {
+pub(crate) struct ChunkLoadState {
     /// Chunks that have been queued for unloading.
     chunk_unload_queue: VecDeque<QueuedChunkUnload>,
 
     chunk_tickets: ChunkTickets,
 }
 
+/// A chunk in a world.
+type WorldChunk = (WorldId, ChunkPosition);
+
 impl ChunkLoadState {
-    pub fn remove_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+    fn remove_ticket(&mut self, chunk: WorldChunk, ticket: Ticket) {
         self.chunk_tickets.remove_ticket(chunk, ticket);
 
         // If this was the last ticket, then queue the chunk to be
@@ -48,19 +61,69 @@ impl ChunkLoadState {
                 .push_back(QueuedChunkUnload::new(chunk));
         }
     }
+
+    /// Adds a forced ticket for the chunk at `pos` in `world`,
+    /// queueing it for loading if needed.
+    ///
+    /// Returns `false` if `owner` already forces the chunk to stay loaded.
+    pub fn force_load(
+        &mut self,
+        world: &mut World,
+        pos: ChunkPosition,
+        owner: ForceLoadOwner,
+    ) -> bool {
+        let ticket = Ticket::Forced(owner);
+        let chunk = (world.id(), pos);
+        if self.chunk_tickets.has_ticket(chunk, ticket) {
+            return false;
+        }
+
+        self.chunk_tickets.insert_ticket(chunk, ticket);
+        load_if_needed(world, pos);
+        true
+    }
+
+    /// Removes the forced ticket of `owner` for `chunk`.
+    ///
+    /// Returns `false` if `owner` did not force the chunk to stay loaded.
+    pub fn release(&mut self, chunk: WorldChunk, owner: ForceLoadOwner) -> bool {
+        let ticket = Ticket::Forced(owner);
+        if !self.chunk_tickets.has_ticket(chunk, ticket) {
+            return false;
+        }
+
+        self.remove_ticket(chunk, ticket);
+        true
+    }
+
+    /// Forgets all tickets of chunks in `world`,
+    /// which has been removed from the game.
+    pub fn forget_world(&mut self, world: WorldId) {
+        self.chunk_tickets.forget_world(world);
+        self.chunk_unload_queue
+            .retain(|unload| unload.chunk.0 != world);
+    }
+
+    /// Removes all forced tickets of `owner`.
+    pub fn release_all(&mut self, owner: ForceLoadOwner) {
+        let ticket = Ticket::Forced(owner);
+        for chunk in self.chunk_tickets.take_entity_tickets(ticket) {
+            self.remove_ticket(chunk, ticket);
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
 struct QueuedChunkUnload {
-    pos: ChunkPosition,
+    chunk: WorldChunk,
     /// Time after which the chunk should be unloaded.
     unload_at_time: Instant,
 }
 
 impl QueuedChunkUnload {
-    pub fn new(pos: ChunkPosition) -> Self {
+    pub fn new(chunk: WorldChunk) -> Self {
         Self {
-            pos,
+            chunk,
             unload_at_time: Instant::now() + UNLOAD_DELAY,
         }
     }
@@ -70,72 +133,135 @@ impl QueuedChunkUnload {
 /// A chunk is queued for unloading when it has no more tickets.
 #[derive(Default)]
 struct ChunkTickets {
-    tickets: AHashMap<ChunkPosition, Vec<Ticket>>,
-    by_entity: AHashMap<Ticket, Vec<ChunkPosition>>,
+    tickets: AHashMap<WorldChunk, Vec<Ticket>>,
+    by_entity: AHashMap<Ticket, Vec<WorldChunk>>,
 }
 
 impl ChunkTickets {
-    pub fn insert_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+    pub fn insert_ticket(&mut self, chunk: WorldChunk, ticket: Ticket) {
         self.tickets.entry(chunk).or_default().push(ticket);
         self.by_entity.entry(ticket).or_default().push(chunk);
     }
 
-    pub fn remove_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+    pub fn remove_ticket(&mut self, chunk: WorldChunk, ticket: Ticket) {
         if let Some(vec) = self.tickets.get_mut(&chunk) {
             vec_remove_item(vec, &ticket);
         }
         vec_remove_item(self.by_entity.get_mut(&ticket).unwrap(), &chunk);
     }
 
-    pub fn num_tickets(&self, chunk: ChunkPosition) -> usize {
+    pub fn has_ticket(&self, chunk: WorldChunk, ticket: Ticket) -> bool {
+        match self.tickets.get(&chunk) {
+            Some(vec) => vec.contains(&ticket),
+            None => false,
+        }
+    }
+
+    pub fn num_tickets(&self, chunk: WorldChunk) -> usize {
         match self.tickets.get(&chunk) {
             Some(vec) => vec.len(),
             None => 0,
         }
     }
 
-    pub fn take_entity_tickets(&mut self, ticket: Ticket) -> Vec<ChunkPosition> {
+    pub fn take_entity_tickets(&mut self, ticket: Ticket) -> Vec<WorldChunk> {
         self.by_entity
             .get_mut(&ticket)
             .map(mem::take)
             .unwrap_or_default()
     }
 
-    pub fn remove_chunk(&mut self, pos: ChunkPosition) {
-        self.tickets.remove(&pos);
+    pub fn remove_chunk(&mut self, chunk: WorldChunk) {
+        self.tickets.remove(&chunk);
+    }
+
+    /// Iterates over the chunks of `world` which have tickets.
+    pub fn chunks_in(&self, world: WorldId) -> impl Iterator<Item = ChunkPosition> + '_ {
+        self.tickets
+            .keys()
+            .filter(move |chunk| chunk.0 == world)
+            .map(|chunk| chunk.1)
+    }
+
+    pub fn forget_world(&mut self, world: WorldId) {
+        self.tickets.retain(|chunk, _| chunk.0 != world);
+        for chunks in self.by_entity.values_mut() {
+            chunks.retain(|chunk| chunk.0 != world);
+        }
     }
 }
 
 /// ID of a chunk ticket that keeps a chunk loaded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Ticket {
+    /// The player that is keeping this chunk loaded.
+    Entity(Entity),
+    /// A forced load, e.g. by a plugin.
+    Forced(ForceLoadOwner),
+}
+
+/// Identifies the owner of a forced chunk load,
+/// such as a plugin.
 ///
-/// Currently just represents an entity, the player
-/// that is keeping this chunk loaded.
+/// See [`Game::force_load_chunk`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-struct Ticket(Entity);
+pub struct ForceLoadOwner(pub u64);
 
 /// System to populate chunk tickets based on players' views.
 fn update_tickets_for_players(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
     for (player, event) in game.ecs.query::<&ViewUpdateEvent>().iter() {
-        let player_ticket = Ticket(player);
+        let player_ticket = Ticket::Entity(player);
 
         // Remove old tickets
+        let old_world = event.old_view.world();
         for &old_chunk in &event.old_chunks {
-            state.remove_ticket(old_chunk, player_ticket);
+            state.remove_ticket((old_world, old_chunk), player_ticket);
         }
 
         // Create new tickets
+        let new_world = event.new_view.world();
+        let world = match game.worlds.get_mut(new_world) {
+            Some(world) => world,
+            None => {
+                log::warn!("Player is in unloaded world {:?}", new_world);
+                continue;
+            }
+        };
         for &new_chunk in &event.new_chunks {
-            state.chunk_tickets.insert_ticket(new_chunk, player_ticket);
+            state
+                .chunk_tickets
+                .insert_ticket((new_world, new_chunk), player_ticket);
+
+            load_if_needed(world, new_chunk);
+        }
+    }
+    Ok(())
+}
 
-            // Load if needed
-            if !game.world.is_chunk_loaded(new_chunk) && !game.world.is_chunk_loading(new_chunk) {
-                game.world.queue_chunk_load(LoadRequest { pos: new_chunk });
+/// System to load chunks which were kept
+/// outside the world border before it changed.
+fn load_chunks_inside_borders(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
+    for (_, event) in game.ecs.query::<&WorldBorderChangeEvent>().iter() {
+        if let Some(world) = game.worlds.get_mut(event.world) {
+            for pos in state.chunk_tickets.chunks_in(event.world) {
+                load_if_needed(world, pos);
             }
         }
     }
     Ok(())
 }
 
+/// Queues a chunk to be loaded unless it is already
+/// loaded or outside the world border.
+fn load_if_needed(world: &mut World, pos: ChunkPosition) {
+    if world.border().contains_chunk(pos)
+        && !world.is_chunk_loaded(pos)
+        && !world.is_chunk_loading(pos)
+    {
+        world.queue_chunk_load(LoadRequest { pos });
+    }
+}
+
 /// System to unload chunks from the `ChunkUnloadQueue`.
 fn unload_chunks(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
     while let Some(&unload) = state.chunk_unload_queue.get(0) {
@@ -149,19 +275,24 @@ fn unload_chunks(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
         state.chunk_unload_queue.pop_front();
 
         // If the chunk has acquired new tickets, then abort unloading it.
-        if state.chunk_tickets.num_tickets(unload.pos) > 0 {
+        if state.chunk_tickets.num_tickets(unload.chunk) > 0 {
             continue;
         }
 
-        game.world.unload_chunk(unload.pos)?;
+        let (world, pos) = unload.chunk;
+        if let Some(world) = game.worlds.get_mut(world) {
+            world.unload_chunk(pos)?;
+        }
+    }
+    for (_, world) in game.worlds.iter_mut() {
+        world.cache.purge_unused();
     }
-    game.world.cache.purge_unused();
     Ok(())
 }
 
 fn remove_dead_entities(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
     for (entity, _event) in game.ecs.query::<&EntityRemoveEvent>().iter() {
-        let entity_ticket = Ticket(entity);
+        let entity_ticket = Ticket::Entity(entity);
         for chunk in state.chunk_tickets.take_entity_tickets(entity_ticket) {
             state.remove_ticket(chunk, entity_ticket);
         }
@@ -169,7 +300,10 @@ fn remove_dead_entities(game: &mut Game, state: &mut ChunkLoadState) -> SysResul
     Ok(())
 }
 
-/// System to call `World::load_chunks` each tick
+/// System to call `World::load_chunks` on each world each tick
 fn load_chunks(game: &mut Game, _state: &mut ChunkLoadState) -> SysResult {
-    game.world.load_chunks(&mut game.ecs)
+    for (_, world) in game.worlds.iter_mut() {
+        world.load_chunks(&mut game.ecs)?;
+    }
+    Ok(())
 }
@@ -1,4 +1,6 @@
 pub mod cache;
 pub mod entities;
+pub mod generation;
 pub mod loading;
+pub mod pregen;
 pub mod worker;
@@ -0,0 +1,308 @@
+//! Pre-generation of the chunks inside a world's border,
+//! so that generating them does not slow down the game later.
+//!
+//! Chunks are requested from the `ChunkWorker` in a spiral
+//! around the center of the border and saved once they are
+//! fully generated. Progress is written to [`PROGRESS_FILE`]
+//! in the world directory, so that an interrupted pre-generation
+//! resumes where it left off.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use base::ChunkPosition;
+use ecs::{SysResult, SystemExecutor};
+use serde::{Deserialize, Serialize};
+
+use crate::{world_border::WorldBorder, Game};
+
+/// The file in the world directory storing the
+/// progress of an unfinished pre-generation.
+pub const PROGRESS_FILE: &str = "pregen.json";
+
+/// Maximum number of chunks requested at once.
+const MAX_IN_FLIGHT: usize = 64;
+
+/// Interval at which progress is logged and saved.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(advance_pregenerations);
+}
+
+/// The progress stored in [`PROGRESS_FILE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Progress {
+    center: (i32, i32),
+    radius: i32,
+    /// Number of chunks at the start of
+    /// the spiral which have been generated.
+    done: u64,
+}
+
+/// Pre-generates the chunks inside the border of a world.
+///
+/// Started through [`World::start_pregeneration`](crate::World::start_pregeneration).
+pub struct Pregeneration {
+    progress_file: PathBuf,
+    border: WorldBorder,
+    center: ChunkPosition,
+    /// Number of rings around the center chunk.
+    radius: i32,
+    /// Index in the spiral of the next chunk to request.
+    next: u64,
+    /// Number of chunks in the spiral.
+    total: u64,
+    /// Requested chunks and their index in the spiral.
+    in_flight: AHashMap<ChunkPosition, u64>,
+    started_at: (Instant, u64),
+    last_report: Instant,
+}
+
+impl Pregeneration {
+    /// Starts pre-generating the chunks inside `border`, resuming the
+    /// progress saved in `world_dir` if it is for the same area.
+    pub fn new(world_dir: &Path, border: WorldBorder) -> Self {
+        let (center_x, center_z) = border.center();
+        let center = ChunkPosition::new(
+            (center_x.floor() as i32).div_euclid(16),
+            (center_z.floor() as i32).div_euclid(16),
+        );
+        let radius = (border.radius().max(border.target_radius()) / 16.0) as i32 + 1;
+        let total = (2 * radius as u64 + 1).pow(2);
+
+        let progress_file = world_dir.join(PROGRESS_FILE);
+        let next = match load_progress(&progress_file) {
+            Ok(Some(progress))
+                if progress.center == (center.x, center.z) && progress.radius == radius =>
+            {
+                log::info!(
+                    "Resuming pre-generation of {} at chunk {} of {}",
+                    world_dir.display(),
+                    progress.done,
+                    total
+                );
+                progress.done.min(total)
+            }
+            Ok(_) => 0,
+            Err(e) => {
+                log::warn!(
+                    "Failed to read {}: {:?}. Starting pre-generation over",
+                    progress_file.display(),
+                    e
+                );
+                0
+            }
+        };
+
+        Self {
+            progress_file,
+            border,
+            center,
+            radius,
+            next,
+            total,
+            in_flight: AHashMap::new(),
+            started_at: (Instant::now(), next),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Gets the number of chunks which have been
+    /// pre-generated and the total number of chunks.
+    pub fn progress(&self) -> (u64, u64) {
+        (self.next - self.in_flight.len() as u64, self.total)
+    }
+
+    /// Returns whether all chunks have been pre-generated.
+    pub fn is_finished(&self) -> bool {
+        self.next == self.total && self.in_flight.is_empty()
+    }
+
+    /// Gets the next chunk to request, if not too
+    /// many chunks are being generated already.
+    pub fn next_chunk(&mut self) -> Option<ChunkPosition> {
+        while self.in_flight.len() < MAX_IN_FLIGHT && self.next < self.total {
+            let index = self.next;
+            self.next += 1;
+
+            let (x, z) = spiral_offset(index);
+            let pos = ChunkPosition::new(self.center.x + x, self.center.z + z);
+            if self.border.contains_chunk(pos) {
+                self.in_flight.insert(pos, index);
+                return Some(pos);
+            }
+        }
+        None
+    }
+
+    /// Marks a requested chunk as generated.
+    ///
+    /// Returns `false` if the chunk was not requested
+    /// by this pre-generation.
+    pub fn on_generated(&mut self, pos: ChunkPosition) -> bool {
+        self.in_flight.remove(&pos).is_some()
+    }
+
+    /// Logs the progress if it has not been done for a while.
+    ///
+    /// Returns whether it was logged, in which case the
+    /// progress should be saved once pending chunk saves
+    /// have been written.
+    pub(crate) fn report(&mut self) -> bool {
+        if self.last_report.elapsed() < REPORT_INTERVAL {
+            return false;
+        }
+        self.last_report = Instant::now();
+
+        let (done, total) = self.progress();
+        let (started, done_at_start) = self.started_at;
+        let rate = (done - done_at_start) as f64 / started.elapsed().as_secs_f64();
+        log::info!(
+            "Pre-generating {}: {} of {} chunks ({:.1}%, {:.0} chunks/s)",
+            self.progress_file
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .display(),
+            done,
+            total,
+            done as f64 / total as f64 * 100.0,
+            rate
+        );
+        true
+    }
+
+    /// Writes the progress to the world directory, or
+    /// removes the progress file if the pre-generation is finished.
+    pub fn save_progress(&self) -> anyhow::Result<()> {
+        if self.is_finished() {
+            if self.progress_file.exists() {
+                fs::remove_file(&self.progress_file)?;
+            }
+            return Ok(());
+        }
+
+        // Chunks are generated out of order, so only the
+        // chunks before the earliest unfinished one are done.
+        let done = self.in_flight.values().copied().min().unwrap_or(self.next);
+        let progress = Progress {
+            center: (self.center.x, self.center.z),
+            radius: self.radius,
+            done,
+        };
+        fs::write(&self.progress_file, serde_json::to_string(&progress)?)?;
+        Ok(())
+    }
+}
+
+fn load_progress(path: &Path) -> anyhow::Result<Option<Progress>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+/// Gets the offset from the center of the chunk at `index`
+/// in a spiral walking square rings of increasing size.
+fn spiral_offset(index: u64) -> (i32, i32) {
+    let mut root = (index as f64).sqrt() as u64;
+    // Correct rounding errors for large indices.
+    while root * root > index {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= index {
+        root += 1;
+    }
+
+    // Ring `n` starts at index (2n - 1)^2 and has 8n chunks.
+    let ring = (root + 1) / 2;
+    if ring == 0 {
+        return (0, 0);
+    }
+    let offset = index - (2 * ring - 1).pow(2);
+    let side_length = 2 * ring;
+    let (side, t) = (offset / side_length, (offset % side_length) as i32);
+    let ring = ring as i32;
+    match side {
+        0 => (-ring + t, -ring),
+        1 => (ring, -ring + t),
+        2 => (ring - t, ring),
+        _ => (-ring, ring - t),
+    }
+}
+
+/// System to request chunks for each world being pre-generated.
+fn advance_pregenerations(game: &mut Game) -> SysResult {
+    for (_, world) in game.worlds.iter_mut() {
+        world.advance_pregeneration();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+
+    use super::*;
+
+    #[test]
+    fn spiral_walks_rings_outwards() {
+        let offsets: Vec<(i32, i32)> = (0..49).map(spiral_offset).collect();
+        assert_eq!(offsets[0], (0, 0));
+
+        let distinct: AHashSet<(i32, i32)> = offsets.iter().copied().collect();
+        assert_eq!(distinct.len(), 49);
+        for (index, &(x, z)) in offsets.iter().enumerate() {
+            let ring = x.abs().max(z.abs());
+            let expected_ring = match index {
+                0 => 0,
+                1..=8 => 1,
+                9..=24 => 2,
+                _ => 3,
+            };
+            assert_eq!(ring, expected_ring, "chunk {} at {:?}", index, (x, z));
+        }
+    }
+
+    #[test]
+    fn pregeneration_stays_inside_border_and_resumes() {
+        let dir = std::env::temp_dir().join(format!("feather-pregen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Blocks -40 to 40 are in chunks -3 to 2.
+        let border = WorldBorder::new(0.0, 0.0, 40.0);
+        let mut pregen = Pregeneration::new(&dir, border);
+        let mut requested = Vec::new();
+        while let Some(pos) = pregen.next_chunk() {
+            requested.push(pos);
+        }
+        assert_eq!(requested.len(), 36);
+        assert!(requested.iter().all(|&pos| border.contains_chunk(pos)));
+        assert_eq!(requested[0], ChunkPosition::new(0, 0));
+
+        // Chunks finishing out of order are generated again after a restart.
+        for &pos in &requested[..10] {
+            assert!(pregen.on_generated(pos));
+        }
+        assert!(pregen.on_generated(requested[20]));
+        assert!(!pregen.on_generated(ChunkPosition::new(10, 10)));
+        pregen.save_progress().unwrap();
+
+        let mut resumed = Pregeneration::new(&dir, border);
+        assert_eq!(resumed.next_chunk(), Some(requested[10]));
+
+        for &pos in &requested[10..] {
+            pregen.on_generated(pos);
+        }
+        assert!(pregen.is_finished());
+        assert_eq!(pregen.progress(), (pregen.total, pregen.total));
+        pregen.save_progress().unwrap();
+        assert!(!dir.join(PROGRESS_FILE).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,15 +1,17 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::VecDeque, path::PathBuf, sync::Arc};
 
 use anyhow::bail;
 use base::{
     anvil::{block_entity::BlockEntityData, entity::EntityData},
-    Chunk, ChunkHandle, ChunkPosition,
+    Chunk, ChunkHandle, ChunkLock, ChunkPosition,
 };
 use flume::{Receiver, Sender};
-use worldgen::WorldGenerator;
+use worldgen::{DecorationRegion, WorldGenerator};
 
 use crate::region_worker::RegionWorker;
 
+use super::generation::{Action, GenerationPipeline};
+
 #[derive(Debug)]
 pub struct LoadRequest {
     pub pos: ChunkPosition,
@@ -26,7 +28,7 @@ pub enum ChunkLoadResult {
     /// The chunk does not exist in this source.
     Missing(ChunkPosition),
     /// An error occurred while loading the chunk.
-    Error(anyhow::Error),
+    Error(ChunkPosition, anyhow::Error),
     /// Successfully loaded the chunk.
     Loaded(LoadedChunk),
 }
@@ -44,13 +46,25 @@ pub struct SaveRequest {
 pub enum WorkerRequest {
     Load(LoadRequest),
     Save(SaveRequest),
+    /// Answered once all earlier requests have been handled.
+    Flush(Sender<()>),
+}
+/// Result of a task run on the generation thread pool.
+#[allow(clippy::large_enum_variant)]
+enum GenerationResult {
+    Generated(Chunk),
+    Decorated(DecorationRegion),
 }
+
 pub struct ChunkWorker {
     generator: Arc<dyn WorldGenerator>,
     send_req: Sender<WorkerRequest>,
-    send_gen: Sender<LoadedChunk>,
-    recv_gen: Receiver<LoadedChunk>, // Chunk generation should be infallible.
+    send_gen: Sender<GenerationResult>,
+    recv_gen: Receiver<GenerationResult>, // Chunk generation should be infallible.
     recv_load: Receiver<ChunkLoadResult>,
+    pipeline: GenerationPipeline,
+    /// Fully generated chunks waiting to be polled.
+    ready: VecDeque<LoadedChunk>,
 }
 
 impl ChunkWorker {
@@ -65,15 +79,57 @@ impl ChunkWorker {
             send_gen,
             recv_gen,
             recv_load,
+            pipeline: GenerationPipeline::new(),
+            ready: VecDeque::new(),
         }
     }
     pub fn queue_load(&mut self, request: LoadRequest) {
-        self.send_req.send(WorkerRequest::Load(request)).unwrap()
+        self.pipeline.request(request.pos);
+        self.perform_actions();
+    }
+
+    /// Performs the actions requested by the generation pipeline.
+    fn perform_actions(&mut self) {
+        while let Some(action) = self.pipeline.next_action() {
+            match action {
+                Action::Load(pos) => self
+                    .send_req
+                    .send(WorkerRequest::Load(LoadRequest { pos }))
+                    .unwrap(),
+                Action::Generate(pos) => {
+                    let send_gen = self.send_gen.clone();
+                    let gen = self.generator.clone();
+                    rayon::spawn(move || {
+                        // spawn task to generate chunk
+                        let chunk = gen.generate_chunk(pos);
+                        // The world may have been unloaded in the meantime.
+                        let _ = send_gen.send(GenerationResult::Generated(chunk));
+                    });
+                }
+                Action::Decorate(mut region) => {
+                    let send_gen = self.send_gen.clone();
+                    let gen = self.generator.clone();
+                    rayon::spawn(move || {
+                        gen.decorate_chunk(&mut region);
+                        let _ = send_gen.send(GenerationResult::Decorated(region));
+                    });
+                }
+                Action::Save(chunk) => self.queue_chunk_save(SaveRequest {
+                    pos: chunk.position(),
+                    chunk: Arc::new(ChunkLock::new(chunk, false)),
+                    entities: vec![],
+                    block_entities: vec![],
+                }),
+                Action::Deliver(chunk) => self.ready.push_back(LoadedChunk {
+                    pos: chunk.position(),
+                    chunk,
+                }),
+            }
+        }
     }
 
     /// Helper function for poll_loaded_chunk. Attemts to receive a freshly generated chunk.
-    /// Function signature identical to that of poll_loaded_chunk for ease of use.
-    fn try_recv_gen(&mut self) -> Result<Option<LoadedChunk>, anyhow::Error> {
+    fn try_recv_gen(&mut self) -> Result<Option<GenerationResult>, anyhow::Error> {
         match self.recv_gen.try_recv() {
             Ok(l) => Ok(Some(l)),
             Err(e) => match e {
@@ -82,34 +138,53 @@ impl ChunkWorker {
             },
         }
     }
-    pub fn poll_loaded_chunk(&mut self) -> Result<Option<LoadedChunk>, anyhow::Error> {
+
+    /// Helper function for poll_loaded_chunk. Attempts to receive an answer from the RegionWorker.
+    fn try_recv_load(&mut self) -> Result<Option<ChunkLoadResult>, anyhow::Error> {
         match self.recv_load.try_recv() {
-            Ok(answer) => {
-                match answer {
-                    // RegionWorker answered
-                    ChunkLoadResult::Missing(pos) => {
-                        // chunk does not exist, queue it for generation
-                        let send_gen = self.send_gen.clone();
-                        let gen = self.generator.clone();
-                        rayon::spawn(move || {
-                            // spawn task to generate chunk
-                            let chunk = gen.generate_chunk(pos);
-                            send_gen.send(LoadedChunk { pos, chunk }).unwrap()
-                        });
-                        self.try_recv_gen() // check for generated chunks
-                    }
-                    ChunkLoadResult::Error(e) => Err(e),
-                    ChunkLoadResult::Loaded(l) => Ok(Some(l)),
-                }
-            }
+            Ok(answer) => Ok(Some(answer)),
             Err(e) => match e {
-                flume::TryRecvError::Empty => self.try_recv_gen(), // check for generated chunks
+                flume::TryRecvError::Empty => Ok(None),
                 flume::TryRecvError::Disconnected => bail!("RegionWorker died"),
             },
         }
     }
 
+    /// Gets the next fully generated chunk, if any.
+    pub fn poll_loaded_chunk(&mut self) -> Result<Option<LoadedChunk>, anyhow::Error> {
+        while let Some(answer) = self.try_recv_load()? {
+            match answer {
+                // RegionWorker answered
+                ChunkLoadResult::Missing(pos) => self.pipeline.on_loaded(pos, None),
+                ChunkLoadResult::Error(pos, e) => {
+                    if self.pipeline.on_load_failed(pos) {
+                        self.perform_actions();
+                        return Err(e);
+                    }
+                    log::warn!("Failed to load chunk {:?}: {:?}", pos, e);
+                }
+                ChunkLoadResult::Loaded(l) => self.pipeline.on_loaded(l.pos, Some(l.chunk)),
+            }
+            self.perform_actions();
+        }
+        while let Some(result) = self.try_recv_gen()? {
+            match result {
+                GenerationResult::Generated(chunk) => self.pipeline.on_generated(chunk),
+                GenerationResult::Decorated(region) => self.pipeline.on_decorated(region),
+            }
+            self.perform_actions();
+        }
+        Ok(self.ready.pop_front())
+    }
+
     pub fn queue_chunk_save(&mut self, req: SaveRequest) {
         self.send_req.send(WorkerRequest::Save(req)).unwrap()
     }
+
+    /// Blocks until all chunks queued for saving have been written.
+    pub fn wait_for_saves(&self) {
+        let (send_done, recv_done) = flume::bounded(1);
+        self.send_req.send(WorkerRequest::Flush(send_done)).unwrap();
+        let _ = recv_done.recv();
+    }
 }
@@ -5,12 +5,20 @@
 //! add default components for that entity.
 
 use ecs::EntityBuilder;
-use quill_common::{components::OnGround, entity_init::EntityInit};
+use quill_common::{
+    components::{OnGround, WorldId},
+    entity_init::EntityInit,
+};
 use uuid::Uuid;
 
 /// Adds default components shared between all entities.
+///
+/// Entities start out in the default world.
 fn build_default(builder: &mut EntityBuilder) {
-    builder.add(Uuid::new_v4()).add(OnGround(true));
+    builder
+        .add(Uuid::new_v4())
+        .add(OnGround(true))
+        .add(WorldId::DEFAULT);
 }
 
 pub mod area_effect_cloud;
@@ -1,4 +1,5 @@
 use base::{ChunkHandle, ChunkPosition};
+use quill_common::components::WorldId;
 
 use crate::view::View;
 
@@ -37,7 +38,8 @@ impl ViewUpdateEvent {
     }
 }
 
-/// Event triggered when an entity crosses into a new chunk.
+/// Event triggered when an entity crosses into a new chunk
+/// within its world.
 ///
 /// Unlike [`ViewUpdateEvent`], this event triggers for all entities,
 /// not just players.
@@ -46,11 +48,31 @@ pub struct ChunkCrossEvent {
     pub new_chunk: ChunkPosition,
 }
 
+/// Event triggered when an entity moves into another world
+/// through [`Game::teleport_to_world`](crate::Game::teleport_to_world).
+///
+/// [`ChunkCrossEvent`] does not trigger in this case.
+#[derive(Debug)]
+pub struct WorldChangeEvent {
+    pub old_world: WorldId,
+    pub new_world: WorldId,
+    /// The chunk the entity was in before it left `old_world`.
+    pub old_chunk: ChunkPosition,
+}
+
 /// Triggered when a chunk is loaded.
 #[derive(Debug)]
 pub struct ChunkLoadEvent {
     pub position: ChunkPosition,
     pub chunk: ChunkHandle,
+    pub world: WorldId,
+}
+
+/// Triggered when the border of a world is changed
+/// through [`Game::update_world_border`](crate::Game::update_world_border).
+#[derive(Debug)]
+pub struct WorldBorderChangeEvent {
+    pub world: WorldId,
 }
 
 /// Triggered when an error occurs while loading a chunk.
@@ -5,6 +5,7 @@ use base::{
     BlockPosition, ChunkPosition, ValidBlockPosition,
 };
 use itertools::Either;
+use quill_common::components::WorldId;
 
 /// Event triggered when one or more blocks are changed.
 ///
@@ -14,6 +15,7 @@ use itertools::Either;
 #[derive(Debug, Clone)]
 pub struct BlockChangeEvent {
     changes: BlockChanges,
+    world: WorldId,
 }
 
 impl BlockChangeEvent {
@@ -21,17 +23,31 @@ impl BlockChangeEvent {
     pub fn single(pos: ValidBlockPosition) -> Self {
         Self {
             changes: BlockChanges::Single { pos },
+            world: WorldId::DEFAULT,
         }
     }
 
     /// Creates an event corresponding to a block update
-    /// that fills an entire chunk section with the same block.
+    /// that overwrites an entire chunk section.
     pub fn fill_chunk_section(chunk: ChunkPosition, section: u32) -> Self {
         Self {
             changes: BlockChanges::FillChunkSection { chunk, section },
+            world: WorldId::DEFAULT,
         }
     }
 
+    /// Moves the change into `world`. Events
+    /// are in the default world by default.
+    pub fn in_world(mut self, world: WorldId) -> Self {
+        self.world = world;
+        self
+    }
+
+    /// Gets the world in which the blocks were changed.
+    pub fn world(&self) -> WorldId {
+        self.world
+    }
+
     /// Determines the number of blocks that were
     /// changed in this block change event.
     pub fn count(&self) -> usize {
@@ -1,18 +1,33 @@
-use std::{cell::RefCell, mem, rc::Rc, sync::Arc};
+use std::{cell::RefCell, mem, path::Path, rc::Rc, sync::Arc};
 
-use base::{BlockId, ChunkPosition, Position, Text, Title, ValidBlockPosition};
+use base::{
+    chunk::{BlockStore, LightStore, NUM_SECTIONS, SECTION_VOLUME},
+    BlockId, ChunkPosition, ChunkSection, Position, Text, Title, ValidBlockPosition,
+};
 use ecs::{
     Ecs, Entity, EntityBuilder, HasEcs, HasResources, NoSuchEntity, Resources, SysResult,
     SystemExecutor,
 };
 use quill_common::events::{EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent};
-use quill_common::{entities::Player, entity_init::EntityInit};
+use quill_common::{
+    components::WorldId,
+    entities::Player,
+    entity_init::EntityInit,
+    world::{is_valid_world_name, WorldError, WorldSettings},
+};
+use uuid::Uuid;
 
 use crate::{
     chat::{ChatKind, ChatMessage},
-    chunk::entities::ChunkEntities,
-    events::BlockChangeEvent,
-    ChatBox, World,
+    chunk::{
+        entities::ChunkEntities,
+        loading::{ChunkLoadState, ForceLoadOwner},
+    },
+    events::{BlockChangeEvent, ViewUpdateEvent, WorldBorderChangeEvent, WorldChangeEvent},
+    scheduler::{Scheduler, TaskId},
+    view::View,
+    world_border::WorldBorder,
+    AccessLists, ChatBox, Permissions, World, Worlds,
 };
 
 type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
@@ -20,7 +35,8 @@ type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
 /// Stores the entire state of a Minecraft game.
 ///
 /// This contains:
-/// * A [`World`](crate::World) containing chunks and blocks.
+/// * A [`Worlds`](crate::Worlds) containing each hosted [`World`](crate::World)
+///   with its chunks and blocks.
 /// * An [`Ecs`](ecs::Ecs) containing entities.
 /// * A [`Resources`](ecs::Resources) containing additional, user-defined data.
 /// * A [`SystemExecutor`] to run systems.
@@ -29,13 +45,13 @@ type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
 /// as "drop item" or "kill entity." These high-level methods
 /// should be preferred over raw interaction with the ECS.
 pub struct Game {
-    /// Contains chunks and blocks.
+    /// Contains chunks and blocks of each world.
     ///
     /// NB: use methods on `Game` to update
     /// blocks, not direct methods on `World`.
     /// The `Game` methods will automatically
     /// trigger the necessary `BlockChangeEvent`s.
-    pub world: World,
+    pub worlds: Worlds,
     /// Contains entities, including players.
     pub ecs: Ecs,
     /// Contains systems.
@@ -52,6 +68,9 @@ pub struct Game {
     /// Total ticks elapsed since the server started.
     pub tick_count: u64,
 
+    /// Delayed and repeating tasks.
+    pub scheduler: Scheduler,
+
     entity_spawn_callbacks: Vec<EntitySpawnCallback>,
 
     entity_builder: EntityBuilder,
@@ -67,12 +86,13 @@ impl Game {
     /// Creates a new, empty `Game`.
     pub fn new() -> Self {
         Self {
-            world: World::new(),
+            worlds: Worlds::default(),
             ecs: Ecs::new(),
             system_executor: Rc::new(RefCell::new(SystemExecutor::new())),
             resources: Arc::new(Resources::new()),
             chunk_entities: ChunkEntities::default(),
             tick_count: 0,
+            scheduler: Scheduler::new(),
             entity_spawn_callbacks: Vec::new(),
             entity_builder: EntityBuilder::new(),
         }
@@ -93,6 +113,27 @@ impl Game {
             .insert(resource);
     }
 
+    /// Schedules `task` to run once after `delay` ticks.
+    pub fn run_later(
+        &mut self,
+        delay: u64,
+        task: impl FnMut(&mut Game) -> SysResult + 'static,
+    ) -> TaskId {
+        self.scheduler.run_later(self.tick_count, delay, task)
+    }
+
+    /// Schedules `task` to run every `period` ticks,
+    /// starting after `delay` ticks.
+    pub fn run_repeating(
+        &mut self,
+        delay: u64,
+        period: u64,
+        task: impl FnMut(&mut Game) -> SysResult + 'static,
+    ) -> TaskId {
+        self.scheduler
+            .run_repeating(self.tick_count, delay, period, task)
+    }
+
     /// Adds a new entity spawn callback, invoked
     /// before an entity is created.
     ///
@@ -181,23 +222,220 @@ impl Game {
         Ok(())
     }
 
-    /// Gets the block at the given position.
+    /// Determines whether a player has the given permission node.
+    ///
+    /// Checks the [`Permissions`] resource, using the player's operator
+    /// level from the `Arc<AccessLists>` resource if there is one.
+    /// Entities without a `Uuid` have no permissions.
+    pub fn has_permission(&self, player: Entity, node: &str) -> bool {
+        let uuid = match self.ecs.get::<Uuid>(player) {
+            Ok(uuid) => *uuid,
+            Err(_) => return false,
+        };
+        let op_level = self
+            .resources
+            .get::<Arc<AccessLists>>()
+            .ok()
+            .and_then(|access_lists| access_lists.op(uuid))
+            .map(|op| op.level)
+            .unwrap_or(0);
+        self.resources
+            .get::<Permissions>()
+            .map(|permissions| permissions.has(uuid, op_level, node))
+            .unwrap_or(false)
+    }
+
+    /// Moves an entity to `position` in another world.
+    ///
+    /// Triggers a `WorldChangeEvent`, and updates the entity's
+    /// `View` if it has one, so that its chunks are loaded
+    /// and sent in the new world.
+    pub fn teleport_to_world(
+        &mut self,
+        entity: Entity,
+        world: WorldId,
+        position: Position,
+    ) -> SysResult {
+        if !self.worlds.contains(world) {
+            anyhow::bail!("world {:?} is not loaded", world);
+        }
+
+        let old_chunk = self.ecs.get::<Position>(entity)?.chunk();
+        let old_world = *self.ecs.get::<WorldId>(entity)?;
+        *self.ecs.get_mut::<Position>(entity)? = position;
+        *self.ecs.get_mut::<WorldId>(entity)? = world;
+        self.ecs.insert_entity_event(
+            entity,
+            WorldChangeEvent {
+                old_world,
+                new_world: world,
+                old_chunk,
+            },
+        )?;
+
+        let old_view = match self.ecs.get::<View>(entity) {
+            Ok(view) => *view,
+            Err(_) => return Ok(()),
+        };
+        let new_view = View::in_world(position.chunk(), old_view.view_distance(), world);
+        *self.ecs.get_mut::<View>(entity)? = new_view;
+        self.ecs
+            .insert_entity_event(entity, ViewUpdateEvent::new(old_view, new_view))?;
+        Ok(())
+    }
+
+    /// Gets the ID of the loaded world with the given name.
+    pub fn world_id(&self, name: &str) -> Option<WorldId> {
+        self.worlds.id(name)
+    }
+
+    /// Creates a new world in the directory `name` and loads it.
+    ///
+    /// Fails if a world with that name already exists on disk.
+    pub fn create_world(
+        &mut self,
+        name: &str,
+        settings: &WorldSettings,
+    ) -> Result<WorldId, WorldError> {
+        self.check_world_name(name)?;
+        if Path::new(name).exists() {
+            return Err(WorldError::AlreadyExists(name.to_owned()));
+        }
+        self.host_world(name, settings)
+    }
+
+    /// Loads the world saved in the directory `name`.
+    ///
+    /// Chunks that have not been generated yet are generated
+    /// according to `settings`.
+    pub fn load_world(
+        &mut self,
+        name: &str,
+        settings: &WorldSettings,
+    ) -> Result<WorldId, WorldError> {
+        self.check_world_name(name)?;
+        if !Path::new(name).is_dir() {
+            return Err(WorldError::NotFound(name.to_owned()));
+        }
+        self.host_world(name, settings)
+    }
+
+    fn check_world_name(&self, name: &str) -> Result<(), WorldError> {
+        if !is_valid_world_name(name) {
+            return Err(WorldError::InvalidName(name.to_owned()));
+        }
+        if self.worlds.id_ignore_case(name).is_some() {
+            return Err(WorldError::AlreadyLoaded(name.to_owned()));
+        }
+        Ok(())
+    }
+
+    fn host_world(&mut self, name: &str, settings: &WorldSettings) -> Result<WorldId, WorldError> {
+        let generator = worldgen::generator_by_name(&settings.generator, settings.seed)
+            .ok_or_else(|| WorldError::UnknownGenerator(settings.generator.clone()))?;
+        let world = World::with_dimension(settings.dimension, generator, name);
+        Ok(self.worlds.insert(name, world))
+    }
+
+    /// Unloads a world, saving its chunks.
+    ///
+    /// Entities with a `View` (usually players) are moved
+    /// to the spawn of the default world; all other entities
+    /// in the world are removed.
+    pub fn unload_world(&mut self, world: WorldId) -> Result<(), WorldError> {
+        if world == WorldId::DEFAULT {
+            return Err(WorldError::DefaultWorld);
+        }
+        if !self.worlds.contains(world) {
+            return Err(WorldError::NotLoaded);
+        }
+
+        let entities: Vec<(Entity, bool)> = self
+            .ecs
+            .query::<(&WorldId, Option<&View>)>()
+            .iter()
+            .filter(|(_, (&entity_world, _))| entity_world == world)
+            .map(|(entity, (_, view))| (entity, view.is_some()))
+            .collect();
+        for (entity, has_view) in entities {
+            let result = if has_view {
+                self.teleport_to_world(entity, WorldId::DEFAULT, Position::default())
+            } else {
+                self.remove_entity(entity).map_err(From::from)
+            };
+            if let Err(e) = result {
+                log::warn!("Failed to move entity out of unloaded world: {:?}", e);
+            }
+        }
+
+        if let Ok(mut state) = self.resources.get_mut::<ChunkLoadState>() {
+            state.forget_world(world);
+        }
+        let mut removed = self.worlds.remove(world).ok_or(WorldError::NotLoaded)?;
+        let chunks: Vec<ChunkPosition> = removed.chunk_map().iter_positions().collect();
+        for pos in chunks {
+            if let Err(e) = removed.unload_chunk(pos) {
+                log::error!("Failed to unload chunk {:?}: {:?}", pos, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Changes the border of a world through `update`,
+    /// notifying the players in the world.
+    pub fn update_world_border(
+        &mut self,
+        world: WorldId,
+        update: impl FnOnce(&mut WorldBorder),
+    ) -> Result<(), WorldError> {
+        let border = self
+            .worlds
+            .get_mut(world)
+            .ok_or(WorldError::NotLoaded)?
+            .border_mut();
+        update(border);
+        self.ecs.insert_event(WorldBorderChangeEvent { world });
+        Ok(())
+    }
+
+    /// Gets the block at the given position in the default world.
     pub fn block(&self, pos: ValidBlockPosition) -> Option<BlockId> {
-        self.world.block_at(pos)
+        self.block_in(WorldId::DEFAULT, pos)
+    }
+
+    /// Gets the block at the given position in `world`.
+    pub fn block_in(&self, world: WorldId, pos: ValidBlockPosition) -> Option<BlockId> {
+        self.worlds.get(world)?.block_at(pos)
     }
 
-    /// Sets the block at the given position.
+    /// Sets the block at the given position in the default world.
     ///
     /// Triggers necessary `BlockChangeEvent`s.
     pub fn set_block(&mut self, pos: ValidBlockPosition, block: BlockId) -> bool {
-        let was_successful = self.world.set_block_at(pos, block);
+        self.set_block_in(WorldId::DEFAULT, pos, block)
+    }
+
+    /// Sets the block at the given position in `world`.
+    ///
+    /// Triggers necessary `BlockChangeEvent`s.
+    pub fn set_block_in(
+        &mut self,
+        world: WorldId,
+        pos: ValidBlockPosition,
+        block: BlockId,
+    ) -> bool {
+        let was_successful = match self.worlds.get(world) {
+            Some(w) => w.set_block_at(pos, block),
+            None => false,
+        };
         if was_successful {
-            self.ecs.insert_event(BlockChangeEvent::single(pos));
+            self.ecs
+                .insert_event(BlockChangeEvent::single(pos).in_world(world));
         }
         was_successful
     }
 
-    /// Fills the given chunk section (16x16x16 blocks).
+    /// Fills the given chunk section (16x16x16 blocks) of the default world.
     ///
     /// All blocks in the chunk section are overwritten with `block`.
     pub fn fill_chunk_section(
@@ -206,7 +444,12 @@ impl Game {
         section_y: usize,
         block: BlockId,
     ) -> bool {
-        let mut chunk = match self.world.chunk_map().chunk_at_mut(chunk_pos) {
+        let mut chunk = match self
+            .worlds
+            .default_world()
+            .chunk_map()
+            .chunk_at_mut(chunk_pos)
+        {
             Some(chunk) => chunk,
             None => return false,
         };
@@ -225,11 +468,93 @@ impl Game {
         true
     }
 
+    /// Overwrites the blocks of the given chunk section of the default world
+    /// (16x16x16 blocks), keeping its light data.
+    ///
+    /// Recalculates the chunk's heightmaps and triggers
+    /// the necessary `BlockChangeEvent`.
+    pub fn set_chunk_section(
+        &mut self,
+        chunk_pos: ChunkPosition,
+        section_y: usize,
+        blocks: BlockStore,
+    ) -> bool {
+        if section_y >= NUM_SECTIONS {
+            return false;
+        }
+
+        let mut chunk = match self
+            .worlds
+            .default_world()
+            .chunk_map()
+            .chunk_at_mut(chunk_pos)
+        {
+            Some(chunk) => chunk,
+            None => return false,
+        };
+
+        let section_index = section_y as isize;
+        if let Some(section) = chunk.section_mut(section_index) {
+            // An all-air section is kept too, so its light isn't lost.
+            *section.blocks_mut() = blocks;
+        } else if blocks.air_blocks() != SECTION_VOLUME as u32 {
+            chunk.set_section_at(
+                section_index,
+                Some(ChunkSection::new(blocks, LightStore::new())),
+            );
+        }
+        chunk.recalculate_heightmaps();
+
+        self.ecs.insert_event(BlockChangeEvent::fill_chunk_section(
+            chunk_pos,
+            section_y as u32,
+        ));
+
+        true
+    }
+
+    /// Keeps the chunk at `pos` of the default world loaded until [`Game::release_chunk`]
+    /// is called with the same `owner`, loading it if needed.
+    ///
+    /// Returns `false` if `owner` already forces the chunk to stay loaded.
+    pub fn force_load_chunk(&mut self, pos: ChunkPosition, owner: ForceLoadOwner) -> bool {
+        let resources = Arc::clone(&self.resources);
+        let mut state = match resources.get_mut::<ChunkLoadState>() {
+            Ok(state) => state,
+            Err(_) => return false,
+        };
+        state.force_load(self.worlds.default_world_mut(), pos, owner)
+    }
+
+    /// Stops forcing the chunk at `pos` of the default world to stay loaded
+    /// on behalf of `owner`. The chunk is unloaded
+    /// once nothing else keeps it loaded.
+    ///
+    /// Returns `false` if `owner` did not force the chunk to stay loaded.
+    pub fn release_chunk(&mut self, pos: ChunkPosition, owner: ForceLoadOwner) -> bool {
+        match self.resources.get_mut::<ChunkLoadState>() {
+            Ok(mut state) => state.release((WorldId::DEFAULT, pos), owner),
+            Err(_) => false,
+        }
+    }
+
+    /// Releases all chunks forced to stay loaded by `owner`.
+    pub fn release_all_chunks(&mut self, owner: ForceLoadOwner) {
+        if let Ok(mut state) = self.resources.get_mut::<ChunkLoadState>() {
+            state.release_all(owner);
+        }
+    }
+
     /// Breaks the block at the given position, propagating any
     /// necessary block updates.
     pub fn break_block(&mut self, pos: ValidBlockPosition) -> bool {
         self.set_block(pos, BlockId::air())
     }
+
+    /// Breaks the block at the given position in `world`.
+    pub fn break_block_in(&mut self, world: WorldId, pos: ValidBlockPosition) -> bool {
+        self.set_block_in(world, pos, BlockId::air())
+    }
 }
 
 impl HasResources for Game {
@@ -247,3 +572,121 @@ impl HasEcs for Game {
         &mut self.ecs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    use base::Chunk;
+    use flume::{Receiver, Sender};
+    use worldgen::WorldGenerator;
+
+    use super::*;
+    use crate::chunk::worker::LoadRequest;
+
+    /// Blocks inside `generate_chunk` until `release` is dropped.
+    struct BlockingGenerator {
+        started: Sender<ChunkPosition>,
+        release: Receiver<()>,
+    }
+
+    impl WorldGenerator for BlockingGenerator {
+        fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+            let _ = self.started.send(position);
+            let _ = self.release.recv();
+            Chunk::new(position)
+        }
+    }
+
+    #[test]
+    fn setting_air_section_keeps_light() {
+        let mut game = Game::new();
+        let pos = ChunkPosition::new(0, 0);
+        let mut chunk = Chunk::new(pos);
+        chunk.fill_section(1, BlockId::stone());
+        chunk
+            .section_mut(0)
+            .unwrap()
+            .light_mut()
+            .set_sky_light_at(1, 2, 3, 7);
+        game.worlds
+            .default_world_mut()
+            .chunk_map_mut()
+            .insert_chunk(chunk);
+
+        assert!(game.set_chunk_section(pos, 0, BlockStore::new()));
+
+        let chunk = game
+            .worlds
+            .default_world()
+            .chunk_map()
+            .chunk_at(pos)
+            .unwrap();
+        let section = chunk.section(0).unwrap();
+        assert!(section.is_empty());
+        assert_eq!(section.light().sky_light_at(1, 2, 3), Some(7));
+    }
+
+    #[test]
+    fn world_names_differing_in_case_conflict() {
+        let mut game = Game::new();
+        game.worlds.insert(
+            "arena",
+            World::with_gen_and_path(Arc::new(worldgen::VoidWorldGenerator), "arena"),
+        );
+        assert_eq!(
+            game.check_world_name("Arena"),
+            Err(WorldError::AlreadyLoaded("Arena".to_owned()))
+        );
+        assert!(game.check_world_name("arena2").is_ok());
+    }
+
+    #[test]
+    fn unloading_world_with_generation_in_flight() {
+        let dir = std::env::temp_dir().join(format!("feather-unload-{}", std::process::id()));
+        let (started_tx, started) = flume::unbounded();
+        let (release, release_rx) = flume::bounded(0);
+        let generator = Arc::new(BlockingGenerator {
+            started: started_tx,
+            release: release_rx,
+        });
+
+        let mut game = Game::new();
+        let world = game
+            .worlds
+            .insert("arena", World::with_gen_and_path(generator.clone(), &dir));
+        game.worlds
+            .get_mut(world)
+            .unwrap()
+            .queue_chunk_load(LoadRequest {
+                pos: ChunkPosition::new(0, 0),
+            });
+
+        // The region worker finds no saved chunk, so generation starts.
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while started.try_recv().is_err() {
+            assert!(Instant::now() < deadline, "generation never started");
+            game.worlds
+                .get_mut(world)
+                .unwrap()
+                .load_chunks(&mut game.ecs)
+                .unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        game.unload_world(world).unwrap();
+        drop(release);
+
+        // The generation task finishes after its world is gone.
+        while Arc::strong_count(&generator) > 1 {
+            assert!(Instant::now() < deadline, "generation never finished");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(game.worlds.get(world).is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
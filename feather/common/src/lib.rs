@@ -25,6 +25,12 @@ mod region_worker;
 pub mod world;
 pub use world::World;
 
+pub mod world_border;
+pub use world_border::WorldBorder;
+
+mod worlds;
+pub use worlds::Worlds;
+
 pub mod chat;
 pub use chat::ChatBox;
 
@@ -32,10 +38,26 @@ pub mod entities;
 
 pub mod interactable;
 
+pub mod scheduler;
+pub use scheduler::Scheduler;
+
+pub mod access;
+pub use access::AccessLists;
+
+pub mod permissions;
+pub use permissions::Permissions;
+
 /// Registers gameplay systems with the given `Game` and `SystemExecutor`.
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    if game.resources.get::<Permissions>().is_err() {
+        game.insert_resource(Permissions::in_memory());
+    }
+
+    scheduler::register(systems);
     view::register(game, systems);
+    world_border::register(systems);
     chunk::loading::register(game, systems);
+    chunk::pregen::register(systems);
     chunk::entities::register(systems);
     interactable::register(game);
 
@@ -0,0 +1,420 @@
+//! Permission nodes, groups and per-player overrides.
+//!
+//! A permission node is a dot-separated string such as
+//! `feather.build`. Nodes are granted or revoked in
+//! `permissions.toml`:
+//!
+//! ```toml
+//! [groups.default]
+//! permissions = { "feather.build" = true }
+//!
+//! [groups.moderator]
+//! inherits = ["default"]
+//! permissions = { "feather.command_block" = true, "worldedit.*" = true }
+//!
+//! [players.905c7e4f-b96b-4513-9645-d123225575e2]
+//! groups = ["moderator"]
+//! permissions = { "worldedit.*" = false }
+//! ```
+//!
+//! A node is resolved by checking, in order, the player's own
+//! permissions, their groups (each before the groups it inherits
+//! from), and the `default` group. The first match wins; within one
+//! list, an exact node beats `a.b.*`, which beats `a.*` and `*`.
+//! If nothing matches, the node is granted to operators whose level
+//! is at least the node's default level.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ahash::{AHashMap, AHashSet};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const PERMISSIONS_FILE: &str = "permissions.toml";
+
+/// The group every player belongs to.
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Operator level required for nodes without a registered default.
+pub const DEFAULT_LEVEL: u8 = 4;
+
+/// Nodes checked by Feather itself.
+pub mod nodes {
+    /// Placing and breaking blocks.
+    pub const BUILD: &str = "feather.build";
+    /// Editing command blocks and command block minecarts.
+    pub const COMMAND_BLOCK: &str = "feather.command_block";
+    /// Querying entity NBT with the debug screen.
+    pub const QUERY_NBT: &str = "feather.query_nbt";
+
+    /// The built-in nodes and their default operator levels.
+    pub(super) const DEFAULTS: &[(&str, u8)] = &[(BUILD, 0), (COMMAND_BLOCK, 2), (QUERY_NBT, 2)];
+}
+
+/// A permission group.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    /// Groups whose permissions this group inherits.
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    /// Nodes granted (`true`) or revoked (`false`) by this group.
+    #[serde(default)]
+    pub permissions: BTreeMap<String, bool>,
+}
+
+/// The groups and overrides of a single player.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerPermissions {
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Nodes granted or revoked for this player,
+    /// overriding their groups.
+    #[serde(default)]
+    pub permissions: BTreeMap<String, bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PermissionsFile {
+    #[serde(default)]
+    groups: BTreeMap<String, Group>,
+    #[serde(default)]
+    players: BTreeMap<Uuid, PlayerPermissions>,
+}
+
+/// The permissions of all players. Stored as a resource in the `Game`.
+///
+/// Use [`Game::has_permission`](crate::Game::has_permission) to check
+/// whether a player entity has a permission. Changes are saved to disk
+/// immediately.
+#[derive(Debug)]
+pub struct Permissions {
+    /// Path to the permissions file, or `None` if the
+    /// permissions are only kept in memory.
+    path: Option<PathBuf>,
+    file: PermissionsFile,
+    /// Operator level required for each node that
+    /// isn't granted or revoked explicitly.
+    default_levels: AHashMap<String, u8>,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl Permissions {
+    /// Loads the permissions file at `path`,
+    /// creating an empty one if it doesn't exist.
+    pub fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let mut permissions = Self {
+            path: Some(path.into()),
+            ..Self::in_memory()
+        };
+        permissions.reload()?;
+        Ok(permissions)
+    }
+
+    /// Creates empty permissions that are never saved to disk.
+    pub fn in_memory() -> Self {
+        let default_levels = nodes::DEFAULTS
+            .iter()
+            .map(|&(node, level)| (node.to_owned(), level))
+            .collect();
+        Self {
+            path: None,
+            file: PermissionsFile::default(),
+            default_levels,
+        }
+    }
+
+    /// Reloads the groups and players from disk.
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if !path.exists() {
+            self.file = PermissionsFile::default();
+            return self.save();
+        }
+
+        let toml = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        self.file =
+            toml::from_str(&toml).with_context(|| format!("malformed {}", path.display()))?;
+        Ok(())
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        match &self.path {
+            Some(path) => save(path, &self.file),
+            None => Ok(()),
+        }
+    }
+
+    /// Sets the operator level needed for `node` when
+    /// it isn't granted or revoked explicitly. A level
+    /// of 0 grants the node to all players by default.
+    ///
+    /// Nodes without a default need level [`DEFAULT_LEVEL`].
+    pub fn register_default(&mut self, node: impl Into<String>, level: u8) {
+        self.default_levels.insert(node.into(), level);
+    }
+
+    /// Gets the operator level needed for `node` by default.
+    pub fn default_level(&self, node: &str) -> u8 {
+        self.default_levels
+            .get(node)
+            .copied()
+            .unwrap_or(DEFAULT_LEVEL)
+    }
+
+    /// Determines whether the player with the given UUID
+    /// and operator level (0 if not an operator) has `node`.
+    pub fn has(&self, player: Uuid, op_level: u8, node: &str) -> bool {
+        self.resolve(player, node)
+            .unwrap_or_else(|| op_level >= self.default_level(node))
+    }
+
+    /// Resolves `node` from the player's permissions and groups,
+    /// ignoring defaults.
+    fn resolve(&self, player: Uuid, node: &str) -> Option<bool> {
+        let mut visited = AHashSet::new();
+        if let Some(player) = self.file.players.get(&player) {
+            if let Some(value) = lookup(&player.permissions, node) {
+                return Some(value);
+            }
+            for group in &player.groups {
+                if let Some(value) = self.resolve_group(group, node, &mut visited) {
+                    return Some(value);
+                }
+            }
+        }
+        self.resolve_group(DEFAULT_GROUP, node, &mut visited)
+    }
+
+    fn resolve_group<'a>(
+        &'a self,
+        name: &'a str,
+        node: &str,
+        visited: &mut AHashSet<&'a str>,
+    ) -> Option<bool> {
+        // Guards against inheritance cycles.
+        if !visited.insert(name) {
+            return None;
+        }
+        let group = self.file.groups.get(name)?;
+        lookup(&group.permissions, node).or_else(|| {
+            group
+                .inherits
+                .iter()
+                .find_map(|parent| self.resolve_group(parent, node, visited))
+        })
+    }
+}
+
+/// Groups and players
+impl Permissions {
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.file.groups.get(name)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (&str, &Group)> + '_ {
+        self.file
+            .groups
+            .iter()
+            .map(|(name, group)| (name.as_str(), group))
+    }
+
+    /// Creates or replaces a group.
+    pub fn set_group(&mut self, name: impl Into<String>, group: Group) -> anyhow::Result<()> {
+        self.file.groups.insert(name.into(), group);
+        self.save()
+    }
+
+    /// Removes a group. Returns `false` if it did not exist.
+    ///
+    /// Players and groups referring to the group are left unchanged.
+    pub fn remove_group(&mut self, name: &str) -> anyhow::Result<bool> {
+        if self.file.groups.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Grants (`Some(true)`), revokes (`Some(false)`) or unsets (`None`)
+    /// a node for a group, creating the group if needed.
+    pub fn set_group_permission(
+        &mut self,
+        group: &str,
+        node: impl Into<String>,
+        value: Option<bool>,
+    ) -> anyhow::Result<()> {
+        let group = self.file.groups.entry(group.to_owned()).or_default();
+        set(&mut group.permissions, node.into(), value);
+        self.save()
+    }
+
+    pub fn player(&self, player: Uuid) -> Option<&PlayerPermissions> {
+        self.file.players.get(&player)
+    }
+
+    /// Grants (`Some(true)`), revokes (`Some(false)`) or unsets (`None`)
+    /// a node for a single player.
+    pub fn set_player_permission(
+        &mut self,
+        player: Uuid,
+        node: impl Into<String>,
+        value: Option<bool>,
+    ) -> anyhow::Result<()> {
+        let player = self.file.players.entry(player).or_default();
+        set(&mut player.permissions, node.into(), value);
+        self.save()
+    }
+
+    /// Adds a player to a group. Returns `false`
+    /// if they were already in the group.
+    pub fn add_player_to_group(&mut self, player: Uuid, group: &str) -> anyhow::Result<bool> {
+        let player = self.file.players.entry(player).or_default();
+        if player.groups.iter().any(|g| g == group) {
+            return Ok(false);
+        }
+        player.groups.push(group.to_owned());
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Removes a player from a group. Returns `false`
+    /// if they were not in the group.
+    pub fn remove_player_from_group(&mut self, player: Uuid, group: &str) -> anyhow::Result<bool> {
+        let player = match self.file.players.get_mut(&player) {
+            Some(player) => player,
+            None => return Ok(false),
+        };
+        let len = player.groups.len();
+        player.groups.retain(|g| g != group);
+        if player.groups.len() == len {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+}
+
+/// Looks up the most specific entry matching `node`.
+fn lookup(permissions: &BTreeMap<String, bool>, node: &str) -> Option<bool> {
+    if let Some(&value) = permissions.get(node) {
+        return Some(value);
+    }
+
+    let mut prefix = node;
+    while let Some(i) = prefix.rfind('.') {
+        prefix = &prefix[..i];
+        if let Some(&value) = permissions.get(&format!("{}.*", prefix)) {
+            return Some(value);
+        }
+    }
+    permissions.get("*").copied()
+}
+
+fn set(permissions: &mut BTreeMap<String, bool>, node: String, value: Option<bool>) {
+    match value {
+        Some(value) => permissions.insert(node, value),
+        None => permissions.remove(&node),
+    };
+}
+
+fn save(path: &Path, file: &PermissionsFile) -> anyhow::Result<()> {
+    let toml = toml::to_string_pretty(file)?;
+    fs::write(path, toml).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Uuid {
+        Uuid::from_u128(0x905c7e4fb96b45139645d123225575e2)
+    }
+
+    fn permissions(toml: &str) -> Permissions {
+        Permissions {
+            file: toml::from_str(toml).unwrap(),
+            ..Permissions::in_memory()
+        }
+    }
+
+    #[test]
+    fn wildcards_and_specificity() {
+        let permissions = permissions(
+            r#"
+            [groups.default.permissions]
+            "worldedit.*" = true
+            "worldedit.region.*" = false
+            "worldedit.region.expand" = true
+            "#,
+        );
+        assert!(permissions.has(player(), 0, "worldedit.brush"));
+        assert!(!permissions.has(player(), 0, "worldedit.region.set"));
+        assert!(permissions.has(player(), 0, "worldedit.region.expand"));
+        assert!(!permissions.has(player(), 0, "homes.set"));
+    }
+
+    #[test]
+    fn groups_and_overrides() {
+        let permissions = permissions(
+            r#"
+            [groups.default]
+            permissions = { "feather.build" = false }
+
+            [groups.builder]
+            inherits = ["trusted"]
+            permissions = { "feather.build" = true }
+
+            [groups.trusted]
+            inherits = ["builder"]
+            permissions = { "homes.*" = true }
+
+            [players.905c7e4f-b96b-4513-9645-d123225575e2]
+            groups = ["builder"]
+            permissions = { "homes.delete" = false }
+            "#,
+        );
+        let other = Uuid::from_u128(1);
+
+        assert!(permissions.has(player(), 0, nodes::BUILD));
+        assert!(!permissions.has(other, 0, nodes::BUILD));
+
+        // Inherited through a cycle
+        assert!(permissions.has(player(), 0, "homes.set"));
+        assert!(!permissions.has(player(), 0, "homes.delete"));
+        assert!(!permissions.has(other, 0, "homes.set"));
+    }
+
+    #[test]
+    fn default_levels() {
+        let mut permissions = Permissions::in_memory();
+        assert!(permissions.has(player(), 0, nodes::BUILD));
+        assert!(!permissions.has(player(), 1, nodes::COMMAND_BLOCK));
+        assert!(permissions.has(player(), 2, nodes::COMMAND_BLOCK));
+        assert!(!permissions.has(player(), 1, nodes::QUERY_NBT));
+        assert!(permissions.has(player(), 2, nodes::QUERY_NBT));
+        assert!(!permissions.has(player(), 3, "homes.set"));
+        assert!(permissions.has(player(), 4, "homes.set"));
+
+        permissions.register_default("homes.set", 0);
+        assert!(permissions.has(player(), 0, "homes.set"));
+
+        permissions
+            .set_player_permission(player(), nodes::COMMAND_BLOCK, Some(false))
+            .unwrap();
+        assert!(!permissions.has(player(), 4, nodes::COMMAND_BLOCK));
+    }
+}
@@ -7,6 +7,7 @@ use std::{
 use ahash::AHashMap;
 use base::anvil::{
     self,
+    block_entity::BlockEntityVariant,
     region::{RegionHandle, RegionPosition},
 };
 use flume::{Receiver, Sender};
@@ -74,6 +75,9 @@ impl RegionWorker {
                 Ok(req) => match req {
                     WorkerRequest::Load(load) => self.load_chunk(load),
                     WorkerRequest::Save(save) => self.save_chunk(save).unwrap(),
+                    WorkerRequest::Flush(done) => {
+                        let _ = done.send(());
+                    }
                 },
                 Err(flume::RecvTimeoutError::Timeout) => (),
                 Err(flume::RecvTimeoutError::Disconnected) => {
@@ -119,10 +123,16 @@ impl RegionWorker {
         };
 
         let chunk = match file.handle.load_chunk(pos) {
-            Ok((chunk, _, _)) => chunk,
+            Ok((mut chunk, _, mut block_entities)) => {
+                // Unknown block entities can't be saved again.
+                block_entities
+                    .retain(|entity| entity.kind.variant() != BlockEntityVariant::Unknown);
+                *chunk.block_entities_mut() = block_entities;
+                chunk
+            }
             Err(e) => match e {
                 anvil::region::Error::ChunkNotExist => return ChunkLoadResult::Missing(pos),
-                err => return ChunkLoadResult::Error(err.into()),
+                err => return ChunkLoadResult::Error(pos, err.into()),
             },
         };
 
@@ -0,0 +1,257 @@
+//! Delayed and repeating tasks, scheduled relative to [`Game::tick_count`].
+
+use std::collections::BTreeSet;
+
+use ahash::AHashMap;
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Game;
+
+/// A function invoked by the [`Scheduler`].
+pub type TaskFn = Box<dyn FnMut(&mut Game) -> SysResult>;
+
+/// Unique ID of a scheduled task.
+///
+/// IDs are never reused, so a stale `TaskId`
+/// can't cancel an unrelated task.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    pub fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+}
+
+struct Task {
+    /// Number of ticks between runs, or `None`
+    /// if the task runs only once.
+    period: Option<u64>,
+    /// Set to `None` while the task is running.
+    function: Option<TaskFn>,
+}
+
+/// Runs tasks after a delay or periodically.
+///
+/// Tasks run at the start of the tick they are due. A task
+/// scheduled with a delay of `n` ticks during tick `t`
+/// runs during tick `t + n`, or on the next tick if `n` is zero.
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: AHashMap<TaskId, Task>,
+    /// Pending runs ordered by the tick they are due.
+    queue: BTreeSet<(u64, TaskId)>,
+    next_id: u64,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules `function` to run once, `delay` ticks after `now`.
+    pub fn run_later(
+        &mut self,
+        now: u64,
+        delay: u64,
+        function: impl FnMut(&mut Game) -> SysResult + 'static,
+    ) -> TaskId {
+        self.schedule(now + delay, None, Box::new(function))
+    }
+
+    /// Schedules `function` to run every `period` ticks,
+    /// starting `delay` ticks after `now`.
+    ///
+    /// A `period` of zero is treated as one.
+    pub fn run_repeating(
+        &mut self,
+        now: u64,
+        delay: u64,
+        period: u64,
+        function: impl FnMut(&mut Game) -> SysResult + 'static,
+    ) -> TaskId {
+        self.schedule(now + delay, Some(period.max(1)), Box::new(function))
+    }
+
+    fn schedule(&mut self, due: u64, period: Option<u64>, function: TaskFn) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+
+        self.tasks.insert(
+            id,
+            Task {
+                period,
+                function: Some(function),
+            },
+        );
+        self.queue.insert((due, id));
+        id
+    }
+
+    /// Cancels a task.
+    ///
+    /// Returns `false` if the task has already finished
+    /// or was cancelled before. A task may cancel itself
+    /// while it is running.
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        if self.tasks.remove(&id).is_none() {
+            return false;
+        }
+        self.queue.retain(|&(_, queued)| queued != id);
+        true
+    }
+
+    /// Determines whether the task is still scheduled to run.
+    pub fn is_scheduled(&self, id: TaskId) -> bool {
+        self.tasks.contains_key(&id)
+    }
+
+    /// Returns the number of scheduled tasks.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns whether no tasks are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Removes and returns all tasks due at or before `now`.
+    fn take_due(&mut self, now: u64) -> Vec<TaskId> {
+        let remaining = self.queue.split_off(&(now + 1, TaskId(0)));
+        let due = std::mem::replace(&mut self.queue, remaining);
+        due.into_iter().map(|(_, id)| id).collect()
+    }
+
+    fn take_function(&mut self, id: TaskId) -> Option<TaskFn> {
+        self.tasks.get_mut(&id)?.function.take()
+    }
+
+    /// Called after a task has run. Requeues
+    /// repeating tasks and drops finished ones.
+    fn finish(&mut self, id: TaskId, now: u64, function: TaskFn) {
+        let task = match self.tasks.get_mut(&id) {
+            Some(task) => task,
+            // Cancelled while running
+            None => return,
+        };
+
+        match task.period {
+            Some(period) => {
+                task.function = Some(function);
+                self.queue.insert((now + period, id));
+            }
+            None => {
+                self.tasks.remove(&id);
+            }
+        }
+    }
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(run_tasks);
+}
+
+/// Runs all tasks that are due on the current tick.
+fn run_tasks(game: &mut Game) -> SysResult {
+    let now = game.tick_count;
+    for id in game.scheduler.take_due(now) {
+        let mut function = match game.scheduler.take_function(id) {
+            Some(function) => function,
+            None => continue,
+        };
+
+        if let Err(e) = function(game) {
+            log::error!("Scheduled task {:?} returned an error: {:?}", id, e);
+        }
+
+        game.scheduler.finish(id, now, function);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    fn tick(game: &mut Game) {
+        run_tasks(game).unwrap();
+        game.tick_count += 1;
+    }
+
+    fn counter() -> (Rc<RefCell<u32>>, impl FnMut(&mut Game) -> SysResult) {
+        let count = Rc::new(RefCell::new(0));
+        let count2 = Rc::clone(&count);
+        (count, move |_: &mut Game| {
+            *count2.borrow_mut() += 1;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn run_later_runs_once() {
+        let mut game = Game::new();
+        let (count, task) = counter();
+        game.run_later(2, task);
+
+        tick(&mut game);
+        tick(&mut game);
+        assert_eq!(*count.borrow(), 0);
+        tick(&mut game);
+        assert_eq!(*count.borrow(), 1);
+        tick(&mut game);
+        assert_eq!(*count.borrow(), 1);
+        assert!(game.scheduler.is_empty());
+    }
+
+    #[test]
+    fn run_repeating() {
+        let mut game = Game::new();
+        let (count, task) = counter();
+        game.run_repeating(0, 3, task);
+
+        for _ in 0..7 {
+            tick(&mut game);
+        }
+        // Runs on ticks 0, 3, and 6
+        assert_eq!(*count.borrow(), 3);
+    }
+
+    #[test]
+    fn cancel() {
+        let mut game = Game::new();
+        let (count, task) = counter();
+        let id = game.run_repeating(1, 1, task);
+
+        tick(&mut game);
+        tick(&mut game);
+        assert!(game.scheduler.cancel(id));
+        assert!(!game.scheduler.cancel(id));
+        tick(&mut game);
+        assert_eq!(*count.borrow(), 1);
+        assert!(!game.scheduler.is_scheduled(id));
+    }
+
+    #[test]
+    fn task_cancels_itself() {
+        let mut game = Game::new();
+        let id = Rc::new(RefCell::new(None));
+        let id2 = Rc::clone(&id);
+        let task = game.run_repeating(0, 1, move |game| {
+            let id = id2.borrow().unwrap();
+            game.scheduler.cancel(id);
+            Ok(())
+        });
+        *id.borrow_mut() = Some(task);
+
+        tick(&mut game);
+        assert!(!game.scheduler.is_scheduled(task));
+        tick(&mut game);
+    }
+}
@@ -2,7 +2,7 @@ use ahash::AHashSet;
 use base::{ChunkPosition, Position};
 use ecs::{SysResult, SystemExecutor};
 use itertools::Either;
-use quill_common::components::Name;
+use quill_common::components::{Name, WorldId};
 use quill_common::events::PlayerJoinEvent;
 
 use crate::{events::ViewUpdateEvent, Game};
@@ -22,7 +22,10 @@ fn update_player_views(game: &mut Game) -> SysResult {
     {
         if position.chunk() != view.center() {
             let old_view = *view;
-            let new_view = View::new(position.chunk(), old_view.view_distance);
+            let new_view = View {
+                center: position.chunk(),
+                ..old_view
+            };
 
             let event = ViewUpdateEvent::new(old_view, new_view);
             events.push((player, event));
@@ -53,20 +56,27 @@ fn update_view_on_join(game: &mut Game) -> SysResult {
 }
 
 /// The view of a player, representing the set of chunks
-/// within their view distance.
+/// within their view distance in their world.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct View {
     center: ChunkPosition,
     view_distance: u32,
+    world: WorldId,
 }
 
 impl View {
-    /// Creates a `View` from a center chunk (the position of the player)
-    /// and the view distance.
+    /// Creates a `View` in the default world from a center chunk
+    /// (the position of the player) and the view distance.
     pub fn new(center: ChunkPosition, view_distance: u32) -> Self {
+        Self::in_world(center, view_distance, WorldId::DEFAULT)
+    }
+
+    /// Creates a `View` in the given world.
+    pub fn in_world(center: ChunkPosition, view_distance: u32, world: WorldId) -> Self {
         Self {
             center,
             view_distance,
+            world,
         }
     }
 
@@ -88,6 +98,10 @@ impl View {
         self.view_distance
     }
 
+    pub fn world(&self) -> WorldId {
+        self.world
+    }
+
     pub fn set_center(&mut self, center: ChunkPosition) {
         self.center = center;
     }
@@ -111,10 +125,17 @@ impl View {
     }
 
     /// Returns the set of chunks that are in `self` but not in `other`.
+    ///
+    /// If the views are in different worlds, this
+    /// contains all chunks in `self`.
     pub fn difference(self, other: View) -> impl Iterator<Item = ChunkPosition> {
         // PERF: consider analytical approach instead of sets
         let self_chunks: AHashSet<_> = self.iter().collect();
-        let other_chunks: AHashSet<_> = other.iter().collect();
+        let other_chunks: AHashSet<_> = if self.world == other.world {
+            other.iter().collect()
+        } else {
+            AHashSet::new()
+        };
         self_chunks
             .difference(&other_chunks)
             .copied()
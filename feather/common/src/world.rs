@@ -6,22 +6,28 @@ use uuid::Uuid;
 
 use base::anvil::player::PlayerData;
 use base::{
-    BlockPosition, Chunk, ChunkHandle, ChunkLock, ChunkPosition, ValidBlockPosition, CHUNK_HEIGHT,
+    BlockPosition, Chunk, ChunkHandle, ChunkLock, ChunkPosition, Dimension, ValidBlockPosition,
+    CHUNK_HEIGHT,
 };
 use blocks::BlockId;
 use ecs::{Ecs, SysResult};
+use quill_common::components::WorldId;
 use worldgen::{ComposableGenerator, WorldGenerator};
 
 use crate::{
     chunk::cache::ChunkCache,
+    chunk::pregen::Pregeneration,
     chunk::worker::{ChunkWorker, LoadRequest, SaveRequest},
     events::ChunkLoadEvent,
+    world_border::{self, WorldBorder},
 };
 
 /// Stores all blocks and chunks in a world,
 /// along with global world data like weather, time,
 /// and the [`WorldSource`](crate::world_source::WorldSource).
 ///
+/// A server can host several worlds; see [`Worlds`](crate::Worlds).
+///
 /// NB: _not_ what most Rust ECSs call "world."
 /// This does not store entities; it only contains blocks.
 pub struct World {
@@ -31,6 +37,10 @@ pub struct World {
     loading_chunks: AHashSet<ChunkPosition>,
     canceled_chunk_loads: AHashSet<ChunkPosition>,
     world_dir: PathBuf,
+    dimension: Dimension,
+    id: WorldId,
+    border: WorldBorder,
+    pregeneration: Option<Pregeneration>,
 }
 
 impl Default for World {
@@ -45,6 +55,10 @@ impl Default for World {
             loading_chunks: AHashSet::new(),
             canceled_chunk_loads: AHashSet::new(),
             world_dir: "world".into(),
+            dimension: Dimension::Overworld,
+            id: WorldId::DEFAULT,
+            border: WorldBorder::default(),
+            pregeneration: None,
         }
     }
 }
@@ -65,6 +79,108 @@ impl World {
         }
     }
 
+    /// Creates a world of the given dimension type saved in `world_dir`.
+    ///
+    /// Like in vanilla, chunks are stored in the dimension's directory,
+    /// e.g. `DIM-1` for the nether, while player data stays in `world_dir`.
+    pub fn with_dimension(
+        dimension: Dimension,
+        generator: Arc<dyn WorldGenerator>,
+        world_dir: impl Into<PathBuf>,
+    ) -> Self {
+        let world_dir = world_dir.into();
+        Self {
+            chunk_worker: ChunkWorker::new(world_dir.join(dimension.directory()), generator),
+            world_dir,
+            dimension,
+            ..Default::default()
+        }
+    }
+
+    /// Gets the dimension type of this world.
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    /// Gets the ID of this world, assigned
+    /// when it is inserted into [`Worlds`](crate::Worlds).
+    pub fn id(&self) -> WorldId {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: WorldId) {
+        self.id = id;
+    }
+
+    /// Gets the border of this world.
+    pub fn border(&self) -> &WorldBorder {
+        &self.border
+    }
+
+    /// Mutably gets the border of this world.
+    ///
+    /// NB: use [`Game::update_world_border`](crate::Game::update_world_border)
+    /// to change the border of a hosted world, so that clients are notified.
+    pub fn border_mut(&mut self) -> &mut WorldBorder {
+        &mut self.border
+    }
+
+    /// Starts pre-generating the chunks inside the border,
+    /// resuming an earlier pre-generation if there is one.
+    ///
+    /// Fails if the border has not been made smaller than the default.
+    pub fn start_pregeneration(&mut self) -> anyhow::Result<()> {
+        if self.border.radius().max(self.border.target_radius()) >= world_border::MAX_RADIUS {
+            anyhow::bail!("the world border must be set to pre-generate a world");
+        }
+        self.pregeneration = Some(Pregeneration::new(&self.world_dir, self.border));
+        Ok(())
+    }
+
+    /// Gets the running pre-generation, if any.
+    pub fn pregeneration(&self) -> Option<&Pregeneration> {
+        self.pregeneration.as_ref()
+    }
+
+    /// Requests chunks for the running pre-generation,
+    /// stopping it once all chunks have been generated.
+    pub(crate) fn advance_pregeneration(&mut self) {
+        let pregeneration = match &mut self.pregeneration {
+            Some(pregeneration) => pregeneration,
+            None => return,
+        };
+        while let Some(pos) = pregeneration.next_chunk() {
+            if self.chunk_map.0.contains_key(&pos) || self.loading_chunks.contains(&pos) {
+                // Chunks loaded for players are generated anyway.
+                pregeneration.on_generated(pos);
+            } else {
+                self.chunk_worker.queue_load(LoadRequest { pos });
+            }
+        }
+
+        if pregeneration.report() {
+            // Chunks counted as done must be on disk before
+            // the progress says so, along with their neighbours.
+            self.chunk_worker.wait_for_saves();
+            if let Err(e) = pregeneration.save_progress() {
+                log::warn!("Failed to save pre-generation progress: {:?}", e);
+            }
+        }
+
+        if pregeneration.is_finished() {
+            if let Err(e) = pregeneration.save_progress() {
+                log::warn!("Failed to remove pre-generation progress: {:?}", e);
+            }
+            log::info!("Finished pre-generating {}", self.world_dir.display());
+            self.pregeneration = None;
+        }
+    }
+
+    /// Blocks until all chunks queued for saving have been written.
+    pub fn wait_for_saves(&self) {
+        self.chunk_worker.wait_for_saves();
+    }
+
     /// Queues the given chunk to be loaded. If the chunk was cached, it is loaded immediately.
     pub fn queue_chunk_load(&mut self, req: LoadRequest) {
         let pos = req.pos;
@@ -84,8 +200,14 @@ impl World {
     /// after a call to [`World::queue_chunk_load`].
     pub fn load_chunks(&mut self, ecs: &mut Ecs) -> SysResult {
         while let Some(loaded) = self.chunk_worker.poll_loaded_chunk()? {
-            self.loading_chunks.remove(&loaded.pos);
-            if self.canceled_chunk_loads.remove(&loaded.pos) {
+            let requested = self.loading_chunks.remove(&loaded.pos);
+            if let Some(pregeneration) = &mut self.pregeneration {
+                pregeneration.on_generated(loaded.pos);
+            }
+            if self.canceled_chunk_loads.remove(&loaded.pos) || !requested {
+                // The chunk worker saves chunks it generates, so chunks
+                // no longer needed and chunks only requested by a
+                // pre-generation are not loaded into the world.
                 continue;
             }
             let chunk = loaded.chunk;
@@ -94,6 +216,7 @@ impl World {
             ecs.insert_event(ChunkLoadEvent {
                 chunk: Arc::clone(&self.chunk_map.0[&loaded.pos]),
                 position: loaded.pos,
+                world: self.id,
             });
             log::trace!("Loaded chunk {:?}", loaded.pos);
         }
@@ -232,6 +355,11 @@ impl ChunkMap {
         self.0.values()
     }
 
+    /// Returns an iterator over the positions of loaded chunks.
+    pub fn iter_positions(&self) -> impl Iterator<Item = ChunkPosition> + '_ {
+        self.0.keys().copied()
+    }
+
     /// Inserts a new chunk into the chunk map.
     pub fn insert_chunk(&mut self, chunk: Chunk) {
         self.0
@@ -0,0 +1,209 @@
+//! The world border, a square around a center
+//! which players cannot leave.
+//!
+//! Like in vanilla, the border may move between two sizes over
+//! time. Clients animate the movement themselves, so they are
+//! only notified when it starts.
+
+use base::ChunkPosition;
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Game;
+
+/// The largest radius of a world border, which is
+/// also the radius of the default border.
+pub const MAX_RADIUS: f64 = 29_999_984.0;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(tick_world_borders);
+}
+
+/// The border of a [`World`](crate::World).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WorldBorder {
+    center_x: f64,
+    center_z: f64,
+    radius: f64,
+    lerp: Option<Lerp>,
+    /// Players closer than this many blocks to
+    /// the border see a warning.
+    pub warning_blocks: u32,
+    /// Players see a warning if a shrinking border reaches
+    /// them in less than this many seconds.
+    pub warning_time: u32,
+}
+
+/// A border moving to another radius.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Lerp {
+    from: f64,
+    to: f64,
+    elapsed_ticks: u64,
+    total_ticks: u64,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, MAX_RADIUS)
+    }
+}
+
+impl WorldBorder {
+    /// Creates a border of the given radius around a center.
+    pub fn new(center_x: f64, center_z: f64, radius: f64) -> Self {
+        Self {
+            center_x,
+            center_z,
+            radius: clamp_radius(radius),
+            lerp: None,
+            warning_blocks: 5,
+            warning_time: 15,
+        }
+    }
+
+    /// Gets the `(x, z)` coordinates of the center.
+    pub fn center(&self) -> (f64, f64) {
+        (self.center_x, self.center_z)
+    }
+
+    pub fn set_center(&mut self, x: f64, z: f64) {
+        self.center_x = x;
+        self.center_z = z;
+    }
+
+    /// Gets the current distance from the center to each side.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Gets the radius the border is moving to,
+    /// or the current radius if it is not moving.
+    pub fn target_radius(&self) -> f64 {
+        self.lerp.map_or(self.radius, |lerp| lerp.to)
+    }
+
+    /// Gets the number of ticks until the border stops moving.
+    pub fn remaining_ticks(&self) -> u64 {
+        self.lerp
+            .map_or(0, |lerp| lerp.total_ticks - lerp.elapsed_ticks)
+    }
+
+    /// Sets the radius, stopping the border if it is moving.
+    pub fn set_radius(&mut self, radius: f64) {
+        self.radius = clamp_radius(radius);
+        self.lerp = None;
+    }
+
+    /// Moves the border from its current radius
+    /// to `radius` over the given number of ticks.
+    pub fn lerp_radius(&mut self, radius: f64, ticks: u64) {
+        if ticks == 0 {
+            self.set_radius(radius);
+            return;
+        }
+        self.lerp = Some(Lerp {
+            from: self.radius,
+            to: clamp_radius(radius),
+            elapsed_ticks: 0,
+            total_ticks: ticks,
+        });
+    }
+
+    /// Advances a moving border by one tick.
+    pub fn tick(&mut self) {
+        if let Some(lerp) = &mut self.lerp {
+            lerp.elapsed_ticks += 1;
+            if lerp.elapsed_ticks >= lerp.total_ticks {
+                self.radius = lerp.to;
+                self.lerp = None;
+            } else {
+                let progress = lerp.elapsed_ticks as f64 / lerp.total_ticks as f64;
+                self.radius = lerp.from + (lerp.to - lerp.from) * progress;
+            }
+        }
+    }
+
+    /// Returns whether the given block coordinates are inside the border.
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        (x - self.center_x).abs() <= self.radius && (z - self.center_z).abs() <= self.radius
+    }
+
+    /// Moves the given coordinates onto the border
+    /// if they are outside of it.
+    pub fn clamp(&self, x: f64, z: f64) -> (f64, f64) {
+        (
+            x.max(self.center_x - self.radius)
+                .min(self.center_x + self.radius),
+            z.max(self.center_z - self.radius)
+                .min(self.center_z + self.radius),
+        )
+    }
+
+    /// Returns whether any part of the chunk is inside the
+    /// border, or will be once the border stops moving.
+    pub fn contains_chunk(&self, pos: ChunkPosition) -> bool {
+        let radius = self.radius.max(self.target_radius());
+        let (min_x, min_z) = (pos.x as f64 * 16.0, pos.z as f64 * 16.0);
+        min_x < self.center_x + radius
+            && min_x + 16.0 > self.center_x - radius
+            && min_z < self.center_z + radius
+            && min_z + 16.0 > self.center_z - radius
+    }
+}
+
+fn clamp_radius(radius: f64) -> f64 {
+    radius.max(0.5).min(MAX_RADIUS)
+}
+
+/// System to move the borders of all worlds.
+fn tick_world_borders(game: &mut Game) -> SysResult {
+    for (_, world) in game.worlds.iter_mut() {
+        world.border_mut().tick();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn border_contains_and_clamps() {
+        let border = WorldBorder::new(100.0, -50.0, 20.0);
+        assert!(border.contains(100.0, -50.0));
+        assert!(border.contains(120.0, -70.0));
+        assert!(!border.contains(120.5, -50.0));
+        assert_eq!(border.clamp(130.0, -100.0), (120.0, -70.0));
+        assert_eq!(border.clamp(110.0, -60.0), (110.0, -60.0));
+
+        // Blocks 80 to 120 are in chunks 5 to 7.
+        assert!(border.contains_chunk(ChunkPosition::new(5, -4)));
+        assert!(border.contains_chunk(ChunkPosition::new(7, -5)));
+        assert!(!border.contains_chunk(ChunkPosition::new(4, -4)));
+        assert!(!border.contains_chunk(ChunkPosition::new(8, -4)));
+        assert!(!border.contains_chunk(ChunkPosition::new(6, -1)));
+    }
+
+    #[test]
+    fn border_lerps_to_target_radius() {
+        let mut border = WorldBorder::new(0.0, 0.0, 100.0);
+        border.lerp_radius(50.0, 10);
+        assert_eq!(border.target_radius(), 50.0);
+        assert_eq!(border.remaining_ticks(), 10);
+        // A shrinking border still loads the chunks it is leaving.
+        assert!(border.contains_chunk(ChunkPosition::new(6, 0)));
+
+        for _ in 0..5 {
+            border.tick();
+        }
+        assert_eq!(border.radius(), 75.0);
+        assert_eq!(border.remaining_ticks(), 5);
+
+        for _ in 0..5 {
+            border.tick();
+        }
+        assert_eq!(border.radius(), 50.0);
+        assert_eq!(border.remaining_ticks(), 0);
+        assert!(!border.contains_chunk(ChunkPosition::new(6, 0)));
+    }
+}
@@ -0,0 +1,162 @@
+use ahash::AHashMap;
+use quill_common::components::WorldId;
+
+use crate::World;
+
+/// The worlds hosted by a `Game`, keyed by [`WorldId`].
+///
+/// Each world has a unique name, which is also the
+/// name of its directory. Names are compared ignoring
+/// ASCII case, since clients see them lowercased. The default world, which
+/// players join, is always present.
+pub struct Worlds {
+    worlds: AHashMap<WorldId, World>,
+    names: AHashMap<WorldId, String>,
+    next_id: u32,
+}
+
+impl Default for Worlds {
+    fn default() -> Self {
+        Self::new("world", World::new())
+    }
+}
+
+impl Worlds {
+    /// Creates a `Worlds` hosting only the given default world.
+    pub fn new(name: impl Into<String>, default_world: World) -> Self {
+        let mut worlds = Self {
+            worlds: AHashMap::new(),
+            names: AHashMap::new(),
+            next_id: WorldId::DEFAULT.0,
+        };
+        worlds.insert(name, default_world);
+        worlds
+    }
+
+    /// Hosts a world, returning its ID.
+    ///
+    /// IDs are never reused, even after a world is removed.
+    ///
+    /// # Panics
+    /// Panics if a world with the same name, ignoring case,
+    /// is already hosted.
+    pub fn insert(&mut self, name: impl Into<String>, mut world: World) -> WorldId {
+        let name = name.into();
+        assert!(
+            self.id_ignore_case(&name).is_none(),
+            "world {:?} is already hosted",
+            name
+        );
+
+        let id = WorldId(self.next_id);
+        self.next_id += 1;
+        world.set_id(id);
+        self.worlds.insert(id, world);
+        self.names.insert(id, name);
+        id
+    }
+
+    /// Stops hosting a world, returning it.
+    ///
+    /// # Panics
+    /// Panics if `id` is the default world.
+    pub fn remove(&mut self, id: WorldId) -> Option<World> {
+        assert_ne!(id, WorldId::DEFAULT, "cannot remove the default world");
+        self.names.remove(&id);
+        self.worlds.remove(&id)
+    }
+
+    /// Gets the ID of the world with the given name.
+    pub fn id(&self, name: &str) -> Option<WorldId> {
+        self.names
+            .iter()
+            .find(|(_, world_name)| *world_name == name)
+            .map(|(&id, _)| id)
+    }
+
+    /// Gets the ID of the world whose name equals `name`
+    /// ignoring ASCII case.
+    pub fn id_ignore_case(&self, name: &str) -> Option<WorldId> {
+        self.names
+            .iter()
+            .find(|(_, world_name)| world_name.eq_ignore_ascii_case(name))
+            .map(|(&id, _)| id)
+    }
+
+    /// Gets the name of a world.
+    pub fn name(&self, id: WorldId) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    pub fn contains(&self, id: WorldId) -> bool {
+        self.worlds.contains_key(&id)
+    }
+
+    pub fn get(&self, id: WorldId) -> Option<&World> {
+        self.worlds.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: WorldId) -> Option<&mut World> {
+        self.worlds.get_mut(&id)
+    }
+
+    pub fn default_world(&self) -> &World {
+        &self.worlds[&WorldId::DEFAULT]
+    }
+
+    pub fn default_world_mut(&mut self) -> &mut World {
+        self.worlds.get_mut(&WorldId::DEFAULT).unwrap()
+    }
+
+    /// Iterates over the IDs of the hosted worlds.
+    pub fn ids(&self) -> impl Iterator<Item = WorldId> + '_ {
+        self.worlds.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (WorldId, &World)> {
+        self.worlds.iter().map(|(&id, world)| (id, world))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (WorldId, &mut World)> {
+        self.worlds.iter_mut().map(|(&id, world)| (id, world))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base::Dimension;
+    use worldgen::VoidWorldGenerator;
+
+    use super::*;
+
+    #[test]
+    fn insert_and_remove_worlds() {
+        let mut worlds = Worlds::default();
+        assert!(worlds.contains(WorldId::DEFAULT));
+        assert_eq!(worlds.id("world"), Some(WorldId::DEFAULT));
+
+        let arena =
+            World::with_dimension(Dimension::TheNether, Arc::new(VoidWorldGenerator), "arena");
+        let id = worlds.insert("arena", arena);
+        assert_ne!(id, WorldId::DEFAULT);
+        assert_eq!(worlds.id("arena"), Some(id));
+        assert_eq!(worlds.id("Arena"), None);
+        assert_eq!(worlds.id_ignore_case("Arena"), Some(id));
+        assert_eq!(worlds.name(id), Some("arena"));
+        assert_eq!(worlds.get(id).unwrap().dimension(), Dimension::TheNether);
+        assert_eq!(worlds.get(id).unwrap().id(), id);
+        assert_eq!(worlds.ids().count(), 2);
+
+        assert!(worlds.remove(id).is_some());
+        assert!(!worlds.contains(id));
+        assert_eq!(worlds.id("arena"), None);
+
+        let id2 = worlds.insert(
+            "arena",
+            World::with_gen_and_path(Arc::new(VoidWorldGenerator), "arena"),
+        );
+        assert_ne!(id2, id);
+    }
+}
@@ -109,9 +109,12 @@ impl Ecs {
     /// Creates an event not related to any entity. Use
     /// `insert_entity_event` for events regarding specific
     /// entities (`PlayerJoinEvent`, `EntityDamageEvent`, etc...)
-    pub fn insert_event<T: Component>(&mut self, event: T) {
+    ///
+    /// Returns the entity holding the event.
+    pub fn insert_event<T: Component>(&mut self, event: T) -> Entity {
         let entity = self.world.spawn((event,));
         self.event_tracker.insert_event(entity);
+        entity
     }
 
     /// Adds an event component to an entity and schedules
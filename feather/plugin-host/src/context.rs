@@ -4,16 +4,21 @@ use std::{
     marker::PhantomData,
     mem::size_of,
     panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
     ptr::NonNull,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
+use ahash::AHashSet;
 use anyhow::anyhow;
 use bytemuck::{Pod, Zeroable};
-use feather_common::Game;
+use feather_common::{scheduler::TaskId, Game};
 use feather_ecs::EntityBuilder;
 use quill_common::Component;
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 use vec_arena::Arena;
 use wasmer::{FromToNativeWasmType, Instance};
 
@@ -179,30 +184,47 @@ pub struct PluginContext {
     /// ID of the plugin.
     id: PluginId,
 
+    /// The plugin's data directory on the host filesystem.
+    data_dir: PathBuf,
+
     /// Active entity builders for the plugin.
     pub entity_builders: ThreadPinned<Arena<EntityBuilder>>,
+
+    /// IDs of tasks the plugin has scheduled.
+    pub scheduled_tasks: ThreadPinned<AHashSet<u64>>,
+
+    /// Data pointers of the plugin's tasks that have
+    /// been dropped by the scheduler and need to be
+    /// freed by the plugin.
+    pub dropped_tasks: Arc<Mutex<Vec<u64>>>,
 }
 
 impl PluginContext {
     /// Creates a new WASM plugin context.
-    pub fn new_wasm(id: PluginId) -> Self {
+    pub fn new_wasm(id: PluginId, data_dir: PathBuf) -> Self {
         Self {
             inner: Inner::Wasm(ThreadPinned::new(wasm::WasmPluginContext::new())),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            data_dir,
             entity_builders: ThreadPinned::new(Arena::new()),
+            scheduled_tasks: ThreadPinned::new(AHashSet::new()),
+            dropped_tasks: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
     /// Creates a new native plugin context.
-    pub fn new_native(id: PluginId) -> Self {
+    pub fn new_native(id: PluginId, data_dir: PathBuf) -> Self {
         Self {
             inner: Inner::Native(native::NativePluginContext::new()),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            data_dir,
             entity_builders: ThreadPinned::new(Arena::new()),
+            scheduled_tasks: ThreadPinned::new(AHashSet::new()),
+            dropped_tasks: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -261,6 +283,50 @@ impl PluginContext {
         self.id
     }
 
+    /// Takes the data pointers of tasks that have been dropped
+    /// by the scheduler, forgetting their task IDs.
+    ///
+    /// # Panics
+    /// Panics if the plugin is not currently being
+    /// invoked on the main thread.
+    pub fn take_dropped_tasks(&self) -> Vec<PluginPtrMut<u8>> {
+        let dropped = std::mem::take(&mut *self.dropped_tasks.lock().unwrap());
+        if dropped.is_empty() {
+            return Vec::new();
+        }
+
+        let game = self.game_mut();
+        self.scheduled_tasks
+            .borrow_mut()
+            .retain(|&task| game.scheduler.is_scheduled(TaskId::from_bits(task)));
+
+        dropped
+            .into_iter()
+            .map(|ptr| PluginPtrMut {
+                ptr,
+                _marker: PhantomData,
+            })
+            .collect()
+    }
+
+    /// Gets the plugin's data directory on the host filesystem.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Gets the path to the plugin's data directory
+    /// as seen from inside the plugin.
+    ///
+    /// WASM plugins have their data directory preopened
+    /// as the WASI root, so this is always `/`. Native plugins
+    /// access the host filesystem directly.
+    pub fn plugin_visible_data_dir(&self) -> String {
+        match &self.inner {
+            Inner::Wasm(_) => String::from("/"),
+            Inner::Native(_) => self.data_dir.to_string_lossy().into_owned(),
+        }
+    }
+
     /// Accesses a byte slice in the plugin's memory space.
     ///
     /// # Safety
@@ -418,6 +484,23 @@ impl PluginContext {
         unsafe { self.write_bytes(ptr.cast(), bytemuck::bytes_of(&value)) }
     }
 
+    /// Serializes `value` with `bincode` into the bump allocator,
+    /// then writes the resulting pointer and length.
+    pub fn write_bincode<T: Serialize>(
+        &self,
+        value: &T,
+        bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+        bytes_len_ptr: PluginPtrMut<u32>,
+    ) -> anyhow::Result<()> {
+        let bytes = bincode::serialize(value)?;
+        let bytes_ptr = self.bump_allocate_and_write_bytes(&bytes)?;
+
+        self.write_pod(bytes_ptr_ptr, bytes_ptr)?;
+        self.write_pod(bytes_len_ptr, bytes.len() as u32)?;
+
+        Ok(())
+    }
+
     /// Deallocates all bump-allocated memory.
     fn bump_reset(&self) {
         match &self.inner {
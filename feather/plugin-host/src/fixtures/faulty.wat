@@ -0,0 +1,36 @@
+;; A plugin whose only system faults every time it runs.
+;;
+;; The system calls `$trap`; tests swap in `$spin` or
+;; `$grow` to exceed the fuel or memory limit instead.
+(module
+  (import "quill_01" "register_system" (func $register_system (param i64 i64 i32)))
+
+  ;; 32 pages are 2 MiB.
+  (memory (export "memory") 32)
+
+  ;; The name of the system.
+  (data (i32.const 16) "fault")
+
+  (func (export "quill_allocate") (param i32 i32) (result i32)
+    (i32.const 1024))
+
+  (func (export "quill_deallocate") (param i32 i32 i32))
+
+  (func (export "quill_setup")
+    (call $register_system (i64.const 0) (i64.const 16) (i32.const 5)))
+
+  (func (export "quill_run_system") (param i32)
+    (call $trap))
+
+  (func $trap
+    unreachable)
+
+  (func $spin
+    (loop $spin
+      (br $spin)))
+
+  (func $grow
+    (loop $grow
+      (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+        (then unreachable))
+      (br $grow))))
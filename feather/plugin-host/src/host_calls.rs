@@ -8,14 +8,19 @@ use paste::paste;
 use crate::env::PluginEnv;
 use crate::host_function::{NativeHostFunction, WasmHostFunction};
 
+mod access;
 mod block;
+mod chunk;
 mod component;
 mod entity;
 mod entity_builder;
 mod event;
+mod plugin;
 mod plugin_message;
 mod query;
+mod scheduler;
 mod system;
+mod world;
 
 macro_rules! host_calls {
     (
@@ -46,14 +51,19 @@ macro_rules! host_calls {
     }
 }
 
+use access::*;
 use block::*;
+use chunk::*;
 use component::*;
 use entity::*;
 use entity_builder::*;
 use event::*;
+use plugin::*;
 use plugin_message::*;
 use query::*;
+use scheduler::*;
 use system::*;
+use world::*;
 
 host_calls! {
     "register_system" => register_system,
@@ -69,8 +79,31 @@ host_calls! {
     "entity_exists" => entity_exists,
     "entity_send_message" => entity_send_message,
     "entity_send_title" => entity_send_title,
+    "entity_has_permission" => entity_has_permission,
     "block_get" => block_get,
     "block_set" => block_set,
     "block_fill_chunk_section" => block_fill_chunk_section,
+    "chunk_is_loaded" => chunk_is_loaded,
+    "chunk_loaded_list" => chunk_loaded_list,
+    "chunk_section_get" => chunk_section_get,
+    "chunk_section_set" => chunk_section_set,
+    "chunk_section_light" => chunk_section_light,
+    "chunk_biomes" => chunk_biomes,
+    "chunk_heightmap" => chunk_heightmap,
+    "chunk_force_load" => chunk_force_load,
+    "chunk_release" => chunk_release,
     "plugin_message_send" => plugin_message_send,
+    "plugin_data_dir" => plugin_data_dir,
+    "scheduler_schedule" => scheduler_schedule,
+    "scheduler_cancel" => scheduler_cancel,
+    "scheduler_is_scheduled" => scheduler_is_scheduled,
+    "access_reload" => access_reload,
+    "access_whitelist_enabled" => access_whitelist_enabled,
+    "access_get" => access_get,
+    "access_update" => access_update,
+    "world_create" => world_create,
+    "world_load" => world_load,
+    "world_unload" => world_unload,
+    "world_find" => world_find,
+    "entity_teleport_to_world" => entity_teleport_to_world,
 }
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use feather_common::AccessLists;
+use feather_plugin_host_macros::host_function;
+use quill_common::access::{AccessListKind, AccessUpdate};
+
+use crate::context::{PluginContext, PluginPtr, PluginPtrMut};
+
+fn access_lists(cx: &PluginContext) -> anyhow::Result<Arc<AccessLists>> {
+    let game = cx.game_mut();
+    let access_lists = game.resources.get::<Arc<AccessLists>>()?;
+    Ok(Arc::clone(&*access_lists))
+}
+
+#[host_function]
+pub fn access_reload(cx: &PluginContext) -> anyhow::Result<u32> {
+    match access_lists(cx)?.reload() {
+        Ok(()) => Ok(true as u32),
+        Err(e) => {
+            log::error!("Failed to reload access lists: {:?}", e);
+            Ok(false as u32)
+        }
+    }
+}
+
+#[host_function]
+pub fn access_whitelist_enabled(cx: &PluginContext) -> anyhow::Result<u32> {
+    Ok(access_lists(cx)?.is_whitelist_enabled() as u32)
+}
+
+#[host_function]
+pub fn access_get(
+    cx: &PluginContext,
+    list: u32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let access_lists = access_lists(cx)?;
+    match AccessListKind::from_u32(list) {
+        Some(AccessListKind::Whitelist) => {
+            cx.write_bincode(&access_lists.whitelist(), bytes_ptr_ptr, bytes_len_ptr)
+        }
+        Some(AccessListKind::Ops) => {
+            cx.write_bincode(&access_lists.ops(), bytes_ptr_ptr, bytes_len_ptr)
+        }
+        Some(AccessListKind::BannedPlayers) => {
+            cx.write_bincode(&access_lists.banned_players(), bytes_ptr_ptr, bytes_len_ptr)
+        }
+        Some(AccessListKind::BannedIps) => {
+            cx.write_bincode(&access_lists.banned_ips(), bytes_ptr_ptr, bytes_len_ptr)
+        }
+        None => bail!("invalid access list {}", list),
+    }
+}
+
+#[host_function]
+pub fn access_update(
+    cx: &PluginContext,
+    bytes_ptr: PluginPtr<u8>,
+    bytes_len: u32,
+) -> anyhow::Result<u32> {
+    let update: AccessUpdate = cx.read_bincode(bytes_ptr, bytes_len)?;
+    match access_lists(cx)?.apply(update) {
+        Ok(changed) => Ok(changed as u32),
+        Err(e) => {
+            // The lists in memory were still updated.
+            log::error!("Failed to save access lists: {:?}", e);
+            Ok(true as u32)
+        }
+    }
+}
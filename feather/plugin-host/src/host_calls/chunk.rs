@@ -0,0 +1,284 @@
+use anyhow::bail;
+use feather_base::{
+    chunk::{
+        BlockStore, LightStore, PackedArray, Palette, GLOBAL_BITS_PER_BLOCK, MAX_BITS_PER_BLOCK,
+        MIN_BITS_PER_BLOCK, NUM_SECTIONS, SECTION_VOLUME,
+    },
+    BlockId, ChunkPosition, HIGHEST_ID,
+};
+use feather_plugin_host_macros::host_function;
+use quill_common::chunk::{ChunkSectionData, HeightmapKind, SectionLight};
+
+use crate::context::{PluginContext, PluginPtr, PluginPtrMut};
+
+#[host_function]
+pub fn chunk_is_loaded(cx: &PluginContext, chunk_x: i32, chunk_z: i32) -> anyhow::Result<u32> {
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    Ok(cx.game_mut().worlds.default_world().is_chunk_loaded(pos) as u32)
+}
+
+#[host_function]
+pub fn chunk_loaded_list(
+    cx: &PluginContext,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let positions: Vec<ChunkPosition> = cx
+        .game_mut()
+        .worlds
+        .default_world()
+        .chunk_map()
+        .iter_positions()
+        .collect();
+    cx.write_bincode(&positions, bytes_ptr_ptr, bytes_len_ptr)
+}
+
+#[host_function]
+pub fn chunk_section_get(
+    cx: &PluginContext,
+    chunk_x: i32,
+    section_y: u32,
+    chunk_z: i32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<u32> {
+    if section_y as usize >= NUM_SECTIONS {
+        return Ok(false as u32);
+    }
+
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let data = {
+        let game = cx.game_mut();
+        let chunk = match game.worlds.default_world().chunk_map().chunk_at(pos) {
+            Some(chunk) => chunk,
+            None => return Ok(false as u32),
+        };
+        match chunk.section(section_y as isize) {
+            Some(section) => section_to_data(section.blocks()),
+            None => ChunkSectionData::filled(BlockId::air().vanilla_id()),
+        }
+    };
+
+    cx.write_bincode(&data, bytes_ptr_ptr, bytes_len_ptr)?;
+    Ok(true as u32)
+}
+
+#[host_function]
+pub fn chunk_section_set(
+    cx: &PluginContext,
+    chunk_x: i32,
+    section_y: u32,
+    chunk_z: i32,
+    bytes_ptr: PluginPtr<u8>,
+    bytes_len: u32,
+) -> anyhow::Result<u32> {
+    let data: ChunkSectionData = cx.read_bincode(bytes_ptr, bytes_len)?;
+    if !data.is_valid() {
+        bail!("malformed chunk section data");
+    }
+
+    let blocks = data_to_block_store(&data)?;
+
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let was_successful = cx
+        .game_mut()
+        .set_chunk_section(pos, section_y as usize, blocks);
+    Ok(was_successful as u32)
+}
+
+#[host_function]
+pub fn chunk_section_light(
+    cx: &PluginContext,
+    chunk_x: i32,
+    section_y: u32,
+    chunk_z: i32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<u32> {
+    if section_y as usize >= NUM_SECTIONS {
+        return Ok(false as u32);
+    }
+
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let light = {
+        let game = cx.game_mut();
+        let chunk = match game.worlds.default_world().chunk_map().chunk_at(pos) {
+            Some(chunk) => chunk,
+            None => return Ok(false as u32),
+        };
+        match chunk.section(section_y as isize) {
+            Some(section) => light_to_data(section.light()),
+            None => light_to_data(&LightStore::new()),
+        }
+    };
+
+    cx.write_bincode(&light, bytes_ptr_ptr, bytes_len_ptr)?;
+    Ok(true as u32)
+}
+
+#[host_function]
+pub fn chunk_biomes(
+    cx: &PluginContext,
+    chunk_x: i32,
+    chunk_z: i32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<u32> {
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let biomes: Vec<u32> = {
+        let game = cx.game_mut();
+        let chunk = match game.worlds.default_world().chunk_map().chunk_at(pos) {
+            Some(chunk) => chunk,
+            None => return Ok(false as u32),
+        };
+        chunk
+            .biomes()
+            .as_slice()
+            .iter()
+            .map(|biome| biome.id())
+            .collect()
+    };
+
+    cx.write_bincode(&biomes, bytes_ptr_ptr, bytes_len_ptr)?;
+    Ok(true as u32)
+}
+
+#[host_function]
+pub fn chunk_heightmap(
+    cx: &PluginContext,
+    chunk_x: i32,
+    chunk_z: i32,
+    kind: u32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<u32> {
+    let kind = match HeightmapKind::from_u32(kind) {
+        Some(kind) => kind,
+        None => bail!("invalid heightmap kind {}", kind),
+    };
+
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let heights: Vec<u16> = {
+        let game = cx.game_mut();
+        let chunk = match game.worlds.default_world().chunk_map().chunk_at(pos) {
+            Some(chunk) => chunk,
+            None => return Ok(false as u32),
+        };
+        let heightmaps = chunk.heightmaps();
+        let height = |x, z| -> usize {
+            match kind {
+                HeightmapKind::MotionBlocking => heightmaps.motion_blocking.height(x, z),
+                HeightmapKind::MotionBlockingNoLeaves => {
+                    heightmaps.motion_blocking_no_leaves.height(x, z)
+                }
+                HeightmapKind::LightBlocking => heightmaps.light_blocking.height(x, z),
+                HeightmapKind::OceanFloor => heightmaps.ocean_floor.height(x, z),
+                HeightmapKind::WorldSurface => heightmaps.world_surface.height(x, z),
+            }
+            .unwrap_or_default()
+        };
+        // ZX order
+        (0..16)
+            .flat_map(|z| (0..16).map(move |x| (x, z)))
+            .map(|(x, z)| height(x, z) as u16)
+            .collect()
+    };
+
+    cx.write_bincode(&heights, bytes_ptr_ptr, bytes_len_ptr)?;
+    Ok(true as u32)
+}
+
+#[host_function]
+pub fn chunk_force_load(cx: &PluginContext, chunk_x: i32, chunk_z: i32) -> anyhow::Result<u32> {
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let owner = cx.plugin_id().force_load_owner();
+    Ok(cx.game_mut().force_load_chunk(pos, owner) as u32)
+}
+
+#[host_function]
+pub fn chunk_release(cx: &PluginContext, chunk_x: i32, chunk_z: i32) -> anyhow::Result<u32> {
+    let pos = ChunkPosition::new(chunk_x, chunk_z);
+    let owner = cx.plugin_id().force_load_owner();
+    Ok(cx.game_mut().release_chunk(pos, owner) as u32)
+}
+
+fn section_to_data(blocks: &BlockStore) -> ChunkSectionData {
+    let blocks: Vec<u16> = (0..SECTION_VOLUME)
+        .map(|i| {
+            let (x, y, z) = (i & 0xF, i >> 8, (i >> 4) & 0xF);
+            blocks
+                .block_at(x, y, z)
+                .unwrap_or_else(BlockId::air)
+                .vanilla_id()
+        })
+        .collect();
+    ChunkSectionData::from_blocks(&blocks)
+}
+
+fn data_to_block_store(data: &ChunkSectionData) -> anyhow::Result<BlockStore> {
+    if let Some(&id) = data.palette().iter().find(|&&id| id > HIGHEST_ID) {
+        bail!("invalid block state ID {} in chunk section data", id);
+    }
+
+    let mut palette = Palette::new();
+    let mapping: Vec<u64> = data
+        .palette()
+        .iter()
+        .map(|&id| palette.index_or_insert(BlockId::from_vanilla_id(id)) as u64)
+        .collect();
+
+    let bits_needed = 64 - (palette.len() as u64 - 1).leading_zeros() as u8;
+    if bits_needed > MAX_BITS_PER_BLOCK {
+        let blocks = PackedArray::from_iter(
+            data.palette_indices().map(|i| data.palette()[i] as u64),
+            GLOBAL_BITS_PER_BLOCK as usize,
+        );
+        return Ok(BlockStore::from_raw_parts(None, blocks));
+    }
+
+    let blocks = PackedArray::from_iter(
+        data.palette_indices().map(|i| mapping[i]),
+        bits_needed.max(MIN_BITS_PER_BLOCK) as usize,
+    );
+    Ok(BlockStore::from_raw_parts(Some(palette), blocks))
+}
+
+fn light_to_data(light: &LightStore) -> SectionLight {
+    SectionLight {
+        block_light: light.block_light().iter().map(|l| l as u8).collect(),
+        sky_light: light.sky_light().iter().map(|l| l as u8).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_data_round_trips() {
+        let blocks: Vec<u16> = (0..SECTION_VOLUME as u16)
+            .map(|i| {
+                if i % 3 == 0 {
+                    BlockId::stone().vanilla_id()
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let data = ChunkSectionData::from_blocks(&blocks);
+        let store = data_to_block_store(&data).unwrap();
+        assert_eq!(store.block_at(0, 0, 0), Some(BlockId::stone()));
+        assert_eq!(store.block_at(1, 0, 0), Some(BlockId::air()));
+        assert_eq!(section_to_data(&store).blocks().collect::<Vec<_>>(), blocks);
+    }
+
+    #[test]
+    fn invalid_block_ids_are_rejected() {
+        for &id in &[HIGHEST_ID + 1, u16::max_value()] {
+            let mut blocks = vec![BlockId::stone().vanilla_id(); SECTION_VOLUME];
+            blocks[100] = id;
+            let data = ChunkSectionData::from_blocks(&blocks);
+            assert!(data_to_block_store(&data).is_err());
+        }
+    }
+}
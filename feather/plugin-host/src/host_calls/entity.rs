@@ -25,6 +25,18 @@ pub fn entity_send_message(
     Ok(())
 }
 
+#[host_function]
+pub fn entity_has_permission(
+    cx: &PluginContext,
+    entity: u64,
+    node_ptr: PluginPtr<u8>,
+    node_len: u32,
+) -> anyhow::Result<u32> {
+    let node = cx.read_string(node_ptr, node_len)?;
+    let entity = Entity::from_bits(entity);
+    Ok(cx.game_mut().has_permission(entity, &node) as u32)
+}
+
 #[host_function]
 pub fn entity_send_title(
     cx: &PluginContext,
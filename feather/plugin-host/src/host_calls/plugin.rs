@@ -0,0 +1,18 @@
+use feather_plugin_host_macros::host_function;
+
+use crate::context::{PluginContext, PluginPtrMut};
+
+#[host_function]
+pub fn plugin_data_dir(
+    cx: &PluginContext,
+    path_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    path_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let path = cx.plugin_visible_data_dir();
+    let path_ptr = cx.bump_allocate_and_write_bytes(path.as_bytes())?;
+
+    cx.write_pod(path_ptr_ptr, path_ptr)?;
+    cx.write_pod(path_len_ptr, path.len() as u32)?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use feather_common::{scheduler::TaskId, Game};
+use feather_ecs::SysResult;
+use feather_plugin_host_macros::host_function;
+
+use crate::{
+    context::{PluginContext, PluginPtrMut},
+    PluginId, PluginManager,
+};
+
+#[host_function]
+pub fn scheduler_schedule(
+    cx: &PluginContext,
+    data_ptr: PluginPtrMut<u8>,
+    delay: u64,
+    period: u64,
+) -> anyhow::Result<u64> {
+    let task = PluginTask {
+        plugin: cx.plugin_id(),
+        data_ptr,
+        dropped_tasks: Arc::clone(&cx.dropped_tasks),
+    };
+    let function = move |game: &mut Game| task.run(game);
+
+    let mut game = cx.game_mut();
+    let id = if period == 0 {
+        game.run_later(delay, function)
+    } else {
+        game.run_repeating(delay, period, function)
+    };
+
+    cx.scheduled_tasks.borrow_mut().insert(id.to_bits());
+    Ok(id.to_bits())
+}
+
+#[host_function]
+pub fn scheduler_cancel(cx: &PluginContext, task: u64) -> anyhow::Result<u32> {
+    // Plugins may only cancel their own tasks.
+    if !cx.scheduled_tasks.borrow_mut().remove(&task) {
+        return Ok(false as u32);
+    }
+
+    let was_cancelled = cx.game_mut().scheduler.cancel(TaskId::from_bits(task));
+    Ok(was_cancelled as u32)
+}
+
+#[host_function]
+pub fn scheduler_is_scheduled(cx: &PluginContext, task: u64) -> anyhow::Result<u32> {
+    let is_scheduled = cx.scheduled_tasks.borrow().contains(&task)
+        && cx
+            .game_mut()
+            .scheduler
+            .is_scheduled(TaskId::from_bits(task));
+    Ok(is_scheduled as u32)
+}
+
+/// A task scheduled by a plugin.
+///
+/// The task data is owned by the plugin. When the scheduler
+/// drops the task, the data pointer is queued so the plugin
+/// can free it the next time it runs.
+struct PluginTask {
+    plugin: PluginId,
+    data_ptr: PluginPtrMut<u8>,
+    dropped_tasks: Arc<Mutex<Vec<u64>>>,
+}
+
+impl PluginTask {
+    fn run(&self, game: &mut Game) -> SysResult {
+        let plugin_manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+        let plugin_manager = plugin_manager.borrow();
+        if let Some(plugin) = plugin_manager.plugin(self.plugin) {
+            plugin.run_system(game, self.data_ptr)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PluginTask {
+    fn drop(&mut self) {
+        self.dropped_tasks.lock().unwrap().push(self.data_ptr.ptr);
+    }
+}
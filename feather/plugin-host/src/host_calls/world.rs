@@ -0,0 +1,88 @@
+use feather_base::Position;
+use feather_ecs::Entity;
+use feather_plugin_host_macros::host_function;
+use quill_common::{components::WorldId, world::WorldSettings};
+
+use crate::context::{PluginContext, PluginPtr, PluginPtrMut};
+
+#[host_function]
+pub fn world_create(
+    cx: &PluginContext,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    settings_ptr: PluginPtr<u8>,
+    settings_len: u32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let settings: WorldSettings = cx.read_bincode(settings_ptr, settings_len)?;
+    let result = cx.game_mut().create_world(&name, &settings);
+    cx.write_bincode(&result, bytes_ptr_ptr, bytes_len_ptr)
+}
+
+#[host_function]
+pub fn world_load(
+    cx: &PluginContext,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    settings_ptr: PluginPtr<u8>,
+    settings_len: u32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let settings: WorldSettings = cx.read_bincode(settings_ptr, settings_len)?;
+    let result = cx.game_mut().load_world(&name, &settings);
+    cx.write_bincode(&result, bytes_ptr_ptr, bytes_len_ptr)
+}
+
+#[host_function]
+pub fn world_unload(
+    cx: &PluginContext,
+    world: u32,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let result = cx.game_mut().unload_world(WorldId(world));
+    cx.write_bincode(&result, bytes_ptr_ptr, bytes_len_ptr)
+}
+
+#[host_function]
+pub fn world_find(
+    cx: &PluginContext,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    world_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<u32> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let world = cx.game_mut().world_id(&name);
+    match world {
+        Some(world) => {
+            cx.write_pod(world_ptr, world.0)?;
+            Ok(true as u32)
+        }
+        None => Ok(false as u32),
+    }
+}
+
+#[host_function]
+pub fn entity_teleport_to_world(
+    cx: &PluginContext,
+    entity: u64,
+    world: u32,
+    position: PluginPtr<Position>,
+) -> anyhow::Result<u32> {
+    let position = cx.read_pod(position)?;
+    let entity = Entity::from_bits(entity);
+    match cx
+        .game_mut()
+        .teleport_to_world(entity, WorldId(world), position)
+    {
+        Ok(()) => Ok(true as u32),
+        Err(e) => {
+            log::debug!("Failed to teleport entity to world {}: {:?}", world, e);
+            Ok(false as u32)
+        }
+    }
+}
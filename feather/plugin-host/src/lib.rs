@@ -7,27 +7,35 @@
 
 use std::{
     fs,
-    path::Path,
-    sync::atomic::{AtomicUsize, Ordering},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use ahash::AHashMap;
-use anyhow::Context;
+use anyhow::{bail, Context};
 use env::PluginEnv;
-use feather_common::Game;
+use feather_common::{chunk::loading::ForceLoadOwner, Game};
+use limits::GrowRefusals;
 use plugin::Plugin;
 use quill_plugin_format::{PluginFile, PluginMetadata};
 use vec_arena::Arena;
 use wasmer::{
-    ChainableNamedResolver, CompilerConfig, ExportError, Features, Function, ImportObject,
-    Instance, Module, Store, JIT,
+    BaseTunables, ChainableNamedResolver, CompilerConfig, ExportError, Features, Function,
+    ImportObject, Instance, Module, Store, Target, JIT,
 };
+use wasmer_middlewares::Metering;
 use wasmer_wasi::{WasiEnv, WasiState, WasiVersion};
 
+pub use limits::{LimitExceeded, PluginLimits};
+
 mod context;
 mod env;
 mod host_calls;
 mod host_function;
+mod limits;
 mod plugin;
 mod thread_pinned;
 mod wasm_ptr_ext;
@@ -50,10 +58,30 @@ const WASM_FEATURES: Features = Features {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PluginId(usize);
 
+impl PluginId {
+    /// Gets the owner of chunks force-loaded by this plugin.
+    pub(crate) fn force_load_owner(self) -> ForceLoadOwner {
+        ForceLoadOwner(self.0 as u64)
+    }
+}
+
+/// The default directory containing plugin files
+/// and plugin data directories.
+pub const DEFAULT_PLUGINS_DIRECTORY: &str = "plugins";
+
 /// Resource storing all enabled plugins plus the WebAssembly VM.
 pub struct PluginManager {
     plugins: Arena<Plugin>,
 
+    /// Directory containing a data directory
+    /// for each plugin, named after the plugin identifier.
+    plugins_dir: PathBuf,
+
+    /// Limits applied to plugins without an override.
+    default_limits: PluginLimits,
+    /// Per-plugin limits, keyed by plugin identifier.
+    limits: AHashMap<String, PluginLimits>,
+
     store: wasmer::Store,
 }
 
@@ -66,20 +94,81 @@ impl Default for PluginManager {
 impl PluginManager {
     /// Creates a plugin manager with no plugins.
     pub fn new() -> Self {
-        let compiler_config = compiler_config();
+        let mut compiler_config = compiler_config();
+        // Metering is always enabled so that each plugin
+        // can be given its own budget before every call.
+        compiler_config
+            .push_middleware(Arc::new(Metering::new(u64::MAX, limits::instruction_cost)));
         let engine_config = JIT::new(compiler_config).features(WASM_FEATURES);
         let engine = engine_config.engine();
         let store = Store::new(&engine);
 
         Self {
             plugins: Arena::new(),
+            plugins_dir: PathBuf::from(DEFAULT_PLUGINS_DIRECTORY),
+            default_limits: PluginLimits::default(),
+            limits: AHashMap::new(),
             store,
         }
     }
 
+    /// Sets the limits applied to plugins that
+    /// don't have an override set with [`PluginManager::set_limits`].
+    ///
+    /// Only affects plugins loaded after this call.
+    pub fn set_default_limits(&mut self, limits: PluginLimits) {
+        self.default_limits = limits;
+    }
+
+    /// Sets the limits for the plugin with the given identifier.
+    ///
+    /// Only affects plugins loaded after this call.
+    pub fn set_limits(&mut self, identifier: impl Into<String>, limits: PluginLimits) {
+        self.limits.insert(identifier.into(), limits);
+    }
+
+    /// Gets the limits for the plugin with the given identifier.
+    pub fn limits(&self, identifier: &str) -> PluginLimits {
+        self.limits
+            .get(identifier)
+            .copied()
+            .unwrap_or(self.default_limits)
+    }
+
+    /// Creates a `Store` that enforces the memory limit in `limits`,
+    /// along with a handle recording when the limit refused to
+    /// let the plugin's memory grow.
+    ///
+    /// The store shares the compilation engine with all other plugins.
+    fn create_store(&self, limits: &PluginLimits) -> (Store, GrowRefusals) {
+        let engine = &**self.store.engine();
+        let base = BaseTunables::for_target(&Target::default());
+        let refusals = GrowRefusals::default();
+        let store = match limits.max_pages() {
+            Some(max_pages) => Store::new_with_tunables(
+                engine,
+                limits::LimitingTunables::new(base, max_pages, refusals.clone()),
+            ),
+            None => Store::new_with_tunables(engine, base),
+        };
+        (store, refusals)
+    }
+
+    /// Sets the directory in which plugin data
+    /// directories are created.
+    ///
+    /// [`PluginManager::load_dir`] sets this to
+    /// the directory it loads plugins from.
+    pub fn set_plugins_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.plugins_dir = dir.into();
+    }
+
     /// Loads all plugins in the given directory.
+    ///
+    /// Plugin data directories are created inside `dir`.
     pub fn load_dir(&mut self, game: &mut Game, dir: impl AsRef<Path>) -> anyhow::Result<()> {
         let dir = dir.as_ref();
+        self.plugins_dir = dir.to_path_buf();
         if !dir.exists() {
             return Ok(());
         }
@@ -119,6 +208,26 @@ impl PluginManager {
         Ok(id)
     }
 
+    /// Gets the data directory for the plugin with the given identifier,
+    /// creating it if it does not exist.
+    ///
+    /// WASM plugins have this directory preopened as their
+    /// WASI root, so they can't access files outside of it.
+    pub fn data_dir(&self, identifier: &str) -> anyhow::Result<PathBuf> {
+        if identifier.is_empty()
+            || identifier == "."
+            || identifier == ".."
+            || identifier.contains(|c| c == '/' || c == '\\')
+        {
+            bail!("invalid plugin identifier '{}'", identifier);
+        }
+
+        let dir = self.plugins_dir.join(identifier);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create plugin data directory {}", dir.display()))?;
+        Ok(dir)
+    }
+
     /// Gets the plugin with the given ID,
     /// or `None` if it has been unloaded.
     pub fn plugin(&self, id: PluginId) -> Option<&Plugin> {
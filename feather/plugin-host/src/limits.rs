@@ -0,0 +1,262 @@
+//! Resource limits for WebAssembly plugins.
+
+use std::{
+    fmt, mem,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use wasmer::{
+    vm::{self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition},
+    wasmparser::Operator,
+    MemoryType, Pages, TableType, Tunables, WASM_MAX_PAGES, WASM_PAGE_SIZE,
+};
+
+/// Resource limits applied to a WebAssembly plugin.
+///
+/// Native plugins are trusted and are not subject to these limits,
+/// except for `max_faults`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PluginLimits {
+    /// Maximum number of WebAssembly instructions the plugin
+    /// may execute in a single call into the plugin,
+    /// such as running one system.
+    pub fuel_per_call: Option<u64>,
+    /// Maximum size of the plugin's linear memory in bytes.
+    pub max_memory: Option<u64>,
+    /// Number of traps after which the plugin is disabled.
+    ///
+    /// Set to zero to never disable a plugin because it trapped.
+    pub max_faults: u32,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel_per_call: Some(50_000_000),
+            max_memory: Some(256 * 1024 * 1024),
+            max_faults: 3,
+        }
+    }
+}
+
+impl PluginLimits {
+    /// Limits that never restrict a plugin.
+    pub fn unlimited() -> Self {
+        Self {
+            fuel_per_call: None,
+            max_memory: None,
+            max_faults: 0,
+        }
+    }
+
+    /// Gets the maximum number of WebAssembly pages
+    /// the plugin may allocate.
+    pub(crate) fn max_pages(&self) -> Option<Pages> {
+        self.max_memory.map(|bytes| {
+            let pages = (bytes / WASM_PAGE_SIZE as u64).clamp(1, WASM_MAX_PAGES as u64);
+            Pages(pages as u32)
+        })
+    }
+}
+
+/// Error returned when a plugin exceeds one of its [`PluginLimits`].
+#[derive(Debug)]
+pub enum LimitExceeded {
+    /// The plugin ran out of fuel.
+    Fuel(u64),
+    /// The plugin's memory grew to its maximum size.
+    Memory(u64),
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitExceeded::Fuel(fuel) => write!(
+                f,
+                "exceeded its budget of {} instructions in a single call",
+                fuel
+            ),
+            LimitExceeded::Memory(bytes) => {
+                write!(f, "exceeded its memory limit of {} bytes", bytes)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Cost function for the metering middleware.
+///
+/// Every instruction costs one point.
+pub(crate) fn instruction_cost(_operator: &Operator) -> u64 {
+    1
+}
+
+/// Records whether a plugin's memory refused to grow.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GrowRefusals(Arc<AtomicBool>);
+
+impl GrowRefusals {
+    fn record(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether a `memory.grow` was refused
+    /// since the last call, and resets the flag.
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// A memory which records refused attempts to grow it.
+#[derive(Debug)]
+struct LimitedMemory {
+    memory: Arc<dyn vm::Memory>,
+    refusals: GrowRefusals,
+}
+
+impl MemoryUsage for LimitedMemory {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.memory.size_of_val(tracker)
+    }
+}
+
+impl vm::Memory for LimitedMemory {
+    fn ty(&self) -> MemoryType {
+        self.memory.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.memory.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.memory.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let result = self.memory.grow(delta);
+        if result.is_err() {
+            self.refusals.record();
+        }
+        result
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.memory.vmmemory()
+    }
+}
+
+/// A [`Tunables`] implementation that caps the
+/// size of linear memories.
+///
+/// Memories that don't declare a maximum size get
+/// the limit as their maximum, so `memory.grow` fails
+/// once the limit is reached. Such failures are
+/// recorded in the tunables' [`GrowRefusals`].
+pub(crate) struct LimitingTunables<T: Tunables> {
+    limit: Pages,
+    base: T,
+    refusals: GrowRefusals,
+}
+
+impl<T: Tunables> MemoryUsage for LimitingTunables<T> {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.base.size_of_val(tracker)
+    }
+}
+
+impl<T: Tunables> LimitingTunables<T> {
+    pub fn new(base: T, limit: Pages, refusals: GrowRefusals) -> Self {
+        Self {
+            limit,
+            base,
+            refusals,
+        }
+    }
+
+    fn wrap(&self, memory: Arc<dyn vm::Memory>) -> Arc<dyn vm::Memory> {
+        Arc::new(LimitedMemory {
+            memory,
+            refusals: self.refusals.clone(),
+        })
+    }
+
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        adjusted.maximum = Some(match requested.maximum {
+            Some(maximum) => maximum.min(self.limit),
+            None => self.limit,
+        });
+        adjusted
+    }
+
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            return Err(MemoryError::Generic(format!(
+                "plugin requires {} pages of memory, but the limit is {} pages",
+                ty.minimum.0, self.limit.0
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        let memory = self.base.create_host_memory(&adjusted, style)?;
+        Ok(self.wrap(memory))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        let memory = self
+            .base
+            .create_vm_memory(&adjusted, style, vm_definition_location)?;
+        Ok(self.wrap(memory))
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
@@ -1,12 +1,13 @@
-use std::sync::Arc;
+use std::{cell::Cell, sync::Arc};
 
 use anyhow::bail;
 use feather_common::Game;
+use quill_common::events::PluginDisableEvent;
 use quill_plugin_format::{PluginFile, PluginMetadata, PluginTarget, Triple};
 
 use crate::{
     context::{PluginContext, PluginPtrMut},
-    PluginId, PluginManager,
+    LimitExceeded, PluginId, PluginLimits, PluginManager,
 };
 
 mod native;
@@ -16,6 +17,14 @@ pub struct Plugin {
     inner: Inner,
     context: Arc<PluginContext>,
     metadata: PluginMetadata,
+    limits: PluginLimits,
+
+    /// Number of times the plugin has trapped
+    /// or otherwise returned an error.
+    faults: Cell<u32>,
+    /// Set when the plugin has been disabled
+    /// because of a fault.
+    disabled: Cell<bool>,
 }
 
 impl Plugin {
@@ -34,11 +43,20 @@ impl Plugin {
             file.metadata().version
         );
 
+        let data_dir = manager.data_dir(&file.metadata().identifier)?;
+        let limits = manager.limits(&file.metadata().identifier);
+
         let (inner, context) = match &file.metadata().target {
             PluginTarget::Wasm => {
-                let context = Arc::new(PluginContext::new_wasm(id));
-                let plugin =
-                    wasm::WasmPlugin::load(manager, &context, file.module(), file.metadata())?;
+                let context = Arc::new(PluginContext::new_wasm(id, data_dir.clone()));
+                let plugin = wasm::WasmPlugin::load(
+                    manager,
+                    &context,
+                    file.module(),
+                    file.metadata(),
+                    &data_dir,
+                    &limits,
+                )?;
                 (Inner::Wasm(plugin), context)
             }
             PluginTarget::Native { target_triple } => {
@@ -50,7 +68,7 @@ impl Plugin {
                     );
                 }
                 let plugin = native::NativePlugin::load(file.module())?;
-                let context = PluginContext::new_native(id);
+                let context = PluginContext::new_native(id, data_dir);
                 (Inner::Native(plugin), Arc::new(context))
             }
         };
@@ -59,6 +77,9 @@ impl Plugin {
             inner,
             context,
             metadata: file.metadata().clone(),
+            limits,
+            faults: Cell::new(0),
+            disabled: Cell::new(false),
         })
     }
 
@@ -85,14 +106,88 @@ impl Plugin {
     ///
     /// `data` must be the data pointer passed
     /// to the `register_system` host call.
+    ///
+    /// If the plugin traps or exceeds its limits, the fault
+    /// is recorded and the plugin may be disabled.
+    /// Does nothing if the plugin has been disabled.
     pub fn run_system(&self, game: &mut Game, data: PluginPtrMut<u8>) -> anyhow::Result<()> {
-        self.context.enter(game, || match &self.inner {
-            Inner::Wasm(w) => w.run_system(data),
-            Inner::Native(n) => {
-                n.run_system(data);
-                Ok(())
+        if self.is_disabled() {
+            return Ok(());
+        }
+
+        let result = self.context.enter(game, || {
+            let result = match &self.inner {
+                Inner::Wasm(w) => w.run_system(data),
+                Inner::Native(n) => {
+                    n.run_system(data);
+                    Ok(())
+                }
+            };
+            result.and_then(|()| self.drop_finished_tasks())
+        });
+
+        if let Err(e) = result {
+            self.record_fault(game, e);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the plugin has been disabled
+    /// because of a fault.
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.get()
+    }
+
+    /// Gets the plugin's metadata.
+    pub fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    /// Frees the data of tasks that the scheduler has dropped.
+    ///
+    /// Must be called inside the plugin context. Tasks are freed
+    /// here rather than when they are dropped because a task may
+    /// cancel itself while it is running.
+    fn drop_finished_tasks(&self) -> anyhow::Result<()> {
+        for data in self.context.take_dropped_tasks() {
+            match &self.inner {
+                Inner::Wasm(w) => w.drop_task(data)?,
+                Inner::Native(n) => n.drop_task(data),
             }
-        })
+        }
+        Ok(())
+    }
+
+    fn record_fault(&self, game: &mut Game, error: anyhow::Error) {
+        let faults = self.faults.get() + 1;
+        self.faults.set(faults);
+
+        log::warn!(
+            "Plugin {} faulted ({} so far): {:?}",
+            self.metadata.name,
+            faults,
+            error
+        );
+
+        let reason = if let Some(limit) = error.downcast_ref::<LimitExceeded>() {
+            limit.to_string()
+        } else if self.limits.max_faults != 0 && faults >= self.limits.max_faults {
+            format!("trapped {} times", faults)
+        } else {
+            return;
+        };
+
+        self.disabled.set(true);
+        log::error!(
+            "Disabled plugin {} because it {}. Restart the server to enable it again.",
+            self.metadata.name,
+            reason
+        );
+        game.release_all_chunks(self.context.plugin_id().force_load_owner());
+        game.ecs.insert_event(PluginDisableEvent {
+            identifier: self.metadata.identifier.clone(),
+            reason,
+        });
     }
 }
 
@@ -100,3 +195,103 @@ enum Inner {
     Wasm(wasm::WasmPlugin),
     Native(native::NativePlugin),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// Limits under which `fixtures/faulty.wat` is disabled after two traps.
+    fn limits() -> PluginLimits {
+        PluginLimits {
+            fuel_per_call: Some(100_000),
+            max_memory: Some(2 * 1024 * 1024),
+            max_faults: 2,
+        }
+    }
+
+    /// Loads `fixtures/faulty.wat`, with its system calling
+    /// `function`, and runs `ticks` ticks.
+    ///
+    /// Returns whether the plugin was disabled and the
+    /// reasons of all `PluginDisableEvent`s.
+    fn run_faulty_plugin(function: &str, ticks: usize) -> (bool, Vec<String>) {
+        let module = wat::parse_str(
+            include_str!("fixtures/faulty.wat")
+                .replace("(call $trap)", &format!("(call {})", function)),
+        )
+        .expect("invalid fixture plugin");
+        let metadata = PluginMetadata {
+            name: "Faulty".to_owned(),
+            identifier: "faulty".to_owned(),
+            version: "0.1.0".to_owned(),
+            api_version: "0.1.0".to_owned(),
+            description: None,
+            authors: Vec::new(),
+            target: PluginTarget::Wasm,
+        };
+        let file = PluginFile::new(module, metadata).encode(1);
+
+        let dir = std::env::temp_dir().join(format!(
+            "feather-faulty-plugin-{}-{}",
+            function.trim_start_matches('$'),
+            std::process::id()
+        ));
+        let mut game = Game::new();
+        let mut manager = PluginManager::new();
+        manager.set_plugins_dir(&dir);
+        manager.set_default_limits(limits());
+        let id = manager.load(&mut game, &file).unwrap();
+        let manager = Rc::new(RefCell::new(manager));
+        game.insert_resource(Rc::clone(&manager));
+
+        let mut reasons = Vec::new();
+        for _ in 0..ticks {
+            let systems = Rc::clone(&game.system_executor);
+            systems.borrow_mut().run(&mut game);
+            reasons.extend(
+                game.ecs
+                    .query::<&PluginDisableEvent>()
+                    .iter()
+                    .map(|(_, event)| {
+                        assert_eq!(event.identifier, "faulty");
+                        event.reason.clone()
+                    }),
+            );
+        }
+
+        let disabled = manager.borrow().plugin(id).unwrap().is_disabled();
+        let _ = std::fs::remove_dir_all(&dir);
+        (disabled, reasons)
+    }
+
+    #[test]
+    fn plugins_are_disabled_after_max_faults() {
+        // A trap with a full memory is not mistaken for exceeding the memory limit.
+        assert_eq!(run_faulty_plugin("$trap", 1), (false, Vec::new()));
+        assert_eq!(
+            run_faulty_plugin("$trap", 4),
+            (true, vec!["trapped 2 times".to_owned()])
+        );
+    }
+
+    #[test]
+    fn plugins_exceeding_fuel_are_disabled() {
+        assert_eq!(
+            run_faulty_plugin("$spin", 3),
+            (true, vec![LimitExceeded::Fuel(100_000).to_string()])
+        );
+    }
+
+    #[test]
+    fn plugins_exceeding_memory_are_disabled() {
+        assert_eq!(
+            run_faulty_plugin("$grow", 3),
+            (
+                true,
+                vec![LimitExceeded::Memory(2 * 1024 * 1024).to_string()]
+            )
+        );
+    }
+}
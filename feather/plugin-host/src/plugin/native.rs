@@ -27,6 +27,13 @@ pub struct NativePlugin {
     /// Parameters:
     /// 1. Plugin data pointer for this system
     run_system: unsafe extern "C" fn(*mut u8),
+
+    /// The plugin's exported quill_drop_task function,
+    /// if it has one.
+    ///
+    /// Parameters:
+    /// 1. Plugin data pointer for the task
+    drop_task: Option<unsafe extern "C" fn(*mut u8)>,
 }
 
 impl NativePlugin {
@@ -56,11 +63,14 @@ impl NativePlugin {
                 .context("plugin is missing quill_run_system export")?
         };
 
+        let drop_task = unsafe { library.get("quill_drop_task".as_bytes()).ok().map(|f| *f) };
+
         Ok(Self {
             tempfile: path,
             library,
             enable,
             run_system,
+            drop_task,
         })
     }
 
@@ -89,4 +99,11 @@ impl NativePlugin {
         // SAFETY: we assume the plugin is sound.
         unsafe { (self.run_system)(data.as_native()) }
     }
+
+    pub fn drop_task(&self, data: PluginPtrMut<u8>) {
+        if let Some(drop_task) = self.drop_task {
+            // SAFETY: we assume the plugin is sound.
+            unsafe { drop_task(data.as_native()) }
+        }
+    }
 }
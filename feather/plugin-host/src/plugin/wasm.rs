@@ -1,15 +1,18 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use quill_plugin_format::PluginMetadata;
 use wasmer::{
-    ChainableNamedResolver, Features, Function, ImportObject, Instance, Module, NativeFunc, Store,
+    ChainableNamedResolver, Features, Function, ImportObject, Instance, Module, NativeFunc,
+    RuntimeError, Store,
 };
+use wasmer_middlewares::metering::{self, MeteringPoints};
 use wasmer_wasi::{WasiEnv, WasiState, WasiVersion};
 
 use crate::{
     context::{PluginContext, PluginPtr, PluginPtrMut},
     env::PluginEnv,
-    PluginManager,
+    limits::GrowRefusals,
+    LimitExceeded, PluginLimits, PluginManager,
 };
 
 pub struct WasmPlugin {
@@ -22,6 +25,16 @@ pub struct WasmPlugin {
 
     /// Exported function to run a system given its data pointer.
     run_system: NativeFunc<u32>,
+
+    /// Exported function to free a scheduled task given its data pointer.
+    ///
+    /// Plugins built against older versions of Quill don't export this.
+    drop_task: Option<NativeFunc<u32>>,
+
+    limits: PluginLimits,
+    /// Records when the memory limit refused to
+    /// let the plugin's memory grow.
+    grow_refusals: GrowRefusals,
 }
 
 impl WasmPlugin {
@@ -30,15 +43,18 @@ impl WasmPlugin {
         cx: &Arc<PluginContext>,
         module: &[u8],
         metadata: &PluginMetadata,
+        data_dir: &Path,
+        limits: &PluginLimits,
     ) -> anyhow::Result<Self> {
+        let (store, grow_refusals) = manager.create_store(limits);
         let env = PluginEnv {
             context: Arc::clone(cx),
         };
-        let quill_imports = crate::host_calls::generate_import_object(&manager.store, &env);
-        let wasi_imports = generate_wasi_import_object(&manager.store, &metadata.identifier)?;
+        let quill_imports = crate::host_calls::generate_import_object(&store, &env);
+        let wasi_imports = generate_wasi_import_object(&store, &metadata.identifier, data_dir)?;
         let imports = quill_imports.chain_back(wasi_imports);
 
-        let module = Module::new(&manager.store, module)?;
+        let module = Module::new(&store, module)?;
         let instance = Instance::new(&module, &imports)?;
 
         let run_system = instance
@@ -47,27 +63,92 @@ impl WasmPlugin {
             .native()?
             .clone();
         let enable = instance.exports.get_function("quill_setup")?.clone();
+        let drop_task = match instance.exports.get_function("quill_drop_task") {
+            Ok(function) => Some(function.native()?.clone()),
+            Err(_) => None,
+        };
 
         Ok(Self {
             instance,
             run_system,
+            drop_task,
             enable,
+            limits: *limits,
+            grow_refusals,
         })
     }
 
     pub fn enable(&self) -> anyhow::Result<()> {
-        self.enable.call(&[])?;
-        Ok(())
+        self.refuel();
+        let result = self.enable.call(&[]).map(|_| ());
+        self.check_result(result)
     }
 
     pub fn run_system(&self, data_ptr: PluginPtrMut<u8>) -> anyhow::Result<()> {
-        self.run_system.call(data_ptr.ptr as u32)?;
+        self.refuel();
+        let result = self.run_system.call(data_ptr.ptr as u32);
+        self.check_result(result)
+    }
+
+    pub fn drop_task(&self, data_ptr: PluginPtrMut<u8>) -> anyhow::Result<()> {
+        if let Some(drop_task) = &self.drop_task {
+            self.refuel();
+            let result = drop_task.call(data_ptr.ptr as u32);
+            self.check_result(result)?;
+        }
         Ok(())
     }
+
+    /// Resets the plugin's remaining fuel and
+    /// refused memory growth before a call.
+    fn refuel(&self) {
+        let fuel = self.limits.fuel_per_call.unwrap_or(u64::MAX);
+        metering::set_remaining_points(&self.instance, fuel);
+        self.grow_refusals.take();
+    }
+
+    /// Converts a trap caused by exceeding a limit
+    /// into a [`LimitExceeded`] error.
+    fn check_result(&self, result: Result<(), RuntimeError>) -> anyhow::Result<()> {
+        let error = match result {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        if let (Some(fuel), MeteringPoints::Exhausted) = (
+            self.limits.fuel_per_call,
+            metering::get_remaining_points(&self.instance),
+        ) {
+            return Err(LimitExceeded::Fuel(fuel).into());
+        }
+
+        // Rust plugins abort when an allocation fails, so a trap
+        // after the limit refused to grow the memory is
+        // attributed to the limit.
+        if let Some(max_memory) = self.limits.max_memory {
+            if self.grow_refusals.take() {
+                return Err(LimitExceeded::Memory(max_memory).into());
+            }
+        }
+
+        Err(error.into())
+    }
 }
 
-fn generate_wasi_import_object(store: &Store, plugin_name: &str) -> anyhow::Result<ImportObject> {
-    let state = WasiState::new(plugin_name).build()?;
+/// Creates the WASI imports for a plugin.
+///
+/// The plugin's data directory is preopened as both `/` and `.`,
+/// so absolute and relative paths resolve inside it. No other
+/// part of the host filesystem is visible to the plugin.
+fn generate_wasi_import_object(
+    store: &Store,
+    plugin_name: &str,
+    data_dir: &Path,
+) -> anyhow::Result<ImportObject> {
+    let state = WasiState::new(plugin_name)
+        .map_dir("/", data_dir)?
+        .map_dir(".", data_dir)?
+        .build()?;
     let env = WasiEnv::new(state);
     Ok(wasmer_wasi::generate_import_object_from_env(
         store,
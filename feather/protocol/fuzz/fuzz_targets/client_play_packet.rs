@@ -0,0 +1,14 @@
+//! Reads a play packet sent by a client from arbitrary bytes.
+
+#![no_main]
+
+use std::io::Cursor;
+
+use feather_protocol::{ClientPlayPacket, ProtocolVersion, Readable};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for &version in ProtocolVersion::ALL {
+        let _ = ClientPlayPacket::read(&mut Cursor::new(data), version);
+    }
+});
@@ -0,0 +1,44 @@
+//! Feeds received bytes to a `MinecraftCodec` configured
+//! like the server's, decoding packets until an error.
+
+#![no_main]
+
+use feather_protocol::{
+    ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket, MinecraftCodec,
+    ProtocolVersion, Readable,
+};
+use libfuzzer_sys::fuzz_target;
+
+/// The server's default maximum packet size.
+const MAX_PACKET_SIZE: usize = 2 * 1024 * 1024;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the protocol state,
+    // version and whether compression is enabled.
+    let (&settings, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    let mut codec = MinecraftCodec::new();
+    codec.set_max_packet_size(MAX_PACKET_SIZE);
+    if settings & 1 != 0 {
+        codec.enable_compression(256);
+    }
+    let versions = ProtocolVersion::ALL;
+    codec.set_protocol_version(versions[(settings >> 1) as usize % versions.len()]);
+    codec.accept(data);
+
+    match settings >> 6 {
+        0 => decode_all::<ClientHandshakePacket>(&mut codec),
+        1 => decode_all::<ClientStatusPacket>(&mut codec),
+        2 => decode_all::<ClientLoginPacket>(&mut codec),
+        _ => decode_all::<ClientPlayPacket>(&mut codec),
+    }
+});
+
+/// Decodes packets until the data runs out or one is invalid,
+/// at which point the server would disconnect the client.
+fn decode_all<T: Readable>(codec: &mut MinecraftCodec) {
+    while let Ok(Some(_)) = codec.next_packet::<T>() {}
+}
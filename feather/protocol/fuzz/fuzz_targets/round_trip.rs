@@ -0,0 +1,42 @@
+//! Checks that arbitrary packets of every state
+//! and direction are read back as they were written.
+
+#![no_main]
+
+use std::fmt::Debug;
+
+use arbitrary::{Arbitrary, Unstructured};
+use feather_protocol::{
+    generators::check_round_trip, ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket,
+    ClientStatusPacket, ProtocolVersion, Readable, ServerLoginPacket, ServerPlayPacket,
+    ServerStatusPacket, Writeable,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = round_trip(&mut Unstructured::new(data));
+});
+
+fn round_trip(u: &mut Unstructured) -> arbitrary::Result<()> {
+    let version = *u.choose(ProtocolVersion::ALL)?;
+    match u.int_in_range(0..=6)? {
+        0 => check::<ClientHandshakePacket>(u, version),
+        1 => check::<ClientStatusPacket>(u, version),
+        2 => check::<ClientLoginPacket>(u, version),
+        3 => check::<ClientPlayPacket>(u, version),
+        4 => check::<ServerStatusPacket>(u, version),
+        5 => check::<ServerLoginPacket>(u, version),
+        _ => check::<ServerPlayPacket>(u, version),
+    }
+}
+
+fn check<'a, T>(u: &mut Unstructured<'a>, version: ProtocolVersion) -> arbitrary::Result<()>
+where
+    T: Arbitrary<'a> + Readable + Writeable + Debug,
+{
+    let packet = T::arbitrary(u)?;
+    if let Err(e) = check_round_trip(&packet, version) {
+        panic!("round trip failed in {}: {:?}", version, e);
+    }
+    Ok(())
+}
@@ -10,6 +10,7 @@ use flate2::{
     Compression,
 };
 use std::io::{Cursor, Read};
+use thiserror::Error;
 
 type AesCfb8 = Cfb8<Aes128>;
 pub type CompressionThreshold = usize;
@@ -17,6 +18,16 @@ pub type CompressionThreshold = usize;
 /// An encryption key for use with AES-CFB8.
 pub type CryptKey = [u8; 16];
 
+/// Error returned by [`MinecraftCodec::next_packet`] when a received
+/// packet is larger than the limit set with
+/// [`MinecraftCodec::set_max_packet_size`].
+#[derive(Debug, Error)]
+#[error("packet of length {length} exceeds the maximum of {max} bytes")]
+pub struct PacketTooLarge {
+    pub length: i32,
+    pub max: usize,
+}
+
 /// State to serialize and deserialize packets from a byte stream.
 #[derive(Default)]
 pub struct MinecraftCodec {
@@ -25,6 +36,11 @@ pub struct MinecraftCodec {
     crypt_key: Option<CryptKey>,
     /// If compression is enabled, then this is the compression threshold.
     compression: Option<CompressionThreshold>,
+    /// The protocol version of the connection.
+    version: ProtocolVersion,
+    /// The maximum length of a received packet,
+    /// before and after decompression.
+    max_packet_size: Option<usize>,
 
     /// A buffer of received bytes.
     received_buf: BytesMut,
@@ -51,8 +67,27 @@ impl MinecraftCodec {
         self.compression = Some(threshold);
     }
 
-    /// Gets another `MinecraftCodec` with the same compression and encryption
-    /// parameters.
+    /// Sets the protocol version used to encode and decode packets.
+    ///
+    /// Call this once the handshake has been received.
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
+
+    /// Gets the protocol version used to encode and decode packets.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Sets the maximum length of a received packet, both compressed
+    /// and decompressed. Longer packets cause
+    /// [`next_packet`](Self::next_packet) to fail with [`PacketTooLarge`].
+    pub fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.max_packet_size = Some(max_packet_size);
+    }
+
+    /// Gets another `MinecraftCodec` with the same compression, encryption,
+    /// protocol version and maximum packet size parameters.
     pub fn clone_with_settings(&self) -> MinecraftCodec {
         MinecraftCodec {
             cryptor: self
@@ -60,6 +95,8 @@ impl MinecraftCodec {
                 .map(|key| AesCfb8::new_from_slices(&key, &key).expect("key size is invalid")),
             crypt_key: self.crypt_key,
             compression: self.compression,
+            version: self.version,
+            max_packet_size: self.max_packet_size,
             received_buf: BytesMut::new(),
             staging_buf: Vec::new(),
             compression_target: Vec::new(),
@@ -68,7 +105,7 @@ impl MinecraftCodec {
 
     /// Writes a packet into the provided writer.
     pub fn encode(&mut self, packet: &impl Writeable, output: &mut Vec<u8>) -> anyhow::Result<()> {
-        packet.write(&mut self.staging_buf, ProtocolVersion::V1_16_2)?;
+        packet.write(&mut self.staging_buf, self.version)?;
 
         if let Some(threshold) = self.compression {
             self.encode_compressed(output, threshold)?;
@@ -104,8 +141,8 @@ impl MinecraftCodec {
             .unwrap();
 
         let packet_length = data_length_bytes.position() as usize + data.len();
-        VarInt(packet_length as i32).write(output, ProtocolVersion::V1_16_2)?;
-        VarInt(data_length as i32).write(output, ProtocolVersion::V1_16_2)?;
+        VarInt(packet_length as i32).write(output, self.version)?;
+        VarInt(data_length as i32).write(output, self.version)?;
         output.extend_from_slice(data);
 
         self.compression_target.clear();
@@ -129,12 +166,18 @@ impl MinecraftCodec {
         // TODO: we should probably be able to determine the length without writing the packet,
         // which could remove an unnecessary copy.
         let length = self.staging_buf.len() as i32;
-        VarInt(length).write(output, ProtocolVersion::V1_16_2)?;
+        VarInt(length).write(output, self.version)?;
         output.extend_from_slice(&self.staging_buf);
 
         Ok(())
     }
 
+    /// Gets the received bytes that have not
+    /// yet been decoded into a packet.
+    pub fn pending_bytes(&self) -> &[u8] {
+        &self.received_buf
+    }
+
     /// Accepts newly received bytes.
     pub fn accept(&mut self, bytes: &[u8]) {
         let start_index = self.received_buf.len();
@@ -153,7 +196,8 @@ impl MinecraftCodec {
         T: Readable,
     {
         let mut cursor = Cursor::new(&self.received_buf[..]);
-        let packet = if let Ok(length) = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2) {
+        let packet = if let Ok(length) = VarInt::read(&mut cursor, self.version) {
+            self.check_packet_size(length.0)?;
             let length_field_length = cursor.position() as usize;
 
             if self.received_buf.len() - length_field_length >= length.0 as usize {
@@ -163,16 +207,23 @@ impl MinecraftCodec {
                 );
 
                 if self.compression.is_some() {
-                    let data_length = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2)?;
+                    let data_length = VarInt::read(&mut cursor, self.version)?;
                     if data_length.0 != 0 {
-                        let mut decoder =
+                        self.check_packet_size(data_length.0)?;
+                        let decoder =
                             ZlibDecoder::new(&cursor.get_ref()[cursor.position() as usize..]);
-                        decoder.read_to_end(&mut self.compression_target)?;
+                        // Don't trust the declared length; a small
+                        // packet could otherwise inflate without bound.
+                        // The buffer may hold the data of a packet that failed to read.
+                        self.compression_target.clear();
+                        decoder
+                            .take(data_length.0 as u64)
+                            .read_to_end(&mut self.compression_target)?;
                         cursor = Cursor::new(&self.compression_target);
                     }
                 }
 
-                let packet = T::read(&mut cursor, ProtocolVersion::V1_16_2)?;
+                let packet = T::read(&mut cursor, self.version)?;
 
                 let bytes_read = length.0 as usize + length_field_length;
                 self.received_buf = self.received_buf.split_off(bytes_read);
@@ -188,4 +239,79 @@ impl MinecraftCodec {
 
         Ok(packet)
     }
+
+    fn check_packet_size(&self, length: i32) -> Result<(), PacketTooLarge> {
+        let max = self.max_packet_size.unwrap_or(usize::MAX);
+        if length < 0 || length as usize > max {
+            Err(PacketTooLarge { length, max })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(packet: &str, compression: Option<CompressionThreshold>) -> Vec<u8> {
+        let mut codec = MinecraftCodec::new();
+        if let Some(threshold) = compression {
+            codec.enable_compression(threshold);
+        }
+        let mut bytes = Vec::new();
+        codec.encode(&packet.to_owned(), &mut bytes).unwrap();
+        bytes
+    }
+
+    fn decode(
+        bytes: &[u8],
+        compression: Option<CompressionThreshold>,
+        max_packet_size: usize,
+    ) -> anyhow::Result<Option<String>> {
+        let mut codec = MinecraftCodec::new();
+        if let Some(threshold) = compression {
+            codec.enable_compression(threshold);
+        }
+        codec.set_max_packet_size(max_packet_size);
+        codec.accept(bytes);
+        codec.next_packet()
+    }
+
+    #[test]
+    fn packets_within_the_limit_are_read() {
+        let packet = "a".repeat(100);
+        for &compression in &[None, Some(0)] {
+            let bytes = encode(&packet, compression);
+            assert_eq!(
+                decode(&bytes, compression, 200).unwrap(),
+                Some(packet.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn packets_above_the_limit_are_rejected() {
+        let packet = "a".repeat(100);
+        let bytes = encode(&packet, None);
+        let error = decode(&bytes, None, 50).unwrap_err();
+        let error = error.downcast::<PacketTooLarge>().unwrap();
+        assert_eq!(error.length, 101);
+        assert_eq!(error.max, 50);
+
+        // The length is checked before the packet has been received completely.
+        assert!(decode(&bytes[..3], None, 50).is_err());
+    }
+
+    #[test]
+    fn compressed_packets_are_checked_after_decompression() {
+        // The repeated string compresses well below the limit,
+        // but its declared decompressed length exceeds it.
+        let packet = "a".repeat(1000);
+        let bytes = encode(&packet, Some(0));
+        assert!(bytes.len() < 100);
+        let error = decode(&bytes, Some(0), 100).unwrap_err();
+        let error = error.downcast::<PacketTooLarge>().unwrap();
+        assert_eq!(error.length, 1002);
+    }
 }
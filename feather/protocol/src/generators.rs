@@ -0,0 +1,543 @@
+//! Generators of arbitrary packets, used by the
+//! round-trip tests and the fuzz targets.
+//!
+//! Generated packets only contain values which can be encoded,
+//! so writing one and reading it back must give the same bytes.
+//! [`check_round_trip`] verifies this.
+
+use std::{convert::TryInto, fmt::Debug, io::Cursor, sync::Arc};
+
+use anyhow::{ensure, Context};
+use arbitrary::{Arbitrary, Unstructured};
+use base::{
+    metadata::MetaEntry, BlockId, BlockPosition, BlockState, Chunk, ChunkHandle, ChunkLock,
+    ChunkPosition, Direction, EntityMetadata, Gamemode, Item, ItemStackBuilder, ParticleKind,
+    ProfileProperty, ValidBlockPosition,
+};
+use libcraft_items::InventorySlot;
+use nbt::{Blob, Value};
+use num_traits::FromPrimitive;
+use quill_common::components::PreviousGamemode;
+use uuid::Uuid;
+
+use crate::{
+    io::MAX_STRING_LENGTH,
+    packets::server::{
+        AddPlayer, ChunkData, ChunkDataKind, DemoEventType, EntityEquipment, EquipmentEntry,
+        EquipmentSlot, GameStateChange, Particle, PlayerInfo, UpdateLight,
+    },
+    Nbt, ProtocolVersion, Readable, Slot, VarInt, VarLong, Writeable,
+};
+
+/// The maximum length of generated collections.
+const MAX_COLLECTION_LENGTH: usize = 16;
+
+/// The highest item ID (`Item::RespawnAnchor`).
+const HIGHEST_ITEM_ID: u32 = 975;
+
+/// The highest particle ID (`ParticleKind::WhiteAsh`).
+const HIGHEST_PARTICLE_ID: u32 = 71;
+
+/// Writes `packet`, reads it back and writes the result again.
+/// Fails unless reading consumes every byte and both writes are equal.
+///
+/// Packets are compared by their encoding since some
+/// values, like angles, lose precision when encoded.
+pub fn check_round_trip<T>(packet: &T, version: ProtocolVersion) -> anyhow::Result<()>
+where
+    T: Readable + Writeable + Debug,
+{
+    let mut bytes = Vec::new();
+    packet.write(&mut bytes, version)?;
+
+    let mut cursor = Cursor::new(bytes.as_slice());
+    let read = T::read(&mut cursor, version)
+        .with_context(|| format!("failed to read back {:?}", packet))?;
+    let remaining = bytes.len() - cursor.position() as usize;
+    ensure!(
+        remaining == 0,
+        "{} bytes were left after reading {:?}",
+        remaining,
+        read
+    );
+
+    let mut rewritten = Vec::new();
+    read.write(&mut rewritten, version)?;
+    ensure!(
+        rewritten == bytes,
+        "{:?} was read back as {:?}",
+        packet,
+        read
+    );
+    Ok(())
+}
+
+/// A type which can generate arbitrary values of itself
+/// that can be encoded.
+///
+/// This stands in for `Arbitrary`, which can't be implemented
+/// for the many field types defined in other crates.
+pub trait Generate: Sized {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self>;
+}
+
+macro_rules! generate_with_arbitrary {
+    ($($typ:ty),* $(,)?) => {
+        $(
+            impl Generate for $typ {
+                fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+                    <$typ>::arbitrary(u)
+                }
+            }
+        )*
+    };
+}
+
+generate_with_arbitrary!(bool, u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+impl Generate for String {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let s = <&str>::arbitrary(u)?;
+        let mut length = s.len().min(MAX_STRING_LENGTH);
+        while !s.is_char_boundary(length) {
+            length -= 1;
+        }
+        Ok(s[..length].to_owned())
+    }
+}
+
+impl<T> Generate for Vec<T>
+where
+    T: Generate,
+{
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let mut vec = Vec::new();
+        while vec.len() < MAX_COLLECTION_LENGTH && u.arbitrary()? {
+            vec.push(T::generate(u)?);
+        }
+        Ok(vec)
+    }
+}
+
+impl<T> Generate for Option<T>
+where
+    T: Generate,
+{
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        if u.arbitrary()? {
+            Ok(Some(T::generate(u)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<A, B> Generate for (A, B)
+where
+    A: Generate,
+    B: Generate,
+{
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok((A::generate(u)?, B::generate(u)?))
+    }
+}
+
+impl Generate for VarInt {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(VarInt(u.arbitrary()?))
+    }
+}
+
+impl Generate for VarLong {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(VarLong(u.arbitrary()?))
+    }
+}
+
+impl Generate for Uuid {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(Uuid::from_u128(u.arbitrary()?))
+    }
+}
+
+impl Generate for ValidBlockPosition {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let position = BlockPosition {
+            x: u.int_in_range(-33_554_432..=33_554_431)?,
+            y: u.int_in_range(-2048..=2047)?,
+            z: u.int_in_range(-33_554_432..=33_554_431)?,
+        };
+        Ok(position.try_into().expect("position is in range"))
+    }
+}
+
+impl Generate for BlockId {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(BlockId::from_vanilla_id(
+            u.int_in_range(0..=blocks::HIGHEST_ID)?,
+        ))
+    }
+}
+
+impl Generate for Item {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(Item::from_id(u.int_in_range(1..=HIGHEST_ITEM_ID)?).expect("item IDs are contiguous"))
+    }
+}
+
+impl Generate for Slot {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        if !u.arbitrary()? {
+            return Ok(InventorySlot::Empty);
+        }
+
+        let stack = ItemStackBuilder::with_item(Item::generate(u)?)
+            .count(u.int_in_range(1..=64)?)
+            .apply_damage(u.arbitrary()?);
+        Ok(InventorySlot::Filled(stack.into()))
+    }
+}
+
+impl Generate for Gamemode {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(Gamemode::from_id(u.int_in_range(0..=3)?).expect("gamemode IDs are contiguous"))
+    }
+}
+
+impl Generate for PreviousGamemode {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(PreviousGamemode::from_id(u.int_in_range(-1..=3)?))
+    }
+}
+
+impl Generate for Nbt<Blob> {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        // Tags in a compound are unordered, so generating
+        // more than one would make the encoding unstable.
+        let mut blob = Blob::new();
+        if u.arbitrary()? {
+            let name = String::generate(u)?;
+            let value = match u.int_in_range(0..=6)? {
+                0 => Value::Byte(u.arbitrary()?),
+                1 => Value::Short(u.arbitrary()?),
+                2 => Value::Int(u.arbitrary()?),
+                3 => Value::Long(u.arbitrary()?),
+                4 => Value::Float(u.arbitrary()?),
+                5 => Value::Double(u.arbitrary()?),
+                6 => Value::String(String::generate(u)?),
+                _ => unreachable!(),
+            };
+            blob.insert(name, value)
+                .expect("scalar tags can always be inserted");
+        }
+        Ok(Nbt(blob))
+    }
+}
+
+impl Generate for EntityMetadata {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let mut metadata = EntityMetadata::new();
+        while metadata.values.len() < MAX_COLLECTION_LENGTH && u.arbitrary()? {
+            // 0xFF marks the end of the metadata.
+            let index = u.int_in_range(0..=0xFE)?;
+            metadata.values.insert(index, generate_meta_entry(u)?);
+        }
+        Ok(metadata)
+    }
+}
+
+/// Generates any entry except `MetaEntry::Particle`,
+/// which can't be written yet.
+fn generate_meta_entry(u: &mut Unstructured) -> arbitrary::Result<MetaEntry> {
+    Ok(match u.int_in_range(0..=17)? {
+        0 => MetaEntry::Byte(u.arbitrary()?),
+        1 => MetaEntry::VarInt(u.arbitrary()?),
+        2 => MetaEntry::Float(u.arbitrary()?),
+        3 => MetaEntry::String(String::generate(u)?),
+        4 => MetaEntry::Chat(String::generate(u)?),
+        5 => MetaEntry::OptChat(Generate::generate(u)?),
+        6 => MetaEntry::Slot(Slot::generate(u)?),
+        7 => MetaEntry::Boolean(u.arbitrary()?),
+        8 => MetaEntry::Rotation(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
+        9 => MetaEntry::Position(Generate::generate(u)?),
+        10 => MetaEntry::OptPosition(Generate::generate(u)?),
+        11 => MetaEntry::Direction(
+            Direction::from_u8(u.int_in_range(0..=3)?).expect("direction IDs are contiguous"),
+        ),
+        12 => MetaEntry::OptUuid(Generate::generate(u)?),
+        13 => MetaEntry::OptBlockId(
+            Option::<BlockId>::generate(u)?.map(|block| block.vanilla_id() as i32),
+        ),
+        14 => MetaEntry::Nbt(Nbt::<Blob>::generate(u)?.0),
+        15 => MetaEntry::VillagerData(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
+        16 => MetaEntry::OptVarInt(u.arbitrary()?),
+        17 => MetaEntry::Pose(u.arbitrary()?),
+        _ => unreachable!(),
+    })
+}
+
+impl Generate for GameStateChange {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=11)? {
+            0 => GameStateChange::SendNoRespawnBlockAvailableMessage,
+            1 => GameStateChange::EndRaining,
+            2 => GameStateChange::BeginRaining,
+            3 => GameStateChange::ChangeGamemode {
+                gamemode: Gamemode::generate(u)?,
+            },
+            4 => GameStateChange::WinGame {
+                show_credits: u.arbitrary()?,
+            },
+            5 => GameStateChange::DemoEvent(
+                u.choose(&[
+                    DemoEventType::ShowWelcomeToDemoScreen,
+                    DemoEventType::TellMovementControls,
+                    DemoEventType::TellJumpControl,
+                    DemoEventType::TellInventoryControl,
+                    DemoEventType::TellDemoIsOver,
+                ])?
+                .clone(),
+            ),
+            6 => GameStateChange::ArrowHitAnyPlayer,
+            7 => GameStateChange::RainLevelChange {
+                rain_level: u.arbitrary()?,
+            },
+            8 => GameStateChange::ThunderLevelChange {
+                thunder_level: u.arbitrary()?,
+            },
+            9 => GameStateChange::PlayPufferfishStingSound,
+            10 => GameStateChange::PlayElderGuardianAppearance,
+            11 => GameStateChange::EnableRespawnScreen {
+                enable: u.arbitrary()?,
+            },
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl Generate for Particle {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let mut particle_kind = ParticleKind::from_id(u.int_in_range(0..=HIGHEST_PARTICLE_ID)?)
+            .expect("particle IDs are contiguous");
+        match &mut particle_kind {
+            ParticleKind::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            } => {
+                *red = u.arbitrary()?;
+                *green = u.arbitrary()?;
+                *blue = u.arbitrary()?;
+                *scale = u.arbitrary()?;
+            }
+            ParticleKind::Block(block_state) | ParticleKind::FallingDust(block_state) => {
+                if let Some(state) = BlockState::from_id(u.int_in_range(0..=blocks::HIGHEST_ID)?) {
+                    *block_state = state;
+                }
+            }
+            ParticleKind::Item(item) => *item = Generate::generate(u)?,
+            _ => {}
+        }
+
+        Ok(Particle {
+            particle_kind,
+            long_distance: u.arbitrary()?,
+            x: u.arbitrary()?,
+            y: u.arbitrary()?,
+            z: u.arbitrary()?,
+            offset_x: u.arbitrary()?,
+            offset_y: u.arbitrary()?,
+            offset_z: u.arbitrary()?,
+            particle_data: u.arbitrary()?,
+            particle_count: u.arbitrary()?,
+        })
+    }
+}
+
+impl Generate for ProfileProperty {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(ProfileProperty {
+            name: String::generate(u)?,
+            value: String::generate(u)?,
+            signature: String::generate(u)?,
+        })
+    }
+}
+
+impl Generate for AddPlayer {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(AddPlayer {
+            uuid: Uuid::generate(u)?,
+            name: String::generate(u)?,
+            properties: Generate::generate(u)?,
+            gamemode: Gamemode::generate(u)?,
+            ping: u.arbitrary()?,
+            display_name: Generate::generate(u)?,
+        })
+    }
+}
+
+impl Generate for PlayerInfo {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=4)? {
+            0 => PlayerInfo::AddPlayers(Generate::generate(u)?),
+            1 => PlayerInfo::UpdateGamemodes(Generate::generate(u)?),
+            2 => PlayerInfo::UpdatePings(Generate::generate(u)?),
+            3 => PlayerInfo::UpdateDisplayNames(Generate::generate(u)?),
+            4 => PlayerInfo::RemovePlayers(Generate::generate(u)?),
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl Generate for EquipmentEntry {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(EquipmentEntry {
+            slot: EquipmentSlot::generate(u)?,
+            item: Slot::generate(u)?,
+        })
+    }
+}
+
+impl Generate for EntityEquipment {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let entity_id = u.arbitrary()?;
+        // The encoding can't represent an empty list of entries.
+        let mut entries = vec![EquipmentEntry::generate(u)?];
+        entries.extend(Vec::<EquipmentEntry>::generate(u)?);
+        Ok(EntityEquipment { entity_id, entries })
+    }
+}
+
+/// The number of distinct blocks placed in sections which
+/// use the global palette.
+const GLOBAL_PALETTE_BLOCKS: u16 = 300;
+
+/// Generates a chunk with arbitrary blocks in some of its sections.
+fn generate_chunk(u: &mut Unstructured) -> arbitrary::Result<ChunkHandle> {
+    let mut chunk = Chunk::new(ChunkPosition {
+        x: u.arbitrary()?,
+        z: u.arbitrary()?,
+    });
+
+    for section in 0..16 {
+        if !u.ratio(1, 4)? {
+            continue;
+        }
+        let y_offset = section * 16;
+
+        if u.ratio(1, 8)? {
+            // Enough distinct blocks to switch to the global palette.
+            let first = u.int_in_range(1..=blocks::HIGHEST_ID - GLOBAL_PALETTE_BLOCKS)?;
+            for i in 0..GLOBAL_PALETTE_BLOCKS {
+                let (x, y, z) = (i as usize % 16, i as usize / 256, i as usize / 16 % 16);
+                chunk.set_block_at(x, y_offset + y, z, BlockId::from_vanilla_id(first + i));
+            }
+        }
+
+        let blocks = u.int_in_range(0..=MAX_COLLECTION_LENGTH)?;
+        for _ in 0..blocks {
+            let x = u.int_in_range(0..=15)?;
+            let y = u.int_in_range(0..=15)?;
+            let z = u.int_in_range(0..=15)?;
+            chunk.set_block_at(x, y_offset + y, z, BlockId::generate(u)?);
+        }
+    }
+
+    Ok(Arc::new(ChunkLock::new(chunk, true)))
+}
+
+impl Generate for ChunkData {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let chunk = generate_chunk(u)?;
+        let kind = if u.arbitrary()? {
+            ChunkDataKind::LoadChunk
+        } else {
+            // Only sections which exist are sent, so only those
+            // are read back.
+            let mut sections = Vec::new();
+            for (index, section) in chunk.read().sections().iter().enumerate().skip(1).take(16) {
+                if section.is_some() && u.arbitrary()? {
+                    sections.push(index);
+                }
+            }
+            ChunkDataKind::OverwriteChunk { sections }
+        };
+        Ok(ChunkData { chunk, kind })
+    }
+}
+
+impl Generate for UpdateLight {
+    fn generate(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        Ok(UpdateLight {
+            chunk: generate_chunk(u)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+    use super::*;
+    use crate::{
+        ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket,
+        ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
+    };
+
+    /// Number of packets generated for each packet enum.
+    const PACKETS: usize = 2000;
+
+    fn assert_round_trips<T>()
+    where
+        T: for<'a> Arbitrary<'a> + Readable + Writeable + Debug,
+    {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = [0; 512];
+        for _ in 0..PACKETS {
+            rng.fill_bytes(&mut data);
+            let packet = T::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            for &version in ProtocolVersion::ALL {
+                if let Err(e) = check_round_trip(&packet, version) {
+                    panic!("round trip failed in {}: {:?}", version, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn client_handshake_packets_round_trip() {
+        assert_round_trips::<ClientHandshakePacket>();
+    }
+
+    #[test]
+    fn client_status_packets_round_trip() {
+        assert_round_trips::<ClientStatusPacket>();
+    }
+
+    #[test]
+    fn client_login_packets_round_trip() {
+        assert_round_trips::<ClientLoginPacket>();
+    }
+
+    #[test]
+    fn client_play_packets_round_trip() {
+        assert_round_trips::<ClientPlayPacket>();
+    }
+
+    #[test]
+    fn server_status_packets_round_trip() {
+        assert_round_trips::<ServerStatusPacket>();
+    }
+
+    #[test]
+    fn server_login_packets_round_trip() {
+        assert_round_trips::<ServerLoginPacket>();
+    }
+
+    #[test]
+    fn server_play_packets_round_trip() {
+        assert_round_trips::<ServerPlayPacket>();
+    }
+}
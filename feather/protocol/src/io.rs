@@ -326,13 +326,11 @@ impl Readable for String {
             .0 as usize;
 
         // TODO: support custom length limits
-        // Current max length is max value of a signed 16-bit int.
-        let max_length = std::i16::MAX as usize;
-        if length > max_length {
+        if length > MAX_STRING_LENGTH {
             bail!(
                 "string length {} exceeds maximum allowed length of {}",
                 length,
-                max_length
+                MAX_STRING_LENGTH
             );
         }
 
@@ -384,6 +382,10 @@ impl Writeable for bool {
 
 pub const MAX_LENGTH: usize = 1024 * 1024; // 2^20 elements
 
+/// The maximum length of a string in bytes,
+/// which is the max value of a signed 16-bit int.
+pub const MAX_STRING_LENGTH: usize = i16::MAX as usize;
+
 /// Reads and writes an array of inner `Writeable`s.
 /// The array is prefixed with a `VarInt` length.
 ///
@@ -561,7 +563,9 @@ impl Readable for Slot {
             let item = Item::from_id(item_id.try_into()?)
                 .ok_or_else(|| anyhow!("unknown item ID {}", item_id))?;
 
-            // Todo fix: Panics if count is zero
+            if count == 0 {
+                bail!("item stack with a count of zero");
+            }
             Ok(Filled(
                 ItemStackBuilder::with_item(item)
                     .count(count)
@@ -675,7 +679,7 @@ fn read_meta_entry(
             if varint == 0 {
                 None
             } else {
-                Some(varint - 1)
+                Some(varint.wrapping_sub(1))
             }
         }),
         18 => MetaEntry::Pose(VarInt::read(buffer, version)?.0),
@@ -751,19 +755,16 @@ fn write_meta_entry(
             }
         }
         MetaEntry::Nbt(val) => Nbt(val).write(buffer, version)?,
-        MetaEntry::Particle => unimplemented!("entity metadata with particles"),
+        MetaEntry::Particle => bail!("entity metadata with particles is not supported"),
         MetaEntry::VillagerData(villager_type, villager_profession, level) => {
             VarInt(*villager_type).write(buffer, version)?;
             VarInt(*villager_profession).write(buffer, version)?;
             VarInt(*level).write(buffer, version)?;
         }
         MetaEntry::OptVarInt(ox) => {
-            if let Some(x) = ox {
-                true.write(buffer, version)?;
-                x.write(buffer, version)?;
-            } else {
-                false.write(buffer, version)?;
-            }
+            // Encoded as the value plus one, with zero meaning absent.
+            let x = ox.map_or(0, |x| x.wrapping_add(1));
+            VarInt(x).write(buffer, version)?;
         }
         MetaEntry::Pose(x) => VarInt(x.to_i32().unwrap()).write(buffer, version)?,
     }
@@ -798,7 +799,7 @@ impl Readable for ValidBlockPosition {
         let val = i64::read(buffer, version)?;
 
         let x = (val >> 38) as i32;
-        let y = (val & 0xFFF) as i32;
+        let y = (val << 52 >> 52) as i32;
         let z = (val << 26 >> 38) as i32;
 
         Ok(BlockPosition { x, y, z }.try_into()?)
@@ -841,11 +842,10 @@ impl Readable for Angle {
 
 impl Writeable for Angle {
     fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) -> anyhow::Result<()> {
-        let temp = (256.0 / 360.0) * (self.0 % 360.0);
-        // Wrap negative values 'x' in the range [-256.0 to 0] to the
-        // correct angle in the range [0 to 256.0 ) by changing 'x' to
-        // x = 256.0 - x
-        let val = ((temp + 256.0) % 256.0) as u8;
+        // Round to the nearest stop so that angles read from a
+        // packet are written back unchanged. Casting through `i64`
+        // wraps negative angles and those of more than a full turn.
+        let val = (self.0 / 360.0 * 256.0).round() as i64 as u8;
         val.write(buffer, version)?;
 
         Ok(())
@@ -859,8 +859,11 @@ impl Readable for BlockId {
     {
         let id = VarInt::read(buffer, version)?.0;
 
-        let block = BlockId::from_vanilla_id(id.try_into()?);
-        Ok(block)
+        let id: u16 = id.try_into()?;
+        if id > blocks::HIGHEST_ID {
+            bail!("invalid block state ID {}", id);
+        }
+        Ok(BlockId::from_vanilla_id(id))
     }
 }
 
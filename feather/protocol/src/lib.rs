@@ -1,10 +1,13 @@
 use anyhow::anyhow;
 
 pub mod codec;
+#[cfg(any(test, feature = "arbitrary"))]
+pub mod generators;
 pub mod io;
 pub mod packets;
+pub mod version;
 
-use crate::codec::CompressionThreshold;
+use crate::codec::{CompressionThreshold, CryptKey};
 #[doc(inline)]
 pub use codec::MinecraftCodec;
 pub use io::Nbt;
@@ -16,15 +19,11 @@ pub use packets::{
     server::{ServerLoginPacket, ServerPlayPacket, ServerStatusPacket},
     VariantOf,
 };
+#[doc(inline)]
+pub use version::ProtocolVersion;
 
 pub type Slot = InventorySlot;
 
-/// A protocol version.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum ProtocolVersion {
-    V1_16_2,
-}
-
 /// A protocol state.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ProtocolState {
@@ -67,6 +66,14 @@ impl ClientPacketCodec {
         self.codec.enable_compression(threshold)
     }
 
+    pub fn enable_encryption(&mut self, key: CryptKey) {
+        self.codec.enable_encryption(key)
+    }
+
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.codec.set_protocol_version(version)
+    }
+
     /// Decodes a `ClientPacket` using the provided data.
     pub fn decode(&mut self, data: &[u8]) -> anyhow::Result<Option<ClientPacket>> {
         self.codec.accept(data);
@@ -90,6 +97,12 @@ impl ClientPacketCodec {
         }
     }
 
+    /// Gets the received bytes that have not
+    /// yet been decoded into a packet.
+    pub fn pending_bytes(&self) -> &[u8] {
+        self.codec.pending_bytes()
+    }
+
     /// Encodes a `ClientPacket` into a buffer.
     pub fn encode(&mut self, packet: &ClientPacket, buffer: &mut Vec<u8>) {
         match packet {
@@ -129,6 +142,14 @@ impl ServerPacketCodec {
         self.codec.enable_compression(threshold)
     }
 
+    pub fn enable_encryption(&mut self, key: CryptKey) {
+        self.codec.enable_encryption(key)
+    }
+
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.codec.set_protocol_version(version)
+    }
+
     /// Decodes a `ServerPacket` using the provided data.
     pub fn decode(&mut self, data: &[u8]) -> anyhow::Result<Option<ServerPacket>> {
         self.codec.accept(data);
@@ -40,6 +40,7 @@ macro_rules! user_type_convert_to_writeable {
     };
 }
 
+/// Defines packet structs.
 macro_rules! packets {
     (
         $(
@@ -88,6 +89,18 @@ macro_rules! packets {
                     Ok(())
                 }
             }
+
+            #[cfg(any(test, feature = "arbitrary"))]
+            impl crate::generators::Generate for $packet {
+                #[allow(unused_variables)]
+                fn generate(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                    Ok(Self {
+                        $(
+                            $field: crate::generators::Generate::generate(u)?,
+                        )*
+                    })
+                }
+            }
         )*
     };
 }
@@ -192,9 +205,32 @@ macro_rules! def_enum {
                 Ok(())
             }
         }
+
+        #[cfg(any(test, feature = "arbitrary"))]
+        impl crate::generators::Generate for $ident {
+            #[allow(unused_assignments)]
+            fn generate(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                let variants = [$(stringify!($variant)),*];
+                let mut index = u.int_in_range(0..=variants.len() - 1)?;
+                $(
+                    if index == 0 {
+                        return Ok($ident::$variant $(
+                            {
+                                $(
+                                    $field: crate::generators::Generate::generate(u)?,
+                                )*
+                            }
+                        )?);
+                    }
+                    index -= 1;
+                )*
+                unreachable!()
+            }
+        }
     };
 }
 
+/// Defines an enum over all packets in a protocol state.
 macro_rules! packet_enum {
     (
         $ident:ident {
@@ -248,6 +284,22 @@ macro_rules! packet_enum {
             }
         }
 
+        #[cfg(any(test, feature = "arbitrary"))]
+        impl<'a> ::arbitrary::Arbitrary<'a> for $ident {
+            #[allow(unused_assignments)]
+            fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+                let packets = [$(stringify!($packet)),*];
+                let mut index = u.int_in_range(0..=packets.len() - 1)?;
+                $(
+                    if index == 0 {
+                        return Ok($ident::$packet(crate::generators::Generate::generate(u)?));
+                    }
+                    index -= 1;
+                )*
+                unreachable!()
+            }
+        }
+
         $(
             impl VariantOf<$ident> for $packet {
                 fn discriminant_id() -> u32 { $id }
@@ -3,9 +3,11 @@ use std::io::Cursor;
 use anyhow::{anyhow, bail};
 
 use base::{
-    BlockState, EntityMetadata, Gamemode, ParticleKind, ProfileProperty, ValidBlockPosition,
+    BlockState, EntityMetadata, Gamemode, ItemStack, ParticleKind, ProfileProperty,
+    ValidBlockPosition,
 };
 pub use chunk_data::{ChunkData, ChunkDataKind};
+use libcraft_items::InventorySlot;
 use quill_common::components::PreviousGamemode;
 pub use update_light::UpdateLight;
 
@@ -642,7 +644,8 @@ impl Readable for Particle {
         Self: Sized,
     {
         let id = i32::read(buffer, version)?;
-        let mut particle_kind = ParticleKind::from_id(id as u32).unwrap();
+        let mut particle_kind = ParticleKind::from_id(id as u32)
+            .ok_or_else(|| anyhow!("invalid particle ID {}", id))?;
         let long_distance = bool::read(buffer, version)?;
         let x = f64::read(buffer, version)?;
         let y = f64::read(buffer, version)?;
@@ -665,17 +668,18 @@ impl Readable for Particle {
                 *blue = f32::read(buffer, version)?;
                 *scale = f32::read(buffer, version)?;
             }
-            ParticleKind::Block(ref mut block_state) => {
-                let state = VarInt::read(buffer, version)?;
-                *block_state = BlockState::from_id(state.0 as u16).unwrap();
-            }
-            ParticleKind::FallingDust(ref mut block_state) => {
-                let state = VarInt::read(buffer, version)?;
-                *block_state = BlockState::from_id(state.0 as u16).unwrap();
+            ParticleKind::Block(ref mut block_state)
+            | ParticleKind::FallingDust(ref mut block_state) => {
+                let state = VarInt::read(buffer, version)?.0;
+                *block_state = BlockState::from_id(state as u16)
+                    .ok_or_else(|| anyhow!("invalid block state ID {}", state))?;
             }
             ParticleKind::Item(ref mut item) => {
-                let _slot = Slot::read(buffer, version)?;
-                *item = None; // TODO: Use item from libcraft once fully moved
+                // TODO: keep the whole stack once particles use `ItemStack`
+                *item = match Slot::read(buffer, version)? {
+                    InventorySlot::Filled(stack) => Some(stack.item()),
+                    InventorySlot::Empty => None,
+                };
             }
             _ => {}
         }
@@ -726,8 +730,12 @@ impl Writeable for Particle {
             ParticleKind::FallingDust(block_state) => {
                 VarInt(block_state.id() as i32).write(buffer, version)?;
             }
-            ParticleKind::Item(_item) => {
-                todo![];
+            ParticleKind::Item(item) => {
+                let slot = match item {
+                    Some(item) => InventorySlot::Filled(ItemStack::new(item, 1)?),
+                    None => InventorySlot::Empty,
+                };
+                slot.write(buffer, version)?;
             }
             _ => {}
         }
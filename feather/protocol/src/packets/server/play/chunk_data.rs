@@ -4,8 +4,15 @@ use std::{
     sync::Arc,
 };
 
-use base::{Chunk, ChunkHandle, ChunkLock, ChunkPosition, ChunkSection};
-use blocks::BlockId;
+use anyhow::ensure;
+use base::{
+    chunk::{
+        BlockStore, LightStore, PackedArray, Palette, GLOBAL_BITS_PER_BLOCK, MAX_BITS_PER_BLOCK,
+        MIN_BITS_PER_BLOCK, SECTION_VOLUME,
+    },
+    Chunk, ChunkHandle, ChunkLock, ChunkPosition, ChunkSection,
+};
+use blocks::{BlockId, HIGHEST_ID};
 use libcraft_core::Biome;
 use serde::{
     de,
@@ -159,10 +166,6 @@ impl Readable for ChunkData {
         });
 
         let full_chunk = bool::read(buffer, version)?;
-        let chunk_data_kind: ChunkDataKind = match full_chunk {
-            true => ChunkDataKind::LoadChunk,
-            false => ChunkDataKind::OverwriteChunk { sections: vec![] },
-        };
 
         let primary_bit_mask = VarInt::read(buffer, version)?.0;
         let heightmaps: Nbt<Heightmaps> = Nbt::read(buffer, version)?;
@@ -176,7 +179,11 @@ impl Readable for ChunkData {
 
         if full_chunk {
             let biomes_length = VarInt::read(buffer, version)?.0;
-            assert_eq!(biomes_length, 1024);
+            ensure!(
+                biomes_length == 1024,
+                "expected 1024 biomes, found {}",
+                biomes_length
+            );
             for y in 0..64 {
                 for z in 0..4 {
                     for x in 0..4 {
@@ -194,48 +201,102 @@ impl Readable for ChunkData {
 
         VarInt::read(buffer, version)?; // Size of following array
 
-        for i in 0..16 {
-            if (primary_bit_mask & (1 << i)) != 0 {
-                if chunk.section(i).is_none() {
-                    chunk.set_section_at(i as isize, Some(ChunkSection::default()));
-                }
-                if let Some(section) = chunk.section_mut(i + 1) {
-                    let non_air_blocks = u16::read(buffer, version)?;
-                    section
-                        .blocks_mut()
-                        .set_air_blocks(4096 - non_air_blocks as u32);
-                    let bits_per_block = u8::read(buffer, version)?;
-                    section
-                        .blocks_mut()
-                        .data_mut()
-                        .set_bits_per_value(bits_per_block as usize);
-                    if bits_per_block <= 4 || (5..=8).contains(&bits_per_block) {
-                        if let Some(pallete) = section.blocks_mut().palette_mut() {
-                            let pallete_length = VarInt::read(buffer, version)?.0 as usize;
-                            for _ in 0..pallete_length {
-                                let block_id = VarInt::read(buffer, version)?.0;
-                                pallete.index_or_insert(BlockId::from_vanilla_id(block_id as u16));
-                            }
-                        }
-                    }
-                    let data_length = VarInt::read(buffer, version)?.0 as usize;
-                    for i in 0..data_length {
-                        section.blocks_mut().data_mut().as_u64_mut_vec()[i] =
-                            u64::read(buffer, version)?;
-                    }
-                }
+        let mut sections = Vec::new();
+        for y in 0..16 {
+            if (primary_bit_mask & (1 << y)) != 0 {
+                let section = decode_section(buffer, version)?;
+                chunk.set_section_at(y, Some(section));
+                // `sections` holds indices into `Chunk::sections`,
+                // which start one section below y = 0.
+                sections.push(y as usize + 1);
             }
         }
 
         VarInt::read(buffer, version)?; // Block entities length, redundant for feather right now
 
+        let kind = if full_chunk {
+            ChunkDataKind::LoadChunk
+        } else {
+            ChunkDataKind::OverwriteChunk { sections }
+        };
+
         Ok(Self {
             chunk: Arc::new(ChunkLock::new(chunk, true)),
-            kind: chunk_data_kind,
+            kind,
         })
     }
 }
 
+/// Reads a section written by [`encode_section`].
+///
+/// Palette entries must come in the order a [`Palette`] would
+/// assign them, so that the section is written back unchanged.
+fn decode_section(
+    buffer: &mut std::io::Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<ChunkSection> {
+    // The air count is recomputed from the blocks.
+    let _non_air_blocks = u16::read(buffer, version)?;
+
+    let bits_per_block = u8::read(buffer, version)?;
+    let palette = if (MIN_BITS_PER_BLOCK..=MAX_BITS_PER_BLOCK).contains(&bits_per_block) {
+        let palette_length = VarInt::read(buffer, version)?.0;
+        ensure!(
+            palette_length >= 1 && palette_length <= 1 << bits_per_block,
+            "palette of length {} does not fit in {} bits",
+            palette_length,
+            bits_per_block
+        );
+
+        let mut palette = Palette::new();
+        for i in 0..palette_length as usize {
+            let block = read_block_id(buffer, version)?;
+            ensure!(
+                palette.index_or_insert(block) == i,
+                "palette entry {} ({:?}) is duplicated or out of order",
+                i,
+                block
+            );
+        }
+        Some(palette)
+    } else {
+        ensure!(
+            bits_per_block == GLOBAL_BITS_PER_BLOCK,
+            "invalid number of bits per block {}",
+            bits_per_block
+        );
+        None
+    };
+
+    let mut data = PackedArray::new(SECTION_VOLUME, bits_per_block as usize);
+    let data_length = VarInt::read(buffer, version)?.0;
+    ensure!(
+        data_length as usize == data.as_u64_slice().len(),
+        "expected section data of length {}, found {}",
+        data.as_u64_slice().len(),
+        data_length
+    );
+    for value in data.as_u64_mut_vec() {
+        *value = u64::read(buffer, version)?;
+    }
+
+    let blocks = BlockStore::from_raw_parts(palette, data);
+    Ok(ChunkSection::new(blocks, LightStore::new()))
+}
+
+fn read_block_id(
+    buffer: &mut std::io::Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<BlockId> {
+    let id = VarInt::read(buffer, version)?.0;
+    ensure!(
+        (0..=HIGHEST_ID as i32).contains(&id),
+        "invalid block ID {}",
+        id
+    );
+    Ok(BlockId::from_vanilla_id(id as u16))
+}
+
 fn deserialize_i64_37<'de, D>(deserializer: D) -> Result<[i64; 37], D::Error>
 where
     D: Deserializer<'de>,
@@ -1,5 +1,6 @@
 use std::{fmt::Debug, sync::Arc};
 
+use anyhow::ensure;
 use base::{chunk::PackedArray, Chunk, ChunkHandle, ChunkLock, ChunkPosition, ChunkSection};
 
 use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
@@ -80,7 +81,11 @@ impl Readable for UpdateLight {
         for i in 0..18 {
             if (sky_light_mask & (1 << i)) != 0 {
                 let probably_2048 = VarInt::read(buffer, version)?.0 as usize;
-                assert_eq!(probably_2048, 2048);
+                ensure!(
+                    probably_2048 == 2048,
+                    "expected 2048 bytes of light, found {}",
+                    probably_2048
+                );
                 let mut bytes: Vec<u8> = Vec::new();
                 for _ in 0..probably_2048 {
                     bytes.push(u8::read(buffer, version)?);
@@ -104,7 +109,11 @@ impl Readable for UpdateLight {
         for i in 0..18 {
             if (block_light_mask & (1 << i)) != 0 {
                 let probably_2048 = VarInt::read(buffer, version)?.0 as usize;
-                assert_eq!(probably_2048, 2048);
+                ensure!(
+                    probably_2048 == 2048,
+                    "expected 2048 bytes of light, found {}",
+                    probably_2048
+                );
                 let mut bytes: Vec<u8> = Vec::new();
                 for _ in 0..probably_2048 {
                     bytes.push(u8::read(buffer, version)?);
@@ -0,0 +1,75 @@
+//! Protocol versions supported by the server.
+//!
+//! The handshake selects a [`ProtocolVersion`] per connection,
+//! but only protocol 754 is implemented. Supporting another
+//! release needs its packet ID tables, version-gated packet
+//! fields and block and item ID remapping, none of which
+//! exist yet.
+
+use std::fmt::{self, Display};
+
+/// A protocol version.
+///
+/// Each variant covers every release sharing its protocol
+/// number. Variants are ordered by release.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    /// 1.16.4 and 1.16.5
+    V1_16_4,
+}
+
+impl ProtocolVersion {
+    /// The newest supported version.
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_16_4;
+
+    /// All supported versions, oldest first.
+    pub const ALL: &'static [ProtocolVersion] = &[ProtocolVersion::V1_16_4];
+
+    /// Gets the version with the given protocol number,
+    /// as sent by the client in the handshake.
+    pub fn from_protocol_number(number: i32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|version| version.protocol_number() == number)
+    }
+
+    /// Gets the protocol number sent in the handshake
+    /// and the status response.
+    pub fn protocol_number(self) -> i32 {
+        match self {
+            ProtocolVersion::V1_16_4 => 754,
+        }
+    }
+
+    /// Gets the releases using this version, oldest first.
+    pub fn releases(self) -> &'static [&'static str] {
+        match self {
+            ProtocolVersion::V1_16_4 => &["1.16.4", "1.16.5"],
+        }
+    }
+
+    /// Gets the name of the newest release using this version.
+    pub fn name(self) -> &'static str {
+        let releases = self.releases();
+        releases[releases.len() - 1]
+    }
+
+    /// Gets a human-readable range of the supported releases,
+    /// e.g. for disconnect messages.
+    pub fn supported_range() -> String {
+        format!("{}-{}", Self::ALL[0].releases()[0], Self::LATEST.name())
+    }
+}
+
+impl Default for ProtocolVersion {
+    fn default() -> Self {
+        ProtocolVersion::LATEST
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.protocol_number())
+    }
+}
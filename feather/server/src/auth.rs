@@ -0,0 +1,195 @@
+//! Authentication of players joining in online mode.
+
+use std::{collections::HashMap, fmt::Debug, net::IpAddr, time::Duration};
+
+use base::ProfileProperty;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use thiserror::Error;
+use ureq::{Agent, AgentBuilder};
+use uuid::Uuid;
+
+/// The session server used by vanilla servers.
+pub const DEFAULT_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+/// The default timeout for requests to the session server.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A request to verify that a player has joined the server
+/// through the session server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthRequest {
+    /// The username sent in Login Start.
+    pub username: String,
+    /// The server hash computed from the shared secret and public key.
+    pub server_hash: String,
+    /// The player's IP address, if the session server
+    /// should check that it matches the one the player
+    /// authenticated from.
+    pub ip: Option<IpAddr>,
+}
+
+/// The profile of an authenticated player.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Error returned when a player could not be authenticated.
+#[derive(Debug, Error)]
+pub enum AuthError {
+    /// The session server does not know of a session for the player,
+    /// e.g. because the client is not logged in or is a cracked client.
+    #[error("failed to verify username")]
+    InvalidSession,
+    /// The session server could not be reached or returned an error.
+    #[error("authentication servers are unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl AuthError {
+    /// Gets the message shown to the player when they are disconnected.
+    pub fn disconnect_message(&self) -> &'static str {
+        match self {
+            AuthError::InvalidSession => "Failed to verify username!",
+            AuthError::Unavailable(_) => {
+                "Authentication servers are down. Please try again later, sorry!"
+            }
+        }
+    }
+}
+
+/// Verifies that players joining in online mode own their account.
+///
+/// Implementations may block; the server calls
+/// [`authenticate`](Authenticator::authenticate) on a blocking thread.
+pub trait Authenticator: Debug + Send + Sync + 'static {
+    fn authenticate(&self, request: &AuthRequest) -> Result<AuthResponse, AuthError>;
+}
+
+/// Authenticates players through a Mojang-compatible
+/// session server.
+#[derive(Debug)]
+pub struct MojangAuthenticator {
+    session_server: String,
+    agent: Agent,
+}
+
+impl MojangAuthenticator {
+    /// Creates an authenticator using the session server at `session_server`,
+    /// e.g. [`DEFAULT_SESSION_SERVER`].
+    pub fn new(session_server: impl Into<String>, timeout: Duration) -> Self {
+        let session_server = session_server.into().trim_end_matches('/').to_owned();
+        Self {
+            session_server,
+            agent: AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+}
+
+impl Default for MojangAuthenticator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_SERVER, DEFAULT_TIMEOUT)
+    }
+}
+
+impl Authenticator for MojangAuthenticator {
+    fn authenticate(&self, request: &AuthRequest) -> Result<AuthResponse, AuthError> {
+        let url = format!("{}/session/minecraft/hasJoined", self.session_server);
+        let mut http_request = self
+            .agent
+            .get(&url)
+            .query("username", &request.username)
+            .query("serverId", &request.server_hash);
+        if let Some(ip) = request.ip {
+            http_request = http_request.query("ip", &ip.to_string());
+        }
+
+        let response = http_request
+            .call()
+            .map_err(|e| AuthError::Unavailable(e.to_string()))?;
+        // The session server responds with 204 No Content
+        // if the player has not joined.
+        if response.status() == 204 {
+            return Err(AuthError::InvalidSession);
+        }
+
+        response
+            .into_json()
+            .map_err(|e| AuthError::Unavailable(format!("malformed response: {}", e)))
+    }
+}
+
+/// An in-memory authenticator for tests.
+///
+/// Only players whose profiles were added with
+/// [`add_profile`](FakeAuthenticator::add_profile) are authenticated.
+#[derive(Debug, Default)]
+pub struct FakeAuthenticator {
+    profiles: Mutex<HashMap<String, AuthResponse>>,
+    requests: Mutex<Vec<AuthRequest>>,
+}
+
+impl FakeAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the player with the given profile to join.
+    pub fn add_profile(&self, profile: AuthResponse) {
+        self.profiles.lock().insert(profile.name.clone(), profile);
+    }
+
+    /// Gets all requests made so far.
+    pub fn requests(&self) -> Vec<AuthRequest> {
+        self.requests.lock().clone()
+    }
+}
+
+impl Authenticator for FakeAuthenticator {
+    fn authenticate(&self, request: &AuthRequest) -> Result<AuthResponse, AuthError> {
+        self.requests.lock().push(request.clone());
+        self.profiles
+            .lock()
+            .get(&request.username)
+            .cloned()
+            .ok_or(AuthError::InvalidSession)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(username: &str) -> AuthRequest {
+        AuthRequest {
+            username: username.to_owned(),
+            server_hash: String::from("-1a2b3c"),
+            ip: None,
+        }
+    }
+
+    #[test]
+    fn fake_authenticator_accepts_known_profiles() {
+        let authenticator = FakeAuthenticator::new();
+        let id = Uuid::from_u128(0x905c7e4fb96b45139645d123225575e2);
+        authenticator.add_profile(AuthResponse {
+            id,
+            name: String::from("caelunshun"),
+            properties: Vec::new(),
+        });
+
+        let response = authenticator.authenticate(&request("caelunshun")).unwrap();
+        assert_eq!(response.id, id);
+        assert!(matches!(
+            authenticator.authenticate(&request("notch")),
+            Err(AuthError::InvalidSession)
+        ));
+        assert_eq!(
+            authenticator.requests(),
+            vec![request("caelunshun"), request("notch")]
+        );
+    }
+}
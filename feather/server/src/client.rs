@@ -1,7 +1,8 @@
 use std::{
     cell::{Cell, RefCell},
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     io::Cursor,
+    net::IpAddr,
     sync::Arc,
 };
 
@@ -11,12 +12,12 @@ use slab::Slab;
 use uuid::Uuid;
 
 use base::{
-    BlockId, ChunkHandle, ChunkPosition, EntityKind, EntityMetadata, Gamemode, Position,
+    BlockId, ChunkHandle, ChunkPosition, Dimension, EntityKind, EntityMetadata, Gamemode, Position,
     ProfileProperty, Text, ValidBlockPosition,
 };
 use common::{
     chat::{ChatKind, ChatMessage},
-    Window,
+    world_border, Window, WorldBorder,
 };
 use libcraft_items::InventorySlot;
 use packets::server::{Particle, SetSlot, SpawnLivingEntity, UpdateLight, WindowConfirmation};
@@ -30,13 +31,14 @@ use protocol::{
         server::{
             AddPlayer, Animation, BlockChange, ChatPosition, ChunkData, ChunkDataKind,
             DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, JoinGame, KeepAlive,
-            PlayerInfo, PlayerPositionAndLook, PluginMessage, SendEntityMetadata, SpawnPlayer,
-            Title, UnloadChunk, UpdateViewPosition, WindowItems,
+            NbtQueryResponse, PlayerInfo, PlayerPositionAndLook, PluginMessage, Respawn,
+            SendEntityMetadata, SpawnPlayer, Title, UnloadChunk, UpdateViewPosition, WindowItems,
         },
     },
-    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, Writeable,
+    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, VarLong, Writeable,
 };
 use quill_common::components::{OnGround, PreviousGamemode};
+use serde::Deserialize;
 
 use crate::{
     entities::{PreviousOnGround, PreviousPosition},
@@ -95,6 +97,8 @@ pub struct Client {
     username: String,
     profile: Vec<ProfileProperty>,
     uuid: Uuid,
+    protocol_version: ProtocolVersion,
+    ip: IpAddr,
 
     teleport_id_counter: Cell<i32>,
 
@@ -124,6 +128,8 @@ impl Client {
             network_id: None,
             profile: player.profile,
             uuid: player.uuid,
+            protocol_version: player.protocol_version,
+            ip: player.ip,
             sent_entities: RefCell::new(AHashSet::new()),
             knows_position: Cell::new(false),
             known_chunks: RefCell::new(AHashSet::new()),
@@ -153,6 +159,16 @@ impl Client {
         self.uuid
     }
 
+    /// Gets the protocol version the client connected with.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Gets the IP address of the client.
+    pub fn ip(&self) -> IpAddr {
+        self.ip
+    }
+
     pub fn username(&self) -> &str {
         &self.username
     }
@@ -197,27 +213,31 @@ impl Client {
         self.network_id = Some(network_id);
     }
 
-    pub fn send_join_game(&self, gamemode: Gamemode, previous_gamemode: PreviousGamemode) {
+    pub fn send_join_game(
+        &self,
+        gamemode: Gamemode,
+        previous_gamemode: PreviousGamemode,
+        dimension: Dimension,
+        world_name: &str,
+        world_names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) {
         log::trace!("Sending Join Game to {}", self.username);
         // Use the dimension codec sent by the default vanilla server. (Data acquired via tools/proxy)
-        let dimension_codec = nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
-            "../../../assets/dimension_codec.nbt"
-        )))
-        .expect("dimension codec asset is malformed");
-        let dimension = nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
-            "../../../assets/dimension.nbt"
-        )))
-        .expect("dimension asset is malformed");
+        let dimension_codec = nbt::Blob::from_reader(&mut Cursor::new(DIMENSION_CODEC))
+            .expect("dimension codec asset is malformed");
 
         self.send_packet(JoinGame {
             entity_id: self.network_id.expect("No network id! Use client.set_network_id(NetworkId) before calling this method.").0,
             is_hardcore: false,
             gamemode,
             previous_gamemode,
-            world_names: vec!["world".to_owned()],
+            world_names: world_names
+                .into_iter()
+                .map(|name| world_identifier(name.as_ref()))
+                .collect(),
             dimension_codec: Nbt(dimension_codec),
-            dimension: Nbt(dimension),
-            world_name: "world".to_owned(),
+            dimension: Nbt(dimension_type(dimension)),
+            world_name: world_identifier(world_name),
             hashed_seed: 0,
             max_players: 0,
             view_distance: self.options.view_distance as i32,
@@ -228,11 +248,41 @@ impl Client {
         });
     }
 
+    /// Moves the client into the world `world_name`
+    /// of the given dimension type.
+    ///
+    /// The client forgets all chunks and entities it
+    /// knew about, so they need to be sent again.
+    pub fn send_respawn(
+        &self,
+        dimension: Dimension,
+        world_name: &str,
+        gamemode: Gamemode,
+        previous_gamemode: PreviousGamemode,
+    ) {
+        log::trace!("Sending Respawn into {} to {}", world_name, self.username);
+        self.send_packet(Respawn {
+            dimension: Nbt(dimension_type(dimension)),
+            world_name: world_identifier(world_name),
+            hashed_seed: 0,
+            gamemode,
+            previous_gamemode: previous_gamemode.0.unwrap_or(gamemode),
+            is_debug: false,
+            is_flat: false,
+            copy_metadata: true,
+        });
+
+        self.known_chunks.borrow_mut().clear();
+        self.chunk_send_queue.borrow_mut().clear();
+        self.sent_entities.borrow_mut().clear();
+        self.knows_position.set(false);
+    }
+
     pub fn send_brand(&self) {
         let mut data = Vec::new();
         "Feather"
             .to_owned()
-            .write(&mut data, ProtocolVersion::V1_16_2)
+            .write(&mut data, ProtocolVersion::LATEST)
             .unwrap();
         self.send_plugin_message("minecraft:brand", data)
     }
@@ -267,6 +317,22 @@ impl Client {
         self.client_known_position.set(Some(new_position));
     }
 
+    /// Sends the border of the client's world,
+    /// including its movement if it is moving.
+    pub fn send_world_border(&self, border: &WorldBorder) {
+        let (x, z) = border.center();
+        self.send_packet(packets::server::WorldBorder::Initialize {
+            x,
+            z,
+            old_diameter: border.radius() * 2.0,
+            new_diameter: border.target_radius() * 2.0,
+            speed: VarLong(border.remaining_ticks() as i64 * base::TICK_MILLIS as i64),
+            portal_teeport_boundary: world_border::MAX_RADIUS as i32,
+            warning_time: border.warning_time as i32,
+            warning_blocks: border.warning_blocks as i32,
+        });
+    }
+
     pub fn update_own_chunk(&self, pos: ChunkPosition) {
         log::trace!("Updating chunk position of {} to {:?}", self.username, pos);
         self.send_packet(UpdateViewPosition {
@@ -292,6 +358,14 @@ impl Client {
         });
     }
 
+    /// Answers an NBT query with an empty compound.
+    pub fn send_empty_nbt_query_response(&self, transaction_id: i32) {
+        self.send_packet(NbtQueryResponse {
+            transaction_id,
+            nbt: Nbt(nbt::Blob::new()),
+        });
+    }
+
     pub fn send_block_change(&self, position: ValidBlockPosition, new_block: BlockId) {
         self.send_packet(BlockChange {
             position,
@@ -644,3 +718,50 @@ fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
         sender: Uuid::default(),
     }
 }
+
+/// Gets the identifier of a world sent to clients.
+///
+/// Clients only reset their world on respawn if
+/// the identifier changes, so each world needs its own.
+fn world_identifier(world_name: &str) -> String {
+    format!("feather:{}", world_name.to_ascii_lowercase())
+}
+
+const DIMENSION_CODEC: &[u8] = include_bytes!("../../../assets/dimension_codec.nbt");
+
+/// Gets the dimension type of `dimension` from the dimension codec.
+fn dimension_type(dimension: Dimension) -> nbt::Blob {
+    #[derive(Deserialize)]
+    struct DimensionCodec {
+        #[serde(rename = "minecraft:dimension_type")]
+        dimension_types: Registry,
+    }
+
+    #[derive(Deserialize)]
+    struct Registry {
+        value: Vec<RegistryEntry>,
+    }
+
+    #[derive(Deserialize)]
+    struct RegistryEntry {
+        name: String,
+        element: HashMap<String, nbt::Value>,
+    }
+
+    let codec: DimensionCodec = nbt::from_reader(&mut Cursor::new(DIMENSION_CODEC))
+        .expect("dimension codec asset is malformed");
+    let entry = codec
+        .dimension_types
+        .value
+        .into_iter()
+        .find(|entry| entry.name == dimension.namespaced_id())
+        .expect("dimension codec is missing a dimension type");
+
+    let mut dimension_type = nbt::Blob::new();
+    for (name, value) in entry.element {
+        dimension_type
+            .insert(name, value)
+            .expect("dimension type is malformed");
+    }
+    dimension_type
+}
@@ -1,12 +1,19 @@
 //! Loads an `Options` from a TOML config.
 
-use std::{fs, net::IpAddr, path::Path, str::FromStr};
+use std::{
+    collections::HashMap, fs, fs::File, net::IpAddr, path::Path, str::FromStr, sync::Arc,
+    time::Duration,
+};
 
 use anyhow::Context;
-use base::Gamemode;
+use base::anvil::level::{LevelData, SuperflatGeneratorOptions};
+use base::{Dimension, Gamemode};
+use common::WorldBorder;
+use plugin_host::PluginLimits;
+use quill_common::world::is_valid_world_name;
 use serde::{Deserialize, Deserializer};
 
-use crate::{favicon::Favicon, Options};
+use crate::{auth, favicon::Favicon, Options};
 
 const DEFAULT_CONFIG: &str = include_str!("../config.toml");
 
@@ -23,7 +30,9 @@ pub fn load(path: &str) -> anyhow::Result<ConfigContainer> {
     }
 
     let config_string = fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&config_string).context("invalid config.toml file")?;
+    let mut config: Config = toml::from_str(&config_string).context("invalid config.toml file")?;
+    config.migrate_legacy_world();
+    config.validate().context("invalid config.toml file")?;
 
     Ok(ConfigContainer {
         config,
@@ -42,11 +51,118 @@ pub struct Config {
     pub network: Network,
     pub server: ServerConfig,
     pub log: Log,
-    pub world: World,
+    #[serde(default)]
+    pub worlds: Vec<WorldConfig>,
+    /// The single `[world]` table of configs written
+    /// before multiple worlds were supported.
+    #[serde(default, rename = "world")]
+    legacy_world: Option<WorldConfig>,
     pub proxy: Proxy,
+    #[serde(default)]
+    pub authentication: Authentication,
+    #[serde(default)]
+    pub plugins: Plugins,
+    #[serde(default)]
+    pub limits: Limits,
 }
 
 impl Config {
+    /// Moves a legacy `[world]` table into [`Config::worlds`],
+    /// unless `[[worlds]]` are configured as well.
+    pub fn migrate_legacy_world(&mut self) {
+        if self.legacy_world.is_some() && self.worlds.is_empty() {
+            log::warn!(
+                "The [world] table in config.toml is deprecated; \
+                 rename it to [[worlds]] to configure more worlds"
+            );
+            self.worlds.extend(self.legacy_world.take());
+        }
+    }
+
+    /// Checks constraints that cannot be expressed
+    /// through deserialization.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.legacy_world.is_some() {
+            anyhow::bail!("the legacy [world] table cannot be combined with [[worlds]]");
+        }
+        if self.worlds.is_empty() {
+            anyhow::bail!("at least one [[worlds]] entry is required");
+        }
+        for (i, world) in self.worlds.iter().enumerate() {
+            if !is_valid_world_name(&world.name) {
+                anyhow::bail!(
+                    "invalid world name {:?}: only letters, digits, '_' and '-' are allowed",
+                    world.name
+                );
+            }
+            if self.worlds[..i]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&world.name))
+            {
+                anyhow::bail!(
+                    "world {:?} is configured more than once (names are case-insensitive)",
+                    world.name
+                );
+            }
+            if !worldgen::GENERATOR_NAMES.contains(&world.generator.as_str()) {
+                anyhow::bail!(
+                    "unknown generator {:?} for world {:?}: valid generators are {}",
+                    world.generator,
+                    world.name,
+                    worldgen::GENERATOR_NAMES.join(", ")
+                );
+            }
+            if !world.flat_preset.is_empty() {
+                if world.generator != "flat" {
+                    anyhow::bail!(
+                        "world {:?} has a flat_preset, but its generator is {:?} rather than \"flat\"",
+                        world.name,
+                        world.generator
+                    );
+                }
+                world
+                    .flat_preset
+                    .parse::<SuperflatGeneratorOptions>()
+                    .with_context(|| format!("invalid flat_preset for world {:?}", world.name))?;
+            }
+            if world.ores.is_some() && !matches!(world.generator.as_str(), "default" | "vanilla") {
+                anyhow::bail!(
+                    "world {:?} has ores, but its generator {:?} does not place configured ores",
+                    world.name,
+                    world.generator
+                );
+            }
+            if let Some(lava_level) = world.lava_level {
+                if !matches!(world.generator.as_str(), "default" | "vanilla") {
+                    anyhow::bail!(
+                        "world {:?} has a lava_level, but its generator {:?} does not use one",
+                        world.name,
+                        world.generator
+                    );
+                }
+                if lava_level >= base::CHUNK_HEIGHT {
+                    anyhow::bail!(
+                        "lava_level of world {:?} must be below {}",
+                        world.name,
+                        base::CHUNK_HEIGHT
+                    );
+                }
+            }
+            if let Some(radius) = world.border_radius {
+                if !(1.0..=common::world_border::MAX_RADIUS).contains(&radius) {
+                    anyhow::bail!(
+                        "invalid border_radius {} for world {:?}: must be between 1 and {}",
+                        radius,
+                        world.name,
+                        common::world_border::MAX_RADIUS
+                    );
+                }
+            }
+        }
+        self.plugins.validate()?;
+        Ok(())
+    }
+
     pub fn to_options(&self) -> Options {
         Options {
             port: self.network.port,
@@ -58,6 +174,10 @@ impl Config {
             } else {
                 self.server.online_mode
             },
+            session_server: self.authentication.session_server.clone(),
+            prevent_proxy_connections: self.authentication.prevent_proxy_connections,
+            authentication_timeout: Duration::from_secs(self.authentication.timeout_secs),
+            whitelist: self.server.whitelist,
             compression_threshold: if self.network.compression_threshold <= 0 {
                 None
             } else {
@@ -65,6 +185,8 @@ impl Config {
             },
             view_distance: self.server.view_distance,
             max_players: self.server.max_players,
+            player_sample_size: self.server.player_sample_size,
+            hide_player_count: self.server.hide_player_count,
             default_gamemode: self.server.default_gamemode,
             proxy_mode: match self.proxy.proxy_mode {
                 ProxyMode::None => None,
@@ -72,6 +194,14 @@ impl Config {
                 ProxyMode::Velocity => Some(crate::options::ProxyMode::Velocity),
             },
             velocity_secret: self.proxy.velocity_secret.clone(),
+            connection_throttle: non_zero(self.limits.connection_throttle_ms)
+                .map(Duration::from_millis),
+            max_handshaking_connections: non_zero(self.limits.max_handshaking_connections as u64)
+                .map(|max| max as usize),
+            login_timeout: Duration::from_secs(self.limits.login_timeout_secs),
+            max_packet_size: self.limits.max_packet_size,
+            max_packets_per_tick: non_zero(self.limits.max_packets_per_tick as u64)
+                .map(|max| max as usize),
         }
     }
 }
@@ -90,6 +220,16 @@ pub struct ServerConfig {
     pub max_players: u32,
     pub default_gamemode: Gamemode,
     pub view_distance: u32,
+    #[serde(default = "default_player_sample_size")]
+    pub player_sample_size: usize,
+    #[serde(default)]
+    pub hide_player_count: bool,
+    #[serde(default)]
+    pub whitelist: bool,
+}
+
+fn default_player_sample_size() -> usize {
+    12
 }
 
 #[derive(Debug, Deserialize)]
@@ -98,11 +238,191 @@ pub struct Log {
     pub level: log::LevelFilter,
 }
 
+/// A world loaded on startup. The first one
+/// is the default world, which players join.
 #[derive(Debug, Deserialize)]
-pub struct World {
+pub struct WorldConfig {
     pub name: String,
     pub generator: String,
     pub seed: String,
+    #[serde(default)]
+    pub dimension: Dimension,
+    /// The superflat preset used by the "flat" generator,
+    /// or empty to use the default layers.
+    #[serde(default)]
+    pub flat_preset: String,
+    /// The `[x, z]` coordinates of the center of the world border.
+    #[serde(default)]
+    pub border_center: [f64; 2],
+    /// The distance from the center to each side of the
+    /// world border, or `None` for the largest possible border.
+    #[serde(default)]
+    pub border_radius: Option<f64>,
+    /// The ores placed by the "default" and "vanilla"
+    /// generators, or `None` to place vanilla's ores.
+    #[serde(default)]
+    pub ores: Option<Vec<worldgen::OreConfig>>,
+    /// The height below which the "default" and "vanilla"
+    /// generators fill caves and ravines with lava,
+    /// or `None` for vanilla's level.
+    #[serde(default)]
+    pub lava_level: Option<usize>,
+}
+
+/// The file in a world directory storing
+/// the seed and generator of the world.
+pub const LEVEL_FILE: &str = "level.dat";
+
+/// The seed of worlds created by Feather versions
+/// which did not write a level.dat.
+const LEGACY_SEED: i64 = 42;
+
+impl WorldConfig {
+    /// Gets the seed to use for generating a new world.
+    ///
+    /// Like in vanilla, an empty seed is random and a seed
+    /// that is not an integer is hashed with Java's `String.hashCode`.
+    pub fn seed(&self) -> u64 {
+        if self.seed.is_empty() {
+            rand::random()
+        } else if let Ok(seed) = self.seed.parse::<i64>() {
+            seed as u64
+        } else {
+            java_string_hash(&self.seed) as i64 as u64
+        }
+    }
+
+    /// Gets the border of this world.
+    pub fn border(&self) -> WorldBorder {
+        let [x, z] = self.border_center;
+        WorldBorder::new(
+            x,
+            z,
+            self.border_radius
+                .unwrap_or(common::world_border::MAX_RADIUS),
+        )
+    }
+
+    /// Loads the level.dat of this world, creating it if the world is new.
+    ///
+    /// Like in vanilla, the configured seed only applies to new worlds,
+    /// so that chunks generated after a restart match the saved chunks.
+    pub fn load_level(&self) -> anyhow::Result<LevelData> {
+        let world_dir = Path::new(&self.name);
+        let level_path = world_dir.join(LEVEL_FILE);
+        if level_path.exists() {
+            return File::open(&level_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut file| LevelData::load_from_file(&mut file))
+                .with_context(|| format!("failed to read {}", level_path.display()));
+        }
+
+        let has_chunks = world_dir
+            .join(self.dimension.directory())
+            .join("region")
+            .exists();
+        let seed = if has_chunks {
+            log::warn!(
+                "World {:?} has no {}; assuming it was generated by an older Feather with seed {}",
+                self.name,
+                LEVEL_FILE,
+                LEGACY_SEED
+            );
+            LEGACY_SEED
+        } else {
+            self.seed() as i64
+        };
+        let generator_options = if self.generator == "flat" {
+            Some(self.configured_superflat_options()?)
+        } else {
+            None
+        };
+        let level = LevelData {
+            seed,
+            generator_name: self.generator.clone(),
+            generator_options,
+            border_size: common::world_border::MAX_RADIUS * 2.0,
+            ..Default::default()
+        };
+
+        fs::create_dir_all(world_dir)?;
+        File::create(&level_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut file| level.save_to_file(&mut file))
+            .with_context(|| format!("failed to write {}", level_path.display()))?;
+        log::info!("Created world {:?} with seed {}", self.name, seed);
+        Ok(level)
+    }
+
+    /// Creates the generator of this world from its level.dat.
+    pub fn generator(
+        &self,
+        level: &LevelData,
+    ) -> anyhow::Result<Arc<dyn worldgen::WorldGenerator>> {
+        let seed = level.seed as u64;
+        if self.generator == "flat" {
+            let options = self.superflat_options(level)?;
+            return Ok(Arc::new(worldgen::SuperflatWorldGenerator::new(
+                options, seed,
+            )?));
+        }
+        if self.ores.is_some() || self.lava_level.is_some() {
+            let mut stages = worldgen::DefaultStages::default();
+            if let Some(ores) = &self.ores {
+                stages.ores = ores.clone();
+            }
+            if let Some(lava_level) = self.lava_level {
+                stages.lava_level = lava_level;
+            }
+            let generator = match self.generator.as_str() {
+                "default" => worldgen::ComposableGenerator::default_with_stages(stages, seed),
+                "vanilla" => worldgen::ComposableGenerator::vanilla_with_stages(stages, seed),
+                _ => unreachable!("ores and lava levels are validated when loading the config"),
+            };
+            return Ok(Arc::new(generator));
+        }
+        Ok(worldgen::generator_by_name(&self.generator, seed)
+            .expect("generator names are validated when loading the config"))
+    }
+
+    /// Gets the options of the superflat generator.
+    ///
+    /// Like in vanilla, the options in the level.dat of an
+    /// existing world take precedence over the configured preset,
+    /// which only applies to new worlds.
+    fn superflat_options(&self, level: &LevelData) -> anyhow::Result<SuperflatGeneratorOptions> {
+        match &level.generator_options {
+            Some(options) => {
+                options.validate().with_context(|| {
+                    format!(
+                        "invalid superflat options in the {} of {:?}",
+                        LEVEL_FILE, self.name
+                    )
+                })?;
+                Ok(options.clone())
+            }
+            // Worlds created by vanilla with the default layers
+            // do not store any options.
+            None => Ok(SuperflatGeneratorOptions::default()),
+        }
+    }
+
+    /// Gets the superflat options configured through `flat_preset`.
+    fn configured_superflat_options(&self) -> anyhow::Result<SuperflatGeneratorOptions> {
+        if self.flat_preset.is_empty() {
+            Ok(SuperflatGeneratorOptions::default())
+        } else {
+            self.flat_preset
+                .parse()
+                .with_context(|| format!("invalid flat_preset for world {:?}", self.name))
+        }
+    }
+}
+
+fn java_string_hash(string: &str) -> i32 {
+    string
+        .encode_utf16()
+        .fold(0i32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as i32))
 }
 
 #[derive(Debug, Deserialize)]
@@ -111,6 +431,156 @@ pub struct Proxy {
     pub velocity_secret: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Authentication {
+    pub session_server: String,
+    pub prevent_proxy_connections: bool,
+    pub timeout_secs: u64,
+}
+
+impl Default for Authentication {
+    fn default() -> Self {
+        Self {
+            session_server: String::from(auth::DEFAULT_SESSION_SERVER),
+            prevent_proxy_connections: false,
+            timeout_secs: auth::DEFAULT_TIMEOUT.as_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub connection_throttle_ms: u64,
+    pub max_handshaking_connections: usize,
+    pub login_timeout_secs: u64,
+    pub max_packet_size: usize,
+    pub max_packets_per_tick: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        let options = Options::default();
+        Self {
+            connection_throttle_ms: options
+                .connection_throttle
+                .map(|throttle| throttle.as_millis() as u64)
+                .unwrap_or(0),
+            max_handshaking_connections: options.max_handshaking_connections.unwrap_or(0),
+            login_timeout_secs: options.login_timeout.as_secs(),
+            max_packet_size: options.max_packet_size,
+            max_packets_per_tick: options.max_packets_per_tick.unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Plugins {
+    pub fuel_per_call: u64,
+    pub max_memory_mb: u64,
+    pub max_faults: u32,
+    pub overrides: HashMap<String, PluginLimitsOverride>,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        let limits = PluginLimits::default();
+        Self {
+            fuel_per_call: limits.fuel_per_call.unwrap_or(0),
+            max_memory_mb: limits
+                .max_memory
+                .map(|bytes| bytes / 1024 / 1024)
+                .unwrap_or(0),
+            max_faults: limits.max_faults,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl Plugins {
+    fn validate(&self) -> anyhow::Result<()> {
+        if memory_limit(self.max_memory_mb).is_none() {
+            anyhow::bail!(
+                "plugins.max_memory_mb must be at most {}",
+                MAX_PLUGIN_MEMORY_MB
+            );
+        }
+        for (identifier, overrides) in &self.overrides {
+            if overrides
+                .max_memory_mb
+                .map_or(false, |mb| memory_limit(mb).is_none())
+            {
+                anyhow::bail!(
+                    "max_memory_mb of plugin {:?} must be at most {}",
+                    identifier,
+                    MAX_PLUGIN_MEMORY_MB
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the limits for plugins without an override.
+    pub fn default_limits(&self) -> PluginLimits {
+        PluginLimits {
+            fuel_per_call: non_zero(self.fuel_per_call),
+            max_memory: non_zero(checked_memory_limit(self.max_memory_mb)),
+            max_faults: self.max_faults,
+        }
+    }
+
+    /// Gets the limits for the plugin with the given identifier.
+    pub fn limits(&self, identifier: &str) -> PluginLimits {
+        let mut limits = self.default_limits();
+        if let Some(overrides) = self.overrides.get(identifier) {
+            if let Some(fuel_per_call) = overrides.fuel_per_call {
+                limits.fuel_per_call = non_zero(fuel_per_call);
+            }
+            if let Some(max_memory_mb) = overrides.max_memory_mb {
+                limits.max_memory = non_zero(checked_memory_limit(max_memory_mb));
+            }
+            if let Some(max_faults) = overrides.max_faults {
+                limits.max_faults = max_faults;
+            }
+        }
+        limits
+    }
+}
+
+/// Per-plugin overrides of the limits in [`Plugins`].
+#[derive(Debug, Default, Deserialize)]
+pub struct PluginLimitsOverride {
+    pub fuel_per_call: Option<u64>,
+    pub max_memory_mb: Option<u64>,
+    pub max_faults: Option<u32>,
+}
+
+/// Largest `max_memory_mb`; WebAssembly memories can't exceed 4 GiB.
+const MAX_PLUGIN_MEMORY_MB: u64 = 4096;
+
+/// Converts a memory limit in megabytes to bytes,
+/// or returns `None` if it is too large.
+fn memory_limit(max_memory_mb: u64) -> Option<u64> {
+    if max_memory_mb > MAX_PLUGIN_MEMORY_MB {
+        return None;
+    }
+    max_memory_mb.checked_mul(1024 * 1024)
+}
+
+fn checked_memory_limit(max_memory_mb: u64) -> u64 {
+    memory_limit(max_memory_mb).expect("plugin memory limits are validated when loading the config")
+}
+
+fn non_zero(value: u64) -> Option<u64> {
+    if value == 0 {
+        None
+    } else {
+        Some(value)
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ProxyMode {
@@ -139,4 +609,271 @@ mod tests {
     fn default_config_is_valid() {
         let _config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
     }
+
+    #[test]
+    fn default_config_worlds() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.worlds[0].name, "world");
+        assert_eq!(config.worlds[1].dimension, Dimension::TheNether);
+    }
+
+    #[test]
+    fn world_seeds() {
+        let world = |seed: &str| WorldConfig {
+            name: "world".to_owned(),
+            generator: "default".to_owned(),
+            seed: seed.to_owned(),
+            dimension: Dimension::Overworld,
+            flat_preset: String::new(),
+            border_center: [0.0, 0.0],
+            border_radius: None,
+            ores: None,
+            lava_level: None,
+        };
+        assert_eq!(world("-5").seed(), -5i64 as u64);
+        assert_eq!(world("feather").seed(), -979220317i64 as u64);
+    }
+
+    #[test]
+    fn legacy_world_table() {
+        let legacy = r#"
+            [network]
+            address = "0.0.0.0"
+            port = 25565
+            compression_threshold = 256
+
+            [server]
+            online_mode = true
+            motd = "A Feather server"
+            max_players = 16
+            default_gamemode = "creative"
+            view_distance = 12
+
+            [log]
+            level = "debug"
+
+            [world]
+            name = "world"
+            generator = "flat"
+            seed = "5"
+
+            [proxy]
+            proxy_mode = "none"
+            velocity_secret = ""
+        "#;
+        let mut config: Config = toml::from_str(legacy).unwrap();
+        config.migrate_legacy_world();
+        config.validate().unwrap();
+        assert_eq!(config.worlds.len(), 1);
+        assert_eq!(config.worlds[0].name, "world");
+        assert_eq!(config.worlds[0].generator, "flat");
+        assert_eq!(config.worlds[0].dimension, Dimension::Overworld);
+
+        let both = format!(
+            r#"{}
+            [[worlds]]
+            name = "other"
+            generator = "default"
+            seed = ""
+            "#,
+            legacy
+        );
+        let mut config: Config = toml::from_str(&both).unwrap();
+        config.migrate_legacy_world();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn worlds_keep_their_seed() {
+        let dir = std::env::temp_dir().join(format!("feather-level-{}", std::process::id()));
+        let world = WorldConfig {
+            name: dir.to_string_lossy().into_owned(),
+            generator: "default".to_owned(),
+            seed: String::new(),
+            dimension: Dimension::Overworld,
+            flat_preset: String::new(),
+            border_center: [0.0, 0.0],
+            border_radius: None,
+            ores: None,
+            lava_level: None,
+        };
+        let seed = world.load_level().unwrap().seed;
+        assert!(dir.join(LEVEL_FILE).exists());
+        assert_eq!(world.load_level().unwrap().seed, seed);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Worlds created before level.dat was written keep their seed.
+        fs::create_dir_all(dir.join("region")).unwrap();
+        assert_eq!(world.load_level().unwrap().seed, LEGACY_SEED);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn flat_worlds_keep_their_preset() {
+        let dir = std::env::temp_dir().join(format!("feather-flat-{}", std::process::id()));
+        let mut world = WorldConfig {
+            name: dir.to_string_lossy().into_owned(),
+            generator: "flat".to_owned(),
+            seed: String::new(),
+            dimension: Dimension::Overworld,
+            flat_preset: "minecraft:bedrock,3*minecraft:stone;minecraft:desert".to_owned(),
+            border_center: [0.0, 0.0],
+            border_radius: None,
+            ores: None,
+            lava_level: None,
+        };
+        world.load_level().unwrap();
+
+        world.flat_preset = "minecraft:bedrock;minecraft:plains".to_owned();
+        let level = world.load_level().unwrap();
+        let options = world.superflat_options(&level).unwrap();
+        assert_eq!(options.layers.len(), 2);
+        assert_eq!(options.layers[1].height, 3);
+        assert_eq!(options.biome, "minecraft:desert");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plugin_limit_overrides() {
+        let plugins: Plugins = toml::from_str(
+            r#"
+            fuel_per_call = 1000
+            max_memory_mb = 0
+            max_faults = 2
+
+            [overrides.worldedit]
+            fuel_per_call = 0
+            max_memory_mb = 512
+            "#,
+        )
+        .unwrap();
+
+        let defaults = plugins.limits("homes");
+        assert_eq!(defaults.fuel_per_call, Some(1000));
+        assert_eq!(defaults.max_memory, None);
+        assert_eq!(defaults.max_faults, 2);
+
+        let worldedit = plugins.limits("worldedit");
+        assert_eq!(worldedit.fuel_per_call, None);
+        assert_eq!(worldedit.max_memory, Some(512 * 1024 * 1024));
+        assert_eq!(worldedit.max_faults, 2);
+    }
+
+    #[test]
+    fn plugin_memory_limits_are_validated() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        config.plugins.max_memory_mb = MAX_PLUGIN_MEMORY_MB;
+        config.validate().unwrap();
+
+        config.plugins.max_memory_mb = u64::MAX;
+        assert!(config.validate().is_err());
+
+        config.plugins.max_memory_mb = 256;
+        config.plugins.overrides.insert(
+            "worldedit".to_owned(),
+            PluginLimitsOverride {
+                max_memory_mb: Some(MAX_PLUGIN_MEMORY_MB + 1),
+                ..Default::default()
+            },
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn world_names_are_unique_ignoring_case() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        config.worlds[1].name = "World".to_owned();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn flat_presets_are_validated() {
+        let config = |generator: &str, preset: &str| {
+            let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+            config.worlds[0].generator = generator.to_owned();
+            config.worlds[0].flat_preset = preset.to_owned();
+            config
+        };
+        config(
+            "flat",
+            "minecraft:bedrock,3*minecraft:stone;minecraft:desert",
+        )
+        .validate()
+        .unwrap();
+        assert!(config("flat", "minecraft:bedrock,3*minecraft:stonee")
+            .validate()
+            .is_err());
+        assert!(config("default", "minecraft:bedrock").validate().is_err());
+    }
+
+    #[test]
+    fn configured_ores() {
+        let load = |generator: &str, ores: &str| {
+            let config = format!(
+                r#"{}
+                [[worlds]]
+                name = "ores"
+                generator = "{}"
+                seed = ""
+                ores = [{}]
+                "#,
+                DEFAULT_CONFIG, generator, ores
+            );
+            toml::from_str::<Config>(&config)
+                .map_err(anyhow::Error::from)
+                .and_then(|config| config.validate().map(|()| config))
+        };
+        let ore = |block: &str, biome: &str| {
+            format!(
+                r#"{{ block = "{}", veins_per_chunk = 4, vein_size = 8, min_height = 0, max_height = 64, biomes = ["{}", "desert"] }}"#,
+                block, biome
+            )
+        };
+
+        let config = load("vanilla", &ore("minecraft:diamond_ore", "minecraft:plains")).unwrap();
+        assert!(config.worlds[0].ores.is_none());
+        let ores = config.worlds[3].ores.as_ref().unwrap();
+        assert_eq!(ores.len(), 1);
+        assert_eq!(ores[0].block, base::BlockId::diamond_ore());
+        assert_eq!(ores[0].heights, 0..64);
+        assert_eq!(
+            ores[0].biomes,
+            Some(vec![base::Biome::Plains, base::Biome::Desert])
+        );
+
+        assert!(load("default", &ore("minecraft:coal_oree", "plains")).is_err());
+        assert!(load("default", &ore("minecraft:coal_ore", "plainz")).is_err());
+        assert!(load("flat", &ore("minecraft:coal_ore", "plains")).is_err());
+    }
+
+    #[test]
+    fn configured_lava_levels() {
+        let config = |generator: &str, lava_level: usize| {
+            let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+            config.worlds[0].generator = generator.to_owned();
+            config.worlds[0].lava_level = Some(lava_level);
+            config
+        };
+        config("vanilla", 20).validate().unwrap();
+        assert!(config("default", base::CHUNK_HEIGHT).validate().is_err());
+        assert!(config("flat", 20).validate().is_err());
+    }
+
+    #[test]
+    fn world_borders() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(config.worlds[0].border(), WorldBorder::default());
+
+        config.worlds[0].border_center = [100.0, -20.5];
+        config.worlds[0].border_radius = Some(500.0);
+        config.validate().unwrap();
+        assert_eq!(
+            config.worlds[0].border(),
+            WorldBorder::new(100.0, -20.5, 500.0)
+        );
+
+        config.worlds[0].border_radius = Some(0.0);
+        assert!(config.validate().is_err());
+    }
 }
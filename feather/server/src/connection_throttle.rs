@@ -0,0 +1,103 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// Number of tracked addresses above which
+/// addresses that are no longer throttled are forgotten.
+const PRUNE_THRESHOLD: usize = 1024;
+
+#[derive(Debug)]
+pub struct Throttled;
+
+/// Limits how often each IP address may attempt to log in.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone)]
+pub struct ConnectionThrottle {
+    inner: Arc<Inner>,
+}
+
+impl ConnectionThrottle {
+    /// Creates a throttle requiring `interval` between two
+    /// attempts from the same address, or one that
+    /// never throttles if `interval` is `None`.
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                interval,
+                last_attempts: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Records an attempt from `ip`. Fails, without recording
+    /// the attempt, if the previous accepted attempt from `ip`
+    /// was less than the interval ago.
+    pub fn try_connect(&self, ip: IpAddr) -> Result<(), Throttled> {
+        self.try_connect_at(ip, Instant::now())
+    }
+
+    fn try_connect_at(&self, ip: IpAddr, now: Instant) -> Result<(), Throttled> {
+        let interval = match self.inner.interval {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+
+        let mut last_attempts = self.inner.last_attempts.lock();
+        if last_attempts.len() >= PRUNE_THRESHOLD {
+            last_attempts.retain(|_, &mut last_attempt| now - last_attempt < interval);
+        }
+
+        match last_attempts.get(&ip) {
+            Some(&last_attempt) if now - last_attempt < interval => Err(Throttled),
+            _ => {
+                last_attempts.insert(ip, now);
+                Ok(())
+            }
+        }
+    }
+}
+
+struct Inner {
+    interval: Option<Duration>,
+    last_attempts: Mutex<HashMap<IpAddr, Instant>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttles_repeated_attempts() {
+        let throttle = ConnectionThrottle::new(Some(Duration::from_secs(4)));
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        let other_ip = IpAddr::from([10, 0, 0, 1]);
+        let start = Instant::now();
+
+        assert!(throttle.try_connect_at(ip, start).is_ok());
+        assert!(throttle.try_connect_at(other_ip, start).is_ok());
+        assert!(throttle
+            .try_connect_at(ip, start + Duration::from_secs(1))
+            .is_err());
+        // The throttled attempt did not restart the interval.
+        assert!(throttle
+            .try_connect_at(ip, start + Duration::from_secs(4))
+            .is_ok());
+        assert!(throttle
+            .try_connect_at(ip, start + Duration::from_secs(5))
+            .is_err());
+    }
+
+    #[test]
+    fn disabled_throttle() {
+        let throttle = ConnectionThrottle::new(None);
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        assert!(throttle.try_connect(ip).is_ok());
+        assert!(throttle.try_connect(ip).is_ok());
+    }
+}
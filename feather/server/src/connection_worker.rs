@@ -1,12 +1,14 @@
 use std::{fmt::Debug, io, net::SocketAddr, sync::Arc, time::Duration};
 
 use base::Text;
+use common::AccessLists;
 use flume::{Receiver, Sender};
 use futures_lite::FutureExt;
 use io::ErrorKind;
 use protocol::{
-    codec::CryptKey, packets::server::Disconnect, ClientPlayPacket, MinecraftCodec, Readable,
-    ServerPlayPacket, Writeable,
+    codec::{CryptKey, PacketTooLarge},
+    packets::server::Disconnect,
+    ClientPlayPacket, MinecraftCodec, ProtocolVersion, Readable, ServerPlayPacket, Writeable,
 };
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -14,15 +16,30 @@ use tokio::{
         tcp::{OwnedReadHalf, OwnedWriteHalf},
         TcpStream,
     },
+    sync::OwnedSemaphorePermit,
     time::timeout,
 };
 
 use crate::{
-    initial_handler::{InitialHandling, NewPlayer},
+    auth::Authenticator,
+    connection_throttle::ConnectionThrottle,
+    initial_handler::{InitialHandling, NewPlayer, StatusPing},
     options::Options,
     player_count::PlayerCount,
 };
 
+/// Handles shared by the `Listener` and all `Worker`s.
+#[derive(Clone)]
+pub struct WorkerContext {
+    pub options: Arc<Options>,
+    pub authenticator: Arc<dyn Authenticator>,
+    pub access_lists: Arc<AccessLists>,
+    pub player_count: PlayerCount,
+    pub connection_throttle: ConnectionThrottle,
+    pub new_players: Sender<NewPlayer>,
+    pub status_pings: Sender<StatusPing>,
+}
+
 /// Tokio task which handles a connection and processes
 /// packets.
 ///
@@ -34,36 +51,46 @@ use crate::{
 pub struct Worker {
     reader: Reader,
     writer: Writer,
-    options: Arc<Options>,
-    player_count: PlayerCount,
+    addr: SocketAddr,
+    context: WorkerContext,
+    /// Counts this connection towards `Options::max_handshaking_connections`
+    /// until initial handling finishes.
+    handshake_permit: Option<OwnedSemaphorePermit>,
     packets_to_send_tx: Sender<ServerPlayPacket>,
     received_packets_rx: Receiver<ClientPlayPacket>,
-    new_players: Sender<NewPlayer>,
 }
 
 impl Worker {
     pub fn new(
         stream: TcpStream,
-        _addr: SocketAddr,
-        options: Arc<Options>,
-        player_count: PlayerCount,
-        new_players: Sender<NewPlayer>,
+        addr: SocketAddr,
+        context: WorkerContext,
+        handshake_permit: Option<OwnedSemaphorePermit>,
     ) -> Self {
         let (reader, writer) = stream.into_split();
 
-        let (received_packets_tx, received_packets_rx) = flume::bounded(32);
+        // Room for one more packet than a player may send in a tick,
+        // so that `handle_packets` can tell when the budget was exceeded.
+        let capacity = context
+            .options
+            .max_packets_per_tick
+            .map_or(32, |max_packets| max_packets + 1);
+        let (received_packets_tx, received_packets_rx) = flume::bounded(capacity);
         let (packets_to_send_tx, packets_to_send_rx) = flume::unbounded();
-        let reader = Reader::new(reader, received_packets_tx);
+        let mut reader = Reader::new(reader, received_packets_tx, packets_to_send_tx.clone());
+        reader
+            .codec
+            .set_max_packet_size(context.options.max_packet_size);
         let writer = Writer::new(writer, packets_to_send_rx);
 
         Self {
             reader,
             writer,
-            options,
-            player_count,
+            addr,
+            context,
+            handshake_permit,
             packets_to_send_tx,
             received_packets_rx,
-            new_players,
         }
     }
 
@@ -75,6 +102,7 @@ impl Worker {
 
     async fn run(mut self) {
         let result = crate::initial_handler::handle(&mut self).await;
+        self.handshake_permit = None;
         match result {
             Ok(result) => self.proceed(result).await,
             Err(e) => log::debug!("Initial handling failed: {:?}", e),
@@ -85,7 +113,13 @@ impl Worker {
         match result {
             InitialHandling::Disconnect => (),
             InitialHandling::Join(new_player) => {
-                if self.player_count.try_add_player().is_err() {
+                if self
+                    .context
+                    .access_lists
+                    .bypasses_player_limit(new_player.uuid)
+                {
+                    self.context.player_count.add_player();
+                } else if self.context.player_count.try_add_player().is_err() {
                     self.write(ServerPlayPacket::Disconnect(Disconnect {
                         reason: Text::from("The server is full!").to_string(),
                     }))
@@ -95,18 +129,39 @@ impl Worker {
                 }
 
                 let username = new_player.username.clone();
-                let _ = self.new_players.send_async(new_player).await;
+                let _ = self.context.new_players.send_async(new_player).await;
                 self.split(username);
             }
         }
     }
 
     pub fn options(&self) -> &Options {
-        &self.options
+        &self.context.options
+    }
+
+    pub fn authenticator(&self) -> Arc<dyn Authenticator> {
+        Arc::clone(&self.context.authenticator)
+    }
+
+    pub fn access_lists(&self) -> &AccessLists {
+        &self.context.access_lists
+    }
+
+    pub fn connection_throttle(&self) -> &ConnectionThrottle {
+        &self.context.connection_throttle
+    }
+
+    pub fn status_pings(&self) -> &Sender<StatusPing> {
+        &self.context.status_pings
+    }
+
+    /// Gets the address of the client.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
     }
 
     pub fn player_count(&self) -> u32 {
-        self.player_count.get()
+        self.context.player_count.get()
     }
 
     #[allow(unused)]
@@ -124,6 +179,17 @@ impl Worker {
         log::debug!("Enabled encryption");
     }
 
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.reader.codec.set_protocol_version(version);
+        self.writer.codec.set_protocol_version(version);
+
+        log::debug!("Using protocol version {}", version);
+    }
+
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.writer.codec.protocol_version()
+    }
+
     pub async fn read<P: Readable>(&mut self) -> anyhow::Result<P> {
         self.reader.read().await
     }
@@ -132,13 +198,26 @@ impl Worker {
         self.writer.write(packet).await
     }
 
+    /// Returns at least `len` received bytes without consuming them,
+    /// or fewer if the client sends nothing more within `wait`.
+    pub async fn peek(&mut self, len: usize, wait: Duration) -> anyhow::Result<&[u8]> {
+        self.reader.peek(len, wait).await
+    }
+
+    /// Writes bytes to the client without framing them as a packet.
+    pub async fn write_raw(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.writer.stream.write_all(bytes).await?;
+        Ok(())
+    }
+
     pub fn split(self, username: String) {
         let Self {
             reader,
             writer,
-            player_count,
+            context,
             ..
         } = self;
+        let player_count = context.player_count;
         let reader = tokio::task::spawn(async move { reader.run().await });
         let writer = tokio::task::spawn(async move { writer.run().await });
 
@@ -166,21 +245,40 @@ struct Reader {
     codec: MinecraftCodec,
     buffer: [u8; 512],
     received_packets: Sender<ClientPlayPacket>,
+    /// Used to tell the client why it is disconnected.
+    packets_to_send: Sender<ServerPlayPacket>,
 }
 
 impl Reader {
-    pub fn new(stream: OwnedReadHalf, received_packets: Sender<ClientPlayPacket>) -> Self {
+    pub fn new(
+        stream: OwnedReadHalf,
+        received_packets: Sender<ClientPlayPacket>,
+        packets_to_send: Sender<ServerPlayPacket>,
+    ) -> Self {
         Self {
             stream,
             codec: MinecraftCodec::new(),
             buffer: [0; 512],
             received_packets,
+            packets_to_send,
         }
     }
 
     pub async fn run(mut self) -> anyhow::Result<()> {
         loop {
-            let packet = self.read::<ClientPlayPacket>().await?;
+            let packet = match self.read::<ClientPlayPacket>().await {
+                Ok(packet) => packet,
+                Err(e) => {
+                    if e.is::<PacketTooLarge>() {
+                        let _ =
+                            self.packets_to_send
+                                .send(ServerPlayPacket::Disconnect(Disconnect {
+                                    reason: Text::from("Packet too large!").to_string(),
+                                }));
+                    }
+                    return Err(e);
+                }
+            };
             let result = self.received_packets.send_async(packet).await;
             if result.is_err() {
                 // server dropped connection
@@ -189,6 +287,22 @@ impl Reader {
         }
     }
 
+    pub async fn peek(&mut self, len: usize, wait: Duration) -> anyhow::Result<&[u8]> {
+        while self.codec.pending_bytes().len() < len {
+            let read_bytes = match timeout(wait, self.stream.read(&mut self.buffer)).await {
+                Ok(read_bytes) => read_bytes?,
+                Err(_) => break,
+            };
+            if read_bytes == 0 {
+                break;
+            }
+
+            let bytes = &self.buffer[..read_bytes];
+            self.codec.accept(bytes);
+        }
+        Ok(self.codec.pending_bytes())
+    }
+
     pub async fn read<P: Readable>(&mut self) -> anyhow::Result<P> {
         // Keep reading bytes and trying to get the packet.
         loop {
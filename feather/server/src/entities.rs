@@ -3,7 +3,7 @@ use ecs::{EntityBuilder, EntityRef, SysResult};
 use quill_common::{components::OnGround, entity_init::EntityInit};
 use uuid::Uuid;
 
-use crate::{Client, NetworkId};
+use crate::{Client, NetworkId, NetworkIdRegistry};
 
 /// Component that sends the spawn packet for an entity
 /// using its components.
@@ -26,9 +26,13 @@ pub struct PreviousPosition(pub Position);
 #[derive(Copy, Clone, Debug)]
 pub struct PreviousOnGround(pub OnGround);
 
-pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
+pub fn add_entity_components(
+    builder: &mut EntityBuilder,
+    init: &EntityInit,
+    network_ids: &NetworkIdRegistry,
+) {
     if !builder.has::<NetworkId>() {
-        builder.add(NetworkId::new());
+        builder.add(network_ids.allocate());
     }
 
     // can't panic because this is only called after both position and onground is added to all entities.
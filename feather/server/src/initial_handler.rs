@@ -1,6 +1,9 @@
 //! Initial handling of a connection.
 
-use crate::{connection_worker::Worker, favicon::Favicon};
+use crate::{
+    auth::{AuthError, AuthRequest, AuthResponse},
+    connection_worker::Worker,
+};
 use anyhow::bail;
 use base::{ProfileProperty, Text};
 use flume::{Receiver, Sender};
@@ -10,27 +13,25 @@ use once_cell::sync::Lazy;
 use protocol::{
     codec::CryptKey,
     packets::{
-        client::{HandshakeState, Ping},
-        server::{
-            DisconnectLogin, EncryptionRequest, LoginSuccess, Pong, Response, SetCompression,
-        },
+        client::HandshakeState,
+        server::{DisconnectLogin, EncryptionRequest, LoginSuccess, SetCompression},
     },
-    ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket,
-    ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
+    ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ProtocolVersion, ServerLoginPacket,
+    ServerPlayPacket,
 };
 use rand::rngs::OsRng;
 use rsa::{PaddingScheme, PublicKeyParts, RsaPrivateKey};
-use serde::{Deserialize, Serialize};
 use sha1::Sha1;
-use std::convert::TryInto;
+use std::{convert::TryInto, net::IpAddr};
+use tokio::time::timeout;
 use uuid::Uuid;
 
 use self::proxy::ProxyData;
 
-const SERVER_NAME: &str = "Feather 1.16.5";
-const PROTOCOL_VERSION: i32 = 754;
-
 mod proxy;
+mod status;
+
+pub use status::StatusPing;
 
 /// Information for a newly connected player.
 #[derive(Debug)]
@@ -38,6 +39,10 @@ pub struct NewPlayer {
     pub uuid: Uuid,
     pub username: String,
     pub profile: Vec<ProfileProperty>,
+    pub protocol_version: ProtocolVersion,
+    /// The client's IP address. Behind a proxy, this
+    /// is the address forwarded by the proxy.
+    pub ip: IpAddr,
 
     pub received_packets: Receiver<ClientPlayPacket>,
     pub packets_to_send: Sender<ServerPlayPacket>,
@@ -54,26 +59,71 @@ pub enum InitialHandling {
 
 /// Handles a connection until the protocol state is switched to Play;
 /// that is, until we send Login Success. Returns the client's information.
+///
+/// Login attempts are throttled by IP address, and the whole
+/// exchange, including the handshake and any status ping,
+/// must finish within `Options::login_timeout`.
 pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
+    let mut logging_in = false;
+    let login_timeout = worker.options().login_timeout;
+    let result = timeout(login_timeout, handle_connection(worker, &mut logging_in)).await;
+    match result {
+        Ok(result) => result,
+        Err(_) => {
+            log::debug!("{} took too long to log in", worker.addr());
+            if logging_in {
+                disconnect_login(worker, "Took too long to log in").await
+            } else {
+                Ok(InitialHandling::Disconnect)
+            }
+        }
+    }
+}
+
+/// Handles the handshake and then the status ping or login.
+/// Sets `logging_in` once the client switches to the Login state.
+async fn handle_connection(
+    worker: &mut Worker,
+    logging_in: &mut bool,
+) -> anyhow::Result<InitialHandling> {
+    if status::is_legacy_ping(worker).await? {
+        return status::handle_legacy_ping(worker).await;
+    }
+
     // Get the handshake packet.
     let handshake = worker.read::<ClientHandshakePacket>().await?;
 
     let ClientHandshakePacket::Handshake(handshake) = handshake;
 
+    let version = ProtocolVersion::from_protocol_number(handshake.protocol_version);
+    if let Some(version) = version {
+        worker.set_protocol_version(version);
+    }
+
     match handshake.next_state {
-        HandshakeState::Status => handle_status(worker).await,
+        HandshakeState::Status => status::handle_status(worker, handshake.protocol_version).await,
         HandshakeState::Login => {
-            if handshake.protocol_version < PROTOCOL_VERSION {
-                worker
-                    .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
-                        reason: Text::from(
-                            "Invalid protocol! The server is running on version 1.16!",
-                        )
-                        .to_string(),
-                    }))
-                    .await
-                    .ok();
-                return Ok(InitialHandling::Disconnect);
+            *logging_in = true;
+            // Behind a proxy, every connection comes from the proxy's address.
+            if worker.options().proxy_mode.is_none()
+                && worker
+                    .connection_throttle()
+                    .try_connect(worker.addr().ip())
+                    .is_err()
+            {
+                log::debug!("Throttled login from {}", worker.addr());
+                return disconnect_login(
+                    worker,
+                    "Connection throttled! Please wait before reconnecting.",
+                )
+                .await;
+            }
+            if version.is_none() {
+                let reason = format!(
+                    "Unsupported protocol! The server supports versions {}.",
+                    ProtocolVersion::supported_range()
+                );
+                return disconnect_login(worker, reason).await;
             }
             let proxy_data =
                 if let Some(crate::options::ProxyMode::Bungeecord) = worker.options().proxy_mode {
@@ -86,68 +136,6 @@ pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
     }
 }
 
-#[derive(Debug, Serialize)]
-struct StatusResponse<'a> {
-    version: Version,
-    players: Players,
-    description: Text,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    favicon: Option<&'a str>,
-}
-
-#[derive(Debug, Serialize)]
-struct Version {
-    name: &'static str,
-    protocol: i32,
-}
-
-#[derive(Debug, Serialize)]
-struct Players {
-    max: u32,
-    online: u32,
-}
-
-async fn handle_status(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
-    let _request = worker.read::<ClientStatusPacket>().await?;
-
-    let payload = StatusResponse {
-        version: Version {
-            name: SERVER_NAME,
-            protocol: PROTOCOL_VERSION,
-        },
-        players: Players {
-            max: worker.options().max_players,
-            online: worker.player_count(),
-        },
-        description: Text::from(worker.options().motd.clone()),
-        favicon: worker
-            .options()
-            .favicon
-            .as_ref()
-            .map(Favicon::base64_encoded),
-    };
-    let response = Response {
-        response: serde_json::to_string(&payload)?,
-    };
-    worker
-        .write(&ServerStatusPacket::Response(response))
-        .await?;
-
-    match worker.read::<Ping>().await {
-        Ok(ping) => {
-            let pong = Pong {
-                payload: ping.payload,
-            };
-            worker.write(&ServerStatusPacket::Pong(pong)).await?;
-        }
-        Err(e) => {
-            log::debug!("Didn't receive ping packet from status call: {}", e);
-        }
-    }
-
-    Ok(InitialHandling::Disconnect)
-}
-
 async fn handle_login(
     worker: &mut Worker,
     mut proxy_data: Option<ProxyData>,
@@ -163,8 +151,9 @@ async fn handle_login(
         proxy_data = Some(proxy::do_velocity_ip_forwarding(worker).await?);
     }
 
+    let ip = client_ip(worker, proxy_data.as_ref());
     if worker.options().online_mode {
-        enable_encryption(worker, login_start.name).await
+        enable_encryption(worker, login_start.name, ip).await
     } else {
         let profile = match proxy_data {
             Some(proxy_data) => AuthResponse {
@@ -174,10 +163,18 @@ async fn handle_login(
             },
             None => offline_mode_profile(login_start.name),
         };
-        finish_login(worker, profile).await
+        finish_login(worker, profile, ip).await
     }
 }
 
+/// Gets the IP address of the client, using
+/// the address forwarded by the proxy if there is one.
+fn client_ip(worker: &Worker, proxy_data: Option<&ProxyData>) -> IpAddr {
+    proxy_data
+        .and_then(|proxy_data| proxy_data.client.parse().ok())
+        .unwrap_or_else(|| worker.addr().ip())
+}
+
 fn offline_mode_profile(username: String) -> AuthResponse {
     // TODO: correct offline mode handling
     AuthResponse {
@@ -214,14 +211,32 @@ static RSA_KEY_ENCODED: Lazy<Vec<u8>> = Lazy::new(|| {
 async fn enable_encryption(
     worker: &mut Worker,
     username: String,
+    ip: IpAddr,
 ) -> anyhow::Result<InitialHandling> {
     log::debug!("Authenticating {}", username);
     let shared_secret = do_encryption_handshake(worker).await?;
     worker.enable_encryption(shared_secret);
 
-    let response = authenticate(shared_secret, username).await?;
+    match authenticate(worker, shared_secret, username).await? {
+        Ok(response) => finish_login(worker, response, ip).await,
+        Err(e) => {
+            log::debug!("Authentication failed: {}", e);
+            disconnect_login(worker, e.disconnect_message()).await
+        }
+    }
+}
 
-    finish_login(worker, response).await
+async fn disconnect_login(
+    worker: &mut Worker,
+    reason: impl Into<Text>,
+) -> anyhow::Result<InitialHandling> {
+    worker
+        .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+            reason: reason.into().to_string(),
+        }))
+        .await
+        .ok();
+    Ok(InitialHandling::Disconnect)
 }
 
 async fn do_encryption_handshake(worker: &mut Worker) -> anyhow::Result<CryptKey> {
@@ -252,28 +267,27 @@ async fn do_encryption_handshake(worker: &mut Worker) -> anyhow::Result<CryptKey
     Ok((&shared_secret[..]).try_into()?)
 }
 
-#[derive(Debug, Deserialize)]
-struct AuthResponse {
-    id: Uuid,
-    name: String,
-    properties: Vec<ProfileProperty>,
-}
-
-async fn authenticate(shared_secret: CryptKey, username: String) -> anyhow::Result<AuthResponse> {
-    let server_hash = compute_server_hash(shared_secret);
-
-    let response: AuthResponse = tokio::task::spawn_blocking(move || {
-        let url = format!(
-            "https://sessionserver.mojang.com/session/minecraft/hasJoined?username={}&serverId={}",
-            username, server_hash
-        );
-        let response = ureq::get(&url).call()?;
-
-        Result::<AuthResponse, anyhow::Error>::Ok(response.into_json()?)
-    })
-    .await??;
+/// Authenticates the player with the server's `Authenticator`.
+///
+/// The outer `Result` fails if the authenticator panicked.
+async fn authenticate(
+    worker: &Worker,
+    shared_secret: CryptKey,
+    username: String,
+) -> anyhow::Result<Result<AuthResponse, AuthError>> {
+    let request = AuthRequest {
+        username,
+        server_hash: compute_server_hash(shared_secret),
+        ip: if worker.options().prevent_proxy_connections {
+            Some(worker.addr().ip())
+        } else {
+            None
+        },
+    };
 
-    Ok(response)
+    let authenticator = worker.authenticator();
+    let result = tokio::task::spawn_blocking(move || authenticator.authenticate(&request)).await?;
+    Ok(result)
 }
 
 fn compute_server_hash(shared_secret: CryptKey) -> String {
@@ -293,7 +307,13 @@ fn hexdigest(bytes: &[u8]) -> String {
 async fn finish_login(
     worker: &mut Worker,
     response: AuthResponse,
+    ip: IpAddr,
 ) -> anyhow::Result<InitialHandling> {
+    if let Err(denied) = worker.access_lists().check_login(response.id, ip) {
+        log::info!("{} ({}) may not join: {:?}", response.name, ip, denied);
+        return disconnect_login(worker, denied.disconnect_message()).await;
+    }
+
     enable_compression(worker).await?;
 
     let success = LoginSuccess {
@@ -308,6 +328,8 @@ async fn finish_login(
         username: response.name,
         uuid: response.id,
         profile: response.properties,
+        protocol_version: worker.protocol_version(),
+        ip,
         received_packets: worker.received_packets(),
         packets_to_send: worker.packets_to_send(),
     };
@@ -325,3 +347,273 @@ async fn enable_compression(worker: &mut Worker) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use common::AccessLists;
+    use protocol::{
+        packets::client::{EncryptionResponse, Handshake, LoginStart, Request},
+        ClientStatusPacket, MinecraftCodec, Readable, ServerStatusPacket, Writeable,
+    };
+    use rsa::{BigUint, PublicKey, RsaPublicKey};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+    use crate::{
+        auth::FakeAuthenticator, connection_throttle::ConnectionThrottle,
+        connection_worker::WorkerContext, options::Options, player_count::PlayerCount,
+    };
+
+    /// The client side of a connection.
+    struct TestClient {
+        stream: TcpStream,
+        codec: MinecraftCodec,
+    }
+
+    impl TestClient {
+        async fn write(&mut self, packet: impl Writeable) {
+            let mut buffer = Vec::new();
+            self.codec.encode(&packet, &mut buffer).unwrap();
+            self.stream.write_all(&buffer).await.unwrap();
+        }
+
+        async fn read<P: Readable>(&mut self) -> P {
+            let mut buffer = [0; 512];
+            loop {
+                if let Some(packet) = self.codec.next_packet::<P>().unwrap() {
+                    return packet;
+                }
+                let read_bytes = self.stream.read(&mut buffer).await.unwrap();
+                assert_ne!(read_bytes, 0, "server closed the connection");
+                self.codec.accept(&buffer[..read_bytes]);
+            }
+        }
+
+        /// Logs in as `username`, returning the shared secret
+        /// once the encryption handshake is done.
+        async fn log_in(&mut self, username: &str) -> CryptKey {
+            self.write(ClientHandshakePacket::Handshake(Handshake {
+                protocol_version: ProtocolVersion::LATEST.protocol_number(),
+                server_address: String::from("localhost"),
+                server_port: 25565,
+                next_state: HandshakeState::Login,
+            }))
+            .await;
+            self.write(ClientLoginPacket::LoginStart(LoginStart {
+                name: username.to_owned(),
+            }))
+            .await;
+
+            let request = match self.read::<ServerLoginPacket>().await {
+                ServerLoginPacket::EncryptionRequest(request) => request,
+                packet => panic!("expected an encryption request, got {:?}", packet),
+            };
+            let (n, e) = rsa_der::public_key_from_der(&request.public_key).unwrap();
+            let key =
+                RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)).unwrap();
+
+            let shared_secret: CryptKey = rand::random();
+            let response = EncryptionResponse {
+                shared_secret: key
+                    .encrypt(&mut OsRng, PaddingScheme::PKCS1v15Encrypt, &shared_secret)
+                    .unwrap(),
+                verify_token: key
+                    .encrypt(
+                        &mut OsRng,
+                        PaddingScheme::PKCS1v15Encrypt,
+                        &request.verify_token,
+                    )
+                    .unwrap(),
+            };
+            self.write(ClientLoginPacket::EncryptionResponse(response))
+                .await;
+            self.codec.enable_encryption(shared_secret);
+            shared_secret
+        }
+    }
+
+    /// Connects a client to a worker which authenticates
+    /// players with `authenticator`.
+    async fn connect(authenticator: Arc<FakeAuthenticator>) -> (TestClient, Worker) {
+        connect_throttled(authenticator, ConnectionThrottle::new(None)).await
+    }
+
+    /// Like `connect`, but logins are throttled by `connection_throttle`.
+    async fn connect_throttled(
+        authenticator: Arc<FakeAuthenticator>,
+        connection_throttle: ConnectionThrottle,
+    ) -> (TestClient, Worker) {
+        let options = Options {
+            compression_threshold: None,
+            connection_throttle: None,
+            ..Default::default()
+        };
+        let (new_players, _) = flume::unbounded();
+        let (status_pings, _) = flume::unbounded();
+        let context = WorkerContext {
+            authenticator,
+            access_lists: Arc::new(AccessLists::in_memory(false)),
+            player_count: PlayerCount::new(options.max_players),
+            connection_throttle,
+            options: Arc::new(options),
+            new_players,
+            status_pings,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, addr) = listener.accept().await.unwrap();
+
+        let client = TestClient {
+            stream: client,
+            codec: MinecraftCodec::new(),
+        };
+        (client, Worker::new(stream, addr, context, None))
+    }
+
+    #[tokio::test]
+    async fn authenticated_players_join() {
+        let id = Uuid::from_u128(0x905c7e4fb96b45139645d123225575e2);
+        let authenticator = Arc::new(FakeAuthenticator::new());
+        authenticator.add_profile(AuthResponse {
+            id,
+            name: String::from("caelunshun"),
+            properties: Vec::new(),
+        });
+        let (mut client, mut worker) = connect(Arc::clone(&authenticator)).await;
+
+        let client = tokio::spawn(async move {
+            let shared_secret = client.log_in("caelunshun").await;
+            match client.read::<ServerLoginPacket>().await {
+                ServerLoginPacket::LoginSuccess(success) => {
+                    assert_eq!(success.uuid, id);
+                    assert_eq!(success.username, "caelunshun");
+                }
+                packet => panic!("expected login success, got {:?}", packet),
+            }
+            shared_secret
+        });
+
+        let new_player = match handle(&mut worker).await.unwrap() {
+            InitialHandling::Join(new_player) => new_player,
+            InitialHandling::Disconnect => panic!("player was disconnected"),
+        };
+        assert_eq!(new_player.uuid, id);
+        assert_eq!(new_player.username, "caelunshun");
+
+        let shared_secret = client.await.unwrap();
+        assert_eq!(
+            authenticator.requests(),
+            vec![AuthRequest {
+                username: String::from("caelunshun"),
+                server_hash: compute_server_hash(shared_secret),
+                ip: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_players_are_disconnected() {
+        let authenticator = Arc::new(FakeAuthenticator::new());
+        let (mut client, mut worker) = connect(Arc::clone(&authenticator)).await;
+
+        let client = tokio::spawn(async move {
+            client.log_in("notch").await;
+            match client.read::<ServerLoginPacket>().await {
+                ServerLoginPacket::DisconnectLogin(disconnect) => {
+                    assert!(disconnect.reason.contains("Failed to verify username!"))
+                }
+                packet => panic!("expected to be disconnected, got {:?}", packet),
+            }
+        });
+
+        assert!(matches!(
+            handle(&mut worker).await.unwrap(),
+            InitialHandling::Disconnect
+        ));
+        client.await.unwrap();
+        assert_eq!(authenticator.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn pings_do_not_throttle_logins() {
+        let authenticator = Arc::new(FakeAuthenticator::new());
+        authenticator.add_profile(AuthResponse {
+            id: Uuid::from_u128(1),
+            name: String::from("caelunshun"),
+            properties: Vec::new(),
+        });
+        let throttle = ConnectionThrottle::new(Some(Duration::from_secs(60)));
+
+        // A server list ping...
+        let (mut client, mut worker) =
+            connect_throttled(Arc::clone(&authenticator), throttle.clone()).await;
+        let client = tokio::spawn(async move {
+            client
+                .write(ClientHandshakePacket::Handshake(Handshake {
+                    protocol_version: ProtocolVersion::LATEST.protocol_number(),
+                    server_address: String::from("localhost"),
+                    server_port: 25565,
+                    next_state: HandshakeState::Status,
+                }))
+                .await;
+            client.write(ClientStatusPacket::Request(Request {})).await;
+            assert!(matches!(
+                client.read::<ServerStatusPacket>().await,
+                ServerStatusPacket::Response(_)
+            ));
+        });
+        assert!(matches!(
+            handle(&mut worker).await.unwrap(),
+            InitialHandling::Disconnect
+        ));
+        client.await.unwrap();
+
+        // ...followed by a login from the same address.
+        let (mut client, mut worker) =
+            connect_throttled(Arc::clone(&authenticator), throttle.clone()).await;
+        let client = tokio::spawn(async move {
+            client.log_in("caelunshun").await;
+            assert!(matches!(
+                client.read::<ServerLoginPacket>().await,
+                ServerLoginPacket::LoginSuccess(_)
+            ));
+        });
+        assert!(matches!(
+            handle(&mut worker).await.unwrap(),
+            InitialHandling::Join(_)
+        ));
+        client.await.unwrap();
+
+        // A second login is throttled with a reason.
+        let (mut client, mut worker) = connect_throttled(authenticator, throttle).await;
+        let client = tokio::spawn(async move {
+            client
+                .write(ClientHandshakePacket::Handshake(Handshake {
+                    protocol_version: ProtocolVersion::LATEST.protocol_number(),
+                    server_address: String::from("localhost"),
+                    server_port: 25565,
+                    next_state: HandshakeState::Login,
+                }))
+                .await;
+            match client.read::<ServerLoginPacket>().await {
+                ServerLoginPacket::DisconnectLogin(disconnect) => {
+                    assert!(disconnect.reason.contains("Connection throttled!"))
+                }
+                packet => panic!("expected to be disconnected, got {:?}", packet),
+            }
+        });
+        assert!(matches!(
+            handle(&mut worker).await.unwrap(),
+            InitialHandling::Disconnect
+        ));
+        client.await.unwrap();
+    }
+}
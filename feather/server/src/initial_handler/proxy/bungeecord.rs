@@ -41,16 +41,14 @@ pub fn extract(packet: &Handshake) -> anyhow::Result<ProxyData> {
 #[cfg(test)]
 mod tests {
     use base::ProfileProperty;
-    use protocol::packets::client::HandshakeState;
-
-    use crate::initial_handler::PROTOCOL_VERSION;
+    use protocol::{packets::client::HandshakeState, ProtocolVersion};
 
     use super::*;
 
     #[test]
     fn extract_bungeecord_data_normal() {
         let handshake = Handshake {
-           protocol_version: PROTOCOL_VERSION,
+           protocol_version: ProtocolVersion::LATEST.protocol_number(),
            server_address: "192.168.1.87\x00192.168.1.67\x00905c7e4fb96b45139645d123225575e2\x00[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
            server_port: 25565,
            next_state: HandshakeState::Login,
@@ -74,7 +72,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_too_short() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
             server_address: "192.168.1.87\x00192.168.1.67\x00905c7e4fb96b45139645d123225575e2"
                 .to_string(),
             server_port: 25565,
@@ -87,7 +85,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_too_long() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
             server_address:
                 "192.168.1.87\x00192.168.1.67\x00905c7e4fb96b45139645d123225575e2\x00a\x00b"
                     .to_string(),
@@ -101,7 +99,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_localhost_host_ip() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
             server_address: "localhost\x00192.168.1.67\x00905c7e4fb96b45139645d123225575e2\x00[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -125,7 +123,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_localhost_client_ip() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
             server_address: "192.168.1.87\x00localhost\x00905c7e4fb96b45139645d123225575e2\x00[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -149,7 +147,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_invalid_uuid() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
             server_address: "192.168.1.87\x00192.168.1.67\x0005c7e4fb9675e2\x00[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -161,7 +159,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_invalid_properties() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
             server_address: "192.168.1.87\x00192.168.1.67\x00905c7e4fb96b45139645d123225575e2\x00[{\"name\":\"textures\",\"value\":\"textures_value\",\"sinature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
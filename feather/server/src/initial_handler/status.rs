@@ -0,0 +1,233 @@
+//! Server list pings, including the legacy (pre-1.7) format.
+
+use std::time::Duration;
+
+use base::Text;
+use flume::Sender;
+use protocol::{
+    packets::{
+        client::Ping,
+        server::{Pong, Response},
+    },
+    ClientStatusPacket, ProtocolVersion, ServerStatusPacket,
+};
+use quill_common::events::{PlayerSample, ServerListPingEvent};
+use serde::Serialize;
+use tokio::time::timeout;
+
+use crate::connection_worker::Worker;
+
+use super::InitialHandling;
+
+/// How long to wait for the main thread to respond
+/// to a ping before falling back to the default response.
+const STATUS_PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long to wait for the rest of a legacy ping
+/// after its first byte.
+const LEGACY_PING_WAIT: Duration = Duration::from_millis(100);
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// First byte of a legacy ping.
+const LEGACY_PING: u8 = 0xFE;
+/// ID of the legacy kick packet, which carries the response.
+const LEGACY_KICK: u8 = 0xFF;
+/// Protocol version sent in legacy responses. Legacy clients
+/// can't join, so this is chosen to never match theirs.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// A server list ping forwarded to the main thread,
+/// where plugins may modify the response.
+#[derive(Debug)]
+pub struct StatusPing {
+    /// The default response.
+    pub event: ServerListPingEvent,
+    /// Channel to send the final response through.
+    pub response: Sender<ServerListPingEvent>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusResponse<'a> {
+    version: Version<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    players: Option<Players<'a>>,
+    description: Text,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    favicon: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Version<'a> {
+    name: &'a str,
+    protocol: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct Players<'a> {
+    max: u32,
+    online: u32,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    sample: &'a [PlayerSample],
+}
+
+pub async fn handle_status(
+    worker: &mut Worker,
+    client_protocol_version: i32,
+) -> anyhow::Result<InitialHandling> {
+    let _request = worker.read::<ClientStatusPacket>().await?;
+
+    let event = server_list_ping(worker, Some(client_protocol_version)).await;
+    let payload = StatusResponse {
+        version: Version {
+            name: &event.version_name,
+            protocol: event.protocol_version,
+        },
+        players: if event.hide_player_count {
+            None
+        } else {
+            Some(Players {
+                max: event.max_players,
+                online: event.online_players,
+                sample: &event.player_sample,
+            })
+        },
+        description: Text::from(event.motd.clone()),
+        favicon: event.favicon.as_deref(),
+    };
+    let response = Response {
+        response: serde_json::to_string(&payload)?,
+    };
+    worker
+        .write(&ServerStatusPacket::Response(response))
+        .await?;
+
+    match worker.read::<Ping>().await {
+        Ok(ping) => {
+            let pong = Pong {
+                payload: ping.payload,
+            };
+            worker.write(&ServerStatusPacket::Pong(pong)).await?;
+        }
+        Err(e) => {
+            log::debug!("Didn't receive ping packet from status call: {}", e);
+        }
+    }
+
+    Ok(InitialHandling::Disconnect)
+}
+
+/// Builds the default response to a ping and gives
+/// plugins a chance to modify it.
+async fn server_list_ping(
+    worker: &Worker,
+    client_protocol_version: Option<i32>,
+) -> ServerListPingEvent {
+    let options = worker.options();
+    let event = ServerListPingEvent {
+        address: worker.addr().ip(),
+        client_protocol_version,
+        motd: options.motd.clone(),
+        version_name: format!("Feather {}", ProtocolVersion::supported_range()),
+        // Echo a supported client's own version so it is shown
+        // as compatible. Otherwise, advertise the latest version.
+        protocol_version: client_protocol_version
+            .and_then(ProtocolVersion::from_protocol_number)
+            .unwrap_or(ProtocolVersion::LATEST)
+            .protocol_number(),
+        online_players: worker.player_count(),
+        max_players: options.max_players,
+        hide_player_count: options.hide_player_count,
+        player_sample: Vec::new(),
+        favicon: options
+            .favicon
+            .as_ref()
+            .map(|favicon| favicon.base64_encoded().to_owned()),
+    };
+
+    let (response_tx, response) = flume::bounded(1);
+    let ping = StatusPing {
+        event: event.clone(),
+        response: response_tx,
+    };
+    if worker.status_pings().try_send(ping).is_err() {
+        return event;
+    }
+
+    match timeout(STATUS_PING_TIMEOUT, response.recv_async()).await {
+        Ok(Ok(event)) => event,
+        _ => {
+            log::debug!("Main thread did not respond to server list ping in time");
+            event
+        }
+    }
+}
+
+/// Determines whether the connection starts with a legacy ping.
+pub async fn is_legacy_ping(worker: &mut Worker) -> anyhow::Result<bool> {
+    if worker.peek(1, READ_TIMEOUT).await?.first() != Some(&LEGACY_PING) {
+        return Ok(false);
+    }
+
+    // A handshake at least 254 bytes long also starts with 0xFE,
+    // but its packet ID (0x00) follows the two-byte length. Legacy
+    // pings are 0xFE, 0xFE 0x01, or 0xFE 0x01 0xFA.
+    let bytes = worker.peek(3, LEGACY_PING_WAIT).await?;
+    Ok(bytes.get(2) != Some(&0x00))
+}
+
+/// Responds to a legacy ping with the legacy kick packet.
+///
+/// Beta 1.8 to 1.3 clients send only `0xFE` and expect
+/// `motd§online§max`. Later clients send `0xFE 0x01` and
+/// expect null-separated fields starting with `§1`.
+pub async fn handle_legacy_ping(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
+    let is_beta = worker.peek(2, Duration::from_secs(0)).await?.get(1) != Some(&0x01);
+
+    let event = server_list_ping(worker, None).await;
+    let (online, max) = if event.hide_player_count {
+        (0, 0)
+    } else {
+        (event.online_players, event.max_players)
+    };
+
+    let message = if is_beta {
+        format!("{}§{}§{}", event.motd.replace('§', ""), online, max)
+    } else {
+        format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            LEGACY_PROTOCOL_VERSION,
+            event.version_name.replace('\0', ""),
+            event.motd.replace('\0', ""),
+            online,
+            max
+        )
+    };
+
+    worker.write_raw(&encode_legacy_kick(&message)).await?;
+    Ok(InitialHandling::Disconnect)
+}
+
+fn encode_legacy_kick(message: &str) -> Vec<u8> {
+    let chars: Vec<u16> = message.encode_utf16().collect();
+    let mut bytes = Vec::with_capacity(3 + chars.len() * 2);
+    bytes.push(LEGACY_KICK);
+    bytes.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+    for c in chars {
+        bytes.extend_from_slice(&c.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_kick_encoding() {
+        let bytes = encode_legacy_kick("A§1");
+        assert_eq!(
+            bytes,
+            [0xFF, 0x00, 0x03, 0x00, 0x41, 0x00, 0xA7, 0x00, 0x31]
+        );
+    }
+}
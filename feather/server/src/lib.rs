@@ -2,17 +2,23 @@
 
 use std::{sync::Arc, time::Instant};
 
+use auth::{Authenticator, MojangAuthenticator};
 use base::Position;
 use chunk_subscriptions::ChunkSubscriptions;
-use common::Game;
-use ecs::SystemExecutor;
-use flume::Receiver;
-use initial_handler::NewPlayer;
+use common::{AccessLists, Game};
+use connection_throttle::ConnectionThrottle;
+use connection_worker::WorkerContext;
+use ecs::{Entity, SystemExecutor};
+use flume::{Receiver, Sender};
+use initial_handler::StatusPing;
 use listener::Listener;
+use quill_common::{components::WorldId, events::ServerListPingEvent};
 
+pub mod auth;
 mod chunk_subscriptions;
 pub mod client;
 pub mod config;
+mod connection_throttle;
 mod connection_worker;
 mod entities;
 pub mod favicon;
@@ -25,11 +31,19 @@ mod player_count;
 mod systems;
 
 pub use client::{Client, ClientId, Clients};
-pub use network_id_registry::NetworkId;
+pub use initial_handler::NewPlayer;
+pub use network_id_registry::{NetworkId, NetworkIdRegistry};
 pub use options::Options;
 use player_count::PlayerCount;
 use systems::view::WaitingChunks;
 
+/// Directory containing the whitelist, ban list and operator list files.
+const ACCESS_LISTS_DIRECTORY: &str = ".";
+
+/// Maximum number of server list pings waiting for the
+/// main thread. Further pings get the default response.
+const MAX_PENDING_STATUS_PINGS: usize = 64;
+
 /// A Minecraft server.
 ///
 /// Call [`link_with_game`](Server::link_with_game) to register the server
@@ -42,6 +56,16 @@ pub struct Server {
     clients: Clients,
     new_players: Receiver<NewPlayer>,
 
+    access_lists: Arc<AccessLists>,
+    /// The `AccessLists::revision` at which online
+    /// players were last checked against the lists.
+    checked_access_revision: u64,
+
+    status_pings: Receiver<StatusPing>,
+    /// Server list ping events awaiting a response, along with
+    /// the channel to send the (possibly modified) event through.
+    pending_status_pings: Vec<(Entity, Sender<ServerListPingEvent>)>,
+
     waiting_chunks: WaitingChunks,
     chunk_subscriptions: ChunkSubscriptions,
 
@@ -53,13 +77,46 @@ pub struct Server {
 impl Server {
     /// Starts a server with the given `Options`.
     ///
+    /// Players are authenticated through the session server
+    /// given by [`Options::session_server`].
+    ///
     /// Must be called within the context of a Tokio runtime.
     pub async fn bind(options: Options) -> anyhow::Result<Self> {
+        let authenticator =
+            MojangAuthenticator::new(&options.session_server, options.authentication_timeout);
+        Self::bind_with_authenticator(options, Arc::new(authenticator)).await
+    }
+
+    /// Starts a server with the given `Options`, authenticating
+    /// players with `authenticator`.
+    ///
+    /// The whitelist, ban lists and operator list are
+    /// loaded from the working directory.
+    ///
+    /// Must be called within the context of a Tokio runtime.
+    pub async fn bind_with_authenticator(
+        options: Options,
+        authenticator: Arc<dyn Authenticator>,
+    ) -> anyhow::Result<Self> {
         let options = Arc::new(options);
         let player_count = PlayerCount::new(options.max_players);
+        let access_lists = Arc::new(AccessLists::load(
+            ACCESS_LISTS_DIRECTORY,
+            options.whitelist,
+        )?);
 
         let (new_players_tx, new_players) = flume::bounded(4);
-        Listener::start(Arc::clone(&options), player_count.clone(), new_players_tx).await?;
+        let (status_pings_tx, status_pings) = flume::bounded(MAX_PENDING_STATUS_PINGS);
+        Listener::start(WorkerContext {
+            options: Arc::clone(&options),
+            authenticator,
+            access_lists: Arc::clone(&access_lists),
+            player_count: player_count.clone(),
+            connection_throttle: ConnectionThrottle::new(options.connection_throttle),
+            new_players: new_players_tx,
+            status_pings: status_pings_tx,
+        })
+        .await?;
 
         log::info!(
             "Server is listening on {}:{}",
@@ -67,22 +124,84 @@ impl Server {
             options.port
         );
 
-        Ok(Self {
+        Ok(Self::new(
+            options,
+            access_lists,
+            player_count,
+            new_players,
+            status_pings,
+        ))
+    }
+
+    /// Creates a server that does not listen for connections.
+    ///
+    /// Players join by sending a [`NewPlayer`] through the
+    /// returned channel. This is useful for tests that
+    /// simulate clients without a network connection.
+    ///
+    /// The access lists start out empty and are not saved to disk.
+    pub fn headless(options: Options) -> (Self, Sender<NewPlayer>) {
+        let options = Arc::new(options);
+        let player_count = PlayerCount::new(options.max_players);
+        let access_lists = Arc::new(AccessLists::in_memory(options.whitelist));
+
+        let (new_players_tx, new_players) = flume::unbounded();
+        // No listener, so nobody can ping the server.
+        let (_, status_pings) = flume::bounded(0);
+        (
+            Self::new(
+                options,
+                access_lists,
+                player_count,
+                new_players,
+                status_pings,
+            ),
+            new_players_tx,
+        )
+    }
+
+    fn new(
+        options: Arc<Options>,
+        access_lists: Arc<AccessLists>,
+        player_count: PlayerCount,
+        new_players: Receiver<NewPlayer>,
+        status_pings: Receiver<StatusPing>,
+    ) -> Self {
+        Self {
             options,
             clients: Clients::new(),
             new_players,
+            checked_access_revision: access_lists.revision(),
+            access_lists,
+            status_pings,
+            pending_status_pings: Vec::new(),
             waiting_chunks: WaitingChunks::default(),
             chunk_subscriptions: ChunkSubscriptions::default(),
             last_keepalive_time: Instant::now(),
             player_count,
-        })
+        }
     }
 
     /// Links this server with a `Game` so that players connecting
     /// to the server become part of this `Game`.
+    ///
+    /// Also inserts the [`NetworkIdRegistry`] used to
+    /// resolve the entity IDs sent by clients.
     pub fn link_with_game(self, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+        let network_ids = NetworkIdRegistry::new();
+        game.insert_resource(network_ids.clone());
         systems::register(self, game, systems);
-        game.add_entity_spawn_callback(entities::add_entity_components);
+        game.add_entity_spawn_callback(move |builder, init| {
+            entities::add_entity_components(builder, init, &network_ids)
+        });
+    }
+
+    /// Gets the whitelist, ban lists and operator list.
+    ///
+    /// [`link_with_game`](Server::link_with_game) also inserts
+    /// them into the `Game` as an `Arc<AccessLists>` resource.
+    pub fn access_lists(&self) -> &Arc<AccessLists> {
+        &self.access_lists
     }
 
     /// Gets the number of online players.
@@ -132,8 +251,16 @@ impl Server {
     /// to the given position. This function should be
     /// used for entity updates, block updates, etc—
     /// any packets that need to be sent only to nearby players.
-    pub fn broadcast_nearby_with(&self, position: Position, mut callback: impl FnMut(&Client)) {
-        for &client_id in self.chunk_subscriptions.subscriptions_for(position.chunk()) {
+    pub fn broadcast_nearby_with(
+        &self,
+        world: WorldId,
+        position: Position,
+        mut callback: impl FnMut(&Client),
+    ) {
+        for &client_id in self
+            .chunk_subscriptions
+            .subscriptions_for(world, position.chunk())
+        {
             if let Some(client) = self.clients.get(client_id) {
                 callback(client);
             }
@@ -1,37 +1,36 @@
 use std::{net::SocketAddr, sync::Arc};
 
 use anyhow::Context;
-use flume::Sender;
-use tokio::net::{TcpListener, TcpStream};
-
-use crate::{
-    connection_worker::Worker, initial_handler::NewPlayer, options::Options,
-    player_count::PlayerCount,
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
 };
 
+use crate::connection_worker::{Worker, WorkerContext};
+
 /// Listens for and accepts incoming connections.
 pub struct Listener {
     listener: TcpListener,
-    options: Arc<Options>,
-    player_count: PlayerCount,
-    new_players: Sender<NewPlayer>,
+    context: WorkerContext,
+    /// Limits the number of connections in initial handling,
+    /// if `Options::max_handshaking_connections` is set.
+    handshake_slots: Option<Arc<Semaphore>>,
 }
 
 impl Listener {
-    pub async fn start(
-        options: Arc<Options>,
-        player_count: PlayerCount,
-        new_players: Sender<NewPlayer>,
-    ) -> anyhow::Result<()> {
+    pub async fn start(context: WorkerContext) -> anyhow::Result<()> {
+        let options = &context.options;
         let listener = TcpListener::bind(format!("{}:{}", options.bind_address, options.port))
             .await
             .context("failed to bind to port - maybe a server is already running?")?;
 
+        let handshake_slots = options
+            .max_handshaking_connections
+            .map(|max| Arc::new(Semaphore::new(max)));
         let listener = Listener {
             listener,
-            options,
-            player_count,
-            new_players,
+            context,
+            handshake_slots,
         };
         tokio::task::spawn(async move {
             listener.run().await;
@@ -49,13 +48,21 @@ impl Listener {
     }
 
     async fn accept(&mut self, stream: TcpStream, addr: SocketAddr) {
-        let worker = Worker::new(
-            stream,
-            addr,
-            Arc::clone(&self.options),
-            self.player_count.clone(),
-            self.new_players.clone(),
-        );
+        let handshake_permit = match &self.handshake_slots {
+            Some(slots) => match Arc::clone(slots).try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    log::debug!(
+                        "Closing connection from {}: too many connections are logging in",
+                        addr
+                    );
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        let worker = Worker::new(stream, addr, self.context.clone(), handshake_permit);
         worker.start();
     }
 }
@@ -1,12 +1,11 @@
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use std::{cell::RefCell, rc::Rc};
 
 use anyhow::Context;
-use base::anvil::level::SuperflatGeneratorOptions;
-use common::{Game, TickLoop, World};
+use common::{permissions::PERMISSIONS_FILE, Game, Permissions, TickLoop, World, Worlds};
 use ecs::SystemExecutor;
 use feather_server::{config::Config, Server};
 use plugin_host::PluginManager;
-use worldgen::{ComposableGenerator, SuperflatWorldGenerator, VoidWorldGenerator, WorldGenerator};
+use quill_common::components::WorldId;
 
 mod logging;
 
@@ -25,6 +24,11 @@ async fn main() -> anyhow::Result<()> {
     }
     log::info!("Loaded config");
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("pregen") {
+        return pregenerate(&config, &args[1..]);
+    }
+
     log::info!("Creating server");
     let options = config.to_options();
     let server = Server::bind(options).await?;
@@ -38,9 +42,12 @@ async fn main() -> anyhow::Result<()> {
 
 fn init_game(server: Server, config: &Config) -> anyhow::Result<Game> {
     let mut game = Game::new();
+    game.insert_resource(
+        Permissions::load(PERMISSIONS_FILE).context("failed to load permissions")?,
+    );
     init_systems(&mut game, server);
-    init_world_source(&mut game, config);
-    init_plugin_manager(&mut game)?;
+    init_worlds(&mut game, config)?;
+    init_plugin_manager(&mut game, config)?;
     Ok(game)
 }
 
@@ -58,26 +65,41 @@ fn init_systems(game: &mut Game, server: Server) {
     game.system_executor = Rc::new(RefCell::new(systems));
 }
 
-fn init_world_source(game: &mut Game, config: &Config) {
-    // Load chunks from the world save first,
-    // and fall back to generating a superflat
-    // world otherwise. This is a placeholder:
-    // we don't have proper world generation yet.
-
-    let seed = 42; // FIXME: load from the level file
-
-    let generator: Arc<dyn WorldGenerator> = match &config.world.generator[..] {
-        "flat" => Arc::new(SuperflatWorldGenerator::new(
-            SuperflatGeneratorOptions::default(),
-        )),
-        "void" => Arc::new(VoidWorldGenerator),
-        _ => Arc::new(ComposableGenerator::default_with_seed(seed)),
-    };
-    game.world = World::with_gen_and_path(generator, config.world.name.clone());
+fn init_worlds(game: &mut Game, config: &Config) -> anyhow::Result<()> {
+    let mut worlds = Vec::with_capacity(config.worlds.len());
+    for world_config in &config.worlds {
+        let generator = world_config
+            .load_level()
+            .and_then(|level| world_config.generator(&level))
+            .with_context(|| {
+                format!(
+                    "failed to create the generator of world {:?}",
+                    world_config.name
+                )
+            })?;
+        let mut world =
+            World::with_dimension(world_config.dimension, generator, world_config.name.clone());
+        *world.border_mut() = world_config.border();
+        worlds.push((world_config.name.clone(), world));
+    }
+
+    let mut worlds = worlds.into_iter();
+    let (name, default_world) = worlds
+        .next()
+        .expect("at least one world is validated to be configured");
+    game.worlds = Worlds::new(name, default_world);
+    for (name, world) in worlds {
+        game.worlds.insert(name, world);
+    }
+    Ok(())
 }
 
-fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
+fn init_plugin_manager(game: &mut Game, config: &Config) -> anyhow::Result<()> {
     let mut plugin_manager = PluginManager::new();
+    plugin_manager.set_default_limits(config.plugins.default_limits());
+    for identifier in config.plugins.overrides.keys() {
+        plugin_manager.set_limits(identifier.clone(), config.plugins.limits(identifier));
+    }
     plugin_manager.load_dir(game, PLUGINS_DIRECTORY)?;
 
     let plugin_manager_rc = Rc::new(RefCell::new(plugin_manager));
@@ -85,6 +107,69 @@ fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Pre-generates the chunks inside the border of the given
+/// worlds without accepting players, then exits.
+///
+/// Without world names, all worlds with a border are pre-generated.
+fn pregenerate(config: &Config, names: &[String]) -> anyhow::Result<()> {
+    for name in names {
+        if !config.worlds.iter().any(|world| &world.name == name) {
+            anyhow::bail!("world {:?} is not configured", name);
+        }
+    }
+
+    let mut game = Game::new();
+    let mut systems = SystemExecutor::new();
+    common::register(&mut game, &mut systems);
+    game.system_executor = Rc::new(RefCell::new(systems));
+    init_worlds(&mut game, config)?;
+
+    let worlds: Vec<(WorldId, String)> = game
+        .worlds
+        .ids()
+        .map(|id| (id, game.worlds.name(id).unwrap_or_default().to_owned()))
+        .collect();
+    let mut started = false;
+    for (id, name) in worlds {
+        if !names.is_empty() && !names.contains(&name) {
+            continue;
+        }
+        let world = game.worlds.get_mut(id).expect("world was just listed");
+        match world.start_pregeneration() {
+            Ok(()) => {
+                log::info!("Pre-generating world {:?}", name);
+                started = true;
+            }
+            Err(e) if names.is_empty() => log::info!("Skipping world {:?}: {}", name, e),
+            Err(e) => return Err(e.context(format!("cannot pre-generate world {:?}", name))),
+        }
+    }
+
+    if !started {
+        anyhow::bail!("no world has a border to pre-generate; set border_radius in config.toml");
+    }
+
+    TickLoop::new(move || {
+        let systems = Rc::clone(&game.system_executor);
+        systems.borrow_mut().run(&mut game);
+        game.tick_count += 1;
+
+        let finished = game
+            .worlds
+            .iter()
+            .all(|(_, world)| world.pregeneration().is_none());
+        if finished {
+            for (_, world) in game.worlds.iter() {
+                world.wait_for_saves();
+            }
+            log::info!("Pre-generation finished");
+        }
+        finished
+    })
+    .run();
+    Ok(())
+}
+
 fn print_systems(systems: &SystemExecutor<Game>) {
     let systems: Vec<&str> = systems.system_names().collect();
     log::debug!("---SYSTEMS---\n{:#?}\n", systems);
@@ -1,17 +1,193 @@
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::Game;
+use ecs::{Entity, SysResult, SystemExecutor};
+use parking_lot::Mutex;
+use quill_common::events::{EntityCreateEvent, EntityRemoveEvent};
+use uuid::Uuid;
+
+/// How long a released network ID is kept out of
+/// circulation. Clients may still refer to a removed
+/// entity for a short while (e.g. packets sent before the
+/// Destroy Entities packet arrived), and those references
+/// must not resolve to a newer entity.
+const RELEASE_COOLDOWN: Duration = Duration::from_secs(10);
 
 /// An entity's ID used by the protocol
 /// in `entity_id` fields.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct NetworkId(pub i32);
 
-impl NetworkId {
-    /// Creates a new, unique network ID.
-    pub(crate) fn new() -> Self {
-        static NEXT: AtomicI32 = AtomicI32::new(0);
-        // In theory, this can overflow if the server
-        // creates 4 billion entities. The hope is that
-        // old entities will have died out at that point.
-        Self(NEXT.fetch_add(1, Ordering::SeqCst))
+/// Resource that allocates [`NetworkId`]s and maps the
+/// IDs (and UUIDs) sent by clients back to `Entity`s.
+///
+/// IDs are released when an entity is removed and
+/// recycled once [`RELEASE_COOLDOWN`] has passed.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Default)]
+pub struct NetworkIdRegistry {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl NetworkIdRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a network ID for a new entity.
+    pub fn allocate(&self) -> NetworkId {
+        self.allocate_at(Instant::now())
+    }
+
+    fn allocate_at(&self, now: Instant) -> NetworkId {
+        let mut inner = self.inner.lock();
+        match inner.released.front() {
+            Some(&(id, released_at)) if now - released_at >= RELEASE_COOLDOWN => {
+                inner.released.pop_front();
+                id
+            }
+            _ => {
+                let id = NetworkId(inner.next);
+                inner.next = inner.next.checked_add(1).expect("ran out of network IDs");
+                id
+            }
+        }
+    }
+
+    /// Associates an allocated network ID and
+    /// a UUID with the entity they belong to.
+    pub fn register(&self, network_id: NetworkId, uuid: Option<Uuid>, entity: Entity) {
+        let mut inner = self.inner.lock();
+        inner.entities.insert(network_id, entity);
+        if let Some(uuid) = uuid {
+            inner.uuids.insert(uuid, entity);
+        }
+    }
+
+    /// Stops resolving the given network ID and UUID and
+    /// schedules the network ID to be recycled.
+    pub fn release(&self, network_id: NetworkId, uuid: Option<Uuid>) {
+        self.release_at(network_id, uuid, Instant::now())
+    }
+
+    fn release_at(&self, network_id: NetworkId, uuid: Option<Uuid>, now: Instant) {
+        let mut inner = self.inner.lock();
+        // Ignore IDs that aren't in use so that
+        // an ID is never recycled twice.
+        let entity = match inner.entities.remove(&network_id) {
+            Some(entity) => entity,
+            None => return,
+        };
+        if let Some(uuid) = uuid {
+            // Don't forget a newer entity that reused the UUID.
+            if inner.uuids.get(&uuid) == Some(&entity) {
+                inner.uuids.remove(&uuid);
+            }
+        }
+        inner.released.push_back((network_id, now));
+    }
+
+    /// Gets the entity with the given network ID.
+    pub fn get(&self, network_id: NetworkId) -> Option<Entity> {
+        self.inner.lock().entities.get(&network_id).copied()
+    }
+
+    /// Gets the entity with the given UUID.
+    pub fn get_by_uuid(&self, uuid: Uuid) -> Option<Entity> {
+        self.inner.lock().uuids.get(&uuid).copied()
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    next: i32,
+    entities: HashMap<NetworkId, Entity>,
+    uuids: HashMap<Uuid, Entity>,
+    /// Released IDs in the order they were released.
+    released: VecDeque<(NetworkId, Instant)>,
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(update_network_id_registry);
+}
+
+/// Registers newly created entities and
+/// releases the IDs of removed entities.
+fn update_network_id_registry(game: &mut Game) -> SysResult {
+    let registry = game.resources.get::<NetworkIdRegistry>()?;
+
+    for (entity, (_event, &network_id, uuid)) in game
+        .ecs
+        .query::<(&EntityCreateEvent, &NetworkId, Option<&Uuid>)>()
+        .iter()
+    {
+        registry.register(network_id, uuid.copied(), entity);
+    }
+
+    for (_, (_event, &network_id, uuid)) in game
+        .ecs
+        .query::<(&EntityRemoveEvent, &NetworkId, Option<&Uuid>)>()
+        .iter()
+    {
+        registry.release(network_id, uuid.copied());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ecs::Ecs;
+
+    use super::*;
+
+    #[test]
+    fn resolves_registered_entities() {
+        let mut ecs = Ecs::new();
+        let entity = ecs.spawn(());
+        let registry = NetworkIdRegistry::new();
+        let uuid = Uuid::new_v4();
+
+        let network_id = registry.allocate();
+        assert_eq!(registry.get(network_id), None);
+        registry.register(network_id, Some(uuid), entity);
+        assert_eq!(registry.get(network_id), Some(entity));
+        assert_eq!(registry.get_by_uuid(uuid), Some(entity));
+
+        registry.release(network_id, Some(uuid));
+        assert_eq!(registry.get(network_id), None);
+        assert_eq!(registry.get_by_uuid(uuid), None);
+    }
+
+    #[test]
+    fn recycles_ids_after_cooldown() {
+        let mut ecs = Ecs::new();
+        let entity = ecs.spawn(());
+        let registry = NetworkIdRegistry::new();
+        let start = Instant::now();
+
+        let first = registry.allocate_at(start);
+        let second = registry.allocate_at(start);
+        assert_ne!(first, second);
+        registry.register(first, None, entity);
+        registry.release_at(first, None, start);
+
+        let third = registry.allocate_at(start + RELEASE_COOLDOWN / 2);
+        assert_ne!(third, first);
+        assert_ne!(third, second);
+
+        let fourth = registry.allocate_at(start + RELEASE_COOLDOWN);
+        assert_eq!(fourth, first);
+        assert_eq!(registry.get(fourth), None);
+
+        // Releasing an ID twice doesn't recycle it twice.
+        registry.release_at(first, None, start);
+        let fifth = registry.allocate_at(start + RELEASE_COOLDOWN);
+        assert_ne!(fifth, first);
     }
 }
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use base::Gamemode;
 
-use crate::favicon::Favicon;
+use crate::{auth, favicon::Favicon};
 
 /// Options for building a [`Server`](crate::Server).
 #[derive(Debug, Clone)]
@@ -17,6 +19,17 @@ pub struct Options {
 
     /// Whether the server should authenticate players.
     pub online_mode: bool,
+    /// Base URL of the session server used to
+    /// authenticate players in online mode.
+    pub session_server: String,
+    /// Whether the session server should reject players
+    /// connecting from a different IP address than the one
+    /// they authenticated from.
+    pub prevent_proxy_connections: bool,
+    /// Timeout for requests to the session server.
+    pub authentication_timeout: Duration,
+    /// Whether only whitelisted players and operators may join.
+    pub whitelist: bool,
 
     /// The maximum view distance, which determines
     /// how far players can see.
@@ -24,6 +37,11 @@ pub struct Options {
 
     /// Maximum number of players to allow on the server.
     pub max_players: u32,
+    /// Number of online players listed in the server list.
+    pub player_sample_size: usize,
+    /// Whether to hide the player count and sample
+    /// in the server list.
+    pub hide_player_count: bool,
 
     /// The default gamemode for new players.
     pub default_gamemode: Gamemode,
@@ -35,6 +53,51 @@ pub struct Options {
 
     /// Packet size threshold at which to compress data
     pub compression_threshold: Option<usize>,
+
+    /// Minimum time between two login attempts from the same IP address.
+    /// Server list pings are not throttled.
+    pub connection_throttle: Option<Duration>,
+    /// Maximum number of connections that may be
+    /// handshaking or logging in at once.
+    pub max_handshaking_connections: Option<usize>,
+    /// Time a client has to finish logging in or
+    /// pinging the server status after connecting.
+    pub login_timeout: Duration,
+    /// Maximum size of a packet received from a client, in bytes.
+    pub max_packet_size: usize,
+    /// Maximum number of packets a player may send
+    /// in a tick before they are kicked.
+    pub max_packets_per_tick: Option<usize>,
+}
+
+/// Matches the defaults in `config.toml`.
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            port: 25565,
+            bind_address: String::from("0.0.0.0"),
+            favicon: None,
+            motd: String::from("A Feather server"),
+            online_mode: true,
+            session_server: String::from(auth::DEFAULT_SESSION_SERVER),
+            prevent_proxy_connections: false,
+            authentication_timeout: auth::DEFAULT_TIMEOUT,
+            whitelist: false,
+            view_distance: 12,
+            max_players: 16,
+            player_sample_size: 12,
+            hide_player_count: false,
+            default_gamemode: Gamemode::Creative,
+            proxy_mode: None,
+            velocity_secret: String::new(),
+            compression_threshold: Some(256),
+            connection_throttle: Some(Duration::from_millis(4000)),
+            max_handshaking_connections: Some(64),
+            login_timeout: Duration::from_secs(30),
+            max_packet_size: 2 * 1024 * 1024,
+            max_packets_per_tick: Some(300),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -1,5 +1,9 @@
-use base::{Position, Text};
-use common::{chat::ChatKind, Game};
+use base::{Gamemode, Position, Text};
+use common::{
+    chat::{ChatKind, ChatMessage},
+    permissions::nodes,
+    Game,
+};
 use ecs::{Entity, EntityRef, SysResult};
 use interaction::{
     handle_held_item_change, handle_interact_entity, handle_player_block_placement,
@@ -12,9 +16,10 @@ use protocol::{
     },
     ClientPlayPacket,
 };
-use quill_common::components::Name;
+use quill_common::components::{Name, WorldId};
+use uuid::Uuid;
 
-use crate::{NetworkId, Server};
+use crate::{ClientId, NetworkId, NetworkIdRegistry, Server};
 
 mod entity_action;
 mod interaction;
@@ -31,10 +36,10 @@ pub fn handle_packet(
     let player = game.ecs.entity(player_id)?;
     match packet {
         ClientPlayPacket::PlayerPosition(packet) => {
-            movement::handle_player_position(server, player, packet)
+            movement::handle_player_position(game, server, player, packet)
         }
         ClientPlayPacket::PlayerPositionAndRotation(packet) => {
-            movement::handle_player_position_and_rotation(server, player, packet)
+            movement::handle_player_position_and_rotation(game, server, player, packet)
         }
         ClientPlayPacket::PlayerRotation(packet) => {
             movement::handle_player_rotation(server, player, packet)
@@ -77,6 +82,17 @@ pub fn handle_packet(
             entity_action::handle_entity_action(game, player_id, packet)
         }
 
+        ClientPlayPacket::Spectate(packet) => handle_spectate(game, server, player_id, packet),
+
+        ClientPlayPacket::QueryEntityNbt(packet) => {
+            handle_query_entity_nbt(game, server, player_id, packet)
+        }
+
+        ClientPlayPacket::UpdateCommandBlock(_)
+        | ClientPlayPacket::UpdateCommandBlockMinecart(_) => {
+            handle_update_command_block(game, player_id)
+        }
+
         ClientPlayPacket::TeleportConfirm(_)
         | ClientPlayPacket::QueryBlockNbt(_)
         | ClientPlayPacket::SetDifficulty(_)
@@ -87,7 +103,6 @@ pub fn handle_packet(
         | ClientPlayPacket::CloseWindow(_)
         | ClientPlayPacket::PluginMessage(_)
         | ClientPlayPacket::EditBook(_)
-        | ClientPlayPacket::QueryEntityNbt(_)
         | ClientPlayPacket::GenerateStructure(_)
         | ClientPlayPacket::KeepAlive(_)
         | ClientPlayPacket::LockDifficulty(_)
@@ -103,22 +118,41 @@ pub fn handle_packet(
         | ClientPlayPacket::AdvancementTab(_)
         | ClientPlayPacket::SelectTrade(_)
         | ClientPlayPacket::SetBeaconEffect(_)
-        | ClientPlayPacket::UpdateCommandBlock(_)
-        | ClientPlayPacket::UpdateCommandBlockMinecart(_)
         | ClientPlayPacket::UpdateJigsawBlock(_)
         | ClientPlayPacket::UpdateStructureBlock(_)
         | ClientPlayPacket::UpdateSign(_)
-        | ClientPlayPacket::Spectate(_)
         | ClientPlayPacket::UseItem(_) => Ok(()),
     }
 }
 
+/// Resolves an entity ID sent by a client.
+/// Returns `None` if the entity doesn't exist (anymore).
+fn resolve_entity(game: &Game, network_id: NetworkId) -> Option<Entity> {
+    let entity = game
+        .resources
+        .get::<NetworkIdRegistry>()
+        .ok()?
+        .get(network_id)?;
+    game.ecs.entity(entity).ok().map(|_| entity)
+}
+
+/// Resolves an entity UUID sent by a client.
+fn resolve_entity_by_uuid(game: &Game, uuid: Uuid) -> Option<Entity> {
+    let entity = game
+        .resources
+        .get::<NetworkIdRegistry>()
+        .ok()?
+        .get_by_uuid(uuid)?;
+    game.ecs.entity(entity).ok().map(|_| entity)
+}
+
 fn handle_animation(
     server: &mut Server,
     player: EntityRef,
     packet: client::Animation,
 ) -> SysResult {
     let pos = *player.get::<Position>()?;
+    let world = *player.get::<WorldId>()?;
     let network_id = *player.get::<NetworkId>()?;
 
     let animation = match packet.hand {
@@ -126,12 +160,71 @@ fn handle_animation(
         Hand::Off => Animation::SwingOffhand,
     };
 
-    server.broadcast_nearby_with(pos, |client| {
+    server.broadcast_nearby_with(world, pos, |client| {
         client.send_entity_animation(network_id, animation.clone())
     });
     Ok(())
 }
 
+/// Teleports a spectator to the entity they selected.
+fn handle_spectate(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    packet: client::Spectate,
+) -> SysResult {
+    if *game.ecs.get::<Gamemode>(player)? != Gamemode::Spectator {
+        return Ok(());
+    }
+    let target = match resolve_entity_by_uuid(game, packet.target_player) {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let target_pos = *game.ecs.get::<Position>(target)?;
+    *game.ecs.get_mut::<Position>(player)? = target_pos;
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    if let Some(client) = server.clients.get(client_id) {
+        client.update_own_position(target_pos);
+    }
+    Ok(())
+}
+
+fn handle_query_entity_nbt(
+    game: &Game,
+    server: &mut Server,
+    player: Entity,
+    packet: client::QueryEntityNbt,
+) -> SysResult {
+    // Like vanilla, queries from players below operator level 2
+    // and queries for unknown entities are ignored.
+    if !game.has_permission(player, nodes::QUERY_NBT)
+        || resolve_entity(game, NetworkId(packet.entity_id)).is_none()
+    {
+        return Ok(());
+    }
+
+    // TODO: entities can't be serialized to NBT yet,
+    // so the client is sent an empty compound.
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    if let Some(client) = server.clients.get(client_id) {
+        client.send_empty_nbt_query_response(packet.transaction_id);
+    }
+    Ok(())
+}
+
+fn handle_update_command_block(game: &mut Game, player: Entity) -> SysResult {
+    // Like vanilla, editing command blocks also requires creative mode.
+    let is_creative = *game.ecs.get::<Gamemode>(player)? == Gamemode::Creative;
+    if !is_creative || !game.has_permission(player, nodes::COMMAND_BLOCK) {
+        let message = Text::translate_with("advMode.notAllowed", Vec::<Text>::new());
+        return game.send_message(player, ChatMessage::new(ChatKind::System, message));
+    }
+
+    // TODO: command blocks are not implemented yet.
+    Ok(())
+}
+
 fn handle_chat_message(game: &Game, player: EntityRef, packet: client::ChatMessage) -> SysResult {
     let name = player.get::<Name>()?;
     let message = Text::translate_with("chat.type.text", vec![name.to_string(), packet.message]);
@@ -1,19 +1,24 @@
+use super::resolve_entity;
 use crate::{ClientId, NetworkId, Server};
 use base::inventory::{SLOT_HOTBAR_OFFSET, SLOT_OFFHAND};
+use base::ValidBlockPosition;
 use common::entities::player::HotbarSlot;
 use common::interactable::InteractableRegistry;
+use common::permissions::nodes;
 use common::{Game, Window};
 use ecs::{Entity, EntityRef, SysResult};
-use libcraft_core::{BlockFace as LibcraftBlockFace, Hand};
+use libcraft_core::{BlockFace as LibcraftBlockFace, BlockPosition, Hand};
 use libcraft_core::{InteractionType, Vec3f};
 use protocol::packets::client::{
     BlockFace, HeldItemChange, InteractEntity, InteractEntityKind, PlayerBlockPlacement,
     PlayerDigging, PlayerDiggingStatus,
 };
 use quill_common::{
+    components::{CanBuild, WorldId},
     events::{BlockInteractEvent, BlockPlacementEvent, InteractEntityEvent},
     EntityId,
 };
+use std::convert::TryFrom;
 /// Handles the player block placement packet. Currently just removes the block client side for the player.
 pub fn handle_player_block_placement(
     game: &mut Game,
@@ -54,7 +59,8 @@ pub fn handle_player_block_placement(
     );
 
     let block_kind = {
-        let result = game.block(packet.position);
+        let world = *game.ecs.get::<WorldId>(player)?;
+        let result = game.block_in(world, packet.position);
         match result {
             Some(block) => block.kind(),
             None => {
@@ -88,6 +94,19 @@ pub fn handle_player_block_placement(
         };
 
         game.ecs.insert_entity_event(player, event)?;
+    } else if !may_build(game, player) {
+        // Undo the placement the client predicted.
+        let clicked = BlockPosition::from(packet.position);
+        let placed = match face {
+            LibcraftBlockFace::Top => clicked.up(),
+            LibcraftBlockFace::Bottom => clicked.down(),
+            LibcraftBlockFace::North => clicked.north(),
+            LibcraftBlockFace::South => clicked.south(),
+            LibcraftBlockFace::East => clicked.east(),
+            LibcraftBlockFace::West => clicked.west(),
+        };
+        resend_block(game, _server, player, clicked)?;
+        resend_block(game, _server, player, placed)?;
     } else {
         // Handle this as a block placement
         let event = BlockPlacementEvent {
@@ -104,6 +123,33 @@ pub fn handle_player_block_placement(
     Ok(())
 }
 
+/// Determines whether a player may place and break blocks.
+fn may_build(game: &Game, player: Entity) -> bool {
+    let can_build = game
+        .ecs
+        .get::<CanBuild>(player)
+        .map(|can_build| can_build.0)
+        .unwrap_or(false);
+    can_build && game.has_permission(player, nodes::BUILD)
+}
+
+/// Sends the actual block at `pos` to a player
+/// whose client predicted a different block.
+fn resend_block(game: &Game, server: &Server, player: Entity, pos: BlockPosition) -> SysResult {
+    let pos = match ValidBlockPosition::try_from(pos) {
+        Ok(pos) => pos,
+        Err(_) => return Ok(()),
+    };
+    let world = *game.ecs.get::<WorldId>(player)?;
+    if let Some(block) = game.block_in(world, pos) {
+        let client_id = *game.ecs.get::<ClientId>(player)?;
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_block_change(pos, block);
+        }
+    }
+    Ok(())
+}
+
 /// Handles the Player Digging packet sent for the following
 /// actions:
 /// * Breaking blocks.
@@ -120,8 +166,13 @@ pub fn handle_player_digging(
     log::trace!("Got player digging with status {:?}", packet.status);
     match packet.status {
         PlayerDiggingStatus::StartDigging | PlayerDiggingStatus::CancelDigging => {
-            game.break_block(packet.position);
-            Ok(())
+            if may_build(game, player) {
+                let world = *game.ecs.get::<WorldId>(player)?;
+                game.break_block_in(world, packet.position);
+                Ok(())
+            } else {
+                resend_block(game, server, player, packet.position.into())
+            }
         }
         PlayerDiggingStatus::SwapItemInHand => {
             let window = game.ecs.get::<Window>(player)?;
@@ -155,26 +206,13 @@ pub fn handle_interact_entity(
     packet: InteractEntity,
     player: Entity,
 ) -> SysResult {
-    let target = {
-        let mut found_entity = None;
-        for (entity, &network_id) in game.ecs.query::<&NetworkId>().iter() {
-            if network_id.0 == packet.entity_id {
-                found_entity = Some(entity);
-                break;
-            }
-        }
-
-        match found_entity {
-            None => {
-                let client_id = game.ecs.get::<ClientId>(player).unwrap();
-
-                let client = _server.clients.get(*client_id).unwrap();
-
-                client.disconnect("Interacted with an invalid entity!");
-
-                anyhow::bail!("Player attempted to interact with an invalid entity.")
-            }
-            Some(entity) => entity,
+    let target = match resolve_entity(game, NetworkId(packet.entity_id)) {
+        Some(target) => target,
+        None => {
+            // The entity may have been removed after
+            // the client sent the packet.
+            log::debug!("Player interacted with unknown entity {}", packet.entity_id);
+            return Ok(());
         }
     };
 
@@ -202,7 +240,15 @@ pub fn handle_interact_entity(
             let hand = match hand {
                 0 => Hand::Main,
                 1 => Hand::Offhand,
-                _ => unreachable!(),
+                _ => {
+                    let client_id = game.ecs.get::<ClientId>(player).unwrap();
+
+                    let client = _server.clients.get(*client_id).unwrap();
+
+                    client.disconnect("Malformed Packet!");
+
+                    anyhow::bail!("Player sent a malformed `InteractEntity` packet.")
+                }
             };
 
             InteractEntityEvent {
@@ -5,7 +5,7 @@ use protocol::packets::client::{
     PlayerAbilities, PlayerMovement, PlayerPosition, PlayerPositionAndRotation, PlayerRotation,
 };
 use quill_common::{
-    components::{CreativeFlying, OnGround},
+    components::{CreativeFlying, OnGround, WorldId},
     events::CreativeFlyingEvent,
 };
 
@@ -36,6 +36,7 @@ pub fn handle_player_movement(player: EntityRef, packet: PlayerMovement) -> SysR
 }
 
 pub fn handle_player_position(
+    game: &Game,
     server: &Server,
     player: EntityRef,
     packet: PlayerPosition,
@@ -48,11 +49,13 @@ pub fn handle_player_position(
     pos.y = packet.feet_y;
     pos.z = packet.z;
     player.get_mut::<OnGround>()?.0 = packet.on_ground;
+    enforce_world_border(game, server, &player, &mut pos)?;
     update_client_position(server, player, *pos)?;
     Ok(())
 }
 
 pub fn handle_player_position_and_rotation(
+    game: &Game,
     server: &Server,
     player: EntityRef,
     packet: PlayerPositionAndRotation,
@@ -67,6 +70,7 @@ pub fn handle_player_position_and_rotation(
     pos.yaw = packet.yaw;
     pos.pitch = packet.pitch;
     player.get_mut::<OnGround>()?.0 = packet.on_ground;
+    enforce_world_border(game, server, &player, &mut pos)?;
     update_client_position(server, player, *pos)?;
     Ok(())
 }
@@ -87,6 +91,32 @@ pub fn handle_player_rotation(
     Ok(())
 }
 
+/// Teleports a player who left the border
+/// of their world back onto the border.
+fn enforce_world_border(
+    game: &Game,
+    server: &Server,
+    player: &EntityRef,
+    pos: &mut Position,
+) -> SysResult {
+    let world = *player.get::<WorldId>()?;
+    let border = match game.worlds.get(world) {
+        Some(world) => world.border(),
+        None => return Ok(()),
+    };
+    if border.contains(pos.x, pos.z) {
+        return Ok(());
+    }
+
+    let (x, z) = border.clamp(pos.x, pos.z);
+    pos.x = x;
+    pos.z = z;
+    if let Some(client) = server.clients.get(*player.get::<ClientId>()?) {
+        client.update_own_position(*pos);
+    }
+    Ok(())
+}
+
 fn update_client_position(server: &Server, player: EntityRef, pos: Position) -> SysResult {
     if let Some(client) = server.clients.get(*player.get::<ClientId>()?) {
         client.set_client_known_position(pos);
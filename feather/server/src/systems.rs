@@ -1,5 +1,6 @@
 //! Systems linking a `Server` and a `Game`.
 
+mod access;
 mod block;
 mod chat;
 mod entity;
@@ -8,10 +9,15 @@ mod particle;
 mod player_join;
 mod player_leave;
 mod plugin_message;
+mod status_ping;
 mod tablist;
 pub mod view;
+mod world_border;
 
-use std::time::{Duration, Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use common::Game;
 use ecs::{SysResult, SystemExecutor};
@@ -21,6 +27,7 @@ use crate::{client::ClientId, Server};
 
 /// Registers systems for a `Server` with a `Game`.
 pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(Arc::clone(server.access_lists()));
     game.insert_resource(server);
 
     player_join::register(systems);
@@ -29,7 +36,9 @@ pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Ga
         .add_system(handle_packets)
         .add_system(send_keepalives);
     view::register(game, systems);
+    world_border::register(systems);
     crate::chunk_subscriptions::register(systems);
+    crate::network_id_registry::register(systems);
     player_leave::register(systems);
     tablist::register(systems);
     block::register(systems);
@@ -38,6 +47,8 @@ pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Ga
     particle::register(systems);
     plugin_message::register(systems);
     gamemode::register(systems);
+    status_ping::register(systems);
+    access::register(systems);
 
     systems.group::<Server>().add_system(tick_clients);
 }
@@ -49,9 +60,20 @@ fn handle_packets(game: &mut Game, server: &mut Server) -> SysResult {
 
     for (player, &client_id) in game.ecs.query::<&ClientId>().iter() {
         if let Some(client) = server.clients.get(client_id) {
-            for packet in client.received_packets() {
+            let start = packets.len();
+            let limit = server
+                .options
+                .max_packets_per_tick
+                .map_or(usize::MAX, |max| max + 1);
+            for packet in client.received_packets().take(limit) {
                 packets.push((player, packet));
             }
+
+            if packets.len() - start == limit {
+                log::info!("Disconnecting {}: too many packets", client.username());
+                client.disconnect("You are sending too many packets!");
+                packets.truncate(start);
+            }
         }
     }
 
@@ -85,3 +107,74 @@ fn tick_clients(_game: &mut Game, server: &mut Server) -> SysResult {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use flume::Sender;
+    use protocol::{
+        packets::client::KeepAlive, ClientPlayPacket, ProtocolVersion, ServerPlayPacket,
+    };
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{client::Client, initial_handler::NewPlayer, Options};
+
+    fn server_with_player(
+        max_packets_per_tick: usize,
+    ) -> (Game, Server, ClientId, Sender<ClientPlayPacket>) {
+        let options = Options {
+            max_packets_per_tick: Some(max_packets_per_tick),
+            ..Default::default()
+        };
+        let (mut server, _) = Server::headless(options);
+        let (received_tx, received_packets) = flume::unbounded();
+        let (packets_to_send, _) = flume::unbounded::<ServerPlayPacket>();
+        let player = NewPlayer {
+            uuid: Uuid::new_v4(),
+            username: "player".to_owned(),
+            profile: Vec::new(),
+            protocol_version: ProtocolVersion::LATEST,
+            ip: [127, 0, 0, 1].into(),
+            received_packets,
+            packets_to_send,
+        };
+        let client_id = server
+            .clients
+            .insert(Client::new(player, Arc::clone(&server.options)));
+
+        let mut game = Game::new();
+        game.ecs.spawn((client_id, Name::new("player")));
+        (game, server, client_id, received_tx)
+    }
+
+    fn send_keepalives(packets: &Sender<ClientPlayPacket>, count: u64) {
+        for id in 0..count {
+            packets
+                .send(ClientPlayPacket::KeepAlive(KeepAlive { id }))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn packets_within_the_limit_are_handled() {
+        let (mut game, mut server, client_id, packets) = server_with_player(3);
+        send_keepalives(&packets, 3);
+        handle_packets(&mut game, &mut server).unwrap();
+
+        let client = server.clients.get(client_id).unwrap();
+        assert!(!client.is_disconnected());
+        assert_eq!(client.received_packets().count(), 0);
+    }
+
+    #[test]
+    fn clients_sending_too_many_packets_are_kicked() {
+        let (mut game, mut server, client_id, packets) = server_with_player(3);
+        send_keepalives(&packets, 5);
+        handle_packets(&mut game, &mut server).unwrap();
+
+        let client = server.clients.get(client_id).unwrap();
+        assert!(client.is_disconnected());
+        // One packet more than the limit is read to detect the flood.
+        assert_eq!(client.received_packets().count(), 1);
+    }
+}
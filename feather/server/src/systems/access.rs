@@ -0,0 +1,27 @@
+//! Disconnects online players who are banned or removed
+//! from the whitelist after they joined.
+
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Server;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(kick_denied_players);
+}
+
+fn kick_denied_players(_game: &mut Game, server: &mut Server) -> SysResult {
+    let revision = server.access_lists.revision();
+    if revision == server.checked_access_revision {
+        return Ok(());
+    }
+    server.checked_access_revision = revision;
+
+    for client in server.clients.iter() {
+        if let Err(denied) = server.access_lists.check_login(client.uuid(), client.ip()) {
+            log::info!("Disconnecting {}: {:?}", client.username(), denied);
+            client.disconnect(&denied.disconnect_message());
+        }
+    }
+    Ok(())
+}
@@ -2,11 +2,14 @@ use ahash::AHashSet;
 use anyhow::Context;
 use base::Position;
 use common::{
-    events::{ChunkCrossEvent, ViewUpdateEvent},
+    events::{ChunkCrossEvent, ViewUpdateEvent, WorldChangeEvent},
     Game,
 };
 use ecs::{SysResult, SystemExecutor};
-use quill_common::events::{EntityCreateEvent, EntityRemoveEvent};
+use quill_common::{
+    components::WorldId,
+    events::{EntityCreateEvent, EntityRemoveEvent},
+};
 
 use crate::{entities::SpawnPacketSender, ClientId, NetworkId, Server};
 
@@ -16,7 +19,8 @@ pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
         .add_system(update_visible_entities)
         .add_system(send_entities_when_created)
         .add_system(unload_entities_when_removed)
-        .add_system(update_entities_on_chunk_cross);
+        .add_system(update_entities_on_chunk_cross)
+        .add_system(update_entities_on_world_change);
 }
 
 /// System to spawn entities on clients when they become visible,
@@ -29,8 +33,9 @@ pub fn update_visible_entities(game: &mut Game, server: &mut Server) -> SysResul
         };
 
         // Send newly visible entities
+        let new_world = event.new_view.world();
         for &new_chunk in &event.new_chunks {
-            for &entity_id in game.chunk_entities.entities_in_chunk(new_chunk) {
+            for &entity_id in game.chunk_entities.entities_in_chunk(new_world, new_chunk) {
                 if entity_id != player {
                     let entity_ref = game.ecs.entity(entity_id)?;
                     if let Ok(spawn_packet) = entity_ref.get::<SpawnPacketSender>() {
@@ -43,8 +48,9 @@ pub fn update_visible_entities(game: &mut Game, server: &mut Server) -> SysResul
         }
 
         // Unload entities no longer visible
+        let old_world = event.old_view.world();
         for &old_chunk in &event.old_chunks {
-            for &entity_id in game.chunk_entities.entities_in_chunk(old_chunk) {
+            for &entity_id in game.chunk_entities.entities_in_chunk(old_world, old_chunk) {
                 if entity_id != player {
                     if let Ok(network_id) = game.ecs.get::<NetworkId>(entity_id) {
                         client.unload_entity(*network_id);
@@ -59,13 +65,13 @@ pub fn update_visible_entities(game: &mut Game, server: &mut Server) -> SysResul
 
 /// System to send an entity to clients when it is created.
 fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult {
-    for (entity, (_event, &position, spawn_packet)) in game
+    for (entity, (_event, &position, &world, spawn_packet)) in game
         .ecs
-        .query::<(&EntityCreateEvent, &Position, &SpawnPacketSender)>()
+        .query::<(&EntityCreateEvent, &Position, &WorldId, &SpawnPacketSender)>()
         .iter()
     {
         let entity_ref = game.ecs.entity(entity)?;
-        server.broadcast_nearby_with(position, |client| {
+        server.broadcast_nearby_with(world, position, |client| {
             spawn_packet
                 .send(&entity_ref, client)
                 .expect("failed to create spawn packet")
@@ -77,12 +83,12 @@ fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult
 
 /// System to unload an entity on clients when it is removed.
 fn unload_entities_when_removed(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (_event, &position, &network_id)) in game
+    for (_, (_event, &position, &world, &network_id)) in game
         .ecs
-        .query::<(&EntityRemoveEvent, &Position, &NetworkId)>()
+        .query::<(&EntityRemoveEvent, &Position, &WorldId, &NetworkId)>()
         .iter()
     {
-        server.broadcast_nearby_with(position, |client| client.unload_entity(network_id));
+        server.broadcast_nearby_with(world, position, |client| client.unload_entity(network_id));
     }
 
     Ok(())
@@ -90,20 +96,20 @@ fn unload_entities_when_removed(game: &mut Game, server: &mut Server) -> SysResu
 
 /// System to send/unsend entities on clients when the entity changes chunks.
 fn update_entities_on_chunk_cross(game: &mut Game, server: &mut Server) -> SysResult {
-    for (entity, (event, spawn_packet, &network_id)) in game
+    for (entity, (event, &world, spawn_packet, &network_id)) in game
         .ecs
-        .query::<(&ChunkCrossEvent, &SpawnPacketSender, &NetworkId)>()
+        .query::<(&ChunkCrossEvent, &WorldId, &SpawnPacketSender, &NetworkId)>()
         .iter()
     {
         let old_clients: AHashSet<_> = server
             .chunk_subscriptions
-            .subscriptions_for(event.old_chunk)
+            .subscriptions_for(world, event.old_chunk)
             .iter()
             .copied()
             .collect();
         let new_clients: AHashSet<_> = server
             .chunk_subscriptions
-            .subscriptions_for(event.new_chunk)
+            .subscriptions_for(world, event.new_chunk)
             .iter()
             .copied()
             .collect();
@@ -124,3 +130,35 @@ fn update_entities_on_chunk_cross(game: &mut Game, server: &mut Server) -> SysRe
 
     Ok(())
 }
+
+/// System to move entities between clients when
+/// the entity changes worlds.
+fn update_entities_on_world_change(game: &mut Game, server: &mut Server) -> SysResult {
+    for (entity, (event, &position, spawn_packet, &network_id)) in game
+        .ecs
+        .query::<(&WorldChangeEvent, &Position, &SpawnPacketSender, &NetworkId)>()
+        .iter()
+    {
+        for &client_id in server
+            .chunk_subscriptions
+            .subscriptions_for(event.old_world, event.old_chunk)
+        {
+            if let Some(client) = server.clients.get(client_id) {
+                if client.is_entity_loaded(network_id) {
+                    client.unload_entity(network_id);
+                }
+            }
+        }
+
+        let entity_ref = game.ecs.entity(entity)?;
+        let mut result = Ok(());
+        server.broadcast_nearby_with(event.new_world, position, |client| {
+            if !client.is_entity_loaded(network_id) && result.is_ok() {
+                result = spawn_packet.send(&entity_ref, client);
+            }
+        });
+        result?;
+    }
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use crate::Server;
 use base::{Particle, Position};
 use common::Game;
 use ecs::{SysResult, SystemExecutor};
+use quill_common::components::WorldId;
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems.group::<Server>().add_system(send_particle_packets);
@@ -10,8 +11,15 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 fn send_particle_packets(game: &mut Game, server: &mut Server) -> SysResult {
     let mut entities = Vec::new();
 
-    for (entity, (&particle, &position)) in game.ecs.query::<(&Particle, &Position)>().iter() {
-        server.broadcast_nearby_with(position, |client| {
+    for (entity, (&particle, &position, world)) in game
+        .ecs
+        .query::<(&Particle, &Position, Option<&WorldId>)>()
+        .iter()
+    {
+        // Particles are spawned without the default entity
+        // components, so they may not have a world.
+        let world = world.copied().unwrap_or_default();
+        server.broadcast_nearby_with(world, position, |client| {
             client.send_particle(&particle, &position);
         });
 
@@ -13,7 +13,7 @@ use common::{
 use ecs::{SysResult, SystemExecutor};
 use quill_common::components::{
     CanBuild, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Health, Instabreak,
-    Invulnerable, PreviousGamemode, WalkSpeed,
+    Invulnerable, PreviousGamemode, WalkSpeed, WorldId,
 };
 use quill_common::events::GamemodeEvent;
 use quill_common::{components::Name, entity_init::EntityInit};
@@ -35,20 +35,28 @@ fn poll_new_players(game: &mut Game, server: &mut Server) -> SysResult {
 
 fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId) -> SysResult {
     let client = server.clients.get_mut(client_id).unwrap();
-    let player_data = game.world.load_player_data(client.uuid());
-    let mut builder = game.create_entity_builder(
-        player_data
-            .as_ref()
-            .map(|data| Position {
-                x: data.animal.base.position[0],
-                y: data.animal.base.position[1],
-                z: data.animal.base.position[2],
-                yaw: data.animal.base.rotation[0],
-                pitch: data.animal.base.rotation[1],
-            })
-            .unwrap_or_default(),
-        EntityInit::Player,
-    );
+    let player_data = game.worlds.default_world().load_player_data(client.uuid());
+
+    // Players in worlds that are no longer loaded
+    // are moved to the spawn of the default world.
+    let world = match player_data.as_ref().map(|data| data.world.as_deref()) {
+        Ok(Some(name)) => game.worlds.id(name),
+        Ok(None) => Some(WorldId::DEFAULT),
+        Err(_) => None,
+    };
+    let position = match (&player_data, world) {
+        (Ok(data), Some(_)) => Position {
+            x: data.animal.base.position[0],
+            y: data.animal.base.position[1],
+            z: data.animal.base.position[2],
+            yaw: data.animal.base.rotation[0],
+            pitch: data.animal.base.rotation[1],
+        },
+        _ => Position::default(),
+    };
+    let world = world.unwrap_or(WorldId::DEFAULT);
+
+    let mut builder = game.create_entity_builder(position, EntityInit::Player);
     client.set_network_id(*builder.get::<NetworkId>().unwrap());
 
     if player_data.is_err() {
@@ -63,7 +71,13 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
         .map(|data| PreviousGamemode::from_id(data.previous_gamemode as i8))
         .unwrap_or(PreviousGamemode(None));
 
-    client.send_join_game(gamemode, previous_gamemode);
+    client.send_join_game(
+        gamemode,
+        previous_gamemode,
+        game.worlds.get(world).unwrap().dimension(),
+        game.worlds.name(world).unwrap(),
+        game.worlds.ids().filter_map(|id| game.worlds.name(id)),
+    );
     client.send_brand();
 
     // Abilities
@@ -105,10 +119,12 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
 
     builder
         .add(client_id)
-        .add(View::new(
+        .add(View::in_world(
             Position::default().chunk(),
             server.options.view_distance,
+            world,
         ))
+        .add(world)
         .add(gamemode)
         .add(previous_gamemode)
         .add(Name::new(client.username()))
@@ -2,13 +2,13 @@ use num_traits::cast::ToPrimitive;
 
 use base::anvil::entity::{AnimalData, BaseEntityData};
 use base::anvil::player::{InventorySlot, PlayerAbilities, PlayerData};
-use base::{Gamemode, Inventory, Position, Text};
+use base::{Dimension, Gamemode, Inventory, Position, Text};
 use common::entities::player::HotbarSlot;
 use common::{chat::ChatKind, Game};
 use ecs::{SysResult, SystemExecutor};
 use quill_common::components::{
     CanBuild, CanCreativeFly, CreativeFlying, CreativeFlyingSpeed, Health, Instabreak,
-    Invulnerable, Name, PreviousGamemode, WalkSpeed,
+    Invulnerable, Name, PreviousGamemode, WalkSpeed, WorldId,
 };
 
 use crate::{ClientId, Server};
@@ -65,11 +65,21 @@ fn remove_disconnected_clients(game: &mut Game, server: &mut Server) -> SysResul
         if client.is_disconnected() {
             entities_to_remove.push(player);
             broadcast_player_leave(game, name);
-            game.world
+            let world = *game.ecs.get::<WorldId>(player)?;
+            let dimension = game
+                .worlds
+                .get(world)
+                .map(|world| world.dimension())
+                .unwrap_or_default();
+            let world_name = game.worlds.name(world).map(str::to_owned);
+            game.worlds
+                .default_world()
                 .save_player_data(
                     client.uuid(),
                     &create_player_data(
                         *position,
+                        dimension,
+                        world_name,
                         *gamemode,
                         *previous_gamemode,
                         *health,
@@ -103,8 +113,11 @@ fn broadcast_player_leave(game: &Game, username: &Name) {
     game.broadcast_chat(ChatKind::System, message);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn create_player_data(
     position: Position,
+    dimension: Dimension,
+    world: Option<String>,
     gamemode: Gamemode,
     previous_gamemode: PreviousGamemode,
     health: Health,
@@ -149,5 +162,7 @@ fn create_player_data(
             .collect(),
         held_item: hotbar_slot.get() as i32,
         abilities,
+        dimension,
+        world,
     }
 }
@@ -0,0 +1,62 @@
+//! Dispatches `ServerListPingEvent`s for server list pings
+//! and sends the responses back to the connection workers.
+
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+use quill_common::{
+    components::Name,
+    events::{PlayerSample, ServerListPingEvent},
+};
+use rand::seq::IteratorRandom;
+use uuid::Uuid;
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    // Responding must run before dispatching so that the events
+    // dispatched on the previous tick still exist: an event is removed
+    // right before the system that triggered it runs again.
+    systems
+        .group::<Server>()
+        .add_system(respond_to_status_pings)
+        .add_system(dispatch_status_pings);
+}
+
+/// Triggers a `ServerListPingEvent` for each new ping.
+fn dispatch_status_pings(game: &mut Game, server: &mut Server) -> SysResult {
+    let pings: Vec<_> = server.status_pings.try_iter().collect();
+    for ping in pings {
+        let mut event = ping.event;
+        if !event.hide_player_count {
+            event.player_sample = player_sample(game, server.options.player_sample_size);
+        }
+
+        let entity = game.ecs.insert_event(event);
+        server.pending_status_pings.push((entity, ping.response));
+    }
+    Ok(())
+}
+
+/// Sends the events triggered on the previous tick, as
+/// modified by plugins, to the connection workers.
+fn respond_to_status_pings(game: &mut Game, server: &mut Server) -> SysResult {
+    for (entity, response) in server.pending_status_pings.drain(..) {
+        if let Ok(event) = game.ecs.get::<ServerListPingEvent>(entity) {
+            // The worker may have timed out.
+            let _ = response.send((*event).clone());
+        }
+    }
+    Ok(())
+}
+
+/// Picks up to `size` random online players, like vanilla.
+fn player_sample(game: &Game, size: usize) -> Vec<PlayerSample> {
+    game.ecs
+        .query::<(&Name, &Uuid, &ClientId)>()
+        .iter()
+        .map(|(_, (name, &id, _))| PlayerSample {
+            name: name.to_string(),
+            id,
+        })
+        .choose_multiple(&mut rand::thread_rng(), size)
+}
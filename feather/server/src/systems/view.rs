@@ -4,12 +4,14 @@
 //! determined based on the player's [`common::view::View`].
 
 use ahash::AHashMap;
-use base::{ChunkPosition, Position};
+use base::{ChunkPosition, Gamemode, Position};
 use common::{
     events::{ChunkLoadEvent, ViewUpdateEvent},
+    view::View,
     Game,
 };
 use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::components::{PreviousGamemode, WorldId};
 
 use crate::{Client, ClientId, Server};
 
@@ -22,15 +24,19 @@ pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
 
 /// Stores the players waiting on chunks that are currently being loaded.
 #[derive(Default)]
-pub struct WaitingChunks(AHashMap<ChunkPosition, Vec<Entity>>);
+pub struct WaitingChunks(AHashMap<(WorldId, ChunkPosition), Vec<Entity>>);
 
 impl WaitingChunks {
-    pub fn drain_players_waiting_for(&mut self, chunk: ChunkPosition) -> Vec<Entity> {
-        self.0.remove(&chunk).unwrap_or_default()
+    pub fn drain_players_waiting_for(
+        &mut self,
+        world: WorldId,
+        chunk: ChunkPosition,
+    ) -> Vec<Entity> {
+        self.0.remove(&(world, chunk)).unwrap_or_default()
     }
 
-    pub fn insert(&mut self, player: Entity, chunk: ChunkPosition) {
-        self.0.entry(chunk).or_default().push(player);
+    pub fn insert(&mut self, player: Entity, world: WorldId, chunk: ChunkPosition) {
+        self.0.entry((world, chunk)).or_default().push(player);
     }
 }
 
@@ -44,6 +50,20 @@ fn send_new_chunks(game: &mut Game, server: &mut Server) -> SysResult {
         // happen that a client is still listed in the ecs but actually removed here so
         // we need to check if the client is actually still there.
         if let Some(client) = server.clients.get(client_id) {
+            // Players joining the game are already in their world.
+            let new_world = event.new_view.world();
+            if event.old_view.world() != new_world && !event.old_view.is_empty() {
+                let world = game
+                    .worlds
+                    .get(new_world)
+                    .ok_or_else(|| anyhow::anyhow!("world {:?} is not loaded", new_world))?;
+                client.send_respawn(
+                    world.dimension(),
+                    game.worlds.name(new_world).unwrap_or_default(),
+                    *game.ecs.get::<Gamemode>(player)?,
+                    *game.ecs.get::<PreviousGamemode>(player)?,
+                );
+            }
             client.update_own_chunk(event.new_view.center());
             update_chunks(
                 game,
@@ -67,17 +87,25 @@ fn update_chunks(
     waiting_chunks: &mut WaitingChunks,
 ) -> SysResult {
     // Send chunks that are in the new view but not the old view.
+    let world_id = event.new_view.world();
+    let world = game
+        .worlds
+        .get(world_id)
+        .ok_or_else(|| anyhow::anyhow!("world {:?} is not loaded", world_id))?;
     for &pos in &event.new_chunks {
-        if let Some(chunk) = game.world.chunk_map().chunk_handle_at(pos) {
+        if let Some(chunk) = world.chunk_map().chunk_handle_at(pos) {
             client.send_chunk(&chunk);
         } else {
-            waiting_chunks.insert(player, pos);
+            waiting_chunks.insert(player, world_id, pos);
         }
     }
 
     // Unsend the chunks that are in the old view but not the new view.
-    for &pos in &event.old_chunks {
-        client.unload_chunk(pos);
+    // After changing worlds, the client has already forgotten them.
+    if event.old_view.world() == world_id {
+        for &pos in &event.old_chunks {
+            client.unload_chunk(pos);
+        }
     }
 
     spawn_client_if_needed(client, position);
@@ -91,8 +119,13 @@ fn send_loaded_chunks(game: &mut Game, server: &mut Server) -> SysResult {
     for (_, event) in game.ecs.query::<&ChunkLoadEvent>().iter() {
         for player in server
             .waiting_chunks
-            .drain_players_waiting_for(event.position)
+            .drain_players_waiting_for(event.world, event.position)
         {
+            // The player may have left the world while waiting.
+            match game.ecs.get::<View>(player) {
+                Ok(view) if view.world() == event.world => {}
+                _ => continue,
+            }
             if let Ok(client_id) = game.ecs.get::<ClientId>(player) {
                 if let Some(client) = server.clients.get(*client_id) {
                     client.send_chunk(&event.chunk);
@@ -0,0 +1,52 @@
+//! Sends the border of their world to players.
+
+use common::{
+    events::{ViewUpdateEvent, WorldBorderChangeEvent},
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::WorldId;
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_border_on_world_change)
+        .add_system(send_border_changes);
+}
+
+/// Sends the world border to players joining
+/// the game or moving into another world.
+fn send_border_on_world_change(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, event)) in game.ecs.query::<(&ClientId, &ViewUpdateEvent)>().iter() {
+        let world = event.new_view.world();
+        if event.old_view.world() == world && !event.old_view.is_empty() {
+            continue;
+        }
+        if let (Some(client), Some(world)) = (server.clients.get(client_id), game.worlds.get(world))
+        {
+            client.send_world_border(world.border());
+        }
+    }
+    Ok(())
+}
+
+/// Sends changed world borders to the players in their world.
+fn send_border_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, event) in game.ecs.query::<&WorldBorderChangeEvent>().iter() {
+        let border = match game.worlds.get(event.world) {
+            Some(world) => world.border(),
+            None => continue,
+        };
+        for (_, (&client_id, &world)) in game.ecs.query::<(&ClientId, &WorldId)>().iter() {
+            if world != event.world {
+                continue;
+            }
+            if let Some(client) = server.clients.get(client_id) {
+                client.send_world_border(border);
+            }
+        }
+    }
+    Ok(())
+}
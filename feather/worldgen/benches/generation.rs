@@ -0,0 +1,221 @@
+//! Benchmarks for each stage of the `ComposableGenerator`
+//! pipeline and for generating whole chunks.
+//!
+//! Run with `cargo bench -p feather-worldgen`.
+
+use base::{anvil::level::SuperflatGeneratorOptions, BlockId, Chunk, ChunkPosition};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use feather_worldgen::{
+    BasicCompositionGenerator, BiomeGenerator, Carver, CaveCarver, ClumpedFoliageDecorator,
+    ComposableGenerator, CompositionGenerator, DecorationRegion, Decorator, DensityMapGenerator,
+    DensityMapGeneratorImpl, EndGenerator, FinishingGenerator, LayeredBiomeGenerator, NearbyBiomes,
+    NetherGenerator, OreDecorator, RavineCarver, SingleFoliageFinisher, SnowFinisher,
+    SuperflatWorldGenerator, TopBlocks, TreeDecorator, TwoLevelBiomeGenerator, WorldGenerator,
+};
+
+const SEED: u64 = 8_675_309;
+const POSITION: ChunkPosition = ChunkPosition::new(-7, 12);
+
+/// The biomes around `POSITION`.
+fn nearby_biomes() -> NearbyBiomes {
+    let generator = TwoLevelBiomeGenerator::default();
+    let mut biomes = Vec::new();
+    for z in -1..=1 {
+        for x in -1..=1 {
+            let pos = ChunkPosition::new(POSITION.x + x, POSITION.z + z);
+            biomes.push(generator.generate_for_chunk(pos, SEED));
+        }
+    }
+    NearbyBiomes::from_slice(&biomes).unwrap()
+}
+
+/// The chunk at `POSITION` after composition.
+fn composed_chunk(biomes: &NearbyBiomes) -> Chunk {
+    let density = DensityMapGeneratorImpl::default().generate_for_chunk(POSITION, biomes, SEED);
+    let mut chunk = Chunk::new(POSITION);
+    *chunk.biomes_mut() = *biomes.center();
+    BasicCompositionGenerator::default().generate_for_chunk(
+        &mut chunk,
+        POSITION,
+        biomes.center(),
+        density.as_bitslice(),
+        SEED,
+    );
+    chunk
+}
+
+fn top_blocks(chunk: &Chunk) -> TopBlocks {
+    let mut top_blocks = TopBlocks::new();
+    for x in 0..16 {
+        for z in 0..16 {
+            if let Some(y) = (0..256)
+                .rev()
+                .find(|&y| chunk.block_at(x, y, z).unwrap() != BlockId::air())
+            {
+                top_blocks.set_top_block_at(x, z, y);
+            }
+        }
+    }
+    top_blocks
+}
+
+/// The chunks around `POSITION`, ready to be decorated.
+fn generated_region(generator: &dyn WorldGenerator) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for z in -1..=1 {
+        for x in -1..=1 {
+            chunks
+                .push(generator.generate_chunk(ChunkPosition::new(POSITION.x + x, POSITION.z + z)));
+        }
+    }
+    chunks
+}
+
+fn region_of(chunks: &[Chunk]) -> DecorationRegion {
+    let mut region = DecorationRegion::new(POSITION);
+    for chunk in chunks {
+        region.insert(chunk.clone());
+    }
+    region
+}
+
+fn biomes(c: &mut Criterion) {
+    let two_level = TwoLevelBiomeGenerator::default();
+    c.bench_function("biomes/two_level", |b| {
+        b.iter(|| two_level.generate_for_chunk(black_box(POSITION), SEED))
+    });
+    let layered = LayeredBiomeGenerator::new(SEED);
+    c.bench_function("biomes/layered", |b| {
+        b.iter(|| layered.generate_for_chunk(black_box(POSITION), SEED))
+    });
+}
+
+fn terrain(c: &mut Criterion) {
+    let biomes = nearby_biomes();
+    let density_map = DensityMapGeneratorImpl::default();
+    c.bench_function("density_map", |b| {
+        b.iter(|| density_map.generate_for_chunk(black_box(POSITION), &biomes, SEED))
+    });
+
+    let density = density_map.generate_for_chunk(POSITION, &biomes, SEED);
+    let composition = BasicCompositionGenerator::default();
+    c.bench_function("composition", |b| {
+        b.iter_batched_ref(
+            || Chunk::new(POSITION),
+            |chunk| {
+                composition.generate_for_chunk(
+                    chunk,
+                    POSITION,
+                    biomes.center(),
+                    density.as_bitslice(),
+                    SEED,
+                )
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn no_water(_x: i32, _y: i32, _z: i32) -> bool {
+    false
+}
+
+fn carvers(c: &mut Criterion) {
+    let chunk = composed_chunk(&nearby_biomes());
+    let carvers: Vec<(&str, Box<dyn Carver>)> = vec![
+        ("carvers/caves", Box::new(CaveCarver::default())),
+        ("carvers/ravines", Box::new(RavineCarver::default())),
+    ];
+    for (name, carver) in carvers {
+        c.bench_function(name, |b| {
+            b.iter_batched_ref(
+                || chunk.clone(),
+                |chunk| carver.carve(chunk, &no_water, SEED),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn finishers(c: &mut Criterion) {
+    let biomes = nearby_biomes();
+    let chunk = composed_chunk(&biomes);
+    let top_blocks = top_blocks(&chunk);
+    let finishers: Vec<(&str, Box<dyn FinishingGenerator>)> = vec![
+        ("finishers/snow", Box::new(SnowFinisher::default())),
+        (
+            "finishers/single_foliage",
+            Box::new(SingleFoliageFinisher::default()),
+        ),
+    ];
+    for (name, finisher) in finishers {
+        c.bench_function(name, |b| {
+            b.iter_batched_ref(
+                || chunk.clone(),
+                |chunk| finisher.generate_for_chunk(chunk, biomes.center(), &top_blocks, SEED),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn decorators(c: &mut Criterion) {
+    let chunks = generated_region(&ComposableGenerator::default_with_seed(SEED));
+    let decorators: Vec<(&str, Box<dyn Decorator>)> = vec![
+        ("decorators/ores", Box::new(OreDecorator::default())),
+        ("decorators/trees", Box::new(TreeDecorator::default())),
+        (
+            "decorators/clumped_foliage",
+            Box::new(ClumpedFoliageDecorator::default()),
+        ),
+    ];
+    for (name, decorator) in decorators {
+        c.bench_function(name, |b| {
+            b.iter_batched_ref(
+                || region_of(&chunks),
+                |region| decorator.decorate(region, SEED),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn end_to_end(c: &mut Criterion) {
+    let generators: Vec<(&str, Box<dyn WorldGenerator>)> = vec![
+        (
+            "default",
+            Box::new(ComposableGenerator::default_with_seed(SEED)),
+        ),
+        (
+            "vanilla",
+            Box::new(ComposableGenerator::vanilla_with_seed(SEED)),
+        ),
+        (
+            "flat",
+            Box::new(
+                SuperflatWorldGenerator::new(SuperflatGeneratorOptions::default(), SEED).unwrap(),
+            ),
+        ),
+        ("nether", Box::new(NetherGenerator::new(SEED))),
+        ("end", Box::new(EndGenerator::new(SEED))),
+    ];
+    for (name, generator) in &generators {
+        c.bench_function(&format!("generate_chunk/{}", name), |b| {
+            b.iter(|| generator.generate_chunk(black_box(POSITION)))
+        });
+    }
+
+    for (name, generator) in &generators {
+        let chunks = generated_region(generator.as_ref());
+        c.bench_function(&format!("decorate_chunk/{}", name), |b| {
+            b.iter_batched_ref(
+                || region_of(&chunks),
+                |region| generator.decorate_chunk(region),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, biomes, terrain, carvers, finishers, decorators, end_to_end);
+criterion_main!(benches);
@@ -0,0 +1,1261 @@
+//! Biome generator reproducing the layered biome algorithm
+//! used by vanilla Minecraft 1.16.
+//!
+//! Only the `java.util.Random` port is checked against reference
+//! values. The biome layout has not yet been compared with vanilla
+//! or a seed-map tool, so it may differ from vanilla in places.
+//!
+//! The biome map is built by a stack of "layers." Each layer
+//! transforms the integer biome grid produced by its parent(s),
+//! usually doubling its resolution (zoom layers) or refining
+//! values based on neighbouring cells. The last layer has a
+//! resolution of one cell per 4x4 block column, which is exactly
+//! the resolution of a chunk's [`BiomeStore`].
+//!
+//! Layers are evaluated over rectangular areas rather than per cell,
+//! so that parent cells shared by neighbouring outputs are only computed once.
+
+use crate::BiomeGenerator;
+use base::chunk::BiomeStore;
+use base::{Biome, ChunkPosition};
+
+/// Biome generator following vanilla 1.16's
+/// layer stack for a given world seed.
+pub struct LayeredBiomeGenerator {
+    layer: Layer,
+}
+
+impl LayeredBiomeGenerator {
+    /// Creates the layer stack for the given world seed.
+    ///
+    /// The seed must be the world seed itself: vanilla layouts
+    /// depend on it directly, which is why the seed passed to
+    /// [`BiomeGenerator::generate_for_chunk`] is ignored.
+    pub fn new(world_seed: u64) -> Self {
+        Self {
+            layer: Layer::overworld(world_seed as i64, 4, 4),
+        }
+    }
+
+    /// Returns the biome IDs of the area with the given origin
+    /// and size, in units of 4 blocks, indexed by `z * width + x`.
+    pub fn biome_ids(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        self.layer.area(x, z, width, height)
+    }
+}
+
+impl BiomeGenerator for LayeredBiomeGenerator {
+    fn generate_for_chunk(&self, chunk: ChunkPosition, _seed: u64) -> BiomeStore {
+        let ids = self.biome_ids(chunk.x * 4, chunk.z * 4, 4, 4);
+
+        let mut biomes = BiomeStore::default();
+        for z in 0..4 {
+            for x in 0..4 {
+                let id = ids[z * 4 + x];
+                let biome = Biome::from_id(id as u32).unwrap_or(Biome::Ocean);
+                for y in 0..64 {
+                    biomes.set(x, y, z, biome);
+                }
+            }
+        }
+        biomes
+    }
+}
+
+/// Reimplementation of `java.util.Random`, used
+/// to initialize the ocean temperature noise.
+struct JavaRandom {
+    seed: i64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: i64 = 0x5DEECE66D;
+    const MASK: i64 = (1 << 48) - 1;
+
+    fn new(seed: i64) -> Self {
+        Self {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(Self::MULTIPLIER).wrapping_add(0xB) & Self::MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    fn next_int(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "bound must be positive");
+        if bound & -bound == bound {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32;
+        }
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+            if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+                return value;
+            }
+        }
+    }
+
+    fn next_double(&mut self) -> f64 {
+        let high = (self.next(26) as i64) << 27;
+        let low = self.next(27) as i64;
+        (high + low) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+/// Vanilla's `ImprovedNoise`, a Perlin noise
+/// generator used for ocean temperatures.
+struct ImprovedNoise {
+    permutations: [u8; 256],
+    offset: [f64; 3],
+}
+
+const GRADIENTS: [[f64; 3]; 16] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0],
+    [0.0, -1.0, 1.0],
+    [-1.0, 1.0, 0.0],
+    [0.0, -1.0, -1.0],
+];
+
+impl ImprovedNoise {
+    fn new(random: &mut JavaRandom) -> Self {
+        let offset = [
+            random.next_double() * 256.0,
+            random.next_double() * 256.0,
+            random.next_double() * 256.0,
+        ];
+        let mut permutations = [0u8; 256];
+        for (i, p) in permutations.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        for i in 0..256 {
+            let j = random.next_int(256 - i as i32) as usize;
+            permutations.swap(i, i + j);
+        }
+        Self {
+            permutations,
+            offset,
+        }
+    }
+
+    fn permutation(&self, i: i32) -> i32 {
+        self.permutations[(i & 255) as usize] as i32
+    }
+
+    fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let x = x + self.offset[0];
+        let y = y + self.offset[1];
+        let z = z + self.offset[2];
+        let (ix, iy, iz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let (dx, dy, dz) = (x - ix as f64, y - iy as f64, z - iz as f64);
+        let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+
+        let a = self.permutation(ix) + iy;
+        let aa = self.permutation(a) + iz;
+        let ab = self.permutation(a + 1) + iz;
+        let b = self.permutation(ix + 1) + iy;
+        let ba = self.permutation(b) + iz;
+        let bb = self.permutation(b + 1) + iz;
+
+        let grad = |hash: i32, x: f64, y: f64, z: f64| {
+            let g = GRADIENTS[(hash & 15) as usize];
+            g[0] * x + g[1] * y + g[2] * z
+        };
+
+        let x00 = lerp(
+            u,
+            grad(self.permutation(aa), dx, dy, dz),
+            grad(self.permutation(ba), dx - 1.0, dy, dz),
+        );
+        let x10 = lerp(
+            u,
+            grad(self.permutation(ab), dx, dy - 1.0, dz),
+            grad(self.permutation(bb), dx - 1.0, dy - 1.0, dz),
+        );
+        let x01 = lerp(
+            u,
+            grad(self.permutation(aa + 1), dx, dy, dz - 1.0),
+            grad(self.permutation(ba + 1), dx - 1.0, dy, dz - 1.0),
+        );
+        let x11 = lerp(
+            u,
+            grad(self.permutation(ab + 1), dx, dy - 1.0, dz - 1.0),
+            grad(self.permutation(bb + 1), dx - 1.0, dy - 1.0, dz - 1.0),
+        );
+        lerp(w, lerp(v, x00, x10), lerp(v, x01, x11))
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The linear congruential step used to seed layers
+/// and to advance their per-cell random state.
+fn mix(a: i64, b: i64) -> i64 {
+    a.wrapping_mul(
+        a.wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407),
+    )
+    .wrapping_add(b)
+}
+
+/// Computes the seed of a layer from the world seed and the layer's salt.
+fn layer_seed(world_seed: i64, salt: i64) -> i64 {
+    let mut salt_mix = mix(salt, salt);
+    salt_mix = mix(salt_mix, salt);
+    salt_mix = mix(salt_mix, salt);
+    let mut seed = mix(world_seed, salt_mix);
+    seed = mix(seed, salt_mix);
+    mix(seed, salt_mix)
+}
+
+/// The random state of a layer at a single cell.
+struct CellRandom {
+    layer_seed: i64,
+    state: i64,
+}
+
+impl CellRandom {
+    fn new(layer_seed: i64, x: i32, z: i32) -> Self {
+        let (x, z) = (x as i64, z as i64);
+        let state = mix(mix(mix(mix(layer_seed, x), z), x), z);
+        Self { layer_seed, state }
+    }
+
+    fn next(&mut self, bound: i32) -> i32 {
+        let value = (self.state >> 24).rem_euclid(bound as i64) as i32;
+        self.state = mix(self.state, self.layer_seed);
+        value
+    }
+
+    fn choose2(&mut self, a: i32, b: i32) -> i32 {
+        if self.next(2) == 0 {
+            a
+        } else {
+            b
+        }
+    }
+
+    fn choose4(&mut self, a: i32, b: i32, c: i32, d: i32) -> i32 {
+        match self.next(4) {
+            0 => a,
+            1 => b,
+            2 => c,
+            _ => d,
+        }
+    }
+}
+
+mod ids {
+    pub const OCEAN: i32 = 0;
+    pub const PLAINS: i32 = 1;
+    pub const DESERT: i32 = 2;
+    pub const MOUNTAINS: i32 = 3;
+    pub const FOREST: i32 = 4;
+    pub const TAIGA: i32 = 5;
+    pub const SWAMP: i32 = 6;
+    pub const RIVER: i32 = 7;
+    pub const FROZEN_OCEAN: i32 = 10;
+    pub const FROZEN_RIVER: i32 = 11;
+    pub const SNOWY_TUNDRA: i32 = 12;
+    pub const MUSHROOM_FIELDS: i32 = 14;
+    pub const MUSHROOM_FIELD_SHORE: i32 = 15;
+    pub const BEACH: i32 = 16;
+    pub const JUNGLE: i32 = 21;
+    pub const JUNGLE_EDGE: i32 = 23;
+    pub const DEEP_OCEAN: i32 = 24;
+    pub const STONE_SHORE: i32 = 25;
+    pub const SNOWY_BEACH: i32 = 26;
+    pub const SNOWY_TAIGA: i32 = 30;
+    pub const GIANT_TREE_TAIGA: i32 = 32;
+    pub const WOODED_MOUNTAINS: i32 = 34;
+    pub const BADLANDS: i32 = 37;
+    pub const WOODED_BADLANDS_PLATEAU: i32 = 38;
+    pub const BADLANDS_PLATEAU: i32 = 39;
+    pub const WARM_OCEAN: i32 = 44;
+    pub const LUKEWARM_OCEAN: i32 = 45;
+    pub const COLD_OCEAN: i32 = 46;
+    pub const DEEP_WARM_OCEAN: i32 = 47;
+    pub const DEEP_LUKEWARM_OCEAN: i32 = 48;
+    pub const DEEP_COLD_OCEAN: i32 = 49;
+    pub const DEEP_FROZEN_OCEAN: i32 = 50;
+    pub const SUNFLOWER_PLAINS: i32 = 129;
+    pub const BAMBOO_JUNGLE: i32 = 168;
+}
+
+use ids::*;
+
+fn is_shallow_ocean(id: i32) -> bool {
+    matches!(
+        id,
+        WARM_OCEAN | LUKEWARM_OCEAN | OCEAN | COLD_OCEAN | FROZEN_OCEAN
+    )
+}
+
+fn is_ocean(id: i32) -> bool {
+    is_shallow_ocean(id)
+        || matches!(
+            id,
+            DEEP_WARM_OCEAN
+                | DEEP_LUKEWARM_OCEAN
+                | DEEP_OCEAN
+                | DEEP_COLD_OCEAN
+                | DEEP_FROZEN_OCEAN
+        )
+}
+
+/// Biome categories used by vanilla to decide
+/// whether two biomes are "the same" for layering purposes.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Category {
+    None,
+    Taiga,
+    ExtremeHills,
+    Jungle,
+    Mesa,
+    BadlandsPlateau,
+    Plains,
+    Savanna,
+    Icy,
+    Beach,
+    Forest,
+    Ocean,
+    Desert,
+    River,
+    Swamp,
+    Mushroom,
+}
+
+fn category(id: i32) -> Category {
+    match id {
+        16 | 26 => Category::Beach,
+        2 | 17 | 130 => Category::Desert,
+        3 | 20 | 34 | 131 | 162 => Category::ExtremeHills,
+        4 | 18 | 27 | 28 | 29 | 132 | 155 | 156 | 157 => Category::Forest,
+        12 | 13 | 140 => Category::Icy,
+        21 | 22 | 23 | 149 | 151 | 168 | 169 => Category::Jungle,
+        37 | 165 | 166 | 167 => Category::Mesa,
+        38 | 39 => Category::BadlandsPlateau,
+        14 | 15 => Category::Mushroom,
+        1 | 129 => Category::Plains,
+        7 | 11 => Category::River,
+        35 | 36 | 163 | 164 => Category::Savanna,
+        6 | 134 => Category::Swamp,
+        5 | 19 | 30 | 31 | 32 | 33 | 133 | 158 | 160 | 161 => Category::Taiga,
+        id if is_ocean(id) => Category::Ocean,
+        _ => Category::None,
+    }
+}
+
+fn is_same(a: i32, b: i32) -> bool {
+    a == b || category(a) == category(b)
+}
+
+/// Returns the mutated ("M") variant of a biome, if it has one.
+fn mutated(id: i32) -> Option<i32> {
+    let mutated = match id {
+        1 => 129,
+        2 => 130,
+        3 => 131,
+        4 => 132,
+        5 => 133,
+        6 => 134,
+        12 => 140,
+        21 => 149,
+        23 => 151,
+        27 => 155,
+        28 => 156,
+        29 => 157,
+        30 => 158,
+        32 => 160,
+        33 => 161,
+        34 => 162,
+        35 => 163,
+        36 => 164,
+        37 => 165,
+        38 => 166,
+        39 => 167,
+        _ => return None,
+    };
+    Some(mutated)
+}
+
+/// The operation performed by a layer.
+enum Op {
+    Island,
+    OceanTemperature(Box<ImprovedNoise>),
+    Zoom,
+    FuzzyZoom,
+    AddIsland,
+    RemoveTooMuchOcean,
+    AddSnow,
+    CoolWarm,
+    HeatIce,
+    Special,
+    AddMushroom,
+    DeepOcean,
+    Biome,
+    Bamboo,
+    BiomeEdge,
+    StartRiver,
+    Hills,
+    River,
+    Smooth,
+    Rare,
+    Shore,
+    MixRiver,
+    MixOceans,
+}
+
+/// How the cells of a layer's parent are read.
+enum Shape {
+    /// No parent.
+    Source,
+    /// Twice the resolution of the parent.
+    Zoom,
+    /// The parent cell at the same position.
+    Cell,
+    /// The parent cell at the same position and its neighbours.
+    Neighbours,
+}
+
+impl Op {
+    fn shape(&self) -> Shape {
+        match self {
+            Op::Island | Op::OceanTemperature(_) => Shape::Source,
+            Op::Zoom | Op::FuzzyZoom => Shape::Zoom,
+            Op::AddSnow
+            | Op::Special
+            | Op::Biome
+            | Op::Bamboo
+            | Op::StartRiver
+            | Op::Rare
+            | Op::MixRiver
+            | Op::MixOceans => Shape::Cell,
+            Op::AddIsland
+            | Op::RemoveTooMuchOcean
+            | Op::CoolWarm
+            | Op::HeatIce
+            | Op::AddMushroom
+            | Op::DeepOcean
+            | Op::BiomeEdge
+            | Op::Hills
+            | Op::River
+            | Op::Smooth
+            | Op::Shore => Shape::Neighbours,
+        }
+    }
+}
+
+/// The values surrounding a cell: the cell itself,
+/// its cardinal neighbours and its diagonal neighbours.
+struct Neighbourhood {
+    center: i32,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    north_west: i32,
+    north_east: i32,
+    south_west: i32,
+    south_east: i32,
+}
+
+impl Neighbourhood {
+    fn cardinal(&self) -> [i32; 4] {
+        [self.north, self.east, self.south, self.west]
+    }
+
+    fn diagonal(&self) -> [i32; 4] {
+        [
+            self.north_west,
+            self.south_west,
+            self.north_east,
+            self.south_east,
+        ]
+    }
+}
+
+/// A layer in the biome layer stack.
+struct Layer {
+    op: Op,
+    seed: i64,
+    parent: Option<Box<Layer>>,
+    /// The second input of merging layers.
+    other: Option<Box<Layer>>,
+}
+
+impl Layer {
+    fn source(op: Op, world_seed: i64, salt: i64) -> Self {
+        Self {
+            op,
+            seed: layer_seed(world_seed, salt),
+            parent: None,
+            other: None,
+        }
+    }
+
+    fn then(self, op: Op, world_seed: i64, salt: i64) -> Self {
+        Self {
+            op,
+            seed: layer_seed(world_seed, salt),
+            parent: Some(Box::new(self)),
+            other: None,
+        }
+    }
+
+    fn merge(self, op: Op, other: Layer, world_seed: i64, salt: i64) -> Self {
+        Self {
+            op,
+            seed: layer_seed(world_seed, salt),
+            parent: Some(Box::new(self)),
+            other: Some(Box::new(other)),
+        }
+    }
+
+    fn zoom(mut self, world_seed: i64, salt: i64, count: i64) -> Self {
+        for i in 0..count {
+            self = self.then(Op::Zoom, world_seed, salt + i);
+        }
+        self
+    }
+
+    /// Builds the overworld layer stack.
+    fn overworld(seed: i64, biome_size: i64, river_size: i64) -> Self {
+        let noise = ImprovedNoise::new(&mut JavaRandom::new(seed));
+        let ocean_temperature =
+            Layer::source(Op::OceanTemperature(Box::new(noise)), seed, 2).zoom(seed, 2001, 6);
+
+        // Layers have a single consumer, so the branches
+        // shared by hills and rivers are built once per use.
+        let river_noise = || {
+            Layer::overworld_land(seed)
+                .then(Op::StartRiver, seed, 100)
+                .zoom(seed, 1000, 2)
+        };
+        let rivers = river_noise()
+            .zoom(seed, 1000, 2)
+            .zoom(seed, 1000, river_size)
+            .then(Op::River, seed, 1)
+            .then(Op::Smooth, seed, 1000);
+
+        let mut biomes = Layer::overworld_land(seed)
+            .then(Op::Biome, seed, 200)
+            .then(Op::Bamboo, seed, 1001)
+            .zoom(seed, 1000, 2)
+            .then(Op::BiomeEdge, seed, 1000)
+            .merge(Op::Hills, river_noise(), seed, 1000)
+            .then(Op::Rare, seed, 1001);
+        for i in 0..biome_size {
+            biomes = biomes.then(Op::Zoom, seed, 1000 + i);
+            if i == 0 {
+                biomes = biomes.then(Op::AddIsland, seed, 3);
+            }
+            if i == 1 || biome_size == 1 {
+                biomes = biomes.then(Op::Shore, seed, 1000);
+            }
+        }
+
+        biomes
+            .then(Op::Smooth, seed, 1000)
+            .merge(Op::MixRiver, rivers, seed, 100)
+            .merge(Op::MixOceans, ocean_temperature, seed, 100)
+    }
+
+    /// Builds the land/temperature part of the overworld stack,
+    /// which the biome and river branches both start from.
+    fn overworld_land(seed: i64) -> Self {
+        Layer::source(Op::Island, seed, 1)
+            .then(Op::FuzzyZoom, seed, 2000)
+            .then(Op::AddIsland, seed, 1)
+            .then(Op::Zoom, seed, 2001)
+            .then(Op::AddIsland, seed, 2)
+            .then(Op::AddIsland, seed, 50)
+            .then(Op::AddIsland, seed, 70)
+            .then(Op::RemoveTooMuchOcean, seed, 2)
+            .then(Op::AddSnow, seed, 2)
+            .then(Op::AddIsland, seed, 3)
+            .then(Op::CoolWarm, seed, 2)
+            .then(Op::HeatIce, seed, 2)
+            .then(Op::Special, seed, 3)
+            .then(Op::Zoom, seed, 2002)
+            .then(Op::Zoom, seed, 2003)
+            .then(Op::AddIsland, seed, 4)
+            .then(Op::AddMushroom, seed, 5)
+            .then(Op::DeepOcean, seed, 4)
+    }
+
+    fn parent(&self) -> &Layer {
+        self.parent.as_deref().expect("layer has no parent")
+    }
+
+    fn other(&self) -> &Layer {
+        self.other.as_deref().expect("layer has no second input")
+    }
+
+    /// Computes the values of the given area, indexed by `z * width + x`.
+    fn area(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let mut out = Vec::with_capacity((width * height) as usize);
+        match self.op.shape() {
+            Shape::Source => {
+                for dz in 0..height {
+                    for dx in 0..width {
+                        out.push(self.source_cell(x + dx, z + dz));
+                    }
+                }
+            }
+            Shape::Zoom => {
+                let parent_x = x >> 1;
+                let parent_z = z >> 1;
+                let parent_width = ((x + width - 1) >> 1) - parent_x + 2;
+                let parent_height = ((z + height - 1) >> 1) - parent_z + 2;
+                let parent = self
+                    .parent()
+                    .area(parent_x, parent_z, parent_width, parent_height);
+                let at = |px: i32, pz: i32| {
+                    parent[((pz - parent_z) * parent_width + (px - parent_x)) as usize]
+                };
+                for dz in 0..height {
+                    for dx in 0..width {
+                        out.push(self.zoom_cell(x + dx, z + dz, at));
+                    }
+                }
+            }
+            Shape::Cell => match self.op {
+                Op::MixRiver => {
+                    let biomes = self.parent().area(x, z, width, height);
+                    let rivers = self.other().area(x, z, width, height);
+                    for (&biome, &river) in biomes.iter().zip(&rivers) {
+                        out.push(mix_river(biome, river));
+                    }
+                }
+                Op::MixOceans => {
+                    // Oceans are warmed or cooled near land
+                    // up to 8 cells away.
+                    let land_width = width + 16;
+                    let land = self.parent().area(x - 8, z - 8, land_width, height + 16);
+                    let temperatures = self.other().area(x, z, width, height);
+                    for dz in 0..height {
+                        for dx in 0..width {
+                            let land_at = |ox: i32, oz: i32| {
+                                land[((dz + 8 + oz) * land_width + dx + 8 + ox) as usize]
+                            };
+                            let temperature = temperatures[(dz * width + dx) as usize];
+                            out.push(mix_oceans(land_at(0, 0), temperature, land_at));
+                        }
+                    }
+                }
+                _ => {
+                    let parent = self.parent().area(x, z, width, height);
+                    for dz in 0..height {
+                        for dx in 0..width {
+                            let mut random = CellRandom::new(self.seed, x + dx, z + dz);
+                            out.push(self.cell(parent[(dz * width + dx) as usize], &mut random));
+                        }
+                    }
+                }
+            },
+            Shape::Neighbours => {
+                let parent_width = width + 2;
+                let parent = self.parent().area(x - 1, z - 1, parent_width, height + 2);
+                let other = match self.op {
+                    Op::Hills => Some(self.other().area(x, z, width, height)),
+                    _ => None,
+                };
+                for dz in 0..height {
+                    for dx in 0..width {
+                        let at = |ox: i32, oz: i32| {
+                            parent[((dz + 1 + oz) * parent_width + dx + 1 + ox) as usize]
+                        };
+                        let cells = Neighbourhood {
+                            center: at(0, 0),
+                            north: at(0, -1),
+                            east: at(1, 0),
+                            south: at(0, 1),
+                            west: at(-1, 0),
+                            north_west: at(-1, -1),
+                            north_east: at(1, -1),
+                            south_west: at(-1, 1),
+                            south_east: at(1, 1),
+                        };
+                        let mut random = CellRandom::new(self.seed, x + dx, z + dz);
+                        let value = match &other {
+                            Some(river_noise) => {
+                                hills(&cells, river_noise[(dz * width + dx) as usize], &mut random)
+                            }
+                            None => self.neighbourhood_cell(&cells, &mut random),
+                        };
+                        out.push(value);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn source_cell(&self, x: i32, z: i32) -> i32 {
+        match &self.op {
+            Op::Island => {
+                if x == 0 && z == 0 {
+                    return 1;
+                }
+                let mut random = CellRandom::new(self.seed, x, z);
+                if random.next(10) == 0 {
+                    1
+                } else {
+                    0
+                }
+            }
+            Op::OceanTemperature(noise) => {
+                let n = noise.noise(x as f64 / 8.0, z as f64 / 8.0, 0.0);
+                if n > 0.4 {
+                    WARM_OCEAN
+                } else if n > 0.2 {
+                    LUKEWARM_OCEAN
+                } else if n < -0.4 {
+                    FROZEN_OCEAN
+                } else if n < -0.2 {
+                    COLD_OCEAN
+                } else {
+                    OCEAN
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn zoom_cell(&self, x: i32, z: i32, parent: impl Fn(i32, i32) -> i32) -> i32 {
+        let v00 = parent(x >> 1, z >> 1);
+        let mut random = CellRandom::new(self.seed, x >> 1 << 1, z >> 1 << 1);
+        let (odd_x, odd_z) = (x & 1 == 1, z & 1 == 1);
+        if !odd_x && !odd_z {
+            return v00;
+        }
+
+        let v01 = parent(x >> 1, (z + 1) >> 1);
+        let first = random.choose2(v00, v01);
+        if !odd_x && odd_z {
+            return first;
+        }
+
+        let v10 = parent((x + 1) >> 1, z >> 1);
+        let second = random.choose2(v00, v10);
+        if odd_x && !odd_z {
+            return second;
+        }
+
+        let v11 = parent((x + 1) >> 1, (z + 1) >> 1);
+        match self.op {
+            Op::FuzzyZoom => random.choose4(v00, v10, v01, v11),
+            _ => select_mode_or_random(&mut random, v00, v10, v01, v11),
+        }
+    }
+
+    fn cell(&self, value: i32, random: &mut CellRandom) -> i32 {
+        match self.op {
+            Op::AddSnow => {
+                if is_shallow_ocean(value) {
+                    return value;
+                }
+                match random.next(6) {
+                    0 => 4,
+                    1 => 3,
+                    _ => 1,
+                }
+            }
+            Op::Special => {
+                if !is_shallow_ocean(value) && random.next(13) == 0 {
+                    value | ((1 + random.next(15)) << 8 & 0xF00)
+                } else {
+                    value
+                }
+            }
+            Op::Biome => biome(value, random),
+            Op::Bamboo => {
+                if random.next(10) == 0 && value == JUNGLE {
+                    BAMBOO_JUNGLE
+                } else {
+                    value
+                }
+            }
+            Op::StartRiver => {
+                if is_shallow_ocean(value) {
+                    value
+                } else {
+                    random.next(299999) + 2
+                }
+            }
+            Op::Rare => {
+                if random.next(57) == 0 && value == PLAINS {
+                    SUNFLOWER_PLAINS
+                } else {
+                    value
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn neighbourhood_cell(&self, cells: &Neighbourhood, random: &mut CellRandom) -> i32 {
+        let center = cells.center;
+        let cardinal = cells.cardinal();
+        match self.op {
+            Op::AddIsland => add_island(cells, random),
+            Op::RemoveTooMuchOcean => {
+                if is_shallow_ocean(center)
+                    && cardinal.iter().all(|&v| is_shallow_ocean(v))
+                    && random.next(2) == 0
+                {
+                    1
+                } else {
+                    center
+                }
+            }
+            Op::CoolWarm => {
+                if center == 1 && cardinal.iter().any(|&v| v == 3 || v == 4) {
+                    2
+                } else {
+                    center
+                }
+            }
+            Op::HeatIce => {
+                if center == 4 && cardinal.iter().any(|&v| v == 1 || v == 2) {
+                    3
+                } else {
+                    center
+                }
+            }
+            Op::AddMushroom => {
+                if is_shallow_ocean(center)
+                    && cells.diagonal().iter().all(|&v| is_shallow_ocean(v))
+                    && random.next(100) == 0
+                {
+                    MUSHROOM_FIELDS
+                } else {
+                    center
+                }
+            }
+            Op::DeepOcean => {
+                if is_shallow_ocean(center) && cardinal.iter().all(|&v| is_shallow_ocean(v)) {
+                    match center {
+                        WARM_OCEAN => DEEP_WARM_OCEAN,
+                        LUKEWARM_OCEAN => DEEP_LUKEWARM_OCEAN,
+                        COLD_OCEAN => DEEP_COLD_OCEAN,
+                        FROZEN_OCEAN => DEEP_FROZEN_OCEAN,
+                        _ => DEEP_OCEAN,
+                    }
+                } else {
+                    center
+                }
+            }
+            Op::BiomeEdge => biome_edge(center, cardinal),
+            Op::River => {
+                let filter = |v: i32| if v >= 2 { 2 + (v & 1) } else { v };
+                let c = filter(center);
+                if cardinal.iter().all(|&v| filter(v) == c) {
+                    -1
+                } else {
+                    RIVER
+                }
+            }
+            Op::Smooth => {
+                let east_west = cells.east == cells.west;
+                let north_south = cells.north == cells.south;
+                if east_west == north_south {
+                    if east_west {
+                        random.choose2(cells.west, cells.north)
+                    } else {
+                        center
+                    }
+                } else if east_west {
+                    cells.west
+                } else {
+                    cells.north
+                }
+            }
+            Op::Shore => shore(center, cardinal),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Picks the most common of four values,
+/// falling back to a random one on a tie.
+fn select_mode_or_random(random: &mut CellRandom, a: i32, b: i32, c: i32, d: i32) -> i32 {
+    if b == c && c == d {
+        b
+    } else if (a == b && (a == c || a == d || c != d))
+        || (a == c && (a == d || b != d))
+        || (a == d && b != c)
+    {
+        a
+    } else if (b == c && a != d) || (b == d && a != c) {
+        b
+    } else if c == d && a != b {
+        c
+    } else {
+        random.choose4(a, b, c, d)
+    }
+}
+
+fn add_island(cells: &Neighbourhood, random: &mut CellRandom) -> i32 {
+    let center = cells.center;
+    let diagonal = cells.diagonal();
+    if !is_shallow_ocean(center) || diagonal.iter().all(|&v| is_shallow_ocean(v)) {
+        if !is_shallow_ocean(center)
+            && diagonal.iter().any(|&v| is_shallow_ocean(v))
+            && random.next(5) == 0
+        {
+            // Checked north-west, south-west, north-east, south-east.
+            if let Some(&ocean) = diagonal.iter().find(|&&v| is_shallow_ocean(v)) {
+                return if center == 4 { 4 } else { ocean };
+            }
+        }
+        center
+    } else {
+        let mut bound = 1;
+        let mut chosen = 1;
+        // Unlike above, checked north-west, north-east, south-west, south-east.
+        for &v in &[
+            cells.north_west,
+            cells.north_east,
+            cells.south_west,
+            cells.south_east,
+        ] {
+            if !is_shallow_ocean(v) {
+                if random.next(bound) == 0 {
+                    chosen = v;
+                }
+                bound += 1;
+            }
+        }
+        if random.next(3) == 0 {
+            chosen
+        } else if chosen == 4 {
+            4
+        } else {
+            center
+        }
+    }
+}
+
+fn biome(value: i32, random: &mut CellRandom) -> i32 {
+    const DRY: [i32; 6] = [2, 2, 2, 35, 35, 1];
+    const TEMPERATE: [i32; 6] = [4, 29, 3, 1, 27, 6];
+    const COOL: [i32; 4] = [4, 3, 5, 1];
+    const ICY: [i32; 4] = [12, 12, 12, 30];
+
+    let special = (value & 0xF00) >> 8;
+    let value = value & !0xF00;
+    if is_ocean(value) || value == MUSHROOM_FIELDS {
+        return value;
+    }
+
+    let pick =
+        |random: &mut CellRandom, biomes: &[i32]| biomes[random.next(biomes.len() as i32) as usize];
+    match value {
+        1 if special > 0 => {
+            if random.next(3) == 0 {
+                BADLANDS_PLATEAU
+            } else {
+                WOODED_BADLANDS_PLATEAU
+            }
+        }
+        1 => pick(random, &DRY),
+        2 if special > 0 => JUNGLE,
+        2 => pick(random, &TEMPERATE),
+        3 if special > 0 => GIANT_TREE_TAIGA,
+        3 => pick(random, &COOL),
+        4 => pick(random, &ICY),
+        _ => MUSHROOM_FIELDS,
+    }
+}
+
+fn biome_edge(center: i32, cardinal: [i32; 4]) -> i32 {
+    if is_same(center, MOUNTAINS) {
+        return center;
+    }
+
+    for &(from, to) in &[
+        (WOODED_BADLANDS_PLATEAU, BADLANDS),
+        (BADLANDS_PLATEAU, BADLANDS),
+        (GIANT_TREE_TAIGA, TAIGA),
+    ] {
+        if center == from {
+            return if cardinal.iter().all(|&v| is_same(v, from)) {
+                center
+            } else {
+                to
+            };
+        }
+    }
+
+    if center == DESERT && cardinal.contains(&SNOWY_TUNDRA) {
+        WOODED_MOUNTAINS
+    } else if center == SWAMP {
+        if cardinal
+            .iter()
+            .any(|&v| v == DESERT || v == SNOWY_TAIGA || v == SNOWY_TUNDRA)
+        {
+            PLAINS
+        } else if cardinal.iter().any(|&v| v == JUNGLE || v == BAMBOO_JUNGLE) {
+            JUNGLE_EDGE
+        } else {
+            center
+        }
+    } else {
+        center
+    }
+}
+
+fn hills(cells: &Neighbourhood, river_noise: i32, random: &mut CellRandom) -> i32 {
+    let biome = cells.center;
+    let k = (river_noise - 2) % 29;
+
+    if !is_shallow_ocean(biome) && river_noise >= 2 && k == 1 {
+        return mutated(biome).unwrap_or(biome);
+    }
+
+    if random.next(3) == 0 || k == 0 {
+        let mut hills = match biome {
+            2 => 17,
+            4 => 18,
+            27 => 28,
+            29 => 1,
+            5 => 19,
+            32 => 33,
+            30 => 31,
+            12 => 13,
+            21 => 22,
+            168 => 169,
+            0 => 24,
+            45 => 48,
+            46 => 49,
+            10 => 50,
+            3 => 34,
+            35 => 36,
+            1 => {
+                if random.next(3) == 0 {
+                    18
+                } else {
+                    4
+                }
+            }
+            b if is_same(b, WOODED_BADLANDS_PLATEAU) => 37,
+            24 | 48 | 49 | 50 if random.next(3) == 0 => {
+                if random.next(2) == 0 {
+                    1
+                } else {
+                    4
+                }
+            }
+            b => b,
+        };
+
+        if k == 0 && hills != biome {
+            hills = mutated(hills).unwrap_or(biome);
+        }
+
+        if hills != biome {
+            let similar = cells
+                .cardinal()
+                .iter()
+                .filter(|&&v| is_same(v, biome))
+                .count();
+            if similar >= 3 {
+                return hills;
+            }
+        }
+    }
+
+    biome
+}
+
+fn shore(center: i32, cardinal: [i32; 4]) -> i32 {
+    let is_jungle = |v: i32| matches!(v, 168 | 169 | 21 | 22 | 23 | 149 | 151);
+    let is_mesa = |v: i32| matches!(v, 37 | 38 | 39 | 165 | 166 | 167);
+    let is_snowy = |v: i32| matches!(v, 26 | 11 | 12 | 13 | 140 | 30 | 31 | 158 | 10);
+    let next_to_ocean = cardinal.iter().any(|&v| is_ocean(v));
+
+    if center == MUSHROOM_FIELDS {
+        if cardinal.iter().any(|&v| is_shallow_ocean(v)) {
+            return MUSHROOM_FIELD_SHORE;
+        }
+    } else if is_jungle(center) {
+        let jungle_compatible = |v: i32| is_jungle(v) || v == FOREST || v == TAIGA || is_ocean(v);
+        if !cardinal.iter().all(|&v| jungle_compatible(v)) {
+            return JUNGLE_EDGE;
+        }
+        if next_to_ocean {
+            return BEACH;
+        }
+    } else if !matches!(center, 3 | 34 | 20) {
+        if is_snowy(center) {
+            if !is_ocean(center) && next_to_ocean {
+                return SNOWY_BEACH;
+            }
+        } else if center != BADLANDS && center != WOODED_BADLANDS_PLATEAU {
+            if !is_ocean(center) && center != RIVER && center != SWAMP && next_to_ocean {
+                return BEACH;
+            }
+        } else if !next_to_ocean && !cardinal.iter().all(|&v| is_mesa(v)) {
+            return DESERT;
+        }
+    } else if !is_ocean(center) && next_to_ocean {
+        return STONE_SHORE;
+    }
+    center
+}
+
+fn mix_river(biome: i32, river: i32) -> i32 {
+    if is_ocean(biome) || river != RIVER {
+        return biome;
+    }
+    match biome {
+        SNOWY_TUNDRA => FROZEN_RIVER,
+        MUSHROOM_FIELDS | MUSHROOM_FIELD_SHORE => MUSHROOM_FIELD_SHORE,
+        _ => river & 255,
+    }
+}
+
+fn mix_oceans(land: i32, temperature: i32, land_at: impl Fn(i32, i32) -> i32) -> i32 {
+    if !is_ocean(land) {
+        return land;
+    }
+
+    for ox in (-8..=8).step_by(4) {
+        for oz in (-8..=8).step_by(4) {
+            if !is_ocean(land_at(ox, oz)) {
+                match temperature {
+                    WARM_OCEAN => return LUKEWARM_OCEAN,
+                    FROZEN_OCEAN => return COLD_OCEAN,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    if land == DEEP_OCEAN {
+        match temperature {
+            LUKEWARM_OCEAN => DEEP_LUKEWARM_OCEAN,
+            OCEAN => DEEP_OCEAN,
+            COLD_OCEAN => DEEP_COLD_OCEAN,
+            FROZEN_OCEAN => DEEP_FROZEN_OCEAN,
+            _ => temperature,
+        }
+    } else {
+        temperature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_random_parity() {
+        // Values produced by java.util.Random with the same seeds, computed
+        // with the linear congruential generator specified in its Javadoc.
+        for &(seed, expected) in &[
+            (0, (187, 48, 249750, 0.6063452159973596)),
+            (1, (187, 88, 144782, 0.4074398012118764)),
+            (-4172144997902289642, (82, 90, 66564, 0.626015552782278)),
+        ] {
+            let mut random = JavaRandom::new(seed);
+            let actual = (
+                random.next_int(256),
+                random.next_int(100),
+                random.next_int(299999),
+                random.next_double(),
+            );
+            assert_eq!(actual.0, expected.0, "seed {}", seed);
+            assert_eq!(actual.1, expected.1, "seed {}", seed);
+            assert_eq!(actual.2, expected.2, "seed {}", seed);
+            approx::assert_abs_diff_eq!(actual.3, expected.3, epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn biome_regression_fixtures() {
+        // (world seed, block x, block z, biome ID), recorded from this
+        // implementation. They are NOT taken from vanilla or a seed-map tool,
+        // so they only detect changes to the layout of existing worlds.
+        // TODO: replace them with values checked against vanilla 1.16.
+        let fixtures: &[(i64, i32, i32, i32)] = &[
+            (0, 0, 0, 4),
+            (0, -700, 300, 34),
+            (0, 1200, -2500, 27),
+            (0, -5000, -5000, 5),
+            (0, 10000, 2000, 6),
+            (1, 0, 0, 0),
+            (1, -700, 300, 3),
+            (1, 1200, -2500, 4),
+            (1, -5000, -5000, 12),
+            (1, 10000, 2000, 32),
+            (-4172144997902289642, 0, 0, 12),
+            (-4172144997902289642, 1200, -2500, 129),
+            (-4172144997902289642, -5000, -5000, 46),
+            (2151901553968352745, 0, 0, 5),
+            (2151901553968352745, 1200, -2500, 48),
+            (2151901553968352745, -5000, -5000, 29),
+        ];
+        for &(seed, x, z, expected) in fixtures {
+            let gen = LayeredBiomeGenerator::new(seed as u64);
+            assert_eq!(
+                gen.biome_ids(x >> 2, z >> 2, 1, 1),
+                vec![expected],
+                "seed {} at ({}, {})",
+                seed,
+                x,
+                z
+            );
+        }
+    }
+
+    #[test]
+    fn area_matches_single_cells() {
+        let gen = LayeredBiomeGenerator::new(99);
+        let area = gen.biome_ids(-13, -7, 24, 24);
+        for z in (0..24).step_by(5) {
+            for x in (0..24).step_by(3) {
+                assert_eq!(
+                    gen.biome_ids(-13 + x, -7 + z, 1, 1)[0],
+                    area[(z * 24 + x) as usize]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_biomes() {
+        let gen = LayeredBiomeGenerator::new(0);
+        let chunk = ChunkPosition::new(-3, 5);
+        let biomes = gen.generate_for_chunk(chunk, 1);
+        let ids = gen.biome_ids(chunk.x * 4, chunk.z * 4, 4, 4);
+        for z in 0..4 {
+            for x in 0..4 {
+                for y in &[0, 63] {
+                    assert_eq!(biomes.get(x, *y, z).id() as i32, ids[z * 4 + x]);
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 //! Biome grid creation.
 
 mod distorted_voronoi;
+mod layered;
 mod two_level;
 
 pub use distorted_voronoi::DistortedVoronoiBiomeGenerator;
+pub use layered::LayeredBiomeGenerator;
 pub use two_level::TwoLevelBiomeGenerator;
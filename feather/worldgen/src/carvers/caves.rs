@@ -0,0 +1,78 @@
+use super::{carve_ellipsoid, start_chunks, start_rng, Tunnel, CARVER_RANGE, LAVA_LEVEL};
+use crate::{Carver, WaterMap};
+use base::Chunk;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::f32::consts::TAU;
+
+/// Carver for winding caves.
+pub struct CaveCarver {
+    /// Height below which caves are filled with lava.
+    lava_level: usize,
+}
+
+impl CaveCarver {
+    /// Creates a cave carver which fills
+    /// caves below `lava_level` with lava.
+    pub fn new(lava_level: usize) -> Self {
+        Self { lava_level }
+    }
+}
+
+impl Default for CaveCarver {
+    fn default() -> Self {
+        Self::new(LAVA_LEVEL)
+    }
+}
+
+impl Carver for CaveCarver {
+    fn carve(&self, chunk: &mut Chunk, water: &dyn WaterMap, seed: u64) {
+        for start in start_chunks(chunk.position()) {
+            let mut rng = start_rng(seed, start);
+
+            // Most chunks have no caves starting in them.
+            if rng.gen_range(0, 7) != 0 {
+                continue;
+            }
+
+            for _ in 0..rng.gen_range(1, 5) {
+                let mut rng = XorShiftRng::seed_from_u64(rng.gen());
+
+                let x = f64::from(start.x * 16 + rng.gen_range(0, 16));
+                // Bias caves towards lower heights.
+                let max_y = rng.gen_range(16, 128);
+                let y = f64::from(rng.gen_range(8, max_y));
+                let z = f64::from(start.z * 16 + rng.gen_range(0, 16));
+
+                // Some caves start with a large room.
+                if rng.gen_range(0, 4) == 0 {
+                    let radius = 1.5 + f64::from(rng.gen::<f32>()) * 6.0;
+                    carve_ellipsoid(
+                        chunk,
+                        water,
+                        (x, y, z),
+                        radius,
+                        radius * 0.5,
+                        self.lava_level,
+                    );
+                }
+
+                let max_length = (CARVER_RANGE * 16) as u32;
+                let steep = rng.gen_range(0, 6) == 0;
+                let tunnel = Tunnel {
+                    x,
+                    y,
+                    z,
+                    yaw: rng.gen::<f32>() * TAU,
+                    pitch: (rng.gen::<f32>() - 0.5) / 4.0,
+                    width: rng.gen::<f32>() * 2.0 + rng.gen::<f32>(),
+                    vertical_scale: 1.0,
+                    length: max_length - rng.gen_range(0, max_length / 4),
+                    pitch_damping: if steep { 0.92 } else { 0.7 },
+                    turn: 4.0,
+                };
+                tunnel.carve(chunk, water, self.lava_level, &mut rng);
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Carvers for world generation, such as caves and ravines.
+//!
+//! Carvers dig tunnels (Perlin worms) through the composed
+//! terrain. A tunnel may start in any chunk within
+//! [`CARVER_RANGE`] chunks, so each carver regenerates the tunnels
+//! of the surrounding chunks and only carves the parts of them
+//! within the chunk being generated.
+
+mod caves;
+mod ravines;
+
+use crate::WaterMap;
+use base::{BlockId, Chunk, ChunkPosition, SimplifiedBlockKind};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::f32::consts::PI;
+
+pub use caves::CaveCarver;
+pub use ravines::RavineCarver;
+
+/// Distance, in chunks, from which a tunnel can
+/// reach the chunk being carved.
+const CARVER_RANGE: i32 = 8;
+
+/// Default height below which carved blocks are filled with lava.
+pub const LAVA_LEVEL: usize = 10;
+
+/// Returns the chunks from which a tunnel may reach `chunk`.
+fn start_chunks(chunk: ChunkPosition) -> impl Iterator<Item = ChunkPosition> {
+    (-CARVER_RANGE..=CARVER_RANGE).flat_map(move |z| {
+        (-CARVER_RANGE..=CARVER_RANGE).map(move |x| ChunkPosition::new(chunk.x + x, chunk.z + z))
+    })
+}
+
+/// Deterministically creates the random number
+/// generator for tunnels starting in `start`.
+fn start_rng(seed: u64, start: ChunkPosition) -> XorShiftRng {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let a = rng.gen::<u64>() | 1;
+    let b = rng.gen::<u64>() | 1;
+    XorShiftRng::seed_from_u64(
+        (start.x as i64 as u64).wrapping_mul(a) ^ (start.z as i64 as u64).wrapping_mul(b) ^ seed,
+    )
+}
+
+/// A worm carving a tunnel through the terrain.
+struct Tunnel {
+    x: f64,
+    y: f64,
+    z: f64,
+    yaw: f32,
+    pitch: f32,
+    /// Maximum horizontal radius in addition to the base radius.
+    width: f32,
+    /// Ratio of the vertical radius to the horizontal radius.
+    vertical_scale: f64,
+    /// Number of steps the worm takes.
+    length: u32,
+    /// Factor by which the pitch decays each step.
+    pitch_damping: f32,
+    /// How sharply the worm turns.
+    turn: f32,
+}
+
+impl Tunnel {
+    /// Walks the tunnel, carving the parts of it within `chunk`.
+    ///
+    /// The tunnel must be carved with its own `rng` so that
+    /// stopping early doesn't affect other tunnels.
+    fn carve(
+        mut self,
+        chunk: &mut Chunk,
+        water: &dyn WaterMap,
+        lava_level: usize,
+        rng: &mut XorShiftRng,
+    ) {
+        let center_x = f64::from(chunk.position().x * 16 + 8);
+        let center_z = f64::from(chunk.position().z * 16 + 8);
+
+        let mut yaw_change = 0.0f32;
+        let mut pitch_change = 0.0f32;
+        for step in 0..self.length {
+            let radius = 1.5 + (step as f32 * PI / self.length as f32).sin() * self.width;
+
+            let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+            self.x += f64::from(self.yaw.cos() * cos_pitch);
+            self.y += f64::from(sin_pitch);
+            self.z += f64::from(self.yaw.sin() * cos_pitch);
+
+            self.pitch *= self.pitch_damping;
+            self.pitch += pitch_change * 0.1;
+            self.yaw += yaw_change * 0.1;
+            pitch_change *= 0.9;
+            pitch_change += (rng.gen::<f32>() - rng.gen::<f32>()) * rng.gen::<f32>() * 2.0;
+            yaw_change *= 0.75;
+            yaw_change += (rng.gen::<f32>() - rng.gen::<f32>()) * rng.gen::<f32>() * self.turn;
+
+            // Stop once the rest of the tunnel can't reach the chunk.
+            let dx = self.x - center_x;
+            let dz = self.z - center_z;
+            let remaining = f64::from(self.length - step);
+            let reach = f64::from(self.width) + 2.0 + 16.0;
+            if dx * dx + dz * dz - remaining * remaining > reach * reach {
+                return;
+            }
+
+            // Skip some segments so the walls are irregular.
+            if rng.gen_range(0, 4) == 0 {
+                continue;
+            }
+
+            let radius = f64::from(radius);
+            carve_ellipsoid(
+                chunk,
+                water,
+                (self.x, self.y, self.z),
+                radius,
+                radius * self.vertical_scale,
+                lava_level,
+            );
+        }
+    }
+}
+
+/// Carves the part of an ellipsoid within `chunk`. Blocks
+/// below `lava_level` are filled with lava instead of air.
+///
+/// Nothing is carved if the bounding box of the ellipsoid (or the
+/// layer right above it) contains water anywhere, including outside
+/// of `chunk`, so that oceans and rivers don't drain into caves and
+/// every chunk the ellipsoid crosses makes the same decision.
+fn carve_ellipsoid(
+    chunk: &mut Chunk,
+    water: &dyn WaterMap,
+    (x, y, z): (f64, f64, f64),
+    horizontal_radius: f64,
+    vertical_radius: f64,
+    lava_level: usize,
+) {
+    let origin_x = chunk.position().x * 16;
+    let origin_z = chunk.position().z * 16;
+
+    let (box_min_x, box_max_x) = (
+        (x - horizontal_radius).floor() as i32,
+        (x + horizontal_radius).ceil() as i32,
+    );
+    let (box_min_z, box_max_z) = (
+        (z - horizontal_radius).floor() as i32,
+        (z + horizontal_radius).ceil() as i32,
+    );
+    let min_x = (box_min_x - origin_x).max(0);
+    let max_x = (box_max_x - origin_x).min(15);
+    let min_z = (box_min_z - origin_z).max(0);
+    let max_z = (box_max_z - origin_z).min(15);
+    // Never carve the bottom layer.
+    let min_y = ((y - vertical_radius).floor() as i32).max(1);
+    let max_y = ((y + vertical_radius).ceil() as i32).min(crate::SKY_LIMIT as i32 - 1);
+    if min_x > max_x || min_z > max_z || min_y > max_y {
+        return;
+    }
+
+    for world_x in box_min_x..=box_max_x {
+        for world_z in box_min_z..=box_max_z {
+            for world_y in min_y..=max_y + 1 {
+                if water.is_water(world_x, world_y, world_z) {
+                    return;
+                }
+            }
+        }
+    }
+
+    for local_x in min_x..=max_x {
+        let dx = (f64::from(origin_x + local_x) + 0.5 - x) / horizontal_radius;
+        for local_z in min_z..=max_z {
+            let dz = (f64::from(origin_z + local_z) + 0.5 - z) / horizontal_radius;
+            if dx * dx + dz * dz >= 1.0 {
+                continue;
+            }
+
+            let (local_x, local_z) = (local_x as usize, local_z as usize);
+            let mut carved_grass = false;
+            for local_y in (min_y..=max_y).rev() {
+                let dy = (f64::from(local_y) + 0.5 - y) / vertical_radius;
+                let local_y = local_y as usize;
+                if dx * dx + dy * dy + dz * dz >= 1.0 {
+                    // Regrow grass on dirt exposed by carving.
+                    if carved_grass
+                        && chunk.block_at(local_x, local_y, local_z) == Some(BlockId::dirt())
+                    {
+                        chunk.set_block_at(local_x, local_y, local_z, BlockId::grass_block());
+                    }
+                    carved_grass = false;
+                    continue;
+                }
+
+                let block = match chunk.block_at(local_x, local_y, local_z) {
+                    Some(block) if is_carvable(block) => block,
+                    _ => continue,
+                };
+                carved_grass |= block.simplified_kind() == SimplifiedBlockKind::GrassBlock;
+
+                let carved = if local_y < lava_level {
+                    BlockId::lava()
+                } else {
+                    BlockId::air()
+                };
+                chunk.set_block_at(local_x, local_y, local_z, carved);
+            }
+        }
+    }
+}
+
+fn is_carvable(block: BlockId) -> bool {
+    !block.is_air() && !block.is_fluid() && block.simplified_kind() != SimplifiedBlockKind::Bedrock
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stone_chunk(pos: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..64 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        chunk
+    }
+
+    fn no_water(_x: i32, _y: i32, _z: i32) -> bool {
+        false
+    }
+
+    /// A straight tunnel along the x axis.
+    fn straight_tunnel(x: f64, y: f64) -> Tunnel {
+        Tunnel {
+            x,
+            y,
+            z: 8.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            width: 2.0,
+            vertical_scale: 1.0,
+            length: 40,
+            pitch_damping: 0.0,
+            turn: 0.0,
+        }
+    }
+
+    #[test]
+    fn tunnels_cross_chunk_borders() {
+        // Starts in chunk (-1, 0) and ends in chunk (1, 0).
+        for chunk_x in -1..=1 {
+            let mut chunk = stone_chunk(ChunkPosition::new(chunk_x, 0));
+            let mut rng = XorShiftRng::seed_from_u64(0);
+            straight_tunnel(-8.0, 32.0).carve(&mut chunk, &no_water, 0, &mut rng);
+
+            // The tunnel may drift up or down a little.
+            let carved = (0..16)
+                .filter(|&x| (20..44).any(|y| chunk.block_at(x, y, 8) == Some(BlockId::air())))
+                .count();
+            assert!(carved > 0, "chunk {} not carved", chunk_x);
+            assert_eq!(chunk.block_at(8, 50, 8), Some(BlockId::stone()));
+        }
+    }
+
+    #[test]
+    fn lava_fills_below_lava_level() {
+        let mut chunk = stone_chunk(ChunkPosition::new(0, 0));
+        carve_ellipsoid(&mut chunk, &no_water, (8.0, 10.0, 8.0), 3.0, 3.0, 10);
+
+        assert_eq!(chunk.block_at(8, 11, 8), Some(BlockId::air()));
+        assert_eq!(chunk.block_at(8, 10, 8), Some(BlockId::air()));
+        assert_eq!(chunk.block_at(8, 9, 8), Some(BlockId::lava()));
+        assert_eq!(chunk.block_at(8, 14, 8), Some(BlockId::stone()));
+    }
+
+    #[test]
+    fn carvers_do_not_breach_water() {
+        let water = |x: i32, y: i32, z: i32| (x, y, z) == (8, 33, 8);
+        let mut chunk = stone_chunk(ChunkPosition::new(0, 0));
+        carve_ellipsoid(&mut chunk, &water, (8.0, 30.0, 8.0), 3.0, 3.0, 0);
+
+        assert_eq!(chunk.block_at(8, 30, 8), Some(BlockId::stone()));
+    }
+
+    #[test]
+    fn water_in_neighbouring_chunks_stops_carving() {
+        // The ellipsoid crosses into chunk (1, 0), which has water
+        // within the bounding box, but not within the ellipsoid.
+        let water = |x: i32, y: i32, z: i32| (x, y, z) == (18, 33, 10);
+        for chunk_x in 0..=1 {
+            let mut chunk = stone_chunk(ChunkPosition::new(chunk_x, 0));
+            carve_ellipsoid(&mut chunk, &water, (16.0, 30.0, 8.0), 3.0, 3.0, 0);
+            for x in 0..16 {
+                assert_eq!(chunk.block_at(x, 30, 8), Some(BlockId::stone()));
+            }
+
+            carve_ellipsoid(&mut chunk, &no_water, (16.0, 30.0, 8.0), 3.0, 3.0, 0);
+            let local_x = if chunk_x == 0 { 15 } else { 0 };
+            assert_eq!(chunk.block_at(local_x, 30, 8), Some(BlockId::air()));
+        }
+    }
+
+    #[test]
+    fn carving_is_deterministic() {
+        let pos = ChunkPosition::new(3, -5);
+        let carve = || {
+            let mut chunk = stone_chunk(pos);
+            crate::Carver::carve(&CaveCarver::default(), &mut chunk, &no_water, 100);
+            crate::Carver::carve(&RavineCarver::default(), &mut chunk, &no_water, 100);
+            chunk
+        };
+
+        let (first, second) = (carve(), carve());
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..64 {
+                    assert_eq!(first.block_at(x, y, z), second.block_at(x, y, z));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::{start_chunks, start_rng, Tunnel, CARVER_RANGE, LAVA_LEVEL};
+use crate::{Carver, WaterMap};
+use base::Chunk;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::f32::consts::TAU;
+
+/// Carver for deep, narrow ravines.
+pub struct RavineCarver {
+    /// Height below which ravines are filled with lava.
+    lava_level: usize,
+}
+
+impl RavineCarver {
+    /// Creates a ravine carver which fills
+    /// ravines below `lava_level` with lava.
+    pub fn new(lava_level: usize) -> Self {
+        Self { lava_level }
+    }
+}
+
+impl Default for RavineCarver {
+    fn default() -> Self {
+        Self::new(LAVA_LEVEL)
+    }
+}
+
+impl Carver for RavineCarver {
+    fn carve(&self, chunk: &mut Chunk, water: &dyn WaterMap, seed: u64) {
+        for start in start_chunks(chunk.position()) {
+            let mut rng = start_rng(seed, start);
+
+            // Ravines are rare.
+            if rng.gen_range(0, 50) != 0 {
+                continue;
+            }
+            let mut rng = XorShiftRng::seed_from_u64(rng.gen());
+
+            let x = f64::from(start.x * 16 + rng.gen_range(0, 16));
+            let max_y = rng.gen_range(28, 68);
+            let y = f64::from(rng.gen_range(20, max_y));
+            let z = f64::from(start.z * 16 + rng.gen_range(0, 16));
+
+            let max_length = (CARVER_RANGE * 16) as u32;
+            let tunnel = Tunnel {
+                x,
+                y,
+                z,
+                yaw: rng.gen::<f32>() * TAU,
+                pitch: (rng.gen::<f32>() - 0.5) / 4.0,
+                width: (rng.gen::<f32>() * 2.0 + rng.gen::<f32>()) * 2.0,
+                vertical_scale: 3.0,
+                length: max_length - rng.gen_range(0, max_length / 4),
+                pitch_damping: 0.7,
+                turn: 1.0,
+            };
+            tunnel.carve(chunk, water, self.lava_level, &mut rng);
+        }
+    }
+}
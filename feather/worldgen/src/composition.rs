@@ -40,6 +40,15 @@ impl CompositionGenerator for BasicCompositionGenerator {
             }
         }
     }
+
+    fn is_water(&self, biome: Biome, y: usize, is_solid: bool) -> bool {
+        places_water(biome, y, is_solid)
+    }
+}
+
+/// Oceans are filled with water up to sea level.
+fn places_water(biome: Biome, y: usize, is_solid: bool) -> bool {
+    biome == Biome::Ocean && y <= SEA_LEVEL && !is_solid
 }
 
 fn basic_composition_for_column(
@@ -75,18 +84,16 @@ fn basic_composition_for_solid_biome(
 
         let mut skip = false;
 
-        if biome == Biome::Ocean {
-            if y <= SEA_LEVEL && !is_solid {
-                block = BlockId::water().with_water_level(water_level);
-                if water_level == 0 {
-                    water_level = 8;
-                } else {
-                    water_level = min(water_level + 1, 15);
-                }
-                skip = true;
-            } else if y >= SEA_LEVEL {
-                continue; // Leave at air - no blocks above sea level in ocean
+        if places_water(biome, y, is_solid) {
+            block = BlockId::water().with_water_level(water_level);
+            if water_level == 0 {
+                water_level = 8;
+            } else {
+                water_level = min(water_level + 1, 15);
             }
+            skip = true;
+        } else if biome == Biome::Ocean && y >= SEA_LEVEL {
+            continue; // Leave at air - no blocks above sea level in ocean
         }
 
         if !skip {
@@ -0,0 +1,74 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{DecorationRegion, Decorator};
+use base::{Biome, BlockId, SimplifiedBlockKind};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::iter;
+
+/// Clumped foliage generator.
+#[derive(Default)]
+pub struct ClumpedFoliageDecorator;
+
+impl Decorator for ClumpedFoliageDecorator {
+    fn decorate(&self, region: &mut DecorationRegion, seed: u64) {
+        // Generate clumps of foliage for the biome.
+        // Clumps centered near the chunk border
+        // extend into the neighbouring chunks.
+        let mut rng =
+            XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, region.center_position()));
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let biome = match region.biome_at(x, 0, z) {
+                    Some(biome) => biome,
+                    None => continue,
+                };
+
+                if let Some(block) = biome_clump_block(biome) {
+                    if rng.gen_range(0, 48) == 0 {
+                        // Generate clump with center at this position.
+                        iter::repeat(()).take(rng.gen_range(3, 6)).for_each(|_| {
+                            let pos_x = x + rng.gen_range(-2, 3);
+                            let pos_z = z + rng.gen_range(-2, 3);
+
+                            if region.biome_at(pos_x, 0, pos_z) != Some(biome) {
+                                return; // Don't generate block outside this biome
+                            }
+
+                            let top = match region.top_block_at(pos_x, pos_z) {
+                                Some(top) => top,
+                                None => return,
+                            };
+                            let on_grass = matches!(
+                                region.block_at(pos_x, top, pos_z),
+                                Some(ground) if ground.simplified_kind() == SimplifiedBlockKind::GrassBlock
+                            );
+                            if on_grass {
+                                region.set_block_at(pos_x, top + 1, pos_z, block);
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn biome_clump_block(biome: Biome) -> Option<BlockId> {
+    match biome {
+        Biome::Plains
+        | Biome::SunflowerPlains
+        | Biome::WoodedMountains
+        | Biome::Mountains
+        | Biome::Savanna
+        | Biome::SavannaPlateau
+        | Biome::Forest
+        | Biome::DarkForest
+        | Biome::DarkForestHills
+        | Biome::BirchForest
+        | Biome::TallBirchForest
+        | Biome::BirchForestHills
+        | Biome::Swamp => Some(BlockId::grass()),
+        _ => None,
+    }
+}
@@ -0,0 +1,265 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{DecorationRegion, Decorator};
+use base::BlockId;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Horizontal size of the box containing a lake.
+const LAKE_WIDTH: i32 = 16;
+/// Vertical size of the box containing a lake. The liquid
+/// fills its lower half, and the upper half is cleared.
+const LAKE_HEIGHT: i32 = 8;
+
+/// Biomes colder than this have frozen lakes.
+const FREEZING_TEMPERATURE: f32 = 0.15;
+
+/// Places lakes of water or lava, carved into the ground
+/// as a union of random ellipsoids.
+pub struct LakeDecorator {
+    liquid: BlockId,
+    /// One in `rarity` chunks has a lake.
+    rarity: u32,
+}
+
+impl LakeDecorator {
+    /// Water lakes, which freeze over in cold biomes.
+    pub fn water() -> Self {
+        Self {
+            liquid: BlockId::water(),
+            rarity: 4,
+        }
+    }
+
+    /// Lava lakes, which are rarer and mostly underground.
+    pub fn lava() -> Self {
+        Self {
+            liquid: BlockId::lava(),
+            rarity: 8,
+        }
+    }
+
+    fn is_lava(&self) -> bool {
+        self.liquid == BlockId::lava()
+    }
+}
+
+impl Decorator for LakeDecorator {
+    fn decorate(&self, region: &mut DecorationRegion, seed: u64) {
+        let mut rng =
+            XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, region.center_position()));
+        if rng.gen_range(0, self.rarity) != 0 {
+            return;
+        }
+
+        // The lake is centered on a random column of the chunk,
+        // so it may extend into the neighbouring chunks.
+        let origin_x = rng.gen_range(0, 16) - LAKE_WIDTH / 2;
+        let origin_z = rng.gen_range(0, 16) - LAKE_WIDTH / 2;
+        let mut y = if self.is_lava() {
+            let max_y = rng.gen_range(8, 256);
+            let y = rng.gen_range(0, max_y);
+            // Lava lakes rarely reach the surface.
+            if y >= 63 && rng.gen_range(0, 10) != 0 {
+                return;
+            }
+            y
+        } else {
+            rng.gen_range(0, 256)
+        };
+
+        // Sink the lake to the ground.
+        let center_x = origin_x + LAKE_WIDTH / 2;
+        let center_z = origin_z + LAKE_WIDTH / 2;
+        while y > 5
+            && region
+                .block_at(center_x, y, center_z)
+                .map_or(true, BlockId::is_air)
+        {
+            y -= 1;
+        }
+        if y <= 4 {
+            return;
+        }
+        let origin_y = y - LAKE_HEIGHT / 2;
+
+        let shape = lake_shape(&mut rng);
+        let in_lake = |x: i32, y: i32, z: i32| {
+            (0..LAKE_WIDTH).contains(&x)
+                && (0..LAKE_HEIGHT).contains(&y)
+                && (0..LAKE_WIDTH).contains(&z)
+                && shape[shape_index(x, y, z)]
+        };
+        let is_border = |x: i32, y: i32, z: i32| {
+            !in_lake(x, y, z)
+                && (in_lake(x + 1, y, z)
+                    || in_lake(x - 1, y, z)
+                    || in_lake(x, y + 1, z)
+                    || in_lake(x, y - 1, z)
+                    || in_lake(x, y, z + 1)
+                    || in_lake(x, y, z - 1))
+        };
+
+        // Only place lakes which are enclosed: no liquid may flow
+        // into the cleared part, and the liquid must be surrounded
+        // by solid blocks.
+        let mut border = Vec::new();
+        for x in 0..LAKE_WIDTH {
+            for z in 0..LAKE_WIDTH {
+                for y in 0..LAKE_HEIGHT {
+                    if !is_border(x, y, z) {
+                        continue;
+                    }
+                    let position = (origin_x + x, origin_y + y, origin_z + z);
+                    let block = match region.block_at(position.0, position.1, position.2) {
+                        Some(block) => block,
+                        None => return,
+                    };
+                    if y >= LAKE_HEIGHT / 2 && block.is_fluid() {
+                        return;
+                    }
+                    if y < LAKE_HEIGHT / 2 && !block.is_solid() && block != self.liquid {
+                        return;
+                    }
+                    border.push((position, y));
+                }
+            }
+        }
+
+        for x in 0..LAKE_WIDTH {
+            for z in 0..LAKE_WIDTH {
+                for y in 0..LAKE_HEIGHT {
+                    if !in_lake(x, y, z) {
+                        continue;
+                    }
+                    let block = if y >= LAKE_HEIGHT / 2 {
+                        BlockId::air()
+                    } else {
+                        self.liquid
+                    };
+                    region.set_block_at(origin_x + x, origin_y + y, origin_z + z, block);
+                }
+            }
+        }
+
+        if self.is_lava() {
+            // Lava is enclosed in stone.
+            for ((x, y, z), shape_y) in border {
+                let solid = region.block_at(x, y, z).map_or(false, BlockId::is_solid);
+                if solid && shape_y < LAKE_HEIGHT / 2 {
+                    region.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        } else {
+            let surface = LAKE_HEIGHT / 2 - 1;
+            for x in 0..LAKE_WIDTH {
+                for z in 0..LAKE_WIDTH {
+                    let (world_x, world_y, world_z) =
+                        (origin_x + x, origin_y + surface, origin_z + z);
+                    let frozen = region
+                        .biome_at(world_x, world_y, world_z)
+                        .map_or(false, |biome| biome.temperature() < FREEZING_TEMPERATURE);
+                    if in_lake(x, surface, z) && frozen {
+                        region.set_block_at(world_x, world_y, world_z, BlockId::ice());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn shape_index(x: i32, y: i32, z: i32) -> usize {
+    ((x * LAKE_WIDTH + z) * LAKE_HEIGHT + y) as usize
+}
+
+/// Computes the blocks of the lake's box
+/// which are part of the lake.
+fn lake_shape(rng: &mut impl Rng) -> Vec<bool> {
+    let mut shape = vec![false; (LAKE_WIDTH * LAKE_WIDTH * LAKE_HEIGHT) as usize];
+    let (width, height) = (LAKE_WIDTH as f64, LAKE_HEIGHT as f64);
+
+    for _ in 0..rng.gen_range(4, 8) {
+        let size_x = rng.gen::<f64>() * 6.0 + 3.0;
+        let size_y = rng.gen::<f64>() * 4.0 + 2.0;
+        let size_z = rng.gen::<f64>() * 6.0 + 3.0;
+        let center_x = rng.gen::<f64>() * (width - size_x - 2.0) + 1.0 + size_x / 2.0;
+        let center_y = rng.gen::<f64>() * (height - size_y - 4.0) + 2.0 + size_y / 2.0;
+        let center_z = rng.gen::<f64>() * (width - size_z - 2.0) + 1.0 + size_z / 2.0;
+
+        // Keep a one block margin, so that the lake is enclosed.
+        for x in 1..LAKE_WIDTH - 1 {
+            for z in 1..LAKE_WIDTH - 1 {
+                for y in 1..LAKE_HEIGHT - 1 {
+                    let dx = (x as f64 - center_x) / (size_x / 2.0);
+                    let dy = (y as f64 - center_y) / (size_y / 2.0);
+                    let dz = (z as f64 - center_z) / (size_z / 2.0);
+                    if dx * dx + dy * dy + dz * dz < 1.0 {
+                        shape[shape_index(x, y, z)] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    shape
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Chunk, ChunkPosition};
+
+    /// Creates a region of chunks filled with stone up to `y = 40`.
+    fn stone_region(center: ChunkPosition) -> DecorationRegion {
+        let mut region = DecorationRegion::new(center);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let mut chunk = Chunk::new(ChunkPosition::new(center.x + dx, center.z + dz));
+                for x in 0..16 {
+                    for z in 0..16 {
+                        for y in 0..=40 {
+                            chunk.set_block_at(x, y, z, BlockId::stone());
+                        }
+                    }
+                }
+                region.insert(chunk);
+            }
+        }
+        region
+    }
+
+    fn count(region: &DecorationRegion, block: BlockId) -> usize {
+        let mut count = 0;
+        for x in -16..32 {
+            for z in -16..32 {
+                for y in 0..256 {
+                    if region.block_at(x, y, z) == Some(block) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn water_lakes_are_placed_in_the_ground() {
+        let decorator = LakeDecorator::water();
+        let lakes = (0..32)
+            .filter(|&i| {
+                let mut region = stone_region(ChunkPosition::new(i, 0));
+                decorator.decorate(&mut region, 5);
+                let water = count(&region, BlockId::water());
+                if water > 0 {
+                    // Water stays below the surface, where it cannot flow away.
+                    for x in -16..32 {
+                        for z in -16..32 {
+                            assert_ne!(region.block_at(x, 41, z), Some(BlockId::water()));
+                        }
+                    }
+                }
+                water > 0
+            })
+            .count();
+        assert!(lakes > 0 && lakes < 32, "{} lakes", lakes);
+    }
+}
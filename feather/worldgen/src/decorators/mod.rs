@@ -0,0 +1,195 @@
+//! Decorators for world generation, such as ores and trees.
+//!
+//! Unlike finishers, decorators run once all neighbours
+//! of a chunk have been generated, so the features they
+//! place may span chunk borders.
+
+mod clumped;
+mod lakes;
+mod ores;
+mod trees;
+
+use base::anvil::block_entity::{BlockEntityBase, BlockEntityData, BlockEntityKind};
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+
+pub use clumped::ClumpedFoliageDecorator;
+pub use lakes::LakeDecorator;
+pub use ores::{default_ores, OreConfig, OreDecorator};
+pub use trees::{TreeDecorator, TreeKind};
+
+/// The 3x3 grid of chunks around the chunk being decorated.
+///
+/// Coordinates are relative to the origin of the center
+/// chunk, so `x` and `z` range from -16 to 31. Neighbours
+/// which are not part of the region (because they have already
+/// been fully generated) read as `None`, and writes to them
+/// are dropped.
+pub struct DecorationRegion {
+    center: ChunkPosition,
+    chunks: [Option<Chunk>; 3 * 3],
+}
+
+impl DecorationRegion {
+    /// Creates an empty region centered on the given chunk.
+    pub fn new(center: ChunkPosition) -> Self {
+        Self {
+            center,
+            chunks: Default::default(),
+        }
+    }
+
+    /// Adds a chunk to this region.
+    ///
+    /// # Panics
+    /// Panics if the chunk is not within one
+    /// chunk of the center chunk.
+    pub fn insert(&mut self, chunk: Chunk) {
+        let dx = chunk.position().x - self.center.x;
+        let dz = chunk.position().z - self.center.z;
+        assert!(
+            (-1..=1).contains(&dx) && (-1..=1).contains(&dz),
+            "chunk {:?} is not adjacent to {:?}",
+            chunk.position(),
+            self.center
+        );
+        self.chunks[((dx + 1) + (dz + 1) * 3) as usize] = Some(chunk);
+    }
+
+    /// Gets the position of the chunk being decorated.
+    pub fn center_position(&self) -> ChunkPosition {
+        self.center
+    }
+
+    /// Gets the world coordinates of the block at
+    /// the origin of the region's coordinates.
+    pub fn origin(&self) -> (i32, i32) {
+        (self.center.x * 16, self.center.z * 16)
+    }
+
+    /// Gets the chunk being decorated, if it has been inserted.
+    pub fn center(&self) -> Option<&Chunk> {
+        self.chunks[4].as_ref()
+    }
+
+    /// Returns the chunks in this region.
+    pub fn into_chunks(self) -> impl Iterator<Item = Chunk> {
+        Vec::from(self.chunks).into_iter().flatten()
+    }
+
+    /// Gets the block at the given coordinates.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockId> {
+        let (index, x, y, z) = Self::index(x, y, z)?;
+        self.chunks[index].as_ref()?.block_at(x, y, z)
+    }
+
+    /// Sets the block at the given coordinates.
+    ///
+    /// Returns `false` if the coordinates are outside
+    /// this region or their chunk is missing.
+    pub fn set_block_at(&mut self, x: i32, y: i32, z: i32, block: BlockId) -> bool {
+        match Self::index(x, y, z) {
+            Some((index, x, y, z)) => self.chunks[index]
+                .as_mut()
+                .and_then(|chunk| chunk.set_block_at(x, y, z, block))
+                .is_some(),
+            None => false,
+        }
+    }
+
+    /// Sets the block entity of the block at the given
+    /// coordinates, replacing any block entity already there.
+    ///
+    /// Returns `false` if the coordinates are outside
+    /// this region or their chunk is missing.
+    pub fn set_block_entity_at(&mut self, x: i32, y: i32, z: i32, kind: BlockEntityKind) -> bool {
+        let (origin_x, origin_z) = self.origin();
+        let chunk = match Self::index(x, y, z) {
+            Some((index, ..)) => match &mut self.chunks[index] {
+                Some(chunk) => chunk,
+                None => return false,
+            },
+            None => return false,
+        };
+
+        let base = BlockEntityBase {
+            x: origin_x + x,
+            y,
+            z: origin_z + z,
+        };
+        let block_entities = chunk.block_entities_mut();
+        block_entities.retain(|entity| {
+            (entity.base.x, entity.base.y, entity.base.z) != (base.x, base.y, base.z)
+        });
+        block_entities.push(BlockEntityData { base, kind });
+        true
+    }
+
+    /// Gets the biome at the given coordinates.
+    pub fn biome_at(&self, x: i32, y: i32, z: i32) -> Option<Biome> {
+        let (index, x, y, z) = Self::index(x, y, z)?;
+        Some(self.chunks[index].as_ref()?.biomes().get_at_block(x, y, z))
+    }
+
+    /// Gets the height of the highest non-air block
+    /// in the given column.
+    pub fn top_block_at(&self, x: i32, z: i32) -> Option<i32> {
+        (0..256)
+            .rev()
+            .find(|&y| matches!(self.block_at(x, y, z), Some(block) if !block.is_air()))
+    }
+
+    /// Returns a tuple of (chunk_index, local_x, local_y, local_z).
+    fn index(x: i32, y: i32, z: i32) -> Option<(usize, usize, usize, usize)> {
+        if !(-16..32).contains(&x) || !(-16..32).contains(&z) || !(0..256).contains(&y) {
+            return None;
+        }
+        let chunk_x = (x >> 4) + 1;
+        let chunk_z = (z >> 4) + 1;
+        Some((
+            (chunk_x + chunk_z * 3) as usize,
+            (x & 15) as usize,
+            y as usize,
+            (z & 15) as usize,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_writes_into_neighbours() {
+        let center = ChunkPosition::new(3, -2);
+        let mut region = DecorationRegion::new(center);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                // Leave out the chunk to the east.
+                if (dx, dz) != (1, 0) {
+                    region.insert(Chunk::new(ChunkPosition::new(center.x + dx, center.z + dz)));
+                }
+            }
+        }
+
+        assert!(region.set_block_at(-1, 10, -16, BlockId::stone()));
+        assert!(region.set_block_at(5, 10, 20, BlockId::stone()));
+        assert!(!region.set_block_at(16, 10, 0, BlockId::stone()));
+        assert!(!region.set_block_at(32, 10, 0, BlockId::stone()));
+        assert_eq!(region.block_at(16, 10, 0), None);
+        assert_eq!(region.top_block_at(5, 20), Some(10));
+        assert_eq!(region.top_block_at(0, 0), None);
+
+        let chunks: Vec<Chunk> = region.into_chunks().collect();
+        assert_eq!(chunks.len(), 8);
+        let north_west = chunks
+            .iter()
+            .find(|chunk| chunk.position() == ChunkPosition::new(2, -3))
+            .unwrap();
+        assert_eq!(north_west.block_at(15, 10, 0), Some(BlockId::stone()));
+        let south = chunks
+            .iter()
+            .find(|chunk| chunk.position() == ChunkPosition::new(3, -1))
+            .unwrap();
+        assert_eq!(south.block_at(5, 10, 4), Some(BlockId::stone()));
+    }
+}
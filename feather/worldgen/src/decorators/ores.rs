@@ -0,0 +1,358 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{DecorationRegion, Decorator};
+use base::{Biome, BlockId, BlockKind};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::f32::consts::PI;
+use std::ops::Range;
+
+/// An ore (or other underground block, like
+/// gravel) placed in veins by the [`OreDecorator`].
+///
+/// Ores can be deserialized from tables like
+/// `{ block = "minecraft:coal_ore", veins_per_chunk = 20, vein_size = 17,
+/// min_height = 0, max_height = 128, biomes = ["plains"] }`,
+/// where `max_height` is exclusive and `biomes` is optional.
+/// Configured veins are limited to [`MAX_VEIN_SIZE`] blocks
+/// and [`MAX_VEINS_PER_CHUNK`] per chunk.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "OreEntry")]
+pub struct OreConfig {
+    /// The block to place.
+    pub block: BlockId,
+    /// The number of veins per chunk.
+    pub veins_per_chunk: u32,
+    /// The approximate number of blocks in a vein.
+    pub vein_size: u32,
+    /// The heights at which veins are centered.
+    pub heights: Range<i32>,
+    /// The biomes in which veins are placed,
+    /// or `None` to place them in all biomes.
+    pub biomes: Option<Vec<Biome>>,
+}
+
+impl OreConfig {
+    pub fn new(block: BlockId, veins_per_chunk: u32, vein_size: u32, heights: Range<i32>) -> Self {
+        Self {
+            block,
+            veins_per_chunk,
+            vein_size,
+            heights,
+            biomes: None,
+        }
+    }
+
+    /// Only places veins in the given biomes.
+    pub fn in_biomes(mut self, biomes: impl IntoIterator<Item = Biome>) -> Self {
+        self.biomes = Some(biomes.into_iter().collect());
+        self
+    }
+
+    fn allowed_in(&self, biome: Biome) -> bool {
+        match &self.biomes {
+            Some(biomes) => biomes.contains(&biome),
+            None => true,
+        }
+    }
+}
+
+/// Largest `vein_size` accepted from configuration.
+pub const MAX_VEIN_SIZE: u32 = 64;
+/// Largest `veins_per_chunk` accepted from configuration.
+pub const MAX_VEINS_PER_CHUNK: u32 = 256;
+
+/// The serialized form of an [`OreConfig`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OreEntry {
+    block: String,
+    veins_per_chunk: u32,
+    vein_size: u32,
+    min_height: i32,
+    max_height: i32,
+    #[serde(default)]
+    biomes: Option<Vec<String>>,
+}
+
+impl TryFrom<OreEntry> for OreConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: OreEntry) -> anyhow::Result<Self> {
+        let block = BlockId::from_identifier(&entry.block)
+            .ok_or_else(|| anyhow::anyhow!("unknown ore block {:?}", entry.block))?;
+        if entry.vein_size > MAX_VEIN_SIZE {
+            anyhow::bail!(
+                "vein_size of {:?} must be at most {}",
+                entry.block,
+                MAX_VEIN_SIZE
+            );
+        }
+        if entry.veins_per_chunk > MAX_VEINS_PER_CHUNK {
+            anyhow::bail!(
+                "veins_per_chunk of {:?} must be at most {}",
+                entry.block,
+                MAX_VEINS_PER_CHUNK
+            );
+        }
+        if entry.min_height >= entry.max_height {
+            anyhow::bail!(
+                "min_height of {:?} must be less than max_height",
+                entry.block
+            );
+        }
+        let biomes = match entry.biomes {
+            Some(names) => Some(
+                names
+                    .iter()
+                    .map(|name| {
+                        Biome::from_name(name.trim_start_matches("minecraft:"))
+                            .ok_or_else(|| anyhow::anyhow!("unknown biome {:?}", name))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            block,
+            veins_per_chunk: entry.veins_per_chunk,
+            vein_size: entry.vein_size,
+            heights: entry.min_height..entry.max_height,
+            biomes,
+        })
+    }
+}
+
+/// Returns the ores placed in vanilla overworld chunks.
+pub fn default_ores() -> Vec<OreConfig> {
+    let mountains = [
+        Biome::Mountains,
+        Biome::WoodedMountains,
+        Biome::GravellyMountains,
+        Biome::ModifiedGravellyMountains,
+        Biome::MountainEdge,
+    ];
+    let badlands = [
+        Biome::Badlands,
+        Biome::ErodedBadlands,
+        Biome::BadlandsPlateau,
+        Biome::WoodedBadlandsPlateau,
+        Biome::ModifiedBadlandsPlateau,
+        Biome::ModifiedWoodedBadlandsPlateau,
+    ];
+
+    vec![
+        OreConfig::new(BlockId::dirt(), 10, 33, 0..256),
+        OreConfig::new(BlockId::gravel(), 8, 33, 0..256),
+        OreConfig::new(BlockId::granite(), 10, 33, 0..80),
+        OreConfig::new(BlockId::diorite(), 10, 33, 0..80),
+        OreConfig::new(BlockId::andesite(), 10, 33, 0..80),
+        OreConfig::new(BlockId::coal_ore(), 20, 17, 0..128),
+        OreConfig::new(BlockId::iron_ore(), 20, 9, 0..64),
+        OreConfig::new(BlockId::gold_ore(), 2, 9, 0..32),
+        OreConfig::new(BlockId::gold_ore(), 20, 9, 32..80).in_biomes(badlands.iter().copied()),
+        OreConfig::new(BlockId::redstone_ore(), 8, 8, 0..16),
+        OreConfig::new(BlockId::diamond_ore(), 1, 8, 0..16),
+        OreConfig::new(BlockId::lapis_ore(), 1, 7, 0..32),
+        OreConfig::new(BlockId::emerald_ore(), 6, 1, 4..32).in_biomes(mountains.iter().copied()),
+    ]
+}
+
+/// Places veins of ores in the stone of a chunk.
+/// Veins near the chunk border extend into the neighbouring chunks.
+pub struct OreDecorator {
+    ores: Vec<OreConfig>,
+}
+
+impl OreDecorator {
+    /// Creates an ore decorator placing the given ores, in order.
+    pub fn new(ores: impl IntoIterator<Item = OreConfig>) -> Self {
+        Self {
+            ores: ores.into_iter().collect(),
+        }
+    }
+
+    /// Gets the ores placed by this decorator.
+    pub fn ores(&self) -> &[OreConfig] {
+        &self.ores
+    }
+}
+
+impl Default for OreDecorator {
+    fn default() -> Self {
+        Self::new(default_ores())
+    }
+}
+
+impl Decorator for OreDecorator {
+    fn decorate(&self, region: &mut DecorationRegion, seed: u64) {
+        let mut rng =
+            XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, region.center_position()));
+
+        let biome = match region.biome_at(8, 0, 8) {
+            Some(biome) => biome,
+            None => return,
+        };
+
+        for ore in &self.ores {
+            if !ore.allowed_in(biome) || ore.heights.start >= ore.heights.end {
+                continue;
+            }
+
+            for _ in 0..ore.veins_per_chunk {
+                let x = rng.gen_range(0, 16);
+                let y = rng.gen_range(ore.heights.start, ore.heights.end);
+                let z = rng.gen_range(0, 16);
+                place_vein(region, ore, x, y, z, &mut rng);
+            }
+        }
+    }
+}
+
+/// Places a vein along a line through the given
+/// position, replacing stone blocks only.
+fn place_vein(
+    region: &mut DecorationRegion,
+    ore: &OreConfig,
+    x: i32,
+    y: i32,
+    z: i32,
+    rng: &mut impl Rng,
+) {
+    if ore.vein_size <= 1 {
+        replace_stone(region, x, y, z, ore.block);
+        return;
+    }
+
+    let size = ore.vein_size as f32;
+    let (sin, cos) = (rng.gen::<f32>() * PI).sin_cos();
+    let spread = size / 8.0;
+    let (x, y, z) = (x as f32 + 0.5, y as f32, z as f32 + 0.5);
+    let start = (
+        x + sin * spread,
+        y + rng.gen_range(-2, 1) as f32,
+        z + cos * spread,
+    );
+    let end = (
+        x - sin * spread,
+        y + rng.gen_range(-2, 1) as f32,
+        z - cos * spread,
+    );
+
+    for i in 0..ore.vein_size {
+        let t = i as f32 / size;
+        let center_x = start.0 + (end.0 - start.0) * t;
+        let center_y = start.1 + (end.1 - start.1) * t;
+        let center_z = start.2 + (end.2 - start.2) * t;
+        // Veins are thickest in the middle.
+        let radius = ((t * PI).sin() + 1.0) * rng.gen::<f32>() * size / 32.0 + 0.5;
+
+        let blocks =
+            |center: f32| (center - radius).floor() as i32..=(center + radius).floor() as i32;
+        for block_x in blocks(center_x) {
+            let dx = (block_x as f32 + 0.5 - center_x) / radius;
+            for block_y in blocks(center_y) {
+                let dy = (block_y as f32 + 0.5 - center_y) / radius;
+                for block_z in blocks(center_z) {
+                    let dz = (block_z as f32 + 0.5 - center_z) / radius;
+                    if dx * dx + dy * dy + dz * dz < 1.0 {
+                        replace_stone(region, block_x, block_y, block_z, ore.block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn replace_stone(region: &mut DecorationRegion, x: i32, y: i32, z: i32, block: BlockId) {
+    let is_stone = matches!(
+        region.block_at(x, y, z).map(BlockId::kind),
+        Some(BlockKind::Stone | BlockKind::Granite | BlockKind::Diorite | BlockKind::Andesite)
+    );
+    if is_stone {
+        region.set_block_at(x, y, z, block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Chunk, ChunkPosition};
+
+    fn stone_region(biome: Biome) -> DecorationRegion {
+        let center = ChunkPosition::new(0, 0);
+        let mut region = DecorationRegion::new(center);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let pos = ChunkPosition::new(center.x + dx, center.z + dz);
+                let mut chunk = Chunk::new_with_default_biome(pos, biome);
+                for x in 0..16 {
+                    for z in 0..16 {
+                        for y in 0..64 {
+                            chunk.set_block_at(x, y, z, BlockId::stone());
+                        }
+                    }
+                }
+                region.insert(chunk);
+            }
+        }
+        region
+    }
+
+    fn count(region: &DecorationRegion, block: BlockId, heights: Range<i32>) -> usize {
+        let mut count = 0;
+        for x in -16..32 {
+            for z in -16..32 {
+                for y in heights.clone() {
+                    if region.block_at(x, y, z) == Some(block) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn ores_replace_stone() {
+        let mut region = stone_region(Biome::Plains);
+        let decorator = OreDecorator::new(vec![OreConfig::new(BlockId::coal_ore(), 20, 17, 0..32)]);
+        decorator.decorate(&mut region, 10);
+
+        assert!(count(&region, BlockId::coal_ore(), 0..40) > 0);
+        // Veins stay near their heights, leaving the stone above them.
+        assert!(count(&region, BlockId::stone(), 40..64) > 0);
+        assert_eq!(count(&region, BlockId::coal_ore(), 40..64), 0);
+    }
+
+    #[test]
+    fn ore_entries_are_bounded() {
+        let entry = |veins_per_chunk, vein_size| OreEntry {
+            block: "minecraft:coal_ore".to_owned(),
+            veins_per_chunk,
+            vein_size,
+            min_height: 0,
+            max_height: 128,
+            biomes: None,
+        };
+        assert!(OreConfig::try_from(entry(MAX_VEINS_PER_CHUNK, MAX_VEIN_SIZE)).is_ok());
+        assert!(OreConfig::try_from(entry(20, MAX_VEIN_SIZE + 1)).is_err());
+        assert!(OreConfig::try_from(entry(MAX_VEINS_PER_CHUNK + 1, 17)).is_err());
+    }
+
+    #[test]
+    fn ores_follow_biome_rules() {
+        let decorator =
+            OreDecorator::new(vec![OreConfig::new(BlockId::emerald_ore(), 20, 1, 4..32)
+                .in_biomes(vec![Biome::Mountains])]);
+
+        let mut plains = stone_region(Biome::Plains);
+        decorator.decorate(&mut plains, 10);
+        assert_eq!(count(&plains, BlockId::emerald_ore(), 0..64), 0);
+
+        let mut mountains = stone_region(Biome::Mountains);
+        decorator.decorate(&mut mountains, 10);
+        assert!(count(&mountains, BlockId::emerald_ore(), 4..32) > 0);
+    }
+}
@@ -0,0 +1,425 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{DecorationRegion, Decorator};
+use base::{Biome, BlockId, SimplifiedBlockKind};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// The kinds of trees placed by the [`TreeDecorator`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TreeKind {
+    Oak,
+    Birch,
+    Spruce,
+    Jungle,
+    Acacia,
+}
+
+impl TreeKind {
+    fn log(self) -> BlockId {
+        match self {
+            TreeKind::Oak => BlockId::oak_log(),
+            TreeKind::Birch => BlockId::birch_log(),
+            TreeKind::Spruce => BlockId::spruce_log(),
+            TreeKind::Jungle => BlockId::jungle_log(),
+            TreeKind::Acacia => BlockId::acacia_log(),
+        }
+    }
+
+    fn leaves(self) -> BlockId {
+        match self {
+            TreeKind::Oak => BlockId::oak_leaves(),
+            TreeKind::Birch => BlockId::birch_leaves(),
+            TreeKind::Spruce => BlockId::spruce_leaves(),
+            TreeKind::Jungle => BlockId::jungle_leaves(),
+            TreeKind::Acacia => BlockId::acacia_leaves(),
+        }
+    }
+}
+
+/// Places trees depending on the biome.
+#[derive(Default)]
+pub struct TreeDecorator;
+
+impl Decorator for TreeDecorator {
+    fn decorate(&self, region: &mut DecorationRegion, seed: u64) {
+        let mut rng =
+            XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, region.center_position()));
+
+        // Like vanilla, the biome in the middle of
+        // the chunk determines the number of trees.
+        let biome = match region.biome_at(8, 0, 8) {
+            Some(biome) => biome,
+            None => return,
+        };
+        let density = biome_tree_density(biome);
+        let mut count = density as u32;
+        if rng.gen::<f32>() < density.fract() {
+            count += 1;
+        }
+
+        for _ in 0..count {
+            let x = rng.gen_range(0, 16);
+            let z = rng.gen_range(0, 16);
+            let kind = match region
+                .biome_at(x, 0, z)
+                .and_then(|biome| biome_tree(biome, &mut rng))
+            {
+                Some(kind) => kind,
+                None => continue,
+            };
+            place_tree(region, kind, x, z, &mut rng);
+        }
+    }
+}
+
+/// Returns the average number of trees per chunk in the given biome.
+fn biome_tree_density(biome: Biome) -> f32 {
+    match biome {
+        Biome::Forest
+        | Biome::WoodedHills
+        | Biome::FlowerForest
+        | Biome::BirchForest
+        | Biome::BirchForestHills
+        | Biome::TallBirchForest
+        | Biome::TallBirchHills
+        | Biome::DarkForest
+        | Biome::DarkForestHills
+        | Biome::Taiga
+        | Biome::TaigaHills
+        | Biome::TaigaMountains
+        | Biome::SnowyTaiga
+        | Biome::SnowyTaigaHills
+        | Biome::SnowyTaigaMountains
+        | Biome::GiantTreeTaiga
+        | Biome::GiantTreeTaigaHills
+        | Biome::GiantSpruceTaiga
+        | Biome::GiantSpruceTaigaHills => 10.0,
+        Biome::Jungle | Biome::JungleHills | Biome::ModifiedJungle | Biome::BambooJungle => 20.0,
+        Biome::JungleEdge | Biome::ModifiedJungleEdge => 2.0,
+        Biome::WoodedMountains => 3.0,
+        Biome::Swamp | Biome::SwampHills => 2.0,
+        Biome::Savanna
+        | Biome::SavannaPlateau
+        | Biome::ShatteredSavanna
+        | Biome::ShatteredSavannaPlateau => 1.5,
+        Biome::Plains
+        | Biome::SunflowerPlains
+        | Biome::Mountains
+        | Biome::GravellyMountains
+        | Biome::ModifiedGravellyMountains
+        | Biome::SnowyTundra
+        | Biome::SnowyMountains => 0.1,
+        _ => 0.0,
+    }
+}
+
+/// Picks the kind of tree to place in the given biome.
+fn biome_tree(biome: Biome, rng: &mut impl Rng) -> Option<TreeKind> {
+    let roll = rng.gen_range(0, 10);
+    Some(match biome {
+        Biome::BirchForest
+        | Biome::BirchForestHills
+        | Biome::TallBirchForest
+        | Biome::TallBirchHills => TreeKind::Birch,
+        Biome::Forest | Biome::WoodedHills | Biome::FlowerForest => {
+            if roll < 2 {
+                TreeKind::Birch
+            } else {
+                TreeKind::Oak
+            }
+        }
+        Biome::Taiga
+        | Biome::TaigaHills
+        | Biome::TaigaMountains
+        | Biome::SnowyTaiga
+        | Biome::SnowyTaigaHills
+        | Biome::SnowyTaigaMountains
+        | Biome::GiantTreeTaiga
+        | Biome::GiantTreeTaigaHills
+        | Biome::GiantSpruceTaiga
+        | Biome::GiantSpruceTaigaHills
+        | Biome::SnowyTundra
+        | Biome::SnowyMountains => TreeKind::Spruce,
+        Biome::Jungle
+        | Biome::JungleHills
+        | Biome::ModifiedJungle
+        | Biome::BambooJungle
+        | Biome::JungleEdge
+        | Biome::ModifiedJungleEdge => {
+            if roll < 3 {
+                TreeKind::Oak
+            } else {
+                TreeKind::Jungle
+            }
+        }
+        Biome::Savanna
+        | Biome::SavannaPlateau
+        | Biome::ShatteredSavanna
+        | Biome::ShatteredSavannaPlateau => {
+            if roll < 2 {
+                TreeKind::Oak
+            } else {
+                TreeKind::Acacia
+            }
+        }
+        Biome::WoodedMountains
+        | Biome::Mountains
+        | Biome::GravellyMountains
+        | Biome::ModifiedGravellyMountains => {
+            if roll < 3 {
+                TreeKind::Oak
+            } else {
+                TreeKind::Spruce
+            }
+        }
+        Biome::Plains | Biome::SunflowerPlains | Biome::Swamp | Biome::SwampHills => TreeKind::Oak,
+        Biome::DarkForest | Biome::DarkForestHills => TreeKind::Oak,
+        _ => return None,
+    })
+}
+
+/// Places a tree whose trunk starts above the
+/// highest block of the column at `x`, `z`.
+///
+/// Returns whether a tree was placed.
+pub(crate) fn place_tree(
+    region: &mut DecorationRegion,
+    kind: TreeKind,
+    x: i32,
+    z: i32,
+    rng: &mut impl Rng,
+) -> bool {
+    let mut ground = match region.top_block_at(x, z) {
+        Some(top) => top,
+        None => return false,
+    };
+    // Grow through snow layers.
+    if region.block_at(x, ground, z).map(BlockId::simplified_kind)
+        == Some(SimplifiedBlockKind::Snow)
+    {
+        ground -= 1;
+    }
+    let soil = match region.block_at(x, ground, z) {
+        Some(block) => block.simplified_kind(),
+        None => return false,
+    };
+    if !matches!(
+        soil,
+        SimplifiedBlockKind::GrassBlock | SimplifiedBlockKind::Dirt | SimplifiedBlockKind::Podzol
+    ) {
+        return false;
+    }
+
+    let shape = TreeShape::new(kind, x, ground + 1, z, rng);
+    if shape.top() > 255
+        || !shape
+            .logs
+            .iter()
+            .all(|&(x, y, z)| can_grow_into(region, x, y, z))
+    {
+        return false;
+    }
+
+    region.set_block_at(x, ground, z, BlockId::dirt());
+    for &(x, y, z) in &shape.logs {
+        region.set_block_at(x, y, z, kind.log());
+    }
+    for &(x, y, z) in &shape.leaves {
+        if !can_grow_into(region, x, y, z) {
+            continue;
+        }
+        let distance = shape
+            .logs
+            .iter()
+            .map(|&(log_x, log_y, log_z)| (x - log_x).abs() + (y - log_y).abs() + (z - log_z).abs())
+            .min()
+            .unwrap_or(7)
+            .clamp(1, 7);
+        region.set_block_at(x, y, z, kind.leaves().with_distance_1_7(distance));
+    }
+    true
+}
+
+/// Determines whether a tree may replace the block at the given position.
+fn can_grow_into(region: &DecorationRegion, x: i32, y: i32, z: i32) -> bool {
+    matches!(
+        region.block_at(x, y, z).map(BlockId::simplified_kind),
+        Some(SimplifiedBlockKind::Air)
+            | Some(SimplifiedBlockKind::Grass)
+            | Some(SimplifiedBlockKind::TallGrass)
+            | Some(SimplifiedBlockKind::Snow)
+            | Some(SimplifiedBlockKind::Leaves)
+    )
+}
+
+/// The blocks making up a tree.
+struct TreeShape {
+    logs: Vec<(i32, i32, i32)>,
+    leaves: Vec<(i32, i32, i32)>,
+}
+
+impl TreeShape {
+    fn new(kind: TreeKind, x: i32, y: i32, z: i32, rng: &mut impl Rng) -> Self {
+        let mut shape = Self {
+            logs: Vec::new(),
+            leaves: Vec::new(),
+        };
+        match kind {
+            TreeKind::Oak => shape.blob_tree(x, y, z, rng.gen_range(4, 7), rng),
+            TreeKind::Birch => shape.blob_tree(x, y, z, rng.gen_range(5, 8), rng),
+            TreeKind::Jungle => shape.blob_tree(x, y, z, rng.gen_range(4, 11), rng),
+            TreeKind::Spruce => shape.spruce(x, y, z, rng),
+            TreeKind::Acacia => shape.acacia(x, y, z, rng),
+        }
+        shape
+    }
+
+    fn top(&self) -> i32 {
+        self.logs
+            .iter()
+            .chain(&self.leaves)
+            .map(|&(_, y, _)| y)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn trunk(&mut self, x: i32, y: i32, z: i32, height: i32) {
+        self.logs.extend((y..y + height).map(|y| (x, y, z)));
+    }
+
+    /// Adds a square layer of leaves, leaving out the
+    /// corners if `corners` is `false`.
+    fn leaf_layer(&mut self, x: i32, y: i32, z: i32, radius: i32, corners: impl Fn() -> bool) {
+        for dx in -radius..=radius {
+            for dz in -radius..=radius {
+                let is_corner = radius > 0 && dx.abs() == radius && dz.abs() == radius;
+                if !is_corner || corners() {
+                    self.leaves.push((x + dx, y, z + dz));
+                }
+            }
+        }
+    }
+
+    /// The shape of oak, birch and small jungle trees.
+    fn blob_tree(&mut self, x: i32, y: i32, z: i32, height: i32, rng: &mut impl Rng) {
+        self.trunk(x, y, z, height);
+        let top = y + height - 1;
+        for leaf_y in top - 2..=top + 1 {
+            let radius = if leaf_y >= top { 1 } else { 2 };
+            let keep_corners = leaf_y < top && rng.gen_range(0, 2) == 0;
+            self.leaf_layer(x, leaf_y, z, radius, || keep_corners);
+        }
+    }
+
+    fn spruce(&mut self, x: i32, y: i32, z: i32, rng: &mut impl Rng) {
+        let height = rng.gen_range(6, 10);
+        self.trunk(x, y, z, height);
+        let top = y + height - 1;
+        let bare_trunk = rng.gen_range(1, 3);
+        for leaf_y in (y + bare_trunk..=top + 1).rev() {
+            let depth = top + 1 - leaf_y;
+            let radius = match depth {
+                0 => 0,
+                1 | 2 => 1,
+                _ if depth % 2 == 1 => 1,
+                _ => 2,
+            };
+            self.leaf_layer(x, leaf_y, z, radius, || false);
+        }
+    }
+
+    fn acacia(&mut self, x: i32, y: i32, z: i32, rng: &mut impl Rng) {
+        let height = rng.gen_range(5, 9);
+        let bend_start = height - rng.gen_range(1, 4);
+        let (step_x, step_z) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0, 4)];
+
+        let (mut trunk_x, mut trunk_z) = (x, z);
+        for dy in 0..height {
+            if dy >= bend_start {
+                trunk_x += step_x;
+                trunk_z += step_z;
+            }
+            self.logs.push((trunk_x, y + dy, trunk_z));
+        }
+
+        let top = y + height - 1;
+        self.leaf_layer(trunk_x, top, trunk_z, 2, || false);
+        self.leaf_layer(trunk_x, top + 1, trunk_z, 1, || false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Chunk, ChunkPosition};
+
+    use super::*;
+
+    fn grass_region(biome: Biome) -> DecorationRegion {
+        let center = ChunkPosition::new(0, 0);
+        let mut region = DecorationRegion::new(center);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let mut chunk = Chunk::new_with_default_biome(ChunkPosition::new(dx, dz), biome);
+                for x in 0..16 {
+                    for z in 0..16 {
+                        chunk.set_block_at(x, 63, z, BlockId::dirt());
+                        chunk.set_block_at(x, 64, z, BlockId::grass_block());
+                    }
+                }
+                region.insert(chunk);
+            }
+        }
+        region
+    }
+
+    #[test]
+    fn trees_span_chunk_borders() {
+        for &kind in &[
+            TreeKind::Oak,
+            TreeKind::Birch,
+            TreeKind::Spruce,
+            TreeKind::Jungle,
+            TreeKind::Acacia,
+        ] {
+            let mut region = grass_region(Biome::Plains);
+            let mut rng = XorShiftRng::seed_from_u64(5);
+            assert!(place_tree(&mut region, kind, 0, 0, &mut rng));
+
+            assert_eq!(region.block_at(0, 64, 0), Some(BlockId::dirt()));
+            assert_eq!(region.block_at(0, 65, 0), Some(kind.log()));
+            let leaves_in_neighbour = (-4..4).any(|x| {
+                (-4..4).any(|z| {
+                    (x < 0 || z < 0)
+                        && (60..80).any(|y| {
+                            region.block_at(x, y, z).map(BlockId::kind)
+                                == Some(kind.leaves().kind())
+                        })
+                })
+            });
+            assert!(leaves_in_neighbour, "{:?} tree was cut off", kind);
+        }
+    }
+
+    #[test]
+    fn trees_need_soil() {
+        let mut region = grass_region(Biome::Forest);
+        region.set_block_at(4, 64, 4, BlockId::stone());
+        let mut rng = XorShiftRng::seed_from_u64(5);
+        assert!(!place_tree(&mut region, TreeKind::Oak, 4, 4, &mut rng));
+        assert_eq!(region.block_at(4, 65, 4), Some(BlockId::air()));
+    }
+
+    #[test]
+    fn forests_have_trees() {
+        let mut region = grass_region(Biome::Forest);
+        TreeDecorator.decorate(&mut region, 10);
+        let logs = (0..16)
+            .flat_map(|x| (0..16).map(move |z| (x, z)))
+            .filter(|&(x, z)| {
+                region.block_at(x, 65, z).map(BlockId::simplified_kind)
+                    == Some(SimplifiedBlockKind::Log)
+            })
+            .count();
+        assert!(logs > 0);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::WorldGenerator;
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Radius of the main end island, in blocks.
+pub const MAIN_ISLAND_RADIUS: f32 = 96.0;
+/// Distance from the origin beyond which the outer islands generate.
+pub const OUTER_ISLANDS_DISTANCE: f32 = 1024.0;
+
+/// Height of the top surface of the main island.
+const ISLAND_SURFACE: f32 = 60.0;
+
+/// Generates the end: a main island of end stone around
+/// the origin, surrounded by void and, further out, by
+/// smaller islands.
+pub struct EndGenerator {
+    seed: u64,
+}
+
+impl EndGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl WorldGenerator for EndGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let (origin_x, origin_z) = (position.x * 16, position.z * 16);
+
+        let chunk_distance = distance_to_origin(origin_x + 8, origin_z + 8);
+        let biome = if chunk_distance < MAIN_ISLAND_RADIUS * 2.0 {
+            Biome::TheEnd
+        } else if chunk_distance < OUTER_ISLANDS_DISTANCE {
+            Biome::SmallEndIslands
+        } else {
+            Biome::EndHighlands
+        };
+        let mut chunk = Chunk::new_with_default_biome(position, biome);
+
+        let mut islands = vec![Island {
+            x: 0.0,
+            z: 0.0,
+            radius: MAIN_ISLAND_RADIUS,
+            surface: ISLAND_SURFACE,
+        }];
+        // Outer islands may reach into the chunk from neighbouring cells.
+        let cells = |origin: i32| {
+            (origin - MAX_OUTER_ISLAND_RADIUS).div_euclid(ISLAND_CELL_SIZE)
+                ..=(origin + 15 + MAX_OUTER_ISLAND_RADIUS).div_euclid(ISLAND_CELL_SIZE)
+        };
+        for cell_z in cells(origin_z) {
+            for cell_x in cells(origin_x) {
+                islands.extend(outer_island(self.seed, cell_x, cell_z));
+            }
+        }
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let (block_x, block_z) = ((origin_x + x) as f32, (origin_z + z) as f32);
+                for island in &islands {
+                    // How far into the island this column is, from
+                    // zero at its shore to one at its middle.
+                    let distance =
+                        ((block_x - island.x).powi(2) + (block_z - island.z).powi(2)).sqrt();
+                    let thickness = 1.0 - distance / island.radius;
+                    if thickness <= 0.0 {
+                        continue;
+                    }
+
+                    let top = island.surface as usize;
+                    let bottom = (island.surface - thickness.sqrt() * island.radius / 2.5) as usize;
+                    for y in bottom..=top {
+                        chunk.set_block_at(x as usize, y, z as usize, BlockId::end_stone());
+                    }
+                }
+            }
+        }
+
+        chunk.recalculate_heightmaps();
+        chunk
+    }
+}
+
+/// Size of the square cells holding at most
+/// one outer island each, in blocks.
+const ISLAND_CELL_SIZE: i32 = 64;
+const MAX_OUTER_ISLAND_RADIUS: i32 = 24;
+
+/// A roughly cone-shaped island.
+struct Island {
+    x: f32,
+    z: f32,
+    radius: f32,
+    surface: f32,
+}
+
+fn distance_to_origin(x: i32, z: i32) -> f32 {
+    ((x as f32).powi(2) + (z as f32).powi(2)).sqrt()
+}
+
+/// Gets the outer island in the given cell, if there is one.
+/// This function is deterministic.
+fn outer_island(seed: u64, cell_x: i32, cell_z: i32) -> Option<Island> {
+    let mut rng = XorShiftRng::seed_from_u64(
+        seed ^ (cell_x as i64 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (cell_z as i64 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F),
+    );
+    if rng.gen_range(0, 3) != 0 {
+        return None;
+    }
+
+    let radius = rng.gen_range(8, MAX_OUTER_ISLAND_RADIUS) as f32;
+    let x = cell_x * ISLAND_CELL_SIZE + rng.gen_range(0, ISLAND_CELL_SIZE);
+    let z = cell_z * ISLAND_CELL_SIZE + rng.gen_range(0, ISLAND_CELL_SIZE);
+    if distance_to_origin(x, z) < OUTER_ISLANDS_DISTANCE {
+        return None;
+    }
+
+    Some(Island {
+        x: x as f32,
+        z: z as f32,
+        radius,
+        surface: ISLAND_SURFACE + rng.gen_range(-8.0, 8.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn main_island_surrounded_by_void() {
+        let generator = EndGenerator::new(42);
+
+        let center = generator.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(
+            center.block_at(0, ISLAND_SURFACE as usize, 0),
+            Some(BlockId::end_stone())
+        );
+        assert_eq!(center.block_at(0, 0, 0), Some(BlockId::air()));
+        assert_eq!(center.biomes().get_at_block(0, 0, 0), Biome::TheEnd);
+
+        let void = generator.generate_chunk(ChunkPosition::new(20, -20));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..256 {
+                    assert_eq!(void.block_at(x, y, z), Some(BlockId::air()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn outer_islands_are_placed() {
+        let generator = EndGenerator::new(42);
+        let island = (20..40)
+            .find_map(|cell_x| outer_island(42, cell_x, 0))
+            .expect("no outer islands");
+
+        let (x, z) = (island.x as i32, island.z as i32);
+        let chunk =
+            generator.generate_chunk(ChunkPosition::new(x.div_euclid(16), z.div_euclid(16)));
+        assert_eq!(
+            chunk.block_at(
+                x.rem_euclid(16) as usize,
+                island.surface as usize,
+                z.rem_euclid(16) as usize
+            ),
+            Some(BlockId::end_stone())
+        );
+        assert_eq!(chunk.biomes().get_at_block(0, 0, 0), Biome::EndHighlands);
+    }
+}
@@ -1,9 +1,7 @@
-//! Various finishers for world generation, such as grass, snow, and trees.
+//! Various finishers for world generation, such as foliage and snow.
 
-mod clumped;
 mod single;
 mod snow;
 
-pub use clumped::ClumpedFoliageFinisher;
 pub use single::SingleFoliageFinisher;
 pub use snow::SnowFinisher;
@@ -6,27 +6,47 @@
 //! which allows configuration of a world generator pipeline.
 
 mod biomes;
+mod carvers;
 mod composition;
+mod decorators;
 mod density_map;
+mod end;
 mod finishers;
+mod nether;
 pub mod noise;
+mod structures;
 mod superflat;
 mod util;
 pub mod voronoi;
 
-use base::chunk::BiomeStore;
-use base::{Biome, BlockId, Chunk, ChunkPosition};
-pub use biomes::{DistortedVoronoiBiomeGenerator, TwoLevelBiomeGenerator};
+use std::sync::Arc;
+
+use base::anvil::level::SuperflatGeneratorOptions;
+use base::chunk::{BiomeStore, BoundingBox};
+use base::{Biome, BlockId, Chunk, ChunkPosition, ChunkStatus};
+pub use biomes::{DistortedVoronoiBiomeGenerator, LayeredBiomeGenerator, TwoLevelBiomeGenerator};
 use bitvec::vec::BitVec;
 use bitvec::{order::LocalBits, slice::BitSlice};
+pub use carvers::{CaveCarver, RavineCarver, LAVA_LEVEL};
 pub use composition::BasicCompositionGenerator;
+pub use decorators::{
+    default_ores, ClumpedFoliageDecorator, DecorationRegion, LakeDecorator, OreConfig,
+    OreDecorator, TreeDecorator, TreeKind,
+};
 pub use density_map::{DensityMapGeneratorImpl, HeightMapGenerator};
-use finishers::{ClumpedFoliageFinisher, SingleFoliageFinisher, SnowFinisher};
+pub use end::EndGenerator;
+pub use finishers::{SingleFoliageFinisher, SnowFinisher};
+pub use nether::{NetherGenerator, NETHER_HEIGHT, NETHER_LAVA_LEVEL};
 pub use noise::NoiseLerper;
 use num_traits::ToPrimitive;
+use once_cell::unsync::OnceCell;
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use smallvec::SmallVec;
+pub use structures::{
+    Dungeon, Rotation, Structure, StructurePiece, StructurePlacement, StructureSet, StructureStart,
+    StructureTemplate, Village,
+};
 pub use superflat::SuperflatWorldGenerator;
 
 /// Sea-level height.
@@ -38,7 +58,52 @@ const OCEAN_DEPTH: usize = 30;
 
 pub trait WorldGenerator: Send + Sync {
     /// Generates the chunk at the given position.
+    ///
+    /// Chunks which still need to be decorated are
+    /// returned with [`ChunkStatus::Proto`].
     fn generate_chunk(&self, position: ChunkPosition) -> Chunk;
+
+    /// Decorates the center chunk of `region`.
+    ///
+    /// Called for [`ChunkStatus::Proto`] chunks once their
+    /// neighbours have been generated. Decoration may write
+    /// into the neighbouring chunks.
+    fn decorate_chunk(&self, _region: &mut DecorationRegion) {}
+
+    /// Finds the bounding box of the nearest structure named
+    /// `name` within `radius` chunks of `near`, if this
+    /// generator places such structures.
+    fn locate_structure(
+        &self,
+        _name: &str,
+        _near: ChunkPosition,
+        _radius: i32,
+    ) -> Option<BoundingBox> {
+        None
+    }
+}
+
+/// Names of the generators available through [`generator_by_name`].
+pub const GENERATOR_NAMES: &[&str] = &["default", "vanilla", "flat", "void", "nether", "end"];
+
+/// Creates the generator with the given name, as
+/// used in the server config, with default settings.
+///
+/// Returns `None` if there is no such generator.
+pub fn generator_by_name(name: &str, seed: u64) -> Option<Arc<dyn WorldGenerator>> {
+    let generator: Arc<dyn WorldGenerator> = match name {
+        "default" => Arc::new(ComposableGenerator::default_with_seed(seed)),
+        "vanilla" => Arc::new(ComposableGenerator::vanilla_with_seed(seed)),
+        "flat" => Arc::new(
+            SuperflatWorldGenerator::new(SuperflatGeneratorOptions::default(), seed)
+                .expect("default superflat options are valid"),
+        ),
+        "void" => Arc::new(VoidWorldGenerator),
+        "nether" => Arc::new(NetherGenerator::new(seed)),
+        "end" => Arc::new(EndGenerator::new(seed)),
+        _ => return None,
+    };
+    Some(generator)
 }
 
 pub struct VoidWorldGenerator;
@@ -59,7 +124,12 @@ impl WorldGenerator for VoidWorldGenerator {
 /// * Biomes - generates a biome grid.
 /// * Terrain density - generates the terrain density values using Perlin noise.
 /// * Terrain composition - sets the correct block types based on the biome and terrain density.
-/// * Finishing generators - generates final elements, such as grass, snow, and trees.
+/// * Carvers - carve caves and ravines, which may cross chunk borders, into the terrain.
+/// * Finishing generators - generates final elements within the chunk, such as snow.
+/// * Structures - records the structures starting in or overlapping the chunk, and
+///   places their pieces once the neighbouring chunks have been generated.
+/// * Decorators - places features which may span chunk borders, such as ores and trees.
+///   These run once the neighbouring chunks have been generated.
 ///
 /// This generator is based on [this document](http://cuberite.xoft.cz/docs/Generator.html).
 pub struct ComposableGenerator {
@@ -69,33 +139,49 @@ pub struct ComposableGenerator {
     density_map: Box<dyn DensityMapGenerator>,
     /// The composition generator.
     composition: Box<dyn CompositionGenerator>,
+    /// The carvers used by this composable generator.
+    carvers: SmallVec<[Box<dyn Carver>; 4]>,
     /// A vector of finishing generators used
     /// by this composable generator.
     finishers: SmallVec<[Box<dyn FinishingGenerator>; 8]>,
+    /// The decorators used by this composable generator.
+    decorators: SmallVec<[Box<dyn Decorator>; 8]>,
+    /// The structures placed by this composable generator.
+    structures: StructureSet,
     /// The world seed.
     seed: u64,
 }
 
 impl ComposableGenerator {
     /// Creates a new `ComposableGenerator` with the given stages.
-    pub fn new<B, D, C, F>(
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<B, D, C, K, F, R, S>(
         biome: B,
         density_map: D,
         composition: C,
+        carvers: K,
         finishers: F,
+        decorators: R,
+        structures: S,
         seed: u64,
     ) -> Self
     where
         B: BiomeGenerator + 'static,
         D: DensityMapGenerator + 'static,
         C: CompositionGenerator + 'static,
+        K: IntoIterator<Item = Box<dyn Carver>>,
         F: IntoIterator<Item = Box<dyn FinishingGenerator>>,
+        R: IntoIterator<Item = Box<dyn Decorator>>,
+        S: IntoIterator<Item = Box<dyn Structure>>,
     {
         Self {
             biome: Box::new(biome),
             density_map: Box::new(density_map),
             composition: Box::new(composition),
+            carvers: carvers.into_iter().collect(),
             finishers: finishers.into_iter().collect(),
+            decorators: decorators.into_iter().collect(),
+            structures: StructureSet::new(structures),
             seed,
         }
     }
@@ -103,19 +189,88 @@ impl ComposableGenerator {
     /// A default composable generator, used
     /// for worlds with "default" world type.
     pub fn default_with_seed(seed: u64) -> Self {
+        Self::default_with_stages(DefaultStages::default(), seed)
+    }
+
+    /// Like [`ComposableGenerator::default_with_seed`],
+    /// but with the given ores and lava level.
+    pub fn default_with_stages(stages: DefaultStages, seed: u64) -> Self {
+        Self::with_default_stages(TwoLevelBiomeGenerator::default(), stages, seed)
+    }
+
+    /// A composable generator laying out biomes with vanilla's layer
+    /// stack for the same seed, used for worlds with "vanilla" world type.
+    /// The other stages are those of [`ComposableGenerator::default_with_seed`].
+    pub fn vanilla_with_seed(seed: u64) -> Self {
+        Self::vanilla_with_stages(DefaultStages::default(), seed)
+    }
+
+    /// Like [`ComposableGenerator::vanilla_with_seed`],
+    /// but with the given ores and lava level.
+    pub fn vanilla_with_stages(stages: DefaultStages, seed: u64) -> Self {
+        Self::with_default_stages(LayeredBiomeGenerator::new(seed), stages, seed)
+    }
+
+    /// Creates a generator with the given biome generator, the
+    /// given ores and lava level and the default terrain,
+    /// carvers, decorators and structures.
+    fn with_default_stages<B>(biome: B, stages: DefaultStages, seed: u64) -> Self
+    where
+        B: BiomeGenerator + 'static,
+    {
+        let carvers: Vec<Box<dyn Carver>> = vec![
+            Box::new(CaveCarver::new(stages.lava_level)),
+            Box::new(RavineCarver::new(stages.lava_level)),
+        ];
         let finishers: Vec<Box<dyn FinishingGenerator>> = vec![
             Box::new(SnowFinisher::default()),
             Box::new(SingleFoliageFinisher::default()),
-            Box::new(ClumpedFoliageFinisher::default()),
         ];
+        let decorators: Vec<Box<dyn Decorator>> = vec![
+            Box::new(OreDecorator::new(stages.ores)),
+            Box::new(TreeDecorator::default()),
+            Box::new(ClumpedFoliageDecorator::default()),
+        ];
+        let structures: Vec<Box<dyn Structure>> =
+            vec![Box::new(Dungeon::default()), Box::new(Village::default())];
         Self::new(
-            TwoLevelBiomeGenerator::default(),
+            biome,
             DensityMapGeneratorImpl::default(),
             BasicCompositionGenerator::default(),
+            carvers,
             finishers,
+            decorators,
+            structures,
             seed,
         )
     }
+
+    /// Gets the biome at the center of the given chunk.
+    fn biome_at_center(&self, chunk: ChunkPosition, biome_seed: u64) -> Biome {
+        self.biome
+            .generate_for_chunk(chunk, biome_seed)
+            .get_at_block(8, 0, 8)
+    }
+}
+
+/// The configurable parts of the stages used by
+/// [`ComposableGenerator::default_with_seed`] and
+/// [`ComposableGenerator::vanilla_with_seed`].
+#[derive(Clone, Debug)]
+pub struct DefaultStages {
+    /// The ores placed in veins.
+    pub ores: Vec<OreConfig>,
+    /// The height below which caves and ravines are filled with lava.
+    pub lava_level: usize,
+}
+
+impl Default for DefaultStages {
+    fn default() -> Self {
+        Self {
+            ores: default_ores(),
+            lava_level: LAVA_LEVEL,
+        }
+    }
 }
 
 impl WorldGenerator for ComposableGenerator {
@@ -135,9 +290,10 @@ impl WorldGenerator for ComposableGenerator {
         }
         let biomes = NearbyBiomes::from_slice(&biomes[..]).unwrap();
 
-        let density_map =
-            self.density_map
-                .generate_for_chunk(position, &biomes, seed_shuffler.gen());
+        let density_seed = seed_shuffler.gen();
+        let density_map = self
+            .density_map
+            .generate_for_chunk(position, &biomes, density_seed);
 
         let mut chunk = Chunk::new(position);
         *chunk.biomes_mut() = *biomes.center();
@@ -150,6 +306,18 @@ impl WorldGenerator for ComposableGenerator {
             seed_shuffler.gen(),
         );
 
+        // Carvers.
+        let water = ComposedWater::new(
+            self,
+            position,
+            (biome_seed, density_seed),
+            &biomes,
+            density_map,
+        );
+        for carver in &self.carvers {
+            carver.carve(&mut chunk, &water, seed_shuffler.gen());
+        }
+
         // Calculate top blocks in chunk.
         // TODO: perhaps this should be moved to `Chunk`?
         let mut top_blocks = TopBlocks::new();
@@ -176,8 +344,138 @@ impl WorldGenerator for ComposableGenerator {
             );
         }
 
+        self.structures.record(&mut chunk, self.seed, |pos| {
+            self.biome_at_center(pos, biome_seed)
+        });
+
+        if !self.decorators.is_empty() || !self.structures.is_empty() {
+            chunk.set_status(ChunkStatus::Proto);
+        }
+
         chunk
     }
+
+    fn decorate_chunk(&self, region: &mut DecorationRegion) {
+        // Decorators use the seeds following
+        // those of the stages above.
+        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
+        for _ in 0..3 + self.carvers.len() + self.finishers.len() {
+            seed_shuffler.gen::<u64>();
+        }
+
+        // Structures are placed first, so
+        // that trees grow around them.
+        self.structures.place(region, self.seed);
+
+        for decorator in &self.decorators {
+            decorator.decorate(region, seed_shuffler.gen());
+        }
+    }
+
+    fn locate_structure(
+        &self,
+        name: &str,
+        near: ChunkPosition,
+        radius: i32,
+    ) -> Option<BoundingBox> {
+        let biome_seed = XorShiftRng::seed_from_u64(self.seed).gen();
+        self.structures
+            .locate(name, near, radius, self.seed, |pos| {
+                self.biome_at_center(pos, biome_seed)
+            })
+    }
+}
+
+/// The water of the composed terrain around a chunk, as seen by carvers.
+///
+/// The biomes and density maps of neighbouring chunks
+/// are only generated once a carver reaches into them.
+struct ComposedWater<'a> {
+    generator: &'a ComposableGenerator,
+    center: ChunkPosition,
+    biome_seed: u64,
+    density_seed: u64,
+    /// The biomes of the 5x5 chunks around the center, which the
+    /// density maps of the 3x3 chunks around the center depend on.
+    biomes: [OnceCell<BiomeStore>; 5 * 5],
+    /// The density maps of the 3x3 chunks around the center.
+    densities: [OnceCell<BitVec<LocalBits, u8>>; 3 * 3],
+}
+
+impl<'a> ComposedWater<'a> {
+    fn new(
+        generator: &'a ComposableGenerator,
+        center: ChunkPosition,
+        (biome_seed, density_seed): (u64, u64),
+        biomes: &NearbyBiomes,
+        density: BitVec<LocalBits, u8>,
+    ) -> Self {
+        let water = Self {
+            generator,
+            center,
+            biome_seed,
+            density_seed,
+            biomes: Default::default(),
+            densities: Default::default(),
+        };
+        for z in 0..3 {
+            for x in 0..3 {
+                let _ = water.biomes[(x + 1) + (z + 1) * 5].set(biomes.biome_stores[x + z * 3]);
+            }
+        }
+        let _ = water.densities[4].set(density);
+        water
+    }
+
+    /// Gets the biomes of the chunk at the given offset from the center.
+    fn biomes(&self, dx: i32, dz: i32) -> &BiomeStore {
+        self.biomes[((dx + 2) + (dz + 2) * 5) as usize].get_or_init(|| {
+            let pos = ChunkPosition::new(self.center.x + dx, self.center.z + dz);
+            self.generator
+                .biome
+                .generate_for_chunk(pos, self.biome_seed)
+        })
+    }
+
+    /// Gets the density map of the chunk at the given offset from the center.
+    fn density(&self, dx: i32, dz: i32) -> &BitSlice<LocalBits, u8> {
+        self.densities[((dx + 1) + (dz + 1) * 3) as usize].get_or_init(|| {
+            let mut biomes = Vec::with_capacity(9);
+            for z in -1..=1 {
+                for x in -1..=1 {
+                    biomes.push(*self.biomes(dx + x, dz + z));
+                }
+            }
+            let biomes = NearbyBiomes::from_slice(&biomes).unwrap();
+            let pos = ChunkPosition::new(self.center.x + dx, self.center.z + dz);
+            self.generator
+                .density_map
+                .generate_for_chunk(pos, &biomes, self.density_seed)
+        })
+    }
+}
+
+impl WaterMap for ComposedWater<'_> {
+    fn is_water(&self, x: i32, y: i32, z: i32) -> bool {
+        let (dx, dz) = (
+            x.div_euclid(16) - self.center.x,
+            z.div_euclid(16) - self.center.z,
+        );
+        if !(0..256).contains(&y) || dx.abs() > 1 || dz.abs() > 1 {
+            return false;
+        }
+        let (x, y, z) = (
+            x.rem_euclid(16) as usize,
+            y as usize,
+            z.rem_euclid(16) as usize,
+        );
+
+        // Only generate the density map if the column can contain water.
+        let biome = self.biomes(dx, dz).get_at_block(x, 0, z);
+        let composition = &self.generator.composition;
+        composition.is_water(biome, y, false)
+            && composition.is_water(biome, y, self.density(dx, dz)[block_index(x, y, z)])
+    }
 }
 
 /// A generator which generates the biome grid for a `ComposableGenerator`.
@@ -215,6 +513,46 @@ pub trait CompositionGenerator: Send + Sync {
         density: &BitSlice<LocalBits, u8>,
         seed: u64,
     );
+
+    /// Returns whether this generator places water at height `y`
+    /// of a column with the given biome and density.
+    ///
+    /// Carvers use this to find the water in neighbouring
+    /// chunks, which have not been composed yet. By default, all
+    /// non-solid blocks up to [`SEA_LEVEL`] are assumed to be water.
+    fn is_water(&self, biome: Biome, y: usize, is_solid: bool) -> bool {
+        y <= SEA_LEVEL && !is_solid
+    }
+}
+
+/// A generator, run after composition, which carves
+/// caves and other tunnels into the terrain.
+pub trait Carver: Send + Sync {
+    /// Carves the given chunk.
+    ///
+    /// Tunnels may start in other chunks, so carving
+    /// must be deterministic for the chunk a tunnel
+    /// starts in, regardless of the chunk being carved.
+    /// `water` covers the chunk and its neighbours, so that
+    /// parts of tunnels crossing chunk borders are skipped
+    /// for water in the same way in every chunk.
+    fn carve(&self, chunk: &mut Chunk, water: &dyn WaterMap, seed: u64);
+}
+
+/// The water of the composed terrain around a chunk being carved.
+pub trait WaterMap {
+    /// Returns whether there is water at the given world coordinates,
+    /// which lie in the chunk being carved or one of its neighbours.
+    fn is_water(&self, x: i32, y: i32, z: i32) -> bool;
+}
+
+impl<F> WaterMap for F
+where
+    F: Fn(i32, i32, i32) -> bool,
+{
+    fn is_water(&self, x: i32, y: i32, z: i32) -> bool {
+        self(x, y, z)
+    }
 }
 
 /// A generator, run after composition,
@@ -232,6 +570,14 @@ pub trait FinishingGenerator: Send + Sync {
     );
 }
 
+/// A generator, run once the neighbours of a chunk
+/// have been generated, which can place features
+/// spanning chunk borders, such as trees.
+pub trait Decorator: Send + Sync {
+    /// Decorates the center chunk of `region`.
+    fn decorate(&self, region: &mut DecorationRegion, seed: u64);
+}
+
 /// Returns an index into a one-dimensional array
 /// for the given x, y, and z values.
 pub fn block_index(x: usize, y: usize, z: usize) -> usize {
@@ -368,6 +714,14 @@ impl BiomeGenerator for StaticBiomeGenerator {
 mod tests {
     use super::*;
 
+    #[test]
+    fn generators_by_name() {
+        for name in GENERATOR_NAMES {
+            assert!(generator_by_name(name, 0).is_some(), "{}", name);
+        }
+        assert!(generator_by_name("amplified", 0).is_none());
+    }
+
     #[test]
     fn test_reproducability() {
         let seeds: [u64; 4] = [std::u64::MAX, 3243, 0, 100];
@@ -390,6 +744,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lava_level_is_configurable() {
+        let lava_blocks = |lava_level| {
+            let stages = DefaultStages {
+                lava_level,
+                ..Default::default()
+            };
+            let gen = ComposableGenerator::default_with_stages(stages, 3243);
+            let mut count = 0;
+            for chunk_x in 0..4 {
+                for chunk_z in 0..4 {
+                    let chunk = gen.generate_chunk(ChunkPosition::new(chunk_x, chunk_z));
+                    for x in 0..16 {
+                        for y in 0..64 {
+                            for z in 0..16 {
+                                if chunk.block_at(x, y, z) == Some(BlockId::lava()) {
+                                    count += 1;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            count
+        };
+        assert_eq!(lava_blocks(0), 0);
+        assert!(lava_blocks(40) > lava_blocks(LAVA_LEVEL));
+    }
+
+    #[test]
+    fn test_decoration_reproducability() {
+        let gen = ComposableGenerator::default_with_seed(3243);
+        let center = ChunkPosition::new(2, -1);
+
+        let decorate = || {
+            let mut region = DecorationRegion::new(center);
+            for z in -1..=1 {
+                for x in -1..=1 {
+                    let chunk = gen.generate_chunk(ChunkPosition::new(center.x + x, center.z + z));
+                    assert_eq!(chunk.status(), ChunkStatus::Proto);
+                    region.insert(chunk);
+                }
+            }
+            gen.decorate_chunk(&mut region);
+            region.into_chunks().collect::<Vec<_>>()
+        };
+
+        for (first, second) in decorate().iter().zip(&decorate()) {
+            test_chunks_eq(first, second);
+        }
+    }
+
     fn test_chunks_eq(a: &Chunk, b: &Chunk) {
         for x in 0..16 {
             for z in 0..16 {
@@ -0,0 +1,147 @@
+use crate::util::shuffle_seed_for_chunk;
+use crate::{noise, NoiseLerper, WorldGenerator};
+use base::{Biome, BlockId, Chunk, ChunkPosition};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use simdnoise::NoiseBuilder;
+
+/// Height of the nether, from the bedrock floor to the bedrock ceiling.
+pub const NETHER_HEIGHT: usize = 128;
+/// The highest block of the nether's lava sea.
+pub const NETHER_LAVA_LEVEL: usize = 31;
+
+const DENSITY_WIDTH: usize = 5;
+const DENSITY_HEIGHT: usize = 33;
+
+/// Generates the nether: netherrack caverns between a bedrock
+/// floor and ceiling, flooded with lava up to [`NETHER_LAVA_LEVEL`].
+pub struct NetherGenerator {
+    seed: u64,
+}
+
+impl NetherGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl WorldGenerator for NetherGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new_with_default_biome(position, Biome::NetherWastes);
+
+        let densities = generate_density(position, self.seed);
+        let noise = NoiseLerper::new(&densities)
+            .with_offset(position.x, position.z)
+            .generate();
+
+        let mut rng = XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(self.seed, position));
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..NETHER_HEIGHT {
+                    // Like vanilla, the bedrock gets
+                    // patchy over its five layers.
+                    let depth = y.min(NETHER_HEIGHT - 1 - y);
+                    let block = if depth < 5 && rng.gen_range(0, 5) >= depth {
+                        BlockId::bedrock()
+                    } else if noise[noise::index(x, y, z)] < 0.0 {
+                        BlockId::netherrack()
+                    } else if y <= NETHER_LAVA_LEVEL {
+                        BlockId::lava()
+                    } else {
+                        continue;
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        chunk.recalculate_heightmaps();
+        chunk
+    }
+}
+
+/// Generates a 5x33x5 density array to pass to `NoiseLerper`.
+///
+/// As with the overworld density map, values below
+/// zero are solid. Cells near the floor and ceiling are
+/// pushed towards solid, so that caverns stay enclosed.
+fn generate_density(chunk: ChunkPosition, seed: u64) -> Vec<f32> {
+    let x_offset = (chunk.x * (DENSITY_WIDTH as i32 - 1)) as f32;
+    let z_offset = (chunk.z * (DENSITY_WIDTH as i32 - 1)) as f32;
+
+    let cavern_noise = NoiseBuilder::fbm_3d_offset(
+        x_offset,
+        DENSITY_WIDTH,
+        0.0,
+        DENSITY_HEIGHT,
+        z_offset,
+        DENSITY_WIDTH,
+    )
+    .with_seed(seed as i32)
+    .with_octaves(3)
+    .with_freq(0.15)
+    .generate()
+    .0;
+
+    let mut result = vec![0.0; DENSITY_WIDTH * DENSITY_HEIGHT * DENSITY_WIDTH];
+    for subx in 0..DENSITY_WIDTH {
+        for subz in 0..DENSITY_WIDTH {
+            for suby in 0..DENSITY_HEIGHT {
+                let y = suby as f32 * 8.0;
+                let ceiling = (NETHER_HEIGHT - 1) as f32;
+
+                // Distance to the closer of the floor
+                // and the ceiling, in blocks.
+                let edge_distance = y.min(ceiling - y);
+                let falloff = if edge_distance < 24.0 {
+                    (24.0 - edge_distance) * 1.5
+                } else {
+                    0.0
+                };
+
+                let index = DENSITY_WIDTH * suby + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz;
+                result[index] = cavern_noise[index] * 100.0 + 5.0 - falloff;
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nether_is_enclosed() {
+        let generator = NetherGenerator::new(42);
+        let chunk = generator.generate_chunk(ChunkPosition::new(-3, 7));
+
+        let mut netherrack = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(chunk.block_at(x, 0, z), Some(BlockId::bedrock()));
+                assert_eq!(
+                    chunk.block_at(x, NETHER_HEIGHT - 1, z),
+                    Some(BlockId::bedrock())
+                );
+                for y in NETHER_HEIGHT..256 {
+                    assert_eq!(chunk.block_at(x, y, z), Some(BlockId::air()));
+                }
+                for y in 5..NETHER_HEIGHT - 5 {
+                    let block = chunk.block_at(x, y, z).unwrap();
+                    if block == BlockId::netherrack() {
+                        netherrack += 1;
+                    } else if block == BlockId::air() {
+                        assert!(y > NETHER_LAVA_LEVEL);
+                    } else {
+                        assert_eq!(block, BlockId::lava());
+                        assert!(y <= NETHER_LAVA_LEVEL);
+                    }
+                }
+            }
+        }
+        assert!(netherrack > 0);
+        assert_eq!(chunk.biomes().get_at_block(0, 0, 0), Biome::NetherWastes);
+    }
+}
@@ -0,0 +1,268 @@
+use super::{Structure, StructurePiece, StructurePlacement};
+use crate::DecorationRegion;
+use base::anvil::block_entity::BlockEntityKind;
+use base::chunk::BoundingBox;
+use base::{Biome, BlockId, ChunkPosition};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// Small cobblestone rooms with a monster spawner
+/// and chests, hidden underground.
+///
+/// Like in vanilla, the chests reference the `simple_dungeon`
+/// loot table and are filled once they are first opened.
+#[derive(Default)]
+pub struct Dungeon;
+
+impl Structure for Dungeon {
+    fn name(&self) -> &str {
+        "dungeon"
+    }
+
+    fn placement(&self) -> StructurePlacement {
+        StructurePlacement {
+            spacing: 4,
+            separation: 1,
+            salt: 14_357_617,
+        }
+    }
+
+    fn max_radius(&self) -> i32 {
+        1
+    }
+
+    fn can_start_in(&self, _biome: Biome) -> bool {
+        true
+    }
+
+    fn pieces(&self, start: ChunkPosition, rng: &mut XorShiftRng) -> Vec<Box<dyn StructurePiece>> {
+        vec![Box::new(DungeonRoom {
+            x: start.x * 16 + rng.gen_range(0, 16),
+            y: rng.gen_range(10, 50),
+            z: start.z * 16 + rng.gen_range(0, 16),
+            radius_x: rng.gen_range(2, 4),
+            radius_z: rng.gen_range(2, 4),
+        })]
+    }
+}
+
+/// The mobs spawned by dungeon spawners. Zombies
+/// are twice as likely as the others, like in vanilla.
+const SPAWNED_MOBS: [&str; 4] = [
+    "minecraft:skeleton",
+    "minecraft:zombie",
+    "minecraft:zombie",
+    "minecraft:spider",
+];
+
+/// The loot table of dungeon chests.
+const LOOT_TABLE: &str = "minecraft:chests/simple_dungeon";
+
+/// The room of a dungeon. `x`, `y` and `z` are the
+/// position of the spawner on the floor of the room.
+struct DungeonRoom {
+    x: i32,
+    y: i32,
+    z: i32,
+    radius_x: i32,
+    radius_z: i32,
+}
+
+impl DungeonRoom {
+    fn is_wall(&self, dx: i32, dz: i32) -> bool {
+        dx.abs() == self.radius_x + 1 || dz.abs() == self.radius_z + 1
+    }
+}
+
+impl StructurePiece for DungeonRoom {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(
+            (
+                self.x - self.radius_x - 1,
+                self.y - 1,
+                self.z - self.radius_z - 1,
+            ),
+            (
+                self.x + self.radius_x + 1,
+                self.y + 4,
+                self.z + self.radius_z + 1,
+            ),
+        )
+    }
+
+    fn place(&self, region: &mut DecorationRegion, rng: &mut XorShiftRng) {
+        let (origin_x, origin_z) = region.origin();
+        let (x, y, z) = (self.x - origin_x, self.y, self.z - origin_z);
+        let (radius_x, radius_z) = (self.radius_x, self.radius_z);
+        let is_solid = |block: Option<BlockId>| block.map_or(false, BlockId::is_solid);
+
+        // Like vanilla, dungeons need solid floors and
+        // ceilings and should open into a cave or two.
+        let mut openings = 0;
+        for dx in -radius_x - 1..=radius_x + 1 {
+            for dz in -radius_z - 1..=radius_z + 1 {
+                if !is_solid(region.block_at(x + dx, y - 1, z + dz))
+                    || !is_solid(region.block_at(x + dx, y + 4, z + dz))
+                {
+                    return;
+                }
+                if self.is_wall(dx, dz)
+                    && region.block_at(x + dx, y, z + dz) == Some(BlockId::air())
+                    && region.block_at(x + dx, y + 1, z + dz) == Some(BlockId::air())
+                {
+                    openings += 1;
+                }
+            }
+        }
+        if !(1..=5).contains(&openings) {
+            return;
+        }
+
+        for dx in -radius_x - 1..=radius_x + 1 {
+            for dz in -radius_z - 1..=radius_z + 1 {
+                let (block_x, block_z) = (x + dx, z + dz);
+                let floor = if rng.gen_range(0, 4) == 0 {
+                    BlockId::mossy_cobblestone()
+                } else {
+                    BlockId::cobblestone()
+                };
+                region.set_block_at(block_x, y - 1, block_z, floor);
+
+                for block_y in y..y + 4 {
+                    if !self.is_wall(dx, dz) {
+                        region.set_block_at(block_x, block_y, block_z, BlockId::air());
+                    } else if is_solid(region.block_at(block_x, block_y, block_z)) {
+                        // Keep the openings.
+                        region.set_block_at(block_x, block_y, block_z, BlockId::cobblestone());
+                    }
+                }
+            }
+        }
+
+        region.set_block_at(x, y, z, BlockId::spawner());
+        let mob = SPAWNED_MOBS[rng.gen_range(0, SPAWNED_MOBS.len())];
+        region.set_block_entity_at(x, y, z, BlockEntityKind::mob_spawner(mob));
+
+        for _ in 0..2 {
+            for _ in 0..3 {
+                let chest_x = x + rng.gen_range(-radius_x, radius_x + 1);
+                let chest_z = z + rng.gen_range(-radius_z, radius_z + 1);
+                if region.block_at(chest_x, y, chest_z) != Some(BlockId::air()) {
+                    continue;
+                }
+                // Chests stand against exactly one wall.
+                let walls = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                    .iter()
+                    .filter(|(dx, dz)| is_solid(region.block_at(chest_x + dx, y, chest_z + dz)))
+                    .count();
+                if walls == 1 {
+                    region.set_block_at(chest_x, y, chest_z, BlockId::chest());
+                    region.set_block_entity_at(
+                        chest_x,
+                        y,
+                        chest_z,
+                        BlockEntityKind::Chest {
+                            items: Vec::new(),
+                            loot_table: Some(LOOT_TABLE.to_owned()),
+                            loot_table_seed: Some(rng.gen()),
+                        },
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::Chunk;
+    use rand::SeedableRng;
+
+    fn stone_region() -> DecorationRegion {
+        let mut region = DecorationRegion::new(ChunkPosition::new(0, 0));
+        for chunk_z in -1..=1 {
+            for chunk_x in -1..=1 {
+                let mut chunk = Chunk::new(ChunkPosition::new(chunk_x, chunk_z));
+                for x in 0..16 {
+                    for z in 0..16 {
+                        for y in 0..64 {
+                            chunk.set_block_at(x, y, z, BlockId::stone());
+                        }
+                    }
+                }
+                region.insert(chunk);
+            }
+        }
+        region
+    }
+
+    const ROOM: DungeonRoom = DungeonRoom {
+        x: 8,
+        y: 20,
+        z: 8,
+        radius_x: 3,
+        radius_z: 2,
+    };
+
+    /// A stone region with a cave running into the west wall of `ROOM`.
+    fn cave_region() -> DecorationRegion {
+        let mut region = stone_region();
+        for x in 0..5 {
+            region.set_block_at(x, 20, 8, BlockId::air());
+            region.set_block_at(x, 21, 8, BlockId::air());
+        }
+        region
+    }
+
+    #[test]
+    fn dungeons_open_into_caves() {
+        let mut region = cave_region();
+        ROOM.place(&mut region, &mut XorShiftRng::seed_from_u64(0));
+        assert_eq!(region.block_at(8, 20, 8), Some(BlockId::spawner()));
+        assert_eq!(region.block_at(7, 21, 8), Some(BlockId::air()));
+        assert_eq!(region.block_at(4, 22, 8), Some(BlockId::cobblestone()));
+        assert_eq!(region.block_at(4, 20, 8), Some(BlockId::air()));
+    }
+
+    #[test]
+    fn dungeons_have_block_entities() {
+        // Chests are not placed in every dungeon.
+        let mut chests = 0;
+        for seed in 0..8 {
+            let mut region = cave_region();
+            ROOM.place(&mut region, &mut XorShiftRng::seed_from_u64(seed));
+
+            let block_entities = region.center().unwrap().block_entities();
+            for entity in block_entities {
+                let block = region.block_at(entity.base.x, entity.base.y, entity.base.z);
+                match &entity.kind {
+                    BlockEntityKind::MobSpawner { spawn_data, .. } => {
+                        assert_eq!(block, Some(BlockId::spawner()));
+                        assert!(SPAWNED_MOBS.contains(&spawn_data.id.as_str()));
+                    }
+                    BlockEntityKind::Chest { loot_table, .. } => {
+                        assert_eq!(block, Some(BlockId::chest()));
+                        assert_eq!(loot_table.as_deref(), Some(LOOT_TABLE));
+                        chests += 1;
+                    }
+                    kind => panic!("unexpected block entity {:?}", kind),
+                }
+            }
+            assert!(block_entities.iter().any(|entity| (
+                entity.base.x,
+                entity.base.y,
+                entity.base.z
+            ) == (8, 20, 8)));
+        }
+        assert!(chests > 0);
+    }
+
+    #[test]
+    fn dungeons_need_openings() {
+        let mut region = stone_region();
+        ROOM.place(&mut region, &mut XorShiftRng::seed_from_u64(0));
+        assert_eq!(region.block_at(8, 20, 8), Some(BlockId::stone()));
+    }
+}
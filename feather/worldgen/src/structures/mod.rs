@@ -0,0 +1,363 @@
+//! Structures, such as villages and dungeons.
+//!
+//! Structure starts are spread over the world like vanilla (see
+//! [`StructurePlacement`]). When a chunk is generated, the structures
+//! starting in or overlapping it are recorded in its
+//! [`ChunkStructures`](base::chunk::ChunkStructures), which are saved
+//! with the chunk. The pieces of a structure are placed while decorating
+//! the chunk containing their center, so pieces may extend into the
+//! neighbouring chunks.
+
+mod dungeon;
+mod template;
+mod village;
+
+use crate::DecorationRegion;
+use base::chunk::BoundingBox;
+use base::{Biome, BlockId, Chunk, ChunkPosition, SimplifiedBlockKind};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+pub use dungeon::Dungeon;
+pub use template::{Rotation, StructureTemplate};
+pub use village::Village;
+
+/// Spreads the starts of a structure over the world like vanilla.
+///
+/// The world is divided into square cells of `spacing` chunks.
+/// Each cell holds one potential start, placed so that the starts
+/// of neighbouring cells are at least `separation` chunks apart.
+#[derive(Copy, Clone, Debug)]
+pub struct StructurePlacement {
+    pub spacing: i32,
+    pub separation: i32,
+    /// Distinguishes structures with the same spacing.
+    pub salt: u64,
+}
+
+impl StructurePlacement {
+    /// Returns the potential start of the cell containing `chunk`.
+    pub fn potential_start(&self, seed: u64, chunk: ChunkPosition) -> ChunkPosition {
+        let cell_x = chunk.x.div_euclid(self.spacing);
+        let cell_z = chunk.z.div_euclid(self.spacing);
+        let mut rng = XorShiftRng::seed_from_u64(
+            (cell_x as i64 as u64)
+                .wrapping_mul(341_873_128_712)
+                .wrapping_add((cell_z as i64 as u64).wrapping_mul(132_897_987_541))
+                .wrapping_add(seed)
+                .wrapping_add(self.salt),
+        );
+        let range = (self.spacing - self.separation).max(1);
+        ChunkPosition::new(
+            cell_x * self.spacing + rng.gen_range(0, range),
+            cell_z * self.spacing + rng.gen_range(0, range),
+        )
+    }
+}
+
+/// A kind of structure, such as villages.
+pub trait Structure: Send + Sync {
+    /// Gets the name used for this structure
+    /// in chunk data and locate queries.
+    fn name(&self) -> &str;
+
+    /// Gets how starts of this structure are spread over the world.
+    fn placement(&self) -> StructurePlacement;
+
+    /// Gets the maximum distance, in chunks, between
+    /// the start chunk and the pieces of this structure.
+    fn max_radius(&self) -> i32;
+
+    /// Determines whether this structure can start in a
+    /// chunk with the given biome at its center.
+    fn can_start_in(&self, biome: Biome) -> bool;
+
+    /// Lays out the pieces of the structure starting in the given chunk.
+    /// This function should be deterministic.
+    fn pieces(&self, start: ChunkPosition, rng: &mut XorShiftRng) -> Vec<Box<dyn StructurePiece>>;
+}
+
+/// A part of a structure, such as a house in a village.
+pub trait StructurePiece: Send + Sync {
+    /// Gets the blocks this piece may occupy.
+    ///
+    /// The center of the bounding box must be less than
+    /// 16 blocks away from its edges, so that the piece fits
+    /// into the region of the chunk containing the center.
+    fn bounding_box(&self) -> BoundingBox;
+
+    /// Places this piece into the region centered
+    /// on the chunk containing its center.
+    fn place(&self, region: &mut DecorationRegion, rng: &mut XorShiftRng);
+}
+
+/// The pieces of a structure starting in a chunk.
+pub struct StructureStart {
+    pub chunk: ChunkPosition,
+    pub pieces: Vec<Box<dyn StructurePiece>>,
+    /// Seed for placing the pieces.
+    seed: u64,
+}
+
+impl StructureStart {
+    /// Gets the bounding box containing all pieces,
+    /// or `None` if there are no pieces.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.pieces
+            .iter()
+            .map(|piece| piece.bounding_box())
+            .reduce(BoundingBox::union)
+    }
+}
+
+/// The structures generated by a world generator.
+#[derive(Default)]
+pub struct StructureSet {
+    structures: Vec<Box<dyn Structure>>,
+}
+
+impl StructureSet {
+    pub fn new(structures: impl IntoIterator<Item = Box<dyn Structure>>) -> Self {
+        Self {
+            structures: structures.into_iter().collect(),
+        }
+    }
+
+    /// Determines whether this set contains no structures.
+    pub fn is_empty(&self) -> bool {
+        self.structures.is_empty()
+    }
+
+    /// Gets the structure with the given name.
+    pub fn get(&self, name: &str) -> Option<&dyn Structure> {
+        self.structures
+            .iter()
+            .find(|structure| structure.name() == name)
+            .map(|structure| &**structure)
+    }
+
+    /// Records the structures starting in or overlapping `chunk`.
+    ///
+    /// `biome_at` returns the biome at the center of a chunk.
+    pub fn record(&self, chunk: &mut Chunk, seed: u64, biome_at: impl Fn(ChunkPosition) -> Biome) {
+        let pos = chunk.position();
+        for structure in &self.structures {
+            let radius = structure.max_radius();
+            let mut candidates = Vec::new();
+            for z in pos.z - radius..=pos.z + radius {
+                for x in pos.x - radius..=pos.x + radius {
+                    let candidate = structure
+                        .placement()
+                        .potential_start(seed, ChunkPosition::new(x, z));
+                    if !candidates.contains(&candidate) {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+
+            for candidate in candidates {
+                let bounding_box = match start(structure.as_ref(), seed, candidate, &biome_at)
+                    .and_then(|start| start.bounding_box())
+                {
+                    Some(bounding_box) => bounding_box,
+                    None => continue,
+                };
+
+                let structures = chunk.structures_mut();
+                if candidate == pos {
+                    structures
+                        .starts
+                        .insert(structure.name().to_owned(), bounding_box);
+                }
+                if bounding_box.intersects_chunk(pos) {
+                    structures
+                        .references
+                        .entry(structure.name().to_owned())
+                        .or_default()
+                        .push(candidate);
+                }
+            }
+        }
+    }
+
+    /// Places the pieces whose center lies within
+    /// the center chunk of `region`.
+    pub fn place(&self, region: &mut DecorationRegion, seed: u64) {
+        let references = match region.center() {
+            Some(center) => center.structures().references.clone(),
+            None => return,
+        };
+
+        for (name, starts) in references {
+            let structure = match self.get(&name) {
+                Some(structure) => structure,
+                None => continue,
+            };
+            for start in starts {
+                let start = layout(structure, seed, start);
+                for (index, piece) in start.pieces.iter().enumerate() {
+                    let (x, z) = piece.bounding_box().center();
+                    let owner = ChunkPosition::new(x.div_euclid(16), z.div_euclid(16));
+                    if owner != region.center_position() {
+                        continue;
+                    }
+                    let mut rng = XorShiftRng::seed_from_u64(start.seed.wrapping_add(index as u64));
+                    piece.place(region, &mut rng);
+                }
+            }
+        }
+    }
+
+    /// Finds the bounding box of the nearest start of the structure
+    /// named `name` within `radius` chunks of `near`.
+    pub fn locate(
+        &self,
+        name: &str,
+        near: ChunkPosition,
+        radius: i32,
+        seed: u64,
+        biome_at: impl Fn(ChunkPosition) -> Biome,
+    ) -> Option<BoundingBox> {
+        let structure = self.get(name)?;
+        let spacing = structure.placement().spacing;
+
+        let cells = |center: i32| {
+            (center - radius).div_euclid(spacing)..=(center + radius).div_euclid(spacing)
+        };
+        let mut nearest: Option<(i64, BoundingBox)> = None;
+        for cell_z in cells(near.z) {
+            for cell_x in cells(near.x) {
+                let candidate = structure
+                    .placement()
+                    .potential_start(seed, ChunkPosition::new(cell_x * spacing, cell_z * spacing));
+                let (dx, dz) = (candidate.x - near.x, candidate.z - near.z);
+                if dx.abs() > radius || dz.abs() > radius {
+                    continue;
+                }
+                let distance = i64::from(dx).pow(2) + i64::from(dz).pow(2);
+                if matches!(nearest, Some((nearest, _)) if nearest <= distance) {
+                    continue;
+                }
+                if let Some(bounding_box) = start(structure, seed, candidate, &biome_at)
+                    .and_then(|start| start.bounding_box())
+                {
+                    nearest = Some((distance, bounding_box));
+                }
+            }
+        }
+        nearest.map(|(_, bounding_box)| bounding_box)
+    }
+}
+
+/// Generates the start of `structure` in `chunk`, if there is one.
+fn start(
+    structure: &dyn Structure,
+    seed: u64,
+    chunk: ChunkPosition,
+    biome_at: &impl Fn(ChunkPosition) -> Biome,
+) -> Option<StructureStart> {
+    if structure.placement().potential_start(seed, chunk) != chunk
+        || !structure.can_start_in(biome_at(chunk))
+    {
+        return None;
+    }
+    Some(layout(structure, seed, chunk))
+}
+
+/// Lays out the pieces of a start, without
+/// checking whether the start is valid.
+fn layout(structure: &dyn Structure, seed: u64, chunk: ChunkPosition) -> StructureStart {
+    let mut rng = XorShiftRng::seed_from_u64(
+        seed.wrapping_add(structure.placement().salt)
+            ^ (chunk.x as i64 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (chunk.z as i64 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F),
+    );
+    let pieces = structure.pieces(chunk, &mut rng);
+    StructureStart {
+        chunk,
+        pieces,
+        seed: rng.gen(),
+    }
+}
+
+/// Gets the height of the highest ground block in the given
+/// column of `region`, ignoring trees and plants.
+pub(crate) fn ground_height(region: &DecorationRegion, x: i32, z: i32) -> Option<i32> {
+    (0..256).rev().find(|&y| match region.block_at(x, y, z) {
+        Some(block) => is_ground(block),
+        None => false,
+    })
+}
+
+fn is_ground(block: BlockId) -> bool {
+    block.is_solid()
+        && !matches!(
+            block.simplified_kind(),
+            SimplifiedBlockKind::Log | SimplifiedBlockKind::Leaves
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_are_separated() {
+        let placement = StructurePlacement {
+            spacing: 32,
+            separation: 8,
+            salt: 10387312,
+        };
+        let mut starts = Vec::new();
+        for cell_z in -3..3 {
+            for cell_x in -3..3 {
+                let start =
+                    placement.potential_start(42, ChunkPosition::new(cell_x * 32, cell_z * 32));
+                assert_eq!(start.x.div_euclid(32), cell_x);
+                assert_eq!(start.z.div_euclid(32), cell_z);
+                // Every chunk of the cell agrees on the start.
+                assert_eq!(
+                    placement
+                        .potential_start(42, ChunkPosition::new(cell_x * 32 + 31, cell_z * 32)),
+                    start
+                );
+                starts.push(start);
+            }
+        }
+        for a in &starts {
+            for b in &starts {
+                if a != b {
+                    assert!((a.x - b.x).abs() >= 8 || (a.z - b.z).abs() >= 8);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn villages_are_recorded_and_located() {
+        let structures =
+            StructureSet::new(vec![Box::new(Village::default()) as Box<dyn Structure>]);
+        let seed = 7;
+        let biome_at = |_| Biome::Plains;
+
+        let bounding_box = structures
+            .locate("village", ChunkPosition::new(0, 0), 64, seed, biome_at)
+            .expect("no village within 64 chunks");
+        let (x, z) = bounding_box.center();
+        let chunk_pos = ChunkPosition::new(x.div_euclid(16), z.div_euclid(16));
+
+        let mut chunk = Chunk::new(chunk_pos);
+        structures.record(&mut chunk, seed, biome_at);
+        assert_eq!(chunk.structures().references["village"].len(), 1);
+        let start = chunk.structures().references["village"][0];
+
+        let mut start_chunk = Chunk::new(start);
+        structures.record(&mut start_chunk, seed, biome_at);
+        assert_eq!(start_chunk.structures().starts["village"], bounding_box);
+
+        let oceans = structures.locate("village", ChunkPosition::new(0, 0), 64, seed, |_| {
+            Biome::DeepOcean
+        });
+        assert_eq!(oceans, None);
+    }
+}
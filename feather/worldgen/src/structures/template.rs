@@ -0,0 +1,180 @@
+use crate::DecorationRegion;
+use anyhow::{bail, Context};
+use base::BlockId;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// A rotation of a [`StructureTemplate`] around the vertical axis.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    None,
+    Clockwise90,
+    Clockwise180,
+    CounterClockwise90,
+}
+
+/// A structure template in the format saved by structure blocks,
+/// e.g. `data/minecraft/structures/*.nbt` in vanilla data packs.
+///
+/// Only blocks are supported; entities and block entity
+/// data in the template are ignored.
+#[derive(Debug, Clone)]
+pub struct StructureTemplate {
+    size: (i32, i32, i32),
+    blocks: Vec<((i32, i32, i32), BlockId)>,
+}
+
+#[derive(Deserialize)]
+struct TemplateFile {
+    size: Vec<i32>,
+    palette: Vec<PaletteEntry>,
+    blocks: Vec<TemplateBlock>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PaletteEntry {
+    name: String,
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct TemplateBlock {
+    pos: Vec<i32>,
+    state: i32,
+}
+
+impl StructureTemplate {
+    /// Parses a gzip-compressed structure file.
+    pub fn from_gzip_reader(reader: impl Read) -> anyhow::Result<Self> {
+        let file: TemplateFile =
+            nbt::from_gzip_reader(reader).context("failed to parse structure template")?;
+
+        let size = match file.size[..] {
+            [x, y, z] => (x, y, z),
+            _ => bail!("invalid template size {:?}", file.size),
+        };
+
+        let palette = file
+            .palette
+            .iter()
+            .map(|entry| {
+                // Blocks without properties use their default state.
+                let block = if entry.properties.is_empty() {
+                    BlockId::from_identifier(&entry.name)
+                } else {
+                    BlockId::from_identifier_and_properties(&entry.name, &entry.properties)
+                };
+                block.with_context(|| format!("unknown block {} in template", entry.name))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let blocks = file
+            .blocks
+            .iter()
+            .map(|block| {
+                let pos = match block.pos[..] {
+                    [x, y, z]
+                        if (0..size.0).contains(&x)
+                            && (0..size.1).contains(&y)
+                            && (0..size.2).contains(&z) =>
+                    {
+                        (x, y, z)
+                    }
+                    _ => bail!("invalid block position {:?} in template", block.pos),
+                };
+                let state = palette
+                    .get(block.state as usize)
+                    .with_context(|| format!("invalid palette index {}", block.state))?;
+                Ok((pos, *state))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self { size, blocks })
+    }
+
+    /// Gets the size of this template along the x, y and z axes.
+    pub fn size(&self) -> (i32, i32, i32) {
+        self.size
+    }
+
+    /// Gets the size of this template after rotating it.
+    pub fn rotated_size(&self, rotation: Rotation) -> (i32, i32, i32) {
+        let (x, y, z) = self.size;
+        match rotation {
+            Rotation::None | Rotation::Clockwise180 => (x, y, z),
+            Rotation::Clockwise90 | Rotation::CounterClockwise90 => (z, y, x),
+        }
+    }
+
+    /// Places this template with its minimum corner at `origin`,
+    /// given in region coordinates. The template is rotated
+    /// around its footprint; block states are not rotated.
+    ///
+    /// Air in the template replaces the blocks in the world.
+    pub fn place(
+        &self,
+        region: &mut DecorationRegion,
+        origin: (i32, i32, i32),
+        rotation: Rotation,
+    ) {
+        for &((x, y, z), block) in &self.blocks {
+            let (x, z) = self.rotate(x, z, rotation);
+            region.set_block_at(origin.0 + x, origin.1 + y, origin.2 + z, block);
+        }
+    }
+
+    fn rotate(&self, x: i32, z: i32, rotation: Rotation) -> (i32, i32) {
+        let (size_x, _, size_z) = self.size;
+        match rotation {
+            Rotation::None => (x, z),
+            Rotation::Clockwise90 => (size_z - 1 - z, x),
+            Rotation::Clockwise180 => (size_x - 1 - x, size_z - 1 - z),
+            Rotation::CounterClockwise90 => (z, size_x - 1 - x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base::{Chunk, ChunkPosition};
+
+    const HOUSE: &[u8] = include_bytes!("../../../../assets/structures/village/house_small.nbt");
+
+    #[test]
+    fn parse_template() {
+        let template = StructureTemplate::from_gzip_reader(HOUSE).unwrap();
+        assert_eq!(template.size(), (5, 5, 5));
+        assert_eq!(template.blocks.len(), 5 * 5 * 5);
+    }
+
+    #[test]
+    fn rotated_templates_stay_in_footprint() {
+        let template = StructureTemplate::from_gzip_reader(HOUSE).unwrap();
+        for &rotation in &[
+            Rotation::None,
+            Rotation::Clockwise90,
+            Rotation::Clockwise180,
+            Rotation::CounterClockwise90,
+        ] {
+            let mut region = DecorationRegion::new(ChunkPosition::new(0, 0));
+            region.insert(Chunk::new(ChunkPosition::new(0, 0)));
+            template.place(&mut region, (4, 10, 4), rotation);
+
+            // The door is in the wall on the rotated front side.
+            let door = match rotation {
+                Rotation::None => (6, 4),
+                Rotation::Clockwise90 => (8, 6),
+                Rotation::Clockwise180 => (6, 8),
+                Rotation::CounterClockwise90 => (4, 6),
+            };
+            assert_eq!(region.block_at(door.0, 11, door.1), Some(BlockId::air()));
+            assert_eq!(region.block_at(4, 10, 4), Some(BlockId::cobblestone()));
+            assert_eq!(region.block_at(8, 14, 8), Some(BlockId::oak_planks()));
+            assert_eq!(region.block_at(9, 14, 9), Some(BlockId::air()));
+        }
+    }
+}
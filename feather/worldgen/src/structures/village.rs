@@ -0,0 +1,179 @@
+use super::{
+    ground_height, Rotation, Structure, StructurePiece, StructurePlacement, StructureTemplate,
+};
+use crate::DecorationRegion;
+use base::chunk::BoundingBox;
+use base::{Biome, ChunkPosition};
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+/// The templates villages are built from.
+struct VillageTemplates {
+    well: StructureTemplate,
+    houses: [StructureTemplate; 2],
+}
+
+static TEMPLATES: Lazy<VillageTemplates> = Lazy::new(|| {
+    let parse = |bytes: &[u8]| {
+        StructureTemplate::from_gzip_reader(bytes).expect("invalid bundled village template")
+    };
+    VillageTemplates {
+        well: parse(include_bytes!(
+            "../../../../assets/structures/village/well.nbt"
+        )),
+        houses: [
+            parse(include_bytes!(
+                "../../../../assets/structures/village/house_small.nbt"
+            )),
+            parse(include_bytes!(
+                "../../../../assets/structures/village/house_large.nbt"
+            )),
+        ],
+    }
+});
+
+/// Distance between the well and the houses around it.
+const HOUSE_DISTANCE: i32 = 12;
+
+/// A well surrounded by houses, built from templates.
+#[derive(Default)]
+pub struct Village;
+
+impl Structure for Village {
+    fn name(&self) -> &str {
+        "village"
+    }
+
+    fn placement(&self) -> StructurePlacement {
+        StructurePlacement {
+            spacing: 32,
+            separation: 8,
+            salt: 10_387_312,
+        }
+    }
+
+    fn max_radius(&self) -> i32 {
+        2
+    }
+
+    fn can_start_in(&self, biome: Biome) -> bool {
+        matches!(
+            biome,
+            Biome::Plains | Biome::Desert | Biome::Savanna | Biome::Taiga | Biome::SnowyTundra
+        )
+    }
+
+    fn pieces(&self, start: ChunkPosition, rng: &mut XorShiftRng) -> Vec<Box<dyn StructurePiece>> {
+        let templates = &*TEMPLATES;
+        let (center_x, center_z) = (start.x * 16 + 8, start.z * 16 + 8);
+
+        let mut pieces: Vec<Box<dyn StructurePiece>> = vec![Box::new(VillagePiece::centered(
+            &templates.well,
+            (center_x, center_z),
+            Rotation::None,
+        ))];
+
+        // Houses stand on the eight sides of the
+        // well, with their doors facing it.
+        let mut slots = Vec::new();
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dz) != (0, 0) {
+                    slots.push((dx, dz));
+                }
+            }
+        }
+        slots.shuffle(rng);
+
+        let count = rng.gen_range(3, slots.len() + 1);
+        for &(dx, dz) in &slots[..count] {
+            let template = templates.houses.choose(rng).unwrap();
+            let rotation = match (dx, dz) {
+                (_, 1) => Rotation::None,
+                (_, -1) => Rotation::Clockwise180,
+                (-1, _) => Rotation::Clockwise90,
+                _ => Rotation::CounterClockwise90,
+            };
+            let x = center_x + dx * HOUSE_DISTANCE + rng.gen_range(-2, 3);
+            let z = center_z + dz * HOUSE_DISTANCE + rng.gen_range(-2, 3);
+            pieces.push(Box::new(VillagePiece::centered(template, (x, z), rotation)));
+        }
+
+        pieces
+    }
+}
+
+/// A template placed on the ground. `x` and `z`
+/// are the minimum corner of the rotated template.
+struct VillagePiece {
+    template: &'static StructureTemplate,
+    x: i32,
+    z: i32,
+    rotation: Rotation,
+}
+
+impl VillagePiece {
+    fn centered(
+        template: &'static StructureTemplate,
+        center: (i32, i32),
+        rotation: Rotation,
+    ) -> Self {
+        let (size_x, _, size_z) = template.rotated_size(rotation);
+        Self {
+            template,
+            x: center.0 - size_x / 2,
+            z: center.1 - size_z / 2,
+            rotation,
+        }
+    }
+}
+
+impl StructurePiece for VillagePiece {
+    fn bounding_box(&self) -> BoundingBox {
+        // The height is only known once the
+        // piece is placed onto the terrain.
+        let (size_x, _, size_z) = self.template.rotated_size(self.rotation);
+        BoundingBox::new(
+            (self.x, 0, self.z),
+            (self.x + size_x - 1, 255, self.z + size_z - 1),
+        )
+    }
+
+    fn place(&self, region: &mut DecorationRegion, _rng: &mut XorShiftRng) {
+        let (origin_x, origin_z) = region.origin();
+        let (x, z) = (self.x - origin_x, self.z - origin_z);
+        let (size_x, size_y, size_z) = self.template.rotated_size(self.rotation);
+
+        let y = match ground_height(region, x + size_x / 2, z + size_z / 2) {
+            Some(y) if y + size_y < 256 => y,
+            _ => return,
+        };
+        self.template.place(region, (x, y, z), self.rotation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn pieces_fit_into_their_regions() {
+        for seed in 0..32 {
+            let mut rng = XorShiftRng::seed_from_u64(seed);
+            let pieces = Village.pieces(ChunkPosition::new(-3, 5), &mut rng);
+            assert!(pieces.len() >= 4);
+            for piece in &pieces {
+                let bounding_box = piece.bounding_box();
+                let (x, z) = bounding_box.center();
+                let owner = ChunkPosition::new(x.div_euclid(16), z.div_euclid(16));
+                assert!(x - bounding_box.min_x < 16 && bounding_box.max_x - x < 16);
+                assert!(z - bounding_box.min_z < 16 && bounding_box.max_z - z < 16);
+                assert!((owner.x + 3).abs() <= Village.max_radius());
+                assert!((owner.z - 5).abs() <= Village.max_radius());
+            }
+        }
+    }
+}
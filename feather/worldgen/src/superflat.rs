@@ -1,49 +1,121 @@
-use base::{anvil::level::SuperflatGeneratorOptions, Biome, BlockId, Chunk, ChunkPosition};
+use base::anvil::level::SuperflatError;
+use base::chunk::BoundingBox;
+use base::{
+    anvil::level::SuperflatGeneratorOptions, Biome, BlockId, Chunk, ChunkPosition, ChunkStatus,
+};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 
-use crate::WorldGenerator;
+use crate::{
+    ClumpedFoliageDecorator, DecorationRegion, Decorator, Dungeon, LakeDecorator, OreDecorator,
+    Structure, StructureSet, TreeDecorator, Village, WorldGenerator,
+};
 
 pub struct SuperflatWorldGenerator {
     pub options: SuperflatGeneratorOptions,
+    /// The blocks and heights of the layers, from the bottom up.
+    layers: Vec<(BlockId, u8)>,
+    biome: Biome,
+    /// The structures enabled in `options`.
+    structures: StructureSet,
+    /// The decorators enabled in `options`.
+    decorators: Vec<Box<dyn Decorator>>,
+    seed: u64,
 }
 
 impl SuperflatWorldGenerator {
-    pub fn new(options: SuperflatGeneratorOptions) -> Self {
-        Self { options }
+    /// Creates a superflat generator, validating the options up front.
+    ///
+    /// The structures in the options select what is placed
+    /// in the world: `village` and `dungeon` add those structures,
+    /// `decoration` adds the ores, trees and foliage of the biome,
+    /// and `lake` and `lava_lake` add lakes.
+    pub fn new(options: SuperflatGeneratorOptions, seed: u64) -> Result<Self, SuperflatError> {
+        options.validate()?;
+        let layers = options
+            .layers
+            .iter()
+            .filter_map(|layer| Some((layer.block_id()?, layer.height)))
+            .collect();
+        let biome = options.biome().unwrap_or(Biome::Plains);
+
+        let mut structures: Vec<Box<dyn Structure>> = Vec::new();
+        let mut decorators: Vec<Box<dyn Decorator>> = Vec::new();
+        // Sort names so that decorators run in the same
+        // order, and thus use the same seeds, every time.
+        let mut names: Vec<&String> = options.structures.keys().collect();
+        names.sort();
+        for name in names {
+            match name.as_str() {
+                "village" => structures.push(Box::new(Village::default())),
+                "dungeon" => structures.push(Box::new(Dungeon::default())),
+                "decoration" => {
+                    decorators.push(Box::new(OreDecorator::default()));
+                    decorators.push(Box::new(TreeDecorator::default()));
+                    decorators.push(Box::new(ClumpedFoliageDecorator::default()));
+                }
+                "lake" => decorators.push(Box::new(LakeDecorator::water())),
+                "lava_lake" => decorators.push(Box::new(LakeDecorator::lava())),
+                _ => log::warn!("Ignoring unsupported superflat structure {}", name),
+            }
+        }
+
+        Ok(Self {
+            options,
+            layers,
+            biome,
+            structures: StructureSet::new(structures),
+            decorators,
+            seed,
+        })
     }
 }
 
 impl WorldGenerator for SuperflatWorldGenerator {
     fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
-        let biome = Biome::from_name(self.options.biome.as_str()).unwrap_or(Biome::Plains);
-        let mut chunk = Chunk::new_with_default_biome(position, biome);
+        let mut chunk = Chunk::new_with_default_biome(position, self.biome);
 
         let mut y_counter = 0;
-        for layer in self.options.clone().layers {
-            if layer.height == 0 {
-                continue;
-            }
-            // FIXME: get rid of this hack by having a consistent naming convention - Item::name() returns `stone` but BlockId::from_identifier requires `minecraft:stone`
-            let layer_block = BlockId::from_identifier(&format!("minecraft:{}", layer.block));
-            if let Some(layer_block) = layer_block {
-                for y in y_counter..(y_counter + layer.height) {
-                    for x in 0..16 {
-                        for z in 0..16 {
-                            chunk.set_block_at(x as usize, y as usize, z as usize, layer_block);
-                        }
+        for &(block, height) in &self.layers {
+            for y in y_counter..(y_counter + height as usize) {
+                for x in 0..16 {
+                    for z in 0..16 {
+                        chunk.set_block_at(x, y, z, block);
                     }
                 }
-            } else {
-                // Skip this layer
-                log::warn!("Failed to generate layer: unknown block {}", layer.block);
             }
-
-            y_counter += layer.height;
+            y_counter += height as usize;
         }
 
         chunk.recalculate_heightmaps();
 
+        self.structures
+            .record(&mut chunk, self.seed, |_| self.biome);
+        if !self.structures.is_empty() || !self.decorators.is_empty() {
+            chunk.set_status(ChunkStatus::Proto);
+        }
+
         chunk
     }
+
+    fn decorate_chunk(&self, region: &mut DecorationRegion) {
+        self.structures.place(region, self.seed);
+
+        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
+        for decorator in &self.decorators {
+            decorator.decorate(region, seed_shuffler.gen());
+        }
+    }
+
+    fn locate_structure(
+        &self,
+        name: &str,
+        near: ChunkPosition,
+        radius: i32,
+    ) -> Option<BoundingBox> {
+        self.structures
+            .locate(name, near, radius, self.seed, |_| self.biome)
+    }
 }
 
 #[cfg(test)]
@@ -58,7 +130,7 @@ mod tests {
         };
 
         let chunk_pos = ChunkPosition { x: 1, z: 2 };
-        let generator = SuperflatWorldGenerator { options };
+        let generator = SuperflatWorldGenerator::new(options, 0).unwrap();
         let chunk = generator.generate_chunk(chunk_pos);
 
         assert_eq!(chunk.position(), chunk_pos);
@@ -82,4 +154,69 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn superflat_structures_follow_options() {
+        let generator =
+            SuperflatWorldGenerator::new(SuperflatGeneratorOptions::default(), 7).unwrap();
+        assert!(generator
+            .locate_structure("village", ChunkPosition::new(0, 0), 64)
+            .is_some());
+        assert_eq!(
+            generator.locate_structure("dungeon", ChunkPosition::new(0, 0), 64),
+            None
+        );
+
+        let options = SuperflatGeneratorOptions {
+            structures: Default::default(),
+            ..Default::default()
+        };
+        let generator = SuperflatWorldGenerator::new(options, 7).unwrap();
+        let chunk = generator.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(chunk.status(), ChunkStatus::Full);
+    }
+
+    #[test]
+    fn superflat_decoration_follows_options() {
+        let options: SuperflatGeneratorOptions =
+            "minecraft:bedrock,30*minecraft:stone,minecraft:grass_block;minecraft:forest;decoration,lake"
+                .parse()
+                .unwrap();
+        let generator = SuperflatWorldGenerator::new(options, 3).unwrap();
+
+        let center = ChunkPosition::new(0, 0);
+        let mut region = DecorationRegion::new(center);
+        for dz in -1..=1 {
+            for dx in -1..=1 {
+                let chunk = generator.generate_chunk(ChunkPosition::new(dx, dz));
+                assert_eq!(chunk.status(), ChunkStatus::Proto);
+                region.insert(chunk);
+            }
+        }
+        generator.decorate_chunk(&mut region);
+
+        // Forests are decorated with trees.
+        let mut logs = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 32..64 {
+                    let block = region.block_at(x, y, z).unwrap();
+                    if block == BlockId::oak_log() || block == BlockId::birch_log() {
+                        logs += 1;
+                    }
+                }
+            }
+        }
+        assert!(logs > 0);
+    }
+
+    #[test]
+    fn invalid_superflat_options() {
+        let mut options = SuperflatGeneratorOptions::default();
+        options.layers[1].block = "minecraft:dirtt".to_owned();
+        assert_eq!(
+            SuperflatWorldGenerator::new(options, 0).err(),
+            Some(SuperflatError::UnknownBlock("minecraft:dirtt".to_owned()))
+        );
+    }
 }
@@ -0,0 +1,142 @@
+//! Golden tests pinning the chunks generated for fixed seeds.
+//!
+//! Changes to any generation stage change the terrain of existing
+//! seeds, which leaves visible seams between chunks generated
+//! before and after the change. If such a change is intentional,
+//! regenerate the hashes in `tests/golden/chunks.txt` with
+//!
+//! ```text
+//! REGENERATE_GOLDEN=1 cargo test -p feather-worldgen --test golden_chunks
+//! ```
+//!
+//! and commit the updated file along with the change.
+
+use std::{env, fs, path::PathBuf};
+
+use base::{Chunk, ChunkPosition};
+use feather_worldgen::{generator_by_name, DecorationRegion};
+
+/// The generators, seeds and chunks whose hashes are pinned.
+const CASES: &[(&str, u64, i32, i32)] = &[
+    ("default", 0, 0, 0),
+    ("default", 8_675_309, -7, 12),
+    ("vanilla", 0, 0, 0),
+    ("vanilla", 8_675_309, 25, -40),
+    ("flat", 0, 3, -3),
+    ("nether", 0, 0, 0),
+    ("nether", 8_675_309, -12, 9),
+    ("end", 0, 0, 0),
+    ("end", 8_675_309, 70, 70),
+];
+
+const HEADER: &str = "\
+# Hashes of generated chunks, checked by tests/golden_chunks.rs.
+# Regenerate with `REGENERATE_GOLDEN=1 cargo test -p feather-worldgen --test golden_chunks`.
+# generator seed chunk_x chunk_z generated decorated
+";
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/chunks.txt")
+}
+
+/// Hashes the blocks and biomes of a chunk with 64-bit FNV-1a,
+/// which unlike `std`'s hasher is stable across Rust versions.
+fn hash_chunk(chunk: &Chunk) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+
+    for y in 0..256 {
+        for z in 0..16 {
+            for x in 0..16 {
+                let block = chunk.block_at(x, y, z).unwrap();
+                write(&block.vanilla_id().to_le_bytes());
+            }
+        }
+    }
+    for y in 0..64 {
+        for z in 0..4 {
+            for x in 0..4 {
+                write(&chunk.biomes().get(x, y, z).id().to_le_bytes());
+            }
+        }
+    }
+    hash
+}
+
+/// Computes the golden file line of a case, holding the hashes
+/// of the chunk as generated and after decorating it.
+fn golden_line(&(name, seed, x, z): &(&str, u64, i32, i32)) -> String {
+    let generator = generator_by_name(name, seed).unwrap();
+
+    let mut region = DecorationRegion::new(ChunkPosition::new(x, z));
+    let mut generated = 0;
+    for dz in -1..=1 {
+        for dx in -1..=1 {
+            let chunk = generator.generate_chunk(ChunkPosition::new(x + dx, z + dz));
+            if (dx, dz) == (0, 0) {
+                generated = hash_chunk(&chunk);
+            }
+            region.insert(chunk);
+        }
+    }
+    generator.decorate_chunk(&mut region);
+    let decorated = hash_chunk(region.center().unwrap());
+
+    format!(
+        "{} {} {} {} {:016x} {:016x}",
+        name, seed, x, z, generated, decorated
+    )
+}
+
+#[test]
+fn generated_chunks_match_golden_hashes() {
+    let actual: Vec<String> = CASES.iter().map(golden_line).collect();
+
+    if env::var_os("REGENERATE_GOLDEN").is_some() {
+        let mut contents = HEADER.to_owned();
+        for line in &actual {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        fs::create_dir_all(golden_path().parent().unwrap()).unwrap();
+        fs::write(golden_path(), contents).unwrap();
+        return;
+    }
+
+    let golden = fs::read_to_string(golden_path()).unwrap_or_else(|e| {
+        panic!(
+            "failed to read {}: {}. Run with REGENERATE_GOLDEN=1 to create it",
+            golden_path().display(),
+            e
+        )
+    });
+    let expected: Vec<&str> = golden
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .collect();
+
+    let mismatches: Vec<String> = actual
+        .iter()
+        .enumerate()
+        .filter(|&(i, line)| expected.get(i) != Some(&line.as_str()))
+        .map(|(i, line)| {
+            format!(
+                "expected {}\n     got {}",
+                expected.get(i).copied().unwrap_or("nothing"),
+                line
+            )
+        })
+        .collect();
+    assert!(
+        mismatches.is_empty() && expected.len() == actual.len(),
+        "generated chunks changed; if this is intentional, \
+         run with REGENERATE_GOLDEN=1 to update {}:\n{}",
+        golden_path().display(),
+        mismatches.join("\n")
+    );
+}
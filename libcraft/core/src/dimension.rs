@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(try_from = "String", into = "&'static str")]
 pub enum Dimension {
     Overworld,
@@ -9,7 +9,23 @@ pub enum Dimension {
     TheEnd,
 }
 
+impl Default for Dimension {
+    fn default() -> Self {
+        Self::Overworld
+    }
+}
+
 impl Dimension {
+    /// Gets the directory holding the data of this dimension,
+    /// relative to the world directory, like vanilla.
+    pub fn directory(&self) -> &'static str {
+        match self {
+            Self::Overworld => "",
+            Self::TheNether => "DIM-1",
+            Self::TheEnd => "DIM1",
+        }
+    }
+
     pub fn id(&self) -> i32 {
         match self {
             Self::Overworld => 0,
@@ -1,34 +1,57 @@
-use std::fmt::{Display, Formatter};
-use std::io::{Read, Write};
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, BufReader, BufWriter};
 use std::net::SocketAddr;
-use std::net::{TcpListener, TcpStream};
 use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
-use clap::{ArgEnum, Parser};
+use clap::{AppSettings, ArgEnum, Parser, Subcommand};
 use colored::Colorize;
 use log::{Level, LevelFilter};
 use time::macros::format_description;
 use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
 
 use feather_protocol::codec::CompressionThreshold;
 use feather_protocol::packets::client::HandshakeState;
 use feather_protocol::{
-    ClientHandshakePacket, ClientPacket, ClientPacketCodec, ProtocolState, ServerLoginPacket,
-    ServerPacket, ServerPacketCodec, VarInt,
+    ClientHandshakePacket, ClientLoginPacket, ClientPacket, ClientPacketCodec, ProtocolState,
+    ProtocolVersion, ServerLoginPacket, ServerPacket, ServerPacketCodec,
 };
 
+use mitm::{Mitm, PendingEncryption};
+use recording::{Recorder, RecordingReader};
+use replay::{ReplayOptions, ReplayTarget};
+
+mod mitm;
+mod recording;
+mod replay;
+
+type SharedRecorder = Arc<Mutex<Recorder<BufWriter<File>>>>;
+
 /// A simple proxy server that logs transmitted packets
 #[derive(Parser)]
-#[clap(about, version)]
+#[clap(about, version, setting = AppSettings::SubcommandsNegateReqs)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
     /// The Minecraft server address (ip:port)
-    #[clap(short = 'a', long)]
-    server_address: SocketAddr,
+    #[clap(short = 'a', long, required = true)]
+    server_address: Option<SocketAddr>,
     /// The address that the proxy should listen on (ip:port)
+    #[clap(short, long, required = true)]
+    proxy_address: Option<SocketAddr>,
+    /// Save all packets to a recording file
     #[clap(short, long)]
-    proxy_address: SocketAddr,
+    record: Option<PathBuf>,
+    /// Terminate encryption so that encrypted connections can be inspected.
+    /// Only works with servers that don't verify sessions
+    #[clap(long)]
+    mitm: bool,
     /// Only log clientside/serverside packets
     #[clap(arg_enum, short, long, default_value_t)]
     side: ConnectionSide,
@@ -43,6 +66,34 @@ struct Args {
     whitelist: Option<Vec<u32>>,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Replay a recording to a server as a fake client, or to a client as a fake server
+    Replay {
+        /// The recording to replay
+        file: PathBuf,
+        /// Whether to replay the client's packets to a server or the server's packets to a client
+        #[clap(arg_enum, long)]
+        to: ReplayTarget,
+        /// The server address to connect to, or the address to listen on for a client (ip:port)
+        address: SocketAddr,
+        /// The connection in the recording to replay. Defaults to the first one
+        #[clap(short, long)]
+        connection: Option<u32>,
+        /// Playback speed relative to the recording. 0 sends packets without delay
+        #[clap(long, default_value_t = 1.0)]
+        speed: f64,
+    },
+    /// Export a recording as JSON
+    Export {
+        /// The recording to export
+        file: PathBuf,
+        /// The file to write the JSON to. Defaults to stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
 fn parse_hex(src: &str) -> Result<u32, ParseIntError> {
     u32::from_str_radix(src, 16)
 }
@@ -61,7 +112,7 @@ impl Default for ConnectionSide {
 }
 
 fn main() {
-    let args: Args = Args::parse();
+    let mut args: Args = Args::parse();
     fern::Dispatch::new()
         .format(|out, message, record| {
             let level_string = match record.level() {
@@ -99,260 +150,422 @@ fn main() {
         .apply()
         .unwrap();
 
-    let listener = TcpListener::bind(args.proxy_address).unwrap();
-
-    log::info!("Listening on {}", args.proxy_address);
-    while let Ok((client, addr)) = listener.accept() {
-        log::info!("Accepting connection from {}", addr);
-
-        let server = match TcpStream::connect(args.server_address) {
-            Ok(server) => server,
-            Err(err) => {
-                log::error!(
-                    "failed to connect to server at {}: {}",
-                    args.server_address,
-                    err
-                );
-                continue;
-            }
+    let result = match args.command.take() {
+        Some(Command::Replay {
+            file,
+            to,
+            address,
+            connection,
+            speed,
+        }) => open_recording(&file).and_then(|recording| {
+            let options = ReplayOptions { connection, speed };
+            block_on(replay::replay(recording, to, address, &options))
+        }),
+        Some(Command::Export { file, output }) => export(&file, output),
+        None => block_on(run_proxy(args)),
+    };
+    if let Err(err) = result {
+        log::error!("{:?}", err);
+        std::process::exit(1);
+    }
+}
+
+fn block_on(future: impl Future<Output = anyhow::Result<()>>) -> anyhow::Result<()> {
+    tokio::runtime::Runtime::new()
+        .context("failed to start the Tokio runtime")?
+        .block_on(future)
+}
+
+fn open_recording(path: &Path) -> anyhow::Result<RecordingReader<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    RecordingReader::new(BufReader::new(file))
+}
+
+fn export(file: &Path, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let recording = open_recording(file)?;
+    match output {
+        Some(output) => {
+            let output = File::create(&output)
+                .with_context(|| format!("failed to create {}", output.display()))?;
+            recording::export_json(recording, BufWriter::new(output))
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            recording::export_json(recording, &mut stdout)
+        }
+    }
+}
+
+/// Options shared by all proxied connections.
+struct ProxyOptions {
+    server_address: SocketAddr,
+    /// Whether to log packets sent by the client.
+    log_client: bool,
+    /// Whether to log packets sent by the server.
+    log_server: bool,
+    blacklist: Option<Vec<u32>>,
+    whitelist: Option<Vec<u32>>,
+    recorder: Option<SharedRecorder>,
+    mitm: Option<Mitm>,
+}
+
+impl ProxyOptions {
+    fn logs(&self, packet_id: u32, sent_by_client: bool) -> bool {
+        let side = if sent_by_client {
+            self.log_client
+        } else {
+            self.log_server
         };
+        side && !hide(packet_id, self.blacklist.as_ref(), self.whitelist.as_ref())
+    }
+}
 
-        let connection = Arc::new(Mutex::new(Connection {
-            username: None,
-            client_codec: ClientPacketCodec::new(),
-            server_codec: ServerPacketCodec::new(),
-        }));
-
-        let client_read = client;
-        let client_write = client_read.try_clone().unwrap();
-        let server_read = server;
-        let server_write = server_read.try_clone().unwrap();
-
-        std::thread::spawn({
-            let connection = connection.clone();
-            let blacklist = args.blacklist.clone();
-            let whitelist = args.whitelist.clone();
-            move || match handle_client(
-                matches!(args.side, ConnectionSide::Both | ConnectionSide::Client),
-                blacklist,
-                whitelist,
-                &connection,
-                client_read,
-                server_write,
-            ) {
-                Ok(()) => {
-                    log::info!(
-                        "{}: client disconnected",
-                        connection
-                            .lock()
-                            .unwrap()
-                            .username
-                            .clone()
-                            .unwrap_or_default()
-                    );
-                }
-                Err(err) => {
-                    log::error!("Client error: {:?}", err);
-                }
-            }
-        });
+async fn run_proxy(args: Args) -> anyhow::Result<()> {
+    let proxy_address = args.proxy_address.expect("required argument");
 
-        std::thread::spawn({
-            let connection = connection.clone();
-            let blacklist = args.blacklist.clone();
-            let whitelist = args.whitelist.clone();
-            move || match handle_server(
-                matches!(args.side, ConnectionSide::Both | ConnectionSide::Server),
-                blacklist,
-                whitelist,
-                &connection,
-                server_read,
-                client_write,
-            ) {
-                Ok(()) => {
-                    log::info!(
-                        "{}: server disconnected",
-                        connection
-                            .lock()
-                            .unwrap()
-                            .username
-                            .clone()
-                            .unwrap_or_default()
-                    )
-                }
-                Err(err) => {
-                    log::error!("Server error: {:?}", err);
-                }
+    let recorder: Option<SharedRecorder> = match &args.record {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            log::info!("Recording packets to {}", path.display());
+            Some(Arc::new(Mutex::new(Recorder::new(BufWriter::new(file))?)))
+        }
+        None => None,
+    };
+    let mitm = if args.mitm {
+        log::info!("Terminating encryption; the server must not verify sessions");
+        Some(Mitm::new()?)
+    } else {
+        None
+    };
+
+    let options = Arc::new(ProxyOptions {
+        server_address: args.server_address.expect("required argument"),
+        log_client: matches!(args.side, ConnectionSide::Both | ConnectionSide::Client),
+        log_server: matches!(args.side, ConnectionSide::Both | ConnectionSide::Server),
+        blacklist: args.blacklist,
+        whitelist: args.whitelist,
+        recorder,
+        mitm,
+    });
+
+    let listener = TcpListener::bind(proxy_address).await?;
+    log::info!("Listening on {}", proxy_address);
+
+    let mut next_connection_id = 0;
+    loop {
+        let (client, addr) = listener.accept().await?;
+        let id = next_connection_id;
+        next_connection_id += 1;
+
+        let options = Arc::clone(&options);
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(id, addr, client, options).await {
+                log::error!("[#{}] {:?}", id, err);
             }
         });
     }
 }
 
+async fn handle_connection(
+    id: u32,
+    addr: SocketAddr,
+    client: TcpStream,
+    options: Arc<ProxyOptions>,
+) -> anyhow::Result<()> {
+    log::info!("[#{}] Accepting connection from {}", id, addr);
+    let server = TcpStream::connect(options.server_address)
+        .await
+        .with_context(|| format!("failed to connect to server at {}", options.server_address))?;
+
+    let connection = Arc::new(Mutex::new(Connection::new(id, addr)));
+    let (client_read, client_write) = client.into_split();
+    let (server_read, server_write) = server.into_split();
+
+    let mut client_task = tokio::spawn(pipe(
+        Arc::clone(&options),
+        Arc::clone(&connection),
+        client_read,
+        server_write,
+        Connection::client_bytes,
+    ));
+    let mut server_task = tokio::spawn(pipe(
+        Arc::clone(&options),
+        Arc::clone(&connection),
+        server_read,
+        client_write,
+        Connection::server_bytes,
+    ));
+
+    // Close both sides once either one disconnects.
+    let (result, side) = tokio::select! {
+        result = &mut client_task => (result, "client"),
+        result = &mut server_task => (result, "server"),
+    };
+    client_task.abort();
+    server_task.abort();
+
+    let prefix = connection.lock().unwrap().prefix();
+    match result.expect("connection task panicked") {
+        Ok(()) => {
+            log::info!("{} {} disconnected", prefix, side);
+            Ok(())
+        }
+        Err(err) => Err(err.context(format!("{} error", side))),
+    }
+}
+
+/// Decodes the bytes read from `read` with `process`
+/// and writes the result to `write`.
+async fn pipe(
+    options: Arc<ProxyOptions>,
+    connection: Arc<Mutex<Connection>>,
+    mut read: OwnedReadHalf,
+    mut write: OwnedWriteHalf,
+    process: fn(&mut Connection, &ProxyOptions, &[u8]) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<()> {
+    let mut buf = vec![0; 8192];
+    loop {
+        let read_bytes = read.read(&mut buf).await?;
+        if read_bytes == 0 {
+            return Ok(());
+        }
+
+        let output = process(
+            &mut connection.lock().unwrap(),
+            &options,
+            &buf[..read_bytes],
+        )?;
+        write.write_all(&output).await?;
+    }
+}
+
+/// The state of a proxied connection.
+///
+/// Packets are decoded as they are received and encoded
+/// again to be forwarded, since the two sides of the
+/// proxy may use different encryption keys.
 struct Connection {
+    /// Index of the connection, used in logs and recordings.
+    id: u32,
+    addr: SocketAddr,
     /// The client's username.
-    username: Option<PlayerName>,
-    client_codec: ClientPacketCodec,
-    server_codec: ServerPacketCodec,
+    username: Option<String>,
+    state: ProtocolState,
+    version: ProtocolVersion,
+
+    from_client: ClientPacketCodec,
+    to_server: ClientPacketCodec,
+    from_server: ServerPacketCodec,
+    to_client: ServerPacketCodec,
+
+    /// Set when `--mitm` intercepted an Encryption Request.
+    pending_encryption: Option<PendingEncryption>,
+    /// Set when the server requested encryption without `--mitm`.
+    /// Once the client responds, the connection can no longer be decoded.
+    encryption_requested: bool,
+    /// Whether bytes are forwarded without decoding them.
+    passthrough: bool,
 }
 
 impl Connection {
-    fn set_state(&mut self, state: ProtocolState) {
-        log::info!(
-            "{}: switching to state {:?}",
-            self.username.clone().unwrap_or_default(),
-            state
-        );
-        self.client_codec.set_state(state);
-        self.server_codec.set_state(state);
+    fn new(id: u32, addr: SocketAddr) -> Self {
+        Self {
+            id,
+            addr,
+            username: None,
+            state: ProtocolState::Handshake,
+            version: ProtocolVersion::default(),
+            from_client: ClientPacketCodec::new(),
+            to_server: ClientPacketCodec::new(),
+            from_server: ServerPacketCodec::new(),
+            to_client: ServerPacketCodec::new(),
+            pending_encryption: None,
+            encryption_requested: false,
+            passthrough: false,
+        }
     }
 
-    fn set_compression(&mut self, threshold: CompressionThreshold) {
-        self.client_codec.set_compression(threshold);
-        self.server_codec.set_compression(threshold);
+    /// Gets the prefix of log messages about this connection.
+    fn prefix(&self) -> String {
+        match &self.username {
+            Some(username) => format!("[#{} {}]", self.id, username),
+            None => format!("[#{} {}]", self.id, self.addr),
+        }
     }
-}
 
-#[derive(Clone)]
-struct PlayerName(String);
+    fn set_state(&mut self, state: ProtocolState) {
+        log::info!("{} switching to state {:?}", self.prefix(), state);
+        self.state = state;
+        self.from_client.set_state(state);
+        self.to_server.set_state(state);
+        self.from_server.set_state(state);
+        self.to_client.set_state(state);
+    }
 
-impl Default for PlayerName {
-    fn default() -> Self {
-        PlayerName("<unknown>".to_string())
+    fn set_compression(&mut self, threshold: CompressionThreshold) {
+        log::info!("{} enabling compression", self.prefix());
+        self.from_client.set_compression(threshold);
+        self.to_server.set_compression(threshold);
+        self.from_server.set_compression(threshold);
+        self.to_client.set_compression(threshold);
     }
-}
 
-impl Display for PlayerName {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+    fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        log::info!("{} using protocol version {}", self.prefix(), version);
+        self.version = version;
+        self.from_client.set_protocol_version(version);
+        self.to_server.set_protocol_version(version);
+        self.from_server.set_protocol_version(version);
+        self.to_client.set_protocol_version(version);
     }
-}
 
-fn handle_client(
-    log: bool,
-    blacklist: Option<Vec<u32>>,
-    whitelist: Option<Vec<u32>>,
-    connection: &Arc<Mutex<Connection>>,
-    mut client_read: TcpStream,
-    mut server_write: TcpStream,
-) -> anyhow::Result<()> {
-    loop {
-        let length = VarInt::read_from(&mut client_read)
-            .map(|var_int| var_int.0)
-            .unwrap_or_default();
-        if length == 0 {
-            break;
-        }
-        let mut buf = vec![0; length as usize];
-        client_read.read_exact(&mut buf)?;
-        let mut vec = Vec::new();
-        VarInt(length).write_to(&mut vec)?;
-        vec.extend(buf);
-        if vec.is_empty() {
-            break;
+    /// Processes bytes sent by the client, returning the bytes to send to the server.
+    fn client_bytes(
+        &mut self,
+        options: &ProxyOptions,
+        mut bytes: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        if self.passthrough {
+            output.extend_from_slice(bytes);
+            return Ok(output);
         }
 
-        let mut connection = connection.lock().unwrap();
-        if let Some(packet) = connection
-            .client_codec
-            .decode(&vec)
+        while let Some(packet) = self
+            .from_client
+            .decode(bytes)
             .context("failed to decode client packet")?
         {
-            if log && !hide(packet.id(), blacklist.as_ref(), whitelist.as_ref()) {
-                log::info!(
-                    "{} -> #{:02X}",
-                    connection.username.clone().unwrap_or_default(),
-                    packet.id()
-                );
-                log::debug!(
-                    "{} -> {:?}",
-                    connection.username.clone().unwrap_or_default(),
-                    packet
-                );
-                log::trace!("{}", pretty_hex::pretty_hex(&&vec));
+            bytes = &[];
+            let start = output.len();
+            let logged = options.logs(packet.id(), true);
+            if logged {
+                log::info!("{} -> #{:02X}", self.prefix(), packet.id());
+                log::debug!("{} -> {:?}", self.prefix(), packet);
+            }
+            if let Some(recorder) = &options.recorder {
+                recorder
+                    .lock()
+                    .unwrap()
+                    .record_client(self.id, self.state, &packet, self.version)
+                    .context("failed to record packet")?;
             }
 
-            // Detect state switches.
-            if let ClientPacket::Handshake(packet) = packet {
-                let state = match packet {
-                    ClientHandshakePacket::Handshake(packet) => match packet.next_state {
+            match &packet {
+                // Detect state switches.
+                ClientPacket::Handshake(ClientHandshakePacket::Handshake(handshake)) => {
+                    if let Some(version) = recording::handshake_version(&packet) {
+                        self.set_protocol_version(version);
+                    }
+                    self.set_state(match handshake.next_state {
                         HandshakeState::Login => ProtocolState::Login,
                         HandshakeState::Status => ProtocolState::Status,
-                    },
-                };
-                connection.set_state(state)
+                    });
+                }
+                ClientPacket::Login(ClientLoginPacket::EncryptionResponse(response)) => {
+                    if let (Some(pending), Some(mitm)) =
+                        (self.pending_encryption.take(), &options.mitm)
+                    {
+                        let keys = pending.intercept_response(mitm, response)?;
+                        let response = ClientPacket::Login(ClientLoginPacket::EncryptionResponse(
+                            keys.response,
+                        ));
+                        self.to_server.encode(&response, &mut output);
+                        self.from_client.enable_encryption(keys.client);
+                        self.to_client.enable_encryption(keys.client);
+                        self.from_server.enable_encryption(keys.server);
+                        self.to_server.enable_encryption(keys.server);
+                        log::info!("{} terminated encryption", self.prefix());
+                        continue;
+                    }
+                    if self.encryption_requested {
+                        self.to_server.encode(&packet, &mut output);
+                        log::warn!(
+                            "{} connection is encrypted; packets will no longer be logged. Use --mitm to inspect encrypted connections",
+                            self.prefix()
+                        );
+                        self.passthrough = true;
+                        // Bytes the client sent after its response
+                        // are already encrypted; forward them as they are.
+                        output.extend_from_slice(self.from_client.pending_bytes());
+                        return Ok(output);
+                    }
+                }
+                _ => (),
             }
 
-            drop(connection);
-            // Forward the packet to the server.
-            server_write.write_all(&vec)?;
+            self.to_server.encode(&packet, &mut output);
+            if logged {
+                log::trace!("{}", pretty_hex::pretty_hex(&&output[start..]));
+            }
         }
+        Ok(output)
     }
-    Ok(())
-}
 
-fn handle_server(
-    log: bool,
-    blacklist: Option<Vec<u32>>,
-    whitelist: Option<Vec<u32>>,
-    connection: &Arc<Mutex<Connection>>,
-    mut server_read: TcpStream,
-    mut client_write: TcpStream,
-) -> anyhow::Result<()> {
-    loop {
-        let length = VarInt::read_from(&mut server_read)
-            .map(|var_int| var_int.0)
-            .unwrap_or_default();
-        if length == 0 {
-            break;
-        }
-        let mut buf = vec![0; length as usize];
-        server_read.read_exact(&mut buf)?;
-        let mut vec = Vec::new();
-        VarInt(length).write_to(&mut vec)?;
-        vec.extend(buf);
-        if vec.is_empty() {
-            break;
+    /// Processes bytes sent by the server, returning the bytes to send to the client.
+    fn server_bytes(
+        &mut self,
+        options: &ProxyOptions,
+        mut bytes: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        if self.passthrough {
+            output.extend_from_slice(bytes);
+            return Ok(output);
         }
 
-        let mut connection = connection.lock().unwrap();
-        if let Some(packet) = connection
-            .server_codec
-            .decode(&vec)
-            .context("failed to decode client packet")?
+        while let Some(packet) = self
+            .from_server
+            .decode(bytes)
+            .context("failed to decode server packet")?
         {
-            if log && !hide(packet.id(), blacklist.as_ref(), whitelist.as_ref()) {
-                log::info!(
-                    "{} <- #{:02X}",
-                    connection.username.clone().unwrap_or_default(),
-                    packet.id()
-                );
-                log::debug!(
-                    "{} <- {:?}",
-                    connection.username.clone().unwrap_or_default(),
-                    packet
-                );
-                log::trace!("{}", pretty_hex::pretty_hex(&&vec));
+            bytes = &[];
+            let start = output.len();
+            if options.logs(packet.id(), false) {
+                log::info!("{} <- #{:02X}", self.prefix(), packet.id());
+                log::debug!("{} <- {:?}", self.prefix(), packet);
+            }
+            if let Some(recorder) = &options.recorder {
+                recorder
+                    .lock()
+                    .unwrap()
+                    .record_server(self.id, self.state, &packet, self.version)
+                    .context("failed to record packet")?;
             }
 
+            self.to_client.encode(&packet, &mut output);
+            if options.logs(packet.id(), false) {
+                log::trace!("{}", pretty_hex::pretty_hex(&&output[start..]));
+            }
             match packet {
-                // Detect state switches
+                ServerPacket::Login(ServerLoginPacket::EncryptionRequest(request)) => {
+                    if let Some(mitm) = &options.mitm {
+                        // Replace the request with one using the proxy's key.
+                        output.truncate(start);
+                        let (request, pending) = mitm.intercept_request(&request)?;
+                        self.to_client.encode(
+                            &ServerPacket::Login(ServerLoginPacket::EncryptionRequest(request)),
+                            &mut output,
+                        );
+                        self.pending_encryption = Some(pending);
+                    } else {
+                        self.encryption_requested = true;
+                    }
+                }
                 ServerPacket::Login(ServerLoginPacket::LoginSuccess(packet)) => {
-                    connection.username = Some(PlayerName(packet.username));
-                    connection.set_state(ProtocolState::Play);
+                    self.username = Some(packet.username);
+                    self.set_state(ProtocolState::Play);
                 }
-                // Detect SetCompression
                 ServerPacket::Login(ServerLoginPacket::SetCompression(packet)) => {
-                    connection.set_compression(packet.threshold as CompressionThreshold)
+                    self.set_compression(packet.threshold as CompressionThreshold)
                 }
                 _ => (),
             }
-
-            drop(connection);
-            // Forward the packet to the server.
-            client_write.write_all(&vec)?;
         }
+        Ok(output)
     }
-    Ok(())
 }
 
 fn hide(packet_id: u32, blacklist: Option<&Vec<u32>>, whitelist: Option<&Vec<u32>>) -> bool {
@@ -364,3 +577,46 @@ fn hide(packet_id: u32, blacklist: Option<&Vec<u32>>, whitelist: Option<&Vec<u32
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use feather_protocol::packets::client::EncryptionResponse;
+
+    use super::*;
+
+    #[test]
+    fn bytes_after_encryption_response_are_forwarded() {
+        let options = ProxyOptions {
+            server_address: "127.0.0.1:25565".parse().unwrap(),
+            log_client: false,
+            log_server: false,
+            blacklist: None,
+            whitelist: None,
+            recorder: None,
+            mitm: None,
+        };
+        let mut connection = Connection::new(0, "127.0.0.1:50000".parse().unwrap());
+        connection.set_state(ProtocolState::Login);
+        connection.encryption_requested = true;
+
+        let response =
+            ClientPacket::Login(ClientLoginPacket::EncryptionResponse(EncryptionResponse {
+                shared_secret: vec![1; 128].into(),
+                verify_token: vec![2; 128].into(),
+            }));
+        let mut bytes = Vec::new();
+        ClientPacketCodec::new().encode(&response, &mut bytes);
+        let response_len = bytes.len();
+        // Encrypted packets sent in the same read as the response.
+        let encrypted = [0x9f, 0x03, 0x51, 0xe2, 0x07];
+        bytes.extend_from_slice(&encrypted);
+
+        let output = connection.client_bytes(&options, &bytes).unwrap();
+        assert!(connection.passthrough);
+        assert_eq!(output, bytes);
+        assert_eq!(&output[response_len..], &encrypted);
+
+        let output = connection.client_bytes(&options, &encrypted).unwrap();
+        assert_eq!(output, encrypted);
+    }
+}
@@ -0,0 +1,114 @@
+//! Terminates encryption so that encrypted connections can be inspected.
+//!
+//! The proxy answers the server's Encryption Request with its own key,
+//! decrypts the client's shared secret, and negotiates a second shared
+//! secret with the server. This only works with servers that don't verify
+//! sessions, since the server hash the client joins with is computed from
+//! the proxy's key rather than the server's.
+
+use anyhow::{bail, Context};
+use rand::rngs::OsRng;
+use rsa::{BigUint, PaddingScheme, PublicKey, PublicKeyParts, RsaPrivateKey, RsaPublicKey};
+
+use feather_protocol::codec::CryptKey;
+use feather_protocol::packets::client::EncryptionResponse;
+use feather_protocol::packets::server::EncryptionRequest;
+
+const RSA_BITS: usize = 1024;
+
+/// The proxy's key pair.
+pub struct Mitm {
+    key: RsaPrivateKey,
+    encoded_key: Vec<u8>,
+}
+
+impl Mitm {
+    pub fn new() -> anyhow::Result<Self> {
+        let key = RsaPrivateKey::new(&mut OsRng, RSA_BITS).context("failed to create RSA key")?;
+        let encoded_key =
+            rsa_der::public_key_to_der(&key.n().to_bytes_be(), &key.e().to_bytes_be());
+        Ok(Self { key, encoded_key })
+    }
+
+    /// Creates the request sent to the client in place of the server's.
+    pub fn intercept_request(
+        &self,
+        request: &EncryptionRequest,
+    ) -> anyhow::Result<(EncryptionRequest, PendingEncryption)> {
+        let (n, e) = rsa_der::public_key_from_der(&request.public_key)
+            .map_err(|e| anyhow::anyhow!("invalid server public key: {:?}", e))?;
+        let server_key = RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e))?;
+
+        let verify_token: [u8; 16] = rand::random();
+        let client_request = EncryptionRequest {
+            server_id: request.server_id.clone(),
+            public_key: self.encoded_key.clone(),
+            verify_token: verify_token.to_vec(),
+        };
+        let pending = PendingEncryption {
+            server_key,
+            server_verify_token: request.verify_token.clone(),
+            client_verify_token: verify_token.to_vec(),
+        };
+        Ok((client_request, pending))
+    }
+}
+
+/// An Encryption Request waiting for the client's response.
+pub struct PendingEncryption {
+    server_key: RsaPublicKey,
+    server_verify_token: Vec<u8>,
+    client_verify_token: Vec<u8>,
+}
+
+/// The result of intercepting an Encryption Response.
+pub struct Keys {
+    /// The shared secret used with the client.
+    pub client: CryptKey,
+    /// The shared secret used with the server.
+    pub server: CryptKey,
+    /// The response to send to the server.
+    pub response: EncryptionResponse,
+}
+
+impl PendingEncryption {
+    /// Decrypts the client's response and creates
+    /// the response sent to the server in its place.
+    pub fn intercept_response(
+        self,
+        mitm: &Mitm,
+        response: &EncryptionResponse,
+    ) -> anyhow::Result<Keys> {
+        let client_secret = mitm
+            .key
+            .decrypt(PaddingScheme::PKCS1v15Encrypt, &response.shared_secret)?;
+        let verify_token = mitm
+            .key
+            .decrypt(PaddingScheme::PKCS1v15Encrypt, &response.verify_token)?;
+        if verify_token != self.client_verify_token {
+            bail!("client sent the wrong verify token");
+        }
+
+        let server_secret: CryptKey = rand::random();
+        let response = EncryptionResponse {
+            shared_secret: self.server_key.encrypt(
+                &mut OsRng,
+                PaddingScheme::PKCS1v15Encrypt,
+                &server_secret,
+            )?,
+            verify_token: self.server_key.encrypt(
+                &mut OsRng,
+                PaddingScheme::PKCS1v15Encrypt,
+                &self.server_verify_token,
+            )?,
+        };
+
+        Ok(Keys {
+            client: client_secret[..]
+                .try_into()
+                .context("client sent a shared secret of the wrong length")?,
+            server: server_secret,
+            response,
+        })
+    }
+}
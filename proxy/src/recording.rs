@@ -0,0 +1,434 @@
+//! Recordings of proxied connections.
+//!
+//! A recording starts with the magic bytes `FPRC` and a format
+//! version byte, followed by one record per packet:
+//! * milliseconds since the previous record (VarInt)
+//! * index of the connection the packet was sent on (VarInt)
+//! * direction in the high bit and protocol state in the low bits (u8)
+//! * length of the packet (VarInt)
+//! * packet ID and data, uncompressed and unencrypted.
+
+use std::io::{self, Cursor, ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use serde::Serialize;
+
+use feather_protocol::{
+    ClientPacket, ClientPacketCodec, ProtocolState, ProtocolVersion, ServerPacket,
+    ServerPacketCodec, VarInt,
+};
+
+const MAGIC: &[u8; 4] = b"FPRC";
+const FORMAT_VERSION: u8 = 1;
+
+const CLIENTBOUND_BIT: u8 = 0x80;
+
+/// The direction a packet was sent in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Sent by the client.
+    Serverbound,
+    /// Sent by the server.
+    Clientbound,
+}
+
+/// A recorded packet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Time since the recording started.
+    pub time: Duration,
+    pub connection: u32,
+    pub direction: Direction,
+    /// The protocol state the packet was sent in.
+    pub state: ProtocolState,
+    /// Packet ID followed by the packet data.
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    /// Decodes a packet sent by the client.
+    pub fn decode_client(&self, version: ProtocolVersion) -> anyhow::Result<ClientPacket> {
+        let mut codec = ClientPacketCodec::new();
+        codec.set_state(self.state);
+        codec.set_protocol_version(version);
+        codec
+            .decode(&frame(&self.data))?
+            .context("recorded packet is truncated")
+    }
+
+    /// Decodes a packet sent by the server.
+    pub fn decode_server(&self, version: ProtocolVersion) -> anyhow::Result<ServerPacket> {
+        let mut codec = ServerPacketCodec::new();
+        codec.set_state(self.state);
+        codec.set_protocol_version(version);
+        codec
+            .decode(&frame(&self.data))?
+            .context("recorded packet is truncated")
+    }
+}
+
+/// Writes records to a recording.
+pub struct Recorder<W> {
+    writer: W,
+    start: Instant,
+    last_time: Duration,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Creates a recorder, writing the header to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            last_time: Duration::ZERO,
+        })
+    }
+
+    /// Records a packet sent now.
+    pub fn record_client(
+        &mut self,
+        connection: u32,
+        state: ProtocolState,
+        packet: &ClientPacket,
+        version: ProtocolVersion,
+    ) -> io::Result<()> {
+        let mut codec = ClientPacketCodec::new();
+        codec.set_protocol_version(version);
+        let mut framed = Vec::new();
+        codec.encode(packet, &mut framed);
+        self.record(connection, Direction::Serverbound, state, unframe(&framed)?)
+    }
+
+    /// Records a packet sent now.
+    pub fn record_server(
+        &mut self,
+        connection: u32,
+        state: ProtocolState,
+        packet: &ServerPacket,
+        version: ProtocolVersion,
+    ) -> io::Result<()> {
+        let mut codec = ServerPacketCodec::new();
+        codec.set_protocol_version(version);
+        let mut framed = Vec::new();
+        codec.encode(packet, &mut framed);
+        self.record(connection, Direction::Clientbound, state, unframe(&framed)?)
+    }
+
+    fn record(
+        &mut self,
+        connection: u32,
+        direction: Direction,
+        state: ProtocolState,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let time = self.start.elapsed();
+        self.write(&Record {
+            time,
+            connection,
+            direction,
+            state,
+            data: data.to_vec(),
+        })
+    }
+
+    fn write(&mut self, record: &Record) -> io::Result<()> {
+        let delta = record.time.saturating_sub(self.last_time);
+        self.last_time = record.time;
+
+        let mut buf = Vec::with_capacity(record.data.len() + 16);
+        VarInt(delta.as_millis().min(i32::MAX as u128) as i32).write_to(&mut buf)?;
+        VarInt(record.connection as i32).write_to(&mut buf)?;
+        let direction = match record.direction {
+            Direction::Serverbound => 0,
+            Direction::Clientbound => CLIENTBOUND_BIT,
+        };
+        buf.push(direction | state_to_u8(record.state));
+        VarInt(record.data.len() as i32).write_to(&mut buf)?;
+        buf.extend_from_slice(&record.data);
+
+        // Flush every record so that nothing is lost
+        // when the proxy is interrupted.
+        self.writer.write_all(&buf)?;
+        self.writer.flush()
+    }
+}
+
+/// Reads the records in a recording.
+pub struct RecordingReader<R> {
+    reader: R,
+    time: Duration,
+}
+
+impl<R: Read> RecordingReader<R> {
+    /// Creates a reader, checking the header of the recording.
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut header = [0; 5];
+        reader
+            .read_exact(&mut header)
+            .context("failed to read recording header")?;
+        if &header[..4] != MAGIC {
+            bail!("not a packet recording");
+        }
+        if header[4] != FORMAT_VERSION {
+            bail!("unsupported recording format version {}", header[4]);
+        }
+        Ok(Self {
+            reader,
+            time: Duration::ZERO,
+        })
+    }
+
+    fn read_record(&mut self) -> anyhow::Result<Option<Record>> {
+        let delta = match VarInt::read_from(&mut self.reader) {
+            Ok(delta) => delta.0,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        self.time += Duration::from_millis(delta as u64);
+
+        let connection = VarInt::read_from(&mut self.reader)?.0 as u32;
+        let mut tag = [0];
+        self.reader.read_exact(&mut tag)?;
+        let direction = if tag[0] & CLIENTBOUND_BIT != 0 {
+            Direction::Clientbound
+        } else {
+            Direction::Serverbound
+        };
+        let state = state_from_u8(tag[0] & !CLIENTBOUND_BIT)
+            .with_context(|| format!("invalid protocol state {}", tag[0]))?;
+
+        let length = VarInt::read_from(&mut self.reader)?.0;
+        if length < 0 {
+            bail!("invalid packet length {}", length);
+        }
+        let mut data = vec![0; length as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(Record {
+            time: self.time,
+            connection,
+            direction,
+            state,
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = anyhow::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Tracks the protocol version of each connection in a recording,
+/// which is needed to decode its packets.
+#[derive(Default)]
+pub struct Versions {
+    versions: Vec<(u32, ProtocolVersion)>,
+}
+
+impl Versions {
+    /// Decodes a record, remembering the protocol
+    /// version if it is a handshake.
+    pub fn decode(&mut self, record: &Record) -> anyhow::Result<DecodedPacket> {
+        let version = self.get(record.connection);
+        match record.direction {
+            Direction::Serverbound => {
+                let packet = record.decode_client(version)?;
+                if let Some(version) = handshake_version(&packet) {
+                    self.versions.retain(|(c, _)| *c != record.connection);
+                    self.versions.push((record.connection, version));
+                }
+                Ok(DecodedPacket::Client(packet))
+            }
+            Direction::Clientbound => Ok(DecodedPacket::Server(record.decode_server(version)?)),
+        }
+    }
+
+    pub fn get(&self, connection: u32) -> ProtocolVersion {
+        self.versions
+            .iter()
+            .find(|(c, _)| *c == connection)
+            .map(|(_, version)| *version)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodedPacket {
+    Client(ClientPacket),
+    Server(ServerPacket),
+}
+
+impl DecodedPacket {
+    pub fn id(&self) -> u32 {
+        match self {
+            DecodedPacket::Client(packet) => packet.id(),
+            DecodedPacket::Server(packet) => packet.id(),
+        }
+    }
+}
+
+/// Gets the protocol version requested by a handshake packet, if
+/// `packet` is one and the version is supported.
+pub fn handshake_version(packet: &ClientPacket) -> Option<ProtocolVersion> {
+    match packet {
+        ClientPacket::Handshake(feather_protocol::ClientHandshakePacket::Handshake(handshake)) => {
+            ProtocolVersion::from_protocol_number(handshake.protocol_version)
+        }
+        _ => None,
+    }
+}
+
+/// A record in the JSON export of a recording.
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    time_ms: u128,
+    connection: u32,
+    direction: Direction,
+    state: String,
+    id: String,
+    /// The `Debug` representation of the decoded packet.
+    packet: Option<String>,
+    /// Packet data in hexadecimal, including the ID.
+    data: &'a str,
+}
+
+/// Writes the records in `reader` as a JSON array.
+pub fn export_json<R: Read>(reader: RecordingReader<R>, output: impl Write) -> anyhow::Result<()> {
+    let mut versions = Versions::default();
+    let mut records = Vec::new();
+    for record in reader {
+        let record = record?;
+        let packet = versions.decode(&record);
+        let id = match &packet {
+            Ok(packet) => packet.id(),
+            Err(_) => VarInt::read_from(record.data.as_slice()).map_or(0, |id| id.0 as u32),
+        };
+        let data = hex(&record.data);
+        records.push(serde_json::to_value(JsonRecord {
+            time_ms: record.time.as_millis(),
+            connection: record.connection,
+            direction: record.direction,
+            state: format!("{:?}", record.state),
+            id: format!("{:02X}", id),
+            packet: packet.ok().map(|packet| match packet {
+                DecodedPacket::Client(packet) => format!("{:?}", packet),
+                DecodedPacket::Server(packet) => format!("{:?}", packet),
+            }),
+            data: &data,
+        })?);
+    }
+    serde_json::to_writer_pretty(output, &records)?;
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Prefixes packet data with its length.
+pub fn frame(data: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(data.len() + 5);
+    VarInt(data.len() as i32)
+        .write_to(&mut framed)
+        .expect("writing to a Vec never fails");
+    framed.extend_from_slice(data);
+    framed
+}
+
+/// Strips the length prefix from an uncompressed packet.
+fn unframe(framed: &[u8]) -> io::Result<&[u8]> {
+    let mut cursor = Cursor::new(framed);
+    VarInt::read_from(&mut cursor)?;
+    Ok(&framed[cursor.position() as usize..])
+}
+
+fn state_to_u8(state: ProtocolState) -> u8 {
+    match state {
+        ProtocolState::Handshake => 0,
+        ProtocolState::Status => 1,
+        ProtocolState::Login => 2,
+        ProtocolState::Play => 3,
+    }
+}
+
+fn state_from_u8(x: u8) -> Option<ProtocolState> {
+    match x {
+        0 => Some(ProtocolState::Handshake),
+        1 => Some(ProtocolState::Status),
+        2 => Some(ProtocolState::Login),
+        3 => Some(ProtocolState::Play),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use feather_protocol::packets::client::{Handshake, HandshakeState, LoginStart};
+    use feather_protocol::{ClientHandshakePacket, ClientLoginPacket};
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let handshake = ClientPacket::Handshake(ClientHandshakePacket::Handshake(Handshake {
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
+            server_address: String::from("localhost"),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        }));
+        let login_start = ClientPacket::Login(ClientLoginPacket::LoginStart(LoginStart {
+            name: String::from("caelunshun"),
+        }));
+
+        let mut recorder = Recorder::new(Vec::new()).unwrap();
+        recorder
+            .record_client(
+                0,
+                ProtocolState::Handshake,
+                &handshake,
+                ProtocolVersion::default(),
+            )
+            .unwrap();
+        recorder
+            .record_client(
+                0,
+                ProtocolState::Login,
+                &login_start,
+                ProtocolVersion::LATEST,
+            )
+            .unwrap();
+
+        let bytes = recorder.writer;
+        let records = RecordingReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].direction, Direction::Serverbound);
+        assert_eq!(records[1].state, ProtocolState::Login);
+
+        let mut versions = Versions::default();
+        versions.decode(&records[0]).unwrap();
+        assert_eq!(versions.get(0), ProtocolVersion::LATEST);
+        match versions.decode(&records[1]).unwrap() {
+            DecodedPacket::Client(ClientPacket::Login(ClientLoginPacket::LoginStart(packet))) => {
+                assert_eq!(packet.name, "caelunshun")
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(RecordingReader::new(&b"{}"[..]).is_err());
+        assert!(RecordingReader::new(&b"FPRC\x02"[..]).is_err());
+    }
+}
@@ -0,0 +1,359 @@
+//! Replays recordings, acting either as the client or as the server.
+
+use std::io::{ErrorKind, Read};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use clap::ArgEnum;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, MutexGuard, Notify};
+use tokio::time::{self, Instant};
+
+use feather_protocol::codec::CompressionThreshold;
+use feather_protocol::packets::client::{HandshakeState, KeepAlive};
+use feather_protocol::{
+    ClientHandshakePacket, ClientPacket, ClientPacketCodec, ClientPlayPacket, ProtocolState,
+    ServerLoginPacket, ServerPacket, ServerPacketCodec, ServerPlayPacket, VarInt,
+};
+
+use crate::recording::{
+    handshake_version, DecodedPacket, Direction, Record, RecordingReader, Versions,
+};
+
+/// How long to wait for the peer to switch to the
+/// protocol state a packet was recorded in.
+const STATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The peer a recording is replayed to.
+#[derive(ArgEnum, Copy, Clone, Debug)]
+pub enum ReplayTarget {
+    /// Send the client's packets to a server.
+    Server,
+    /// Send the server's packets to a client.
+    Client,
+}
+
+pub struct ReplayOptions {
+    /// The connection in the recording to replay, or `None` for the first one.
+    pub connection: Option<u32>,
+    /// Playback speed relative to the recording.
+    /// Packets are sent without delay if this is 0.
+    pub speed: f64,
+}
+
+/// Replays a recording to the peer at (or, for clients, connecting to) `address`.
+pub async fn replay<R: Read>(
+    recording: RecordingReader<R>,
+    target: ReplayTarget,
+    address: SocketAddr,
+    options: &ReplayOptions,
+) -> anyhow::Result<()> {
+    let records = select_connection(recording, options.connection)?;
+    let (stream, direction) = match target {
+        ReplayTarget::Server => {
+            log::info!("Connecting to {}", address);
+            let stream = TcpStream::connect(address)
+                .await
+                .with_context(|| format!("failed to connect to {}", address))?;
+            (stream, Direction::Serverbound)
+        }
+        ReplayTarget::Client => {
+            let listener = TcpListener::bind(address).await?;
+            log::info!("Waiting for a client on {}", address);
+            let (stream, addr) = listener.accept().await?;
+            log::info!("Accepted connection from {}", addr);
+            (stream, Direction::Clientbound)
+        }
+    };
+
+    let (read, write) = stream.into_split();
+    let session = Arc::new(Session::new(write, direction));
+    let reader = tokio::spawn({
+        let session = Arc::clone(&session);
+        async move { session.read(read).await }
+    });
+
+    let start = Instant::now();
+    let first_time = records
+        .first()
+        .map(|record| record.time)
+        .unwrap_or_default();
+    let mut versions = Versions::default();
+    let mut sent = 0;
+    for record in &records {
+        let packet = versions
+            .decode(record)
+            .with_context(|| format!("failed to decode recorded packet at {:?}", record.time))?;
+        if record.direction != direction || is_keep_alive_response(&packet) {
+            continue;
+        }
+
+        wait_until(start, record.time - first_time, options.speed).await;
+        let mut state = session.wait_for_state(record.state).await?;
+        log::info!("Sending #{:02X}", packet.id());
+        log::debug!("{:?}", packet);
+        match packet {
+            DecodedPacket::Client(packet) => state.send_client(&packet).await?,
+            DecodedPacket::Server(packet) => state.send_server(&packet).await?,
+        }
+        drop(state);
+        session.state_changed.notify_waiters();
+        sent += 1;
+    }
+    log::info!("Replayed {} packets", sent);
+
+    match target {
+        ReplayTarget::Server => {
+            session.state.lock().await.stream.shutdown().await?;
+            reader.abort();
+        }
+        ReplayTarget::Client => log::info!("Waiting for the client to disconnect"),
+    }
+    match reader.await {
+        Ok(result) => result,
+        Err(err) if err.is_cancelled() => Ok(()),
+        Err(err) => panic!("reader panicked: {}", err),
+    }
+}
+
+/// Gets the records of one connection in a recording.
+fn select_connection<R: Read>(
+    recording: RecordingReader<R>,
+    connection: Option<u32>,
+) -> anyhow::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for record in recording {
+        let record = record?;
+        let connection = *connection.get_or_insert(record.connection);
+        if record.connection == connection {
+            records.push(record);
+        }
+    }
+    if records.is_empty() {
+        bail!("no packets to replay");
+    }
+    Ok(records)
+}
+
+/// Keepalives are answered as they arrive
+/// instead of replaying the recorded ones.
+fn is_keep_alive_response(packet: &DecodedPacket) -> bool {
+    matches!(
+        packet,
+        DecodedPacket::Client(ClientPacket::Play(ClientPlayPacket::KeepAlive(_)))
+    )
+}
+
+async fn wait_until(start: Instant, offset: Duration, speed: f64) {
+    if speed > 0.0 {
+        time::sleep_until(start + offset.div_f64(speed)).await;
+    }
+}
+
+/// A replayed connection.
+struct Session {
+    state: Mutex<SessionState>,
+    /// Notified when the protocol state changes.
+    state_changed: Notify,
+}
+
+struct SessionState {
+    stream: OwnedWriteHalf,
+    /// The direction of the packets we send.
+    direction: Direction,
+    state: ProtocolState,
+    client_codec: ClientPacketCodec,
+    server_codec: ServerPacketCodec,
+}
+
+impl Session {
+    fn new(stream: OwnedWriteHalf, direction: Direction) -> Self {
+        Self {
+            state: Mutex::new(SessionState {
+                stream,
+                direction,
+                state: ProtocolState::Handshake,
+                client_codec: ClientPacketCodec::new(),
+                server_codec: ServerPacketCodec::new(),
+            }),
+            state_changed: Notify::new(),
+        }
+    }
+
+    async fn wait_for_state(
+        &self,
+        state: ProtocolState,
+    ) -> anyhow::Result<MutexGuard<'_, SessionState>> {
+        let deadline = Instant::now() + STATE_TIMEOUT;
+        loop {
+            // Created before checking the state so that
+            // no notification is missed in between.
+            let state_changed = self.state_changed.notified();
+            let guard = self.state.lock().await;
+            if guard.state == state {
+                return Ok(guard);
+            }
+            drop(guard);
+
+            if time::timeout_at(deadline, state_changed).await.is_err() {
+                bail!(
+                    "timed out waiting for the connection to switch to state {:?}",
+                    state
+                );
+            }
+        }
+    }
+
+    /// Reads and logs packets sent by the peer until it disconnects.
+    async fn read(&self, mut stream: OwnedReadHalf) -> anyhow::Result<()> {
+        while let Some(frame) = read_frame(&mut stream).await? {
+            let mut state = self.state.lock().await;
+            match state.direction {
+                Direction::Serverbound => {
+                    if let Some(packet) = state.server_codec.decode(&frame)? {
+                        log::info!("Received #{:02X}", packet.id());
+                        log::debug!("{:?}", packet);
+                        state.on_server_packet(&packet).await?;
+                    }
+                }
+                Direction::Clientbound => {
+                    if let Some(packet) = state.client_codec.decode(&frame)? {
+                        log::info!("Received #{:02X}", packet.id());
+                        log::debug!("{:?}", packet);
+                        state.on_client_packet(&packet);
+                    }
+                }
+            }
+            drop(state);
+            self.state_changed.notify_waiters();
+        }
+        log::info!("Connection closed");
+        Ok(())
+    }
+}
+
+impl SessionState {
+    async fn send_client(&mut self, packet: &ClientPacket) -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        self.client_codec.encode(packet, &mut buf);
+        self.stream.write_all(&buf).await?;
+        self.on_client_packet(packet);
+        Ok(())
+    }
+
+    async fn send_server(&mut self, packet: &ServerPacket) -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        self.server_codec.encode(packet, &mut buf);
+        self.stream.write_all(&buf).await?;
+        self.on_server_packet(packet).await
+    }
+
+    /// Detects state switches caused by a packet sent by the client.
+    fn on_client_packet(&mut self, packet: &ClientPacket) {
+        if let ClientPacket::Handshake(ClientHandshakePacket::Handshake(handshake)) = packet {
+            let version = handshake_version(packet).unwrap_or_default();
+            self.client_codec.set_protocol_version(version);
+            self.server_codec.set_protocol_version(version);
+            self.set_state(match handshake.next_state {
+                HandshakeState::Login => ProtocolState::Login,
+                HandshakeState::Status => ProtocolState::Status,
+            });
+        }
+    }
+
+    /// Detects state switches and compression caused by a packet
+    /// sent by the server. Answers keepalives when acting as the client.
+    async fn on_server_packet(&mut self, packet: &ServerPacket) -> anyhow::Result<()> {
+        match packet {
+            ServerPacket::Login(ServerLoginPacket::LoginSuccess(_)) => {
+                self.set_state(ProtocolState::Play)
+            }
+            ServerPacket::Login(ServerLoginPacket::SetCompression(packet)) => {
+                let threshold = packet.threshold as CompressionThreshold;
+                self.client_codec.set_compression(threshold);
+                self.server_codec.set_compression(threshold);
+            }
+            ServerPacket::Play(ServerPlayPacket::KeepAlive(keep_alive)) => {
+                if self.direction == Direction::Serverbound {
+                    self.send_client(&ClientPacket::Play(ClientPlayPacket::KeepAlive(
+                        KeepAlive {
+                            id: keep_alive.id as u64,
+                        },
+                    )))
+                    .await?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn set_state(&mut self, state: ProtocolState) {
+        log::info!("Switching to state {:?}", state);
+        self.state = state;
+        self.client_codec.set_state(state);
+        self.server_codec.set_state(state);
+    }
+}
+
+/// Reads a length-prefixed packet, or `None` if the
+/// peer disconnected between two packets.
+async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> anyhow::Result<Option<Vec<u8>>> {
+    // The length is a VarInt, which is at most 5 bytes long.
+    let mut frame = Vec::new();
+    loop {
+        let byte = match stream.read_u8().await {
+            Ok(byte) => byte,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof && frame.is_empty() => {
+                return Ok(None)
+            }
+            Err(err) => return Err(err).context("failed to read the packet length"),
+        };
+        frame.push(byte);
+        if byte & 0b1000_0000 == 0 {
+            break;
+        }
+        ensure!(frame.len() < 5, "packet length is longer than 5 bytes");
+    }
+
+    let length = VarInt::read_from(frame.as_slice())?.0;
+    ensure!(length > 0, "invalid packet length {}", length);
+    let start = frame.len();
+    frame.resize(start + length as usize, 0);
+    stream
+        .read_exact(&mut frame[start..])
+        .await
+        .context("failed to read a packet")?;
+    Ok(Some(frame))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn frames_are_read_whole() {
+        let mut bytes = &[3, 0, 1, 2, 1, 7][..];
+        assert_eq!(
+            read_frame(&mut bytes).await.unwrap(),
+            Some(vec![3, 0, 1, 2])
+        );
+        assert_eq!(read_frame(&mut bytes).await.unwrap(), Some(vec![1, 7]));
+        assert_eq!(read_frame(&mut bytes).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn malformed_lengths_are_errors() {
+        // Cut off in the middle of the length.
+        assert!(read_frame(&mut &[0x80][..]).await.is_err());
+        // A length longer than 5 bytes.
+        assert!(read_frame(&mut &[0xFF; 6][..]).await.is_err());
+        assert!(read_frame(&mut &[0][..]).await.is_err());
+        // Cut off in the middle of the packet.
+        assert!(read_frame(&mut &[2, 0][..]).await.is_err());
+    }
+}
@@ -97,6 +97,14 @@ pub fn plugin(_attr: TokenStream, mut item: TokenStream) -> TokenStream {
             system(plugin, &mut ::quill::Game::new());
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_drop_task(data: *mut u8) {
+            drop(Box::from_raw(
+                data.cast::<Box<dyn FnMut(&mut #name, &mut ::quill::Game)>>(),
+            ));
+        }
+
         /// Never called by Quill, but this is needed
         /// to avoid linker errors with WASI.
         #[doc(hidden)]
@@ -0,0 +1,164 @@
+//! The whitelist, ban lists and operator list.
+
+use std::{net::IpAddr, ptr};
+
+use quill_common::{access::AccessListKind, Pointer, PointerMut};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+pub use quill_common::access::{
+    date, AccessUpdate, IpBan, OffsetDateTime, OpEntry, PlayerBan, WhitelistEntry,
+    DEFAULT_BAN_REASON, DEFAULT_BAN_SOURCE, DEFAULT_OP_LEVEL,
+};
+
+/// Reads and modifies the server's whitelist, ban lists
+/// and operator list.
+///
+/// Obtain one through [`Game::access_lists`](crate::Game::access_lists).
+/// Changes are saved to the vanilla JSON files immediately.
+/// Players who are banned or removed from an enforced
+/// whitelist are disconnected on the next tick.
+#[derive(Debug, Copy, Clone)]
+pub struct AccessLists {
+    _priv: (),
+}
+
+impl AccessLists {
+    pub(crate) fn new() -> Self {
+        Self { _priv: () }
+    }
+
+    /// Reloads all lists from disk. Returns `false`
+    /// if a file could not be read.
+    pub fn reload(&self) -> bool {
+        unsafe { quill_sys::access_reload() }
+    }
+
+    /// Determines whether only whitelisted players
+    /// and operators may join.
+    pub fn is_whitelist_enabled(&self) -> bool {
+        unsafe { quill_sys::access_whitelist_enabled() }
+    }
+
+    /// Enables or disables the whitelist until the server restarts.
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        update(&AccessUpdate::SetWhitelistEnabled(enabled));
+    }
+
+    pub fn whitelist(&self) -> Vec<WhitelistEntry> {
+        get(AccessListKind::Whitelist)
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.whitelist().iter().any(|entry| entry.uuid == uuid)
+    }
+
+    /// Adds a player to the whitelist. Returns `false`
+    /// if they were already whitelisted.
+    pub fn add_to_whitelist(&self, uuid: Uuid, name: impl Into<String>) -> bool {
+        update(&AccessUpdate::AddToWhitelist(WhitelistEntry {
+            uuid,
+            name: name.into(),
+        }))
+    }
+
+    /// Removes a player from the whitelist. Returns `false`
+    /// if they were not whitelisted.
+    pub fn remove_from_whitelist(&self, uuid: Uuid) -> bool {
+        update(&AccessUpdate::RemoveFromWhitelist(uuid))
+    }
+
+    pub fn ops(&self) -> Vec<OpEntry> {
+        get(AccessListKind::Ops)
+    }
+
+    /// Gets the player's operator entry if they are an operator.
+    pub fn op(&self, uuid: Uuid) -> Option<OpEntry> {
+        self.ops().into_iter().find(|op| op.uuid == uuid)
+    }
+
+    /// Makes a player an operator, replacing
+    /// their existing entry if there is one.
+    pub fn add_op(&self, entry: OpEntry) {
+        update(&AccessUpdate::AddOp(entry));
+    }
+
+    /// Removes a player's operator status. Returns `false`
+    /// if they were not an operator.
+    pub fn remove_op(&self, uuid: Uuid) -> bool {
+        update(&AccessUpdate::RemoveOp(uuid))
+    }
+
+    /// Gets all bans that have not expired.
+    pub fn banned_players(&self) -> Vec<PlayerBan> {
+        get(AccessListKind::BannedPlayers)
+    }
+
+    /// Gets the player's ban if they are banned.
+    pub fn player_ban(&self, uuid: Uuid) -> Option<PlayerBan> {
+        self.banned_players()
+            .into_iter()
+            .find(|ban| ban.uuid == uuid)
+    }
+
+    /// Bans a player, replacing their existing ban if there is one.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use quill::{access::PlayerBan, Game, Uuid};
+    ///
+    /// fn ban(game: &Game, uuid: Uuid) {
+    ///     let mut ban = PlayerBan::new(uuid, "Notch");
+    ///     ban.reason = String::from("Griefing");
+    ///     game.access_lists().ban_player(ban);
+    /// }
+    /// ```
+    pub fn ban_player(&self, ban: PlayerBan) {
+        update(&AccessUpdate::BanPlayer(ban));
+    }
+
+    /// Removes a player's ban. Returns `false` if they were not banned.
+    pub fn pardon_player(&self, uuid: Uuid) -> bool {
+        update(&AccessUpdate::PardonPlayer(uuid))
+    }
+
+    /// Gets all IP bans that have not expired.
+    pub fn banned_ips(&self) -> Vec<IpBan> {
+        get(AccessListKind::BannedIps)
+    }
+
+    /// Gets the ban of an IP address if it is banned.
+    pub fn ip_ban(&self, ip: IpAddr) -> Option<IpBan> {
+        self.banned_ips().into_iter().find(|ban| ban.ip == ip)
+    }
+
+    /// Bans an IP address, replacing its existing ban if there is one.
+    pub fn ban_ip(&self, ban: IpBan) {
+        update(&AccessUpdate::BanIp(ban));
+    }
+
+    /// Removes the ban of an IP address. Returns `false`
+    /// if it was not banned.
+    pub fn pardon_ip(&self, ip: IpAddr) -> bool {
+        update(&AccessUpdate::PardonIp(ip))
+    }
+}
+
+fn get<T: DeserializeOwned>(list: AccessListKind) -> Vec<T> {
+    let mut bytes_ptr = Pointer::new(ptr::null());
+    let mut bytes_len = 0u32;
+    unsafe {
+        quill_sys::access_get(
+            list as u32,
+            PointerMut::new(&mut bytes_ptr),
+            PointerMut::new(&mut bytes_len),
+        );
+        let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+        bincode::deserialize(bytes).expect("host gave malformed access list")
+    }
+}
+
+fn update(update: &AccessUpdate) -> bool {
+    let bytes = bincode::serialize(update).expect("failed to serialize access update");
+    unsafe { quill_sys::access_update(bytes.as_ptr().into(), bytes.len() as u32) }
+}
@@ -0,0 +1,132 @@
+//! Bulk access to chunk data.
+//!
+//! Reading or writing blocks one at a time through
+//! [`Game::block`](crate::Game::block) costs one host call per block.
+//! The types in this module transfer a whole chunk section
+//! or chunk column at once.
+
+use std::ptr;
+
+use libcraft_core::Biome;
+use quill_common::{Pointer, PointerMut};
+use serde::de::DeserializeOwned;
+
+pub use quill_common::chunk::{
+    ChunkSectionData, HeightmapKind, SectionLight, BIOMES_PER_CHUNK, NUM_SECTIONS, SECTION_VOLUME,
+    SECTION_WIDTH,
+};
+
+/// The biomes of a chunk.
+///
+/// Biomes are sampled in cells of 4x4x4 blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkBiomes {
+    biomes: Vec<Biome>,
+}
+
+impl ChunkBiomes {
+    pub(crate) fn from_ids(ids: &[u32]) -> Self {
+        let biomes = ids
+            .iter()
+            .map(|&id| Biome::from_id(id).expect("host gave invalid biome ID"))
+            .collect();
+        Self { biomes }
+    }
+
+    /// Gets the biome at the given block coordinates
+    /// relative to the chunk.
+    ///
+    /// Returns `None` if the coordinates are out of bounds.
+    pub fn biome_at(&self, x: usize, y: usize, z: usize) -> Option<Biome> {
+        if x >= 16 || z >= 16 {
+            return None;
+        }
+        let index = ((y / 4) << 4) | ((z / 4) << 2) | (x / 4);
+        self.biomes.get(index).copied()
+    }
+
+    /// Gets all biome samples in YZX order.
+    pub fn as_slice(&self) -> &[Biome] {
+        &self.biomes
+    }
+}
+
+/// A heightmap of a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    heights: Vec<u16>,
+}
+
+impl Heightmap {
+    /// Gets the height at the given column relative to the chunk.
+    ///
+    /// This is one above the Y coordinate of the highest
+    /// block matching the heightmap's criteria, or zero
+    /// if there is no such block.
+    ///
+    /// Returns `None` if the coordinates are out of bounds.
+    pub fn height(&self, x: usize, z: usize) -> Option<u16> {
+        if x >= 16 || z >= 16 {
+            return None;
+        }
+        self.heights.get((z << 4) | x).copied()
+    }
+}
+
+impl From<Vec<u16>> for Heightmap {
+    fn from(heights: Vec<u16>) -> Self {
+        Self { heights }
+    }
+}
+
+/// Invokes a host call that writes a `bincode`-serialized
+/// buffer and returns whether it succeeded.
+///
+/// # Safety
+/// `host_call` must write a valid pointer to a buffer
+/// of the written length whenever it returns `true`.
+pub(crate) unsafe fn read_from_host<T: DeserializeOwned>(
+    host_call: impl FnOnce(PointerMut<Pointer<u8>>, PointerMut<u32>) -> bool,
+) -> Option<T> {
+    let mut bytes_ptr = Pointer::new(ptr::null());
+    let mut bytes_len = 0u32;
+    if !host_call(
+        PointerMut::new(&mut bytes_ptr),
+        PointerMut::new(&mut bytes_len),
+    ) {
+        return None;
+    }
+
+    let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+    Some(bincode::deserialize(bytes).expect("host gave malformed chunk data"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biome_at() {
+        let mut ids = vec![Biome::Plains.id(); BIOMES_PER_CHUNK];
+        // Cell (1, 2, 3)
+        ids[(2 << 4) | (3 << 2) | 1] = Biome::Desert.id();
+        let biomes = ChunkBiomes::from_ids(&ids);
+
+        assert_eq!(biomes.biome_at(4, 8, 12), Some(Biome::Desert));
+        assert_eq!(biomes.biome_at(7, 11, 15), Some(Biome::Desert));
+        assert_eq!(biomes.biome_at(3, 8, 12), Some(Biome::Plains));
+        assert_eq!(biomes.biome_at(16, 0, 0), None);
+        assert_eq!(biomes.biome_at(0, 256, 0), None);
+    }
+
+    #[test]
+    fn heightmap_height() {
+        let mut heights = vec![0u16; 256];
+        heights[(5 << 4) | 2] = 64;
+        let heightmap = Heightmap::from(heights);
+
+        assert_eq!(heightmap.height(2, 5), Some(64));
+        assert_eq!(heightmap.height(5, 2), Some(0));
+        assert_eq!(heightmap.height(0, 16), None);
+    }
+}
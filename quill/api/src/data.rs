@@ -0,0 +1,357 @@
+//! Access to the plugin's data directory.
+//!
+//! Every plugin gets its own data directory at
+//! `plugins/<identifier>/` on the server. WebAssembly plugins
+//! see this directory as their filesystem root and
+//! cannot access any other files.
+//!
+//! This module provides helpers to load typed configuration
+//! files and to persist simple key-value data there.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+    ptr,
+};
+
+use quill_common::{Pointer, PointerMut};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Error returned when loading or saving plugin data fails.
+#[derive(Debug, thiserror::Error)]
+pub enum DataError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed TOML: {0}")]
+    DeserializeToml(#[from] toml::de::Error),
+    #[error("failed to serialize TOML: {0}")]
+    SerializeToml(#[from] toml::ser::Error),
+    #[error("malformed JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported file format for '{0}' (expected a .toml or .json file)")]
+    UnsupportedFormat(String),
+    #[error("'{0}' is not a relative path inside the data directory")]
+    InvalidPath(String),
+}
+
+/// Gets the path to the plugin's data directory.
+///
+/// On WebAssembly, this is always `/`.
+pub fn data_dir() -> PathBuf {
+    unsafe {
+        let mut path_ptr = Pointer::new(ptr::null());
+        let mut path_len = 0u32;
+        quill_sys::plugin_data_dir(
+            PointerMut::new(&mut path_ptr),
+            PointerMut::new(&mut path_len),
+        );
+
+        let bytes = std::slice::from_raw_parts(path_ptr.as_ptr(), path_len as usize);
+        PathBuf::from(std::str::from_utf8(bytes).expect("host gave invalid UTF-8 path"))
+    }
+}
+
+/// Joins `file_name` onto `dir`, refusing absolute paths
+/// and paths containing `..` which could escape `dir`.
+fn path_in(dir: &Path, file_name: &str) -> Result<PathBuf, DataError> {
+    let mut path = dir.to_path_buf();
+    for component in Path::new(file_name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => (),
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(DataError::InvalidPath(file_name.to_owned()))
+            }
+        }
+    }
+    if path == dir {
+        return Err(DataError::InvalidPath(file_name.to_owned()));
+    }
+    Ok(path)
+}
+
+/// Loads a configuration file from the plugin's data directory.
+///
+/// The file format is chosen based on the extension of `file_name`,
+/// which must be either `.toml` or `.json`. `file_name` must
+/// be relative and may not contain `..`.
+///
+/// If the file does not exist, it is created with the
+/// default value of `T`, and the default value is returned.
+/// To fill in missing fields of an existing file with defaults,
+/// annotate your config struct with `#[serde(default)]`.
+///
+/// # Example
+/// ```no_run
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// #[serde(default)]
+/// struct Config {
+///     max_homes: u32,
+/// }
+///
+/// let config: Config = quill::data::load_config("config.toml").unwrap();
+/// ```
+pub fn load_config<T>(file_name: &str) -> Result<T, DataError>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    load_config_at(&path_in(&data_dir(), file_name)?)
+}
+
+fn load_config_at<T>(path: &Path) -> Result<T, DataError>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let format = Format::from_path(path)?;
+    if !path.exists() {
+        let config = T::default();
+        fs::write(path, format.serialize(&config)?)?;
+        return Ok(config);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    format.deserialize(&contents)
+}
+
+/// A file format for configuration files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Result<Self, DataError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(Format::Toml),
+            Some("json") => Ok(Format::Json),
+            _ => Err(DataError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    fn serialize<T: Serialize>(self, value: &T) -> Result<String, DataError> {
+        match self {
+            Format::Toml => Ok(toml::to_string_pretty(value)?),
+            Format::Json => Ok(serde_json::to_string_pretty(value)?),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(self, s: &str) -> Result<T, DataError> {
+        match self {
+            Format::Toml => Ok(toml::from_str(s)?),
+            Format::Json => Ok(serde_json::from_str(s)?),
+        }
+    }
+}
+
+/// A persistent key-value store in the plugin's data directory.
+///
+/// Values can be any type implementing `Serialize` and `Deserialize`.
+/// The store is kept in memory and written to
+/// `<name>.json` when [`Storage::save`] is called.
+///
+/// # Example
+/// ```no_run
+/// use quill::data::Storage;
+///
+/// let mut homes = Storage::open("homes").unwrap();
+/// homes.set("caelunshun", &[0.0f64, 64.0, 0.0]).unwrap();
+/// homes.save().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Storage {
+    path: PathBuf,
+    entries: BTreeMap<String, serde_json::Value>,
+    dirty: bool,
+}
+
+impl Storage {
+    /// Opens the store with the given name, loading
+    /// any existing values.
+    ///
+    /// The store is created if it does not exist yet.
+    /// Like file names passed to [`load_config`], `name`
+    /// must be relative and may not contain `..`.
+    pub fn open(name: &str) -> Result<Self, DataError> {
+        Self::open_at(path_in(&data_dir(), &format!("{}.json", name))?)
+    }
+
+    fn open_at(path: PathBuf) -> Result<Self, DataError> {
+        let entries = if path.exists() {
+            serde_json::from_slice(&fs::read(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path,
+            entries,
+            dirty: false,
+        })
+    }
+
+    /// Gets the value stored under `key`.
+    ///
+    /// Returns `Ok(None)` if there is no such value
+    /// and an error if the value has a different type.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, DataError> {
+        match self.entries.get(key) {
+            Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores `value` under `key`, overwriting any
+    /// previous value.
+    pub fn set<T: Serialize + ?Sized>(
+        &mut self,
+        key: impl Into<String>,
+        value: &T,
+    ) -> Result<(), DataError> {
+        let value = serde_json::to_value(value)?;
+        self.entries.insert(key.into(), value);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Removes the value stored under `key`.
+    ///
+    /// Returns whether a value was removed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let removed = self.entries.remove(key).is_some();
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Determines whether a value is stored under `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns an iterator over all keys in the store.
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Writes the store to disk if it has been modified.
+    ///
+    /// The file is replaced atomically, so a crash while
+    /// saving does not corrupt existing data.
+    pub fn save(&mut self) -> Result<(), DataError> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(&self.entries)?)?;
+        fs::rename(&temp_path, &self.path)?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct TestConfig {
+        name: String,
+        count: u32,
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("quill-data-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn paths_stay_in_data_dir() {
+        let dir = Path::new("plugins/homes");
+        assert_eq!(
+            path_in(dir, "config.toml").unwrap(),
+            dir.join("config.toml")
+        );
+        assert_eq!(
+            path_in(dir, "./players/caelunshun.json").unwrap(),
+            dir.join("players/caelunshun.json")
+        );
+        for file_name in &[
+            "../server.properties",
+            "a/../../b.json",
+            "/etc/passwd",
+            "",
+            ".",
+        ] {
+            assert!(
+                matches!(path_in(dir, file_name), Err(DataError::InvalidPath(_))),
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn load_config_writes_defaults() {
+        let dir = temp_dir("defaults");
+        let path = dir.join("config.toml");
+        let _ = fs::remove_file(&path);
+
+        let config: TestConfig = load_config_at(&path).unwrap();
+        assert_eq!(config, TestConfig::default());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn load_config_fills_missing_fields() {
+        let dir = temp_dir("missing");
+        let path = dir.join("config.json");
+        fs::write(&path, r#"{ "count": 5 }"#).unwrap();
+
+        let config: TestConfig = load_config_at(&path).unwrap();
+        assert_eq!(
+            config,
+            TestConfig {
+                name: String::new(),
+                count: 5
+            }
+        );
+    }
+
+    #[test]
+    fn load_config_unsupported_format() {
+        let dir = temp_dir("unsupported");
+        assert!(matches!(
+            load_config_at::<TestConfig>(&dir.join("config.yml")),
+            Err(DataError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn storage_round_trip() {
+        let dir = temp_dir("storage");
+        let path = dir.join("homes.json");
+        let _ = fs::remove_file(&path);
+
+        let mut storage = Storage::open_at(path.clone()).unwrap();
+        storage.set("spawn", &[1, 2, 3]).unwrap();
+        storage.set("count", &10u32).unwrap();
+        assert!(storage.remove("count"));
+        storage.save().unwrap();
+
+        let storage = Storage::open_at(path).unwrap();
+        assert_eq!(
+            storage.get::<Vec<i32>>("spawn").unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(storage.get::<u32>("count").unwrap(), None);
+        assert!(storage.get::<String>("spawn").is_err());
+    }
+}
@@ -1,7 +1,8 @@
 use libcraft_text::Text;
 use std::{marker::PhantomData, ptr};
 
-use quill_common::{Component, Pointer, PointerMut};
+use libcraft_core::Position;
+use quill_common::{components::WorldId, Component, Pointer, PointerMut};
 
 /// Unique internal ID of an entity.
 ///
@@ -118,6 +119,18 @@ impl Entity {
         }
     }
 
+    /// Determines whether this entity has a permission node,
+    /// such as `feather.build`.
+    ///
+    /// Nodes can contain wildcards, e.g. granting `myplugin.*`
+    /// grants `myplugin.home.set`. Entities other than players
+    /// have no permissions.
+    pub fn has_permission(&self, node: &str) -> bool {
+        unsafe {
+            quill_sys::entity_has_permission(self.id.0, node.as_ptr().into(), node.len() as u32)
+        }
+    }
+
     /// Sends the given title to this entity.
     pub fn send_title(&self, title: &libcraft_text::Title) {
         let title = serde_json::to_string(title).expect("failed to serialize Title");
@@ -136,6 +149,14 @@ impl Entity {
         self.send_title(&libcraft_text::title::Title::RESET)
     }
 
+    /// Moves this entity to `position` in another world.
+    ///
+    /// Returns `false` if the world is not loaded.
+    pub fn teleport_to_world(&self, world: WorldId, position: Position) -> bool {
+        let position: &[u8] = bytemuck::cast_slice(std::slice::from_ref(&position));
+        unsafe { quill_sys::entity_teleport_to_world(self.id.0, world.0, position.as_ptr().into()) }
+    }
+
     /// Gets the unique ID of this entity.
     pub fn id(&self) -> EntityId {
         self.id
@@ -7,7 +7,10 @@ use quill_common::entity_init::EntityInit;
 use quill_common::Component;
 
 use crate::{
+    access::AccessLists,
+    chunk::{self, ChunkBiomes, ChunkSectionData, Heightmap, HeightmapKind, SectionLight},
     query::{Query, QueryIter},
+    world::{self, WorldError, WorldId, WorldSettings},
     EntityBuilder,
 };
 use crate::{Entity, EntityId};
@@ -205,6 +208,184 @@ impl Game {
         }
     }
 
+    /// Determines whether the chunk at `chunk` is loaded.
+    pub fn is_chunk_loaded(&self, chunk: ChunkPosition) -> bool {
+        unsafe { quill_sys::chunk_is_loaded(chunk.x, chunk.z) }
+    }
+
+    /// Gets the positions of all loaded chunks.
+    pub fn loaded_chunks(&self) -> Vec<ChunkPosition> {
+        unsafe {
+            chunk::read_from_host(|ptr, len| {
+                quill_sys::chunk_loaded_list(ptr, len);
+                true
+            })
+        }
+        .unwrap_or_default()
+    }
+
+    /// Gets all blocks in the given chunk section (16x16x16 blocks)
+    /// with a single host call.
+    ///
+    /// This function returns an error if the
+    /// chunk is not loaded. Unlike in Bukkit, calling this method
+    /// will not cause chunks to be loaded.
+    pub fn chunk_section(
+        &self,
+        chunk: ChunkPosition,
+        section_y: u32,
+    ) -> Result<ChunkSectionData, BlockAccessError> {
+        check_section_y(section_y)?;
+        unsafe {
+            chunk::read_from_host(|ptr, len| {
+                quill_sys::chunk_section_get(chunk.x, section_y, chunk.z, ptr, len)
+            })
+        }
+        .ok_or(BlockAccessError::ChunkNotLoaded)
+    }
+
+    /// Efficiently overwrites all blocks in the given chunk section (16x16x16 blocks).
+    ///
+    /// This function returns an error if the
+    /// chunk is not loaded. Unlike in Bukkit, calling this method
+    /// will not cause chunks to be loaded.
+    ///
+    /// The host rejects sections containing invalid block
+    /// state IDs, which traps the plugin.
+    pub fn set_chunk_section(
+        &self,
+        chunk: ChunkPosition,
+        section_y: u32,
+        section: &ChunkSectionData,
+    ) -> Result<(), BlockAccessError> {
+        check_section_y(section_y)?;
+
+        let bytes = bincode::serialize(section).expect("failed to serialize ChunkSectionData");
+        let was_successful = unsafe {
+            quill_sys::chunk_section_set(
+                chunk.x,
+                section_y,
+                chunk.z,
+                bytes.as_ptr().into(),
+                bytes.len() as u32,
+            )
+        };
+
+        if was_successful {
+            Ok(())
+        } else {
+            Err(BlockAccessError::ChunkNotLoaded)
+        }
+    }
+
+    /// Gets the block and sky light levels of
+    /// the given chunk section.
+    ///
+    /// This function returns an error if the
+    /// chunk is not loaded.
+    pub fn section_light(
+        &self,
+        chunk: ChunkPosition,
+        section_y: u32,
+    ) -> Result<SectionLight, BlockAccessError> {
+        check_section_y(section_y)?;
+        unsafe {
+            chunk::read_from_host(|ptr, len| {
+                quill_sys::chunk_section_light(chunk.x, section_y, chunk.z, ptr, len)
+            })
+        }
+        .ok_or(BlockAccessError::ChunkNotLoaded)
+    }
+
+    /// Gets the biomes of the given chunk.
+    ///
+    /// This function returns an error if the
+    /// chunk is not loaded.
+    pub fn chunk_biomes(&self, chunk: ChunkPosition) -> Result<ChunkBiomes, BlockAccessError> {
+        let ids: Vec<u32> = unsafe {
+            chunk::read_from_host(|ptr, len| quill_sys::chunk_biomes(chunk.x, chunk.z, ptr, len))
+        }
+        .ok_or(BlockAccessError::ChunkNotLoaded)?;
+        Ok(ChunkBiomes::from_ids(&ids))
+    }
+
+    /// Gets a heightmap of the given chunk.
+    ///
+    /// This function returns an error if the
+    /// chunk is not loaded.
+    pub fn heightmap(
+        &self,
+        chunk: ChunkPosition,
+        kind: HeightmapKind,
+    ) -> Result<Heightmap, BlockAccessError> {
+        let heights: Vec<u16> = unsafe {
+            chunk::read_from_host(|ptr, len| {
+                quill_sys::chunk_heightmap(chunk.x, chunk.z, kind.to_u32(), ptr, len)
+            })
+        }
+        .ok_or(BlockAccessError::ChunkNotLoaded)?;
+        Ok(Heightmap::from(heights))
+    }
+
+    /// Keeps the chunk at `chunk` loaded until [`Game::release_chunk`]
+    /// is called. If the chunk is not loaded, it is queued for
+    /// loading and becomes available within a few ticks.
+    ///
+    /// Returns `false` if this plugin already forces
+    /// the chunk to stay loaded.
+    pub fn force_load_chunk(&self, chunk: ChunkPosition) -> bool {
+        unsafe { quill_sys::chunk_force_load(chunk.x, chunk.z) }
+    }
+
+    /// Stops forcing the chunk at `chunk` to stay loaded.
+    /// The chunk is unloaded once no players or other
+    /// plugins keep it loaded.
+    ///
+    /// Returns `false` if this plugin did not force
+    /// the chunk to stay loaded.
+    pub fn release_chunk(&self, chunk: ChunkPosition) -> bool {
+        unsafe { quill_sys::chunk_release(chunk.x, chunk.z) }
+    }
+
+    /// Gets the whitelist, ban lists and operator list.
+    pub fn access_lists(&self) -> AccessLists {
+        AccessLists::new()
+    }
+
+    /// Gets the loaded world with the given name.
+    pub fn world(&self, name: &str) -> Option<WorldId> {
+        world::find(name)
+    }
+
+    /// Creates a new world in the directory `name` and loads it.
+    ///
+    /// Fails if the world already exists; use [`Game::load_world`]
+    /// to load existing worlds.
+    pub fn create_world(
+        &self,
+        name: &str,
+        settings: &WorldSettings,
+    ) -> Result<WorldId, WorldError> {
+        world::create(name, settings)
+    }
+
+    /// Loads the world saved in the directory `name`.
+    ///
+    /// Chunks that have not been generated yet
+    /// are generated according to `settings`.
+    pub fn load_world(&self, name: &str, settings: &WorldSettings) -> Result<WorldId, WorldError> {
+        world::load(name, settings)
+    }
+
+    /// Unloads a world, saving its chunks.
+    ///
+    /// Players in the world are moved to the spawn of
+    /// the default world, and all other entities in the
+    /// world are removed. The default world cannot be unloaded.
+    pub fn unload_world(&self, world: WorldId) -> Result<(), WorldError> {
+        world::unload(world)
+    }
+
     /// Sends a custom packet to an entity.
     pub fn send_plugin_message(entity: EntityId, channel: &str, data: &[u8]) {
         let channel_ptr = channel.as_ptr().into();
@@ -1,15 +1,21 @@
 //! A WebAssembly-based plugin API for Minecraft servers.
 
+pub mod access;
+pub mod chunk;
+pub mod data;
 pub mod entities;
 mod entity;
 mod entity_builder;
 mod game;
 pub mod query;
+mod scheduler;
 mod setup;
+pub mod world;
 
 pub use entity::{Entity, EntityId};
 pub use entity_builder::EntityBuilder;
 pub use game::Game;
+pub use scheduler::{Scheduler, TaskHandle};
 pub use setup::Setup;
 
 #[doc(inline)]
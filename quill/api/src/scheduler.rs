@@ -0,0 +1,125 @@
+use std::marker::PhantomData;
+
+use crate::Game;
+
+/// Runs tasks after a delay or periodically.
+///
+/// Obtain a `Scheduler` from [`Setup::scheduler`](crate::Setup::scheduler)
+/// in your plugin's `enable()` function. The scheduler can be copied
+/// and stored in your plugin struct to schedule tasks from systems.
+///
+/// Delays and periods are measured in ticks; there are 20 ticks
+/// per second.
+///
+/// # Example
+/// ```no_run
+/// use quill::{Game, Plugin, Scheduler, Setup};
+///
+/// struct MyPlugin {
+///     scheduler: Scheduler<Self>,
+/// }
+///
+/// impl Plugin for MyPlugin {
+///     fn enable(_game: &mut Game, setup: &mut Setup<Self>) -> Self {
+///         let scheduler = setup.scheduler();
+///         scheduler.run_later(5 * 20, |_plugin, _game| {
+///             println!("Five seconds have passed");
+///         });
+///         Self { scheduler }
+///     }
+///
+///     fn disable(self, _game: &mut Game) {}
+/// }
+/// ```
+pub struct Scheduler<Plugin> {
+    _marker: PhantomData<*mut Plugin>,
+}
+
+impl<Plugin> Clone for Scheduler<Plugin> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Plugin> Copy for Scheduler<Plugin> {}
+
+impl<Plugin> Scheduler<Plugin> {
+    pub(crate) fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    /// Runs `task` once after `delay` ticks.
+    ///
+    /// A delay of zero runs the task on the next tick.
+    pub fn run_later<T>(&self, delay: u64, task: T) -> TaskHandle
+    where
+        T: FnOnce(&mut Plugin, &mut Game) + 'static,
+    {
+        let mut task = Some(task);
+        self.schedule(
+            delay,
+            0,
+            Box::new(move |plugin: &mut Plugin, game: &mut Game| {
+                if let Some(task) = task.take() {
+                    task(plugin, game);
+                }
+            }),
+        )
+    }
+
+    /// Runs `task` every `period` ticks, starting on the next tick.
+    pub fn run_repeating<T>(&self, period: u64, task: T) -> TaskHandle
+    where
+        T: FnMut(&mut Plugin, &mut Game) + 'static,
+    {
+        self.run_repeating_after(0, period, task)
+    }
+
+    /// Runs `task` every `period` ticks, starting after `delay` ticks.
+    pub fn run_repeating_after<T>(&self, delay: u64, period: u64, task: T) -> TaskHandle
+    where
+        T: FnMut(&mut Plugin, &mut Game) + 'static,
+    {
+        self.schedule(delay, period.max(1), Box::new(task))
+    }
+
+    fn schedule(
+        &self,
+        delay: u64,
+        period: u64,
+        task: Box<dyn FnMut(&mut Plugin, &mut Game)>,
+    ) -> TaskHandle {
+        // The host runs the task through `quill_run_system`
+        // and frees it through `quill_drop_task`, so it
+        // must have the same layout as a system.
+        let task_data = Box::leak(Box::new(task)) as *mut Box<_> as *mut u8;
+
+        let id = unsafe { quill_sys::scheduler_schedule(task_data.into(), delay, period) };
+        TaskHandle { id }
+    }
+}
+
+/// A handle to a scheduled task.
+///
+/// Dropping the handle does _not_ cancel the task.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TaskHandle {
+    id: u64,
+}
+
+impl TaskHandle {
+    /// Cancels the task.
+    ///
+    /// Returns `false` if the task has already
+    /// finished or was cancelled before.
+    pub fn cancel(self) -> bool {
+        unsafe { quill_sys::scheduler_cancel(self.id) }
+    }
+
+    /// Determines whether the task is still going to run.
+    pub fn is_scheduled(self) -> bool {
+        unsafe { quill_sys::scheduler_is_scheduled(self.id) }
+    }
+}
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::Game;
+use crate::{Game, Scheduler};
 
 /// Struct passed to your plugin's `enable()` function.
 ///
@@ -36,4 +36,10 @@ impl<Plugin> Setup<Plugin> {
 
         self
     }
+
+    /// Gets a [`Scheduler`] to run tasks after a delay
+    /// or periodically.
+    pub fn scheduler(&self) -> Scheduler<Plugin> {
+        Scheduler::new()
+    }
 }
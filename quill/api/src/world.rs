@@ -0,0 +1,77 @@
+//! Worlds created, loaded and unloaded at runtime.
+
+use std::ptr;
+
+use quill_common::{Pointer, PointerMut};
+use serde::de::DeserializeOwned;
+
+#[doc(inline)]
+pub use quill_common::{
+    components::WorldId,
+    world::{is_valid_world_name, WorldError, WorldSettings},
+};
+
+pub(crate) fn create(name: &str, settings: &WorldSettings) -> Result<WorldId, WorldError> {
+    let settings = bincode::serialize(settings).expect("failed to serialize world settings");
+    read_result(|bytes_ptr, bytes_len| unsafe {
+        quill_sys::world_create(
+            name.as_ptr().into(),
+            name.len() as u32,
+            settings.as_ptr().into(),
+            settings.len() as u32,
+            bytes_ptr,
+            bytes_len,
+        )
+    })
+}
+
+pub(crate) fn load(name: &str, settings: &WorldSettings) -> Result<WorldId, WorldError> {
+    let settings = bincode::serialize(settings).expect("failed to serialize world settings");
+    read_result(|bytes_ptr, bytes_len| unsafe {
+        quill_sys::world_load(
+            name.as_ptr().into(),
+            name.len() as u32,
+            settings.as_ptr().into(),
+            settings.len() as u32,
+            bytes_ptr,
+            bytes_len,
+        )
+    })
+}
+
+pub(crate) fn unload(world: WorldId) -> Result<(), WorldError> {
+    read_result(|bytes_ptr, bytes_len| unsafe {
+        quill_sys::world_unload(world.0, bytes_ptr, bytes_len)
+    })
+}
+
+pub(crate) fn find(name: &str) -> Option<WorldId> {
+    let mut world = 0u32;
+    let found = unsafe {
+        quill_sys::world_find(
+            name.as_ptr().into(),
+            name.len() as u32,
+            PointerMut::new(&mut world),
+        )
+    };
+    if found {
+        Some(WorldId(world))
+    } else {
+        None
+    }
+}
+
+fn read_result<T: DeserializeOwned>(
+    host_call: impl FnOnce(PointerMut<Pointer<u8>>, PointerMut<u32>),
+) -> T {
+    let mut bytes_ptr = Pointer::new(ptr::null());
+    let mut bytes_len = 0u32;
+    host_call(
+        PointerMut::new(&mut bytes_ptr),
+        PointerMut::new(&mut bytes_len),
+    );
+    unsafe {
+        let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+        bincode::deserialize(bytes).expect("host gave malformed world result")
+    }
+}
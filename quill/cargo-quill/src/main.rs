@@ -12,6 +12,9 @@ use std::{
 const WASM_TARGET_FEATURES: &str = "target-feature=+bulk-memory,+mutable-globals,+simd128";
 const WASM_TARGET: &str = "wasm32-wasi";
 
+/// Must match `quill_testing::PLUGIN_ENV_VAR`.
+const PLUGIN_ENV_VAR: &str = "QUILL_TEST_PLUGIN";
+
 #[derive(Debug, FromArgs)]
 /// Cargo subcommand to build and test Quill/Feather plugins.
 struct CargoQuill {
@@ -23,6 +26,7 @@ struct CargoQuill {
 #[argh(subcommand)]
 enum Subcommand {
     Build(Build),
+    Test(Test),
 }
 
 #[derive(Debug, FromArgs)]
@@ -42,6 +46,23 @@ struct Build {
     compression_level: u32,
 }
 
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "test")]
+/// Build a Quill plugin and run its tests against
+/// an in-process server.
+struct Test {
+    #[argh(switch)]
+    /// whether to build and test in release mode
+    release: bool,
+    #[argh(switch)]
+    /// whether to test the plugin compiled to a native
+    /// shared library instead of a WebAssembly module
+    native: bool,
+    #[argh(positional)]
+    /// additional arguments passed to `cargo test`
+    cargo_test_args: Vec<String>,
+}
+
 impl Build {
     pub fn module_extension(&self) -> &'static str {
         if !self.native {
@@ -88,21 +109,23 @@ impl Build {
 fn main() -> anyhow::Result<()> {
     let args: CargoQuill = argh::from_env();
     match args.subcommand {
-        Subcommand::Build(args) => build(args),
+        Subcommand::Build(args) => build(&args).map(|_| ()),
+        Subcommand::Test(args) => test(args),
     }
 }
 
-fn build(args: Build) -> anyhow::Result<()> {
+/// Builds the plugin file, returning its path.
+fn build(args: &Build) -> anyhow::Result<PathBuf> {
     let cargo_meta = get_cargo_metadata()?;
     validate_cargo_metadata(&cargo_meta)?;
 
-    let mut command = cargo_build_command(&args);
+    let mut command = cargo_build_command(args);
     let status = command.spawn()?.wait()?;
     if !status.success() {
         bail!("build failed");
     }
 
-    let meta = find_metadata(&cargo_meta, &args)?;
+    let meta = find_metadata(&cargo_meta, args)?;
     let module_path = args.module_path(&cargo_meta, &meta);
     let module = fs::read(&module_path)
         .with_context(|| format!("failed to read {}", module_path.display()))?;
@@ -115,6 +138,30 @@ fn build(args: Build) -> anyhow::Result<()> {
     fs::write(&target_path, file.encode(args.compression_level))?;
 
     println!("Wrote plugin file to {}", target_path.display());
+    Ok(target_path)
+}
+
+fn test(args: Test) -> anyhow::Result<()> {
+    let plugin_path = build(&Build {
+        release: args.release,
+        native: args.native,
+        // Tests load the plugin right away, so
+        // favor build speed over file size.
+        compression_level: 0,
+    })?;
+
+    let mut command = Command::new("cargo");
+    command.arg("test");
+    if args.release {
+        command.arg("--release");
+    }
+    command.args(&args.cargo_test_args);
+    command.env(PLUGIN_ENV_VAR, &plugin_path);
+
+    let status = command.spawn()?.wait()?;
+    if !status.success() {
+        bail!("tests failed");
+    }
     Ok(())
 }
 
@@ -0,0 +1,304 @@
+//! Entries of the whitelist, ban lists and operator list.
+//!
+//! These serialize to the same JSON as the vanilla `whitelist.json`,
+//! `ops.json`, `banned-players.json` and `banned-ips.json` files.
+
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use time::OffsetDateTime;
+
+/// Reason given for bans that don't specify one.
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+/// Source given for bans that don't specify one.
+pub const DEFAULT_BAN_SOURCE: &str = "Server";
+/// Permission level of operators that don't specify one.
+pub const DEFAULT_OP_LEVEL: u8 = 4;
+
+/// A player on the whitelist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A server operator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    #[serde(default)]
+    pub name: String,
+    /// Permission level, from 1 to 4.
+    #[serde(default = "default_op_level")]
+    pub level: u8,
+    /// Whether the operator may join when the server is full.
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+fn default_op_level() -> u8 {
+    DEFAULT_OP_LEVEL
+}
+
+/// A banned player.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerBan {
+    pub uuid: Uuid,
+    #[serde(default)]
+    pub name: String,
+    #[serde(with = "date", default = "OffsetDateTime::now_utc")]
+    pub created: OffsetDateTime,
+    /// Who issued the ban.
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// When the ban expires, or `None` if it is permanent.
+    #[serde(with = "expiry", default)]
+    pub expires: Option<OffsetDateTime>,
+    #[serde(default = "default_reason")]
+    pub reason: String,
+}
+
+impl PlayerBan {
+    /// Creates a permanent ban issued by the server now,
+    /// with the default reason.
+    pub fn new(uuid: Uuid, name: impl Into<String>) -> Self {
+        Self {
+            uuid,
+            name: name.into(),
+            created: OffsetDateTime::now_utc(),
+            source: default_source(),
+            expires: None,
+            reason: default_reason(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        is_expired(self.expires)
+    }
+
+    /// Gets the message shown to the player when they are disconnected.
+    pub fn disconnect_message(&self) -> String {
+        disconnect_message(
+            "You are banned from this server.",
+            &self.reason,
+            self.expires,
+        )
+    }
+}
+
+/// A banned IP address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: IpAddr,
+    #[serde(with = "date", default = "OffsetDateTime::now_utc")]
+    pub created: OffsetDateTime,
+    /// Who issued the ban.
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// When the ban expires, or `None` if it is permanent.
+    #[serde(with = "expiry", default)]
+    pub expires: Option<OffsetDateTime>,
+    #[serde(default = "default_reason")]
+    pub reason: String,
+}
+
+impl IpBan {
+    /// Creates a permanent ban issued by the server now,
+    /// with the default reason.
+    pub fn new(ip: IpAddr) -> Self {
+        Self {
+            ip,
+            created: OffsetDateTime::now_utc(),
+            source: default_source(),
+            expires: None,
+            reason: default_reason(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        is_expired(self.expires)
+    }
+
+    /// Gets the message shown to the player when they are disconnected.
+    pub fn disconnect_message(&self) -> String {
+        disconnect_message(
+            "Your IP address is banned from this server.",
+            &self.reason,
+            self.expires,
+        )
+    }
+}
+
+fn default_source() -> String {
+    DEFAULT_BAN_SOURCE.to_owned()
+}
+
+fn default_reason() -> String {
+    DEFAULT_BAN_REASON.to_owned()
+}
+
+fn is_expired(expires: Option<OffsetDateTime>) -> bool {
+    matches!(expires, Some(expires) if expires <= OffsetDateTime::now_utc())
+}
+
+fn disconnect_message(banned: &str, reason: &str, expires: Option<OffsetDateTime>) -> String {
+    let mut message = format!("{}\nReason: {}", banned, reason);
+    if let Some(expires) = expires {
+        message.push_str("\nYour ban will be removed on ");
+        message.push_str(&date::format(expires));
+    }
+    message
+}
+
+/// The update applied by the `access_update` host call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AccessUpdate {
+    SetWhitelistEnabled(bool),
+    AddToWhitelist(WhitelistEntry),
+    RemoveFromWhitelist(Uuid),
+    AddOp(OpEntry),
+    RemoveOp(Uuid),
+    BanPlayer(PlayerBan),
+    PardonPlayer(Uuid),
+    BanIp(IpBan),
+    PardonIp(IpAddr),
+}
+
+/// Identifies a list for the `access_get` host call.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum AccessListKind {
+    Whitelist = 0,
+    Ops = 1,
+    BannedPlayers = 2,
+    BannedIps = 3,
+}
+
+impl AccessListKind {
+    pub fn from_u32(x: u32) -> Option<Self> {
+        match x {
+            0 => Some(AccessListKind::Whitelist),
+            1 => Some(AccessListKind::Ops),
+            2 => Some(AccessListKind::BannedPlayers),
+            3 => Some(AccessListKind::BannedIps),
+            _ => None,
+        }
+    }
+}
+
+/// Dates in the vanilla format, e.g. `2021-03-05 18:22:10 +0000`.
+pub mod date {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use time::{format_description::FormatItem, macros::format_description, OffsetDateTime};
+
+    const FORMAT: &[FormatItem] = format_description!(
+        "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+    );
+
+    pub fn format(date: OffsetDateTime) -> String {
+        date.format(FORMAT)
+            .expect("date can always be formatted in the vanilla format")
+    }
+
+    pub fn parse(s: &str) -> Option<OffsetDateTime> {
+        OffsetDateTime::parse(s, FORMAT).ok()
+    }
+
+    pub fn serialize<S: Serializer>(
+        date: &OffsetDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(*date))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OffsetDateTime, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse(&s).ok_or_else(|| de::Error::custom(format!("invalid date '{}'", s)))
+    }
+}
+
+/// Ban expiry dates, which are either a date or `forever`.
+mod expiry {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    use super::date;
+
+    const FOREVER: &str = "forever";
+
+    pub fn serialize<S: Serializer>(
+        expires: &Option<OffsetDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match expires {
+            Some(expires) => date::serialize(expires, serializer),
+            None => serializer.serialize_str(FOREVER),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OffsetDateTime>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s == FOREVER {
+            Ok(None)
+        } else {
+            date::parse(&s)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom(format!("invalid date '{}'", s)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn vanilla_player_ban_roundtrip() {
+        let json = r#"{
+            "uuid": "905c7e4f-b96b-4513-9645-d123225575e2",
+            "name": "caelunshun",
+            "created": "2021-03-05 18:22:10 +0100",
+            "source": "Server",
+            "expires": "forever",
+            "reason": "Griefing"
+        }"#;
+        let ban: PlayerBan = serde_json::from_str(json).unwrap();
+        assert_eq!(ban.created, datetime!(2021-03-05 18:22:10 +1));
+        assert_eq!(ban.expires, None);
+        assert_eq!(ban.reason, "Griefing");
+
+        let serialized = serde_json::to_string(&ban).unwrap();
+        assert!(serialized.contains(r#""created":"2021-03-05 18:22:10 +0100""#));
+        assert!(serialized.contains(r#""expires":"forever""#));
+        assert_eq!(serde_json::from_str::<PlayerBan>(&serialized).unwrap(), ban);
+
+        let bytes = bincode::serialize(&ban).unwrap();
+        assert_eq!(bincode::deserialize::<PlayerBan>(&bytes).unwrap(), ban);
+    }
+
+    #[test]
+    fn ban_expiry() {
+        let mut ban = IpBan::new(IpAddr::from([127, 0, 0, 1]));
+        assert!(!ban.is_expired());
+        assert_eq!(
+            ban.disconnect_message(),
+            "Your IP address is banned from this server.\nReason: Banned by an operator."
+        );
+
+        ban.expires = Some(datetime!(2021-03-05 18:22:10 UTC));
+        assert!(ban.is_expired());
+        assert!(ban
+            .disconnect_message()
+            .ends_with("\nYour ban will be removed on 2021-03-05 18:22:10 +0000"));
+    }
+}
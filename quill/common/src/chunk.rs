@@ -0,0 +1,294 @@
+//! Bulk chunk data exchanged between plugins and the host.
+//!
+//! All types in this module are transferred with `bincode`.
+
+use serde::{Deserialize, Serialize};
+
+/// Number of blocks along each axis of a chunk section.
+pub const SECTION_WIDTH: usize = 16;
+
+/// Number of blocks in a chunk section.
+pub const SECTION_VOLUME: usize = SECTION_WIDTH * SECTION_WIDTH * SECTION_WIDTH;
+
+/// Number of sections in a chunk.
+pub const NUM_SECTIONS: usize = 16;
+
+/// Number of biome samples in a chunk. Biomes are
+/// sampled in 4x4x4 block cells.
+pub const BIOMES_PER_CHUNK: usize = 4 * 4 * 64;
+
+fn block_index(x: usize, y: usize, z: usize) -> Option<usize> {
+    if x >= SECTION_WIDTH || y >= SECTION_WIDTH || z >= SECTION_WIDTH {
+        None
+    } else {
+        Some((y << 8) | (z << 4) | x)
+    }
+}
+
+/// The blocks of a chunk section, encoded as a palette of
+/// vanilla block state IDs and a packed array of palette indices.
+///
+/// Blocks are ordered by Y, then Z, then X. Indices are packed
+/// into `u64`s starting at the least significant bit, and
+/// an index never spans two `u64`s. This matches the chunk
+/// format of the 1.16 protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkSectionData {
+    palette: Vec<u16>,
+    bits_per_index: u8,
+    indices: Vec<u64>,
+}
+
+impl ChunkSectionData {
+    /// Creates a section filled with `block`.
+    pub fn filled(block: u16) -> Self {
+        let bits_per_index = 1;
+        Self {
+            palette: vec![block],
+            bits_per_index,
+            indices: vec![0; needed_u64s(bits_per_index)],
+        }
+    }
+
+    /// Encodes a section from its `SECTION_VOLUME` blocks
+    /// in YZX order.
+    ///
+    /// # Panics
+    /// Panics if `blocks.len() != SECTION_VOLUME`.
+    pub fn from_blocks(blocks: &[u16]) -> Self {
+        assert_eq!(
+            blocks.len(),
+            SECTION_VOLUME,
+            "a chunk section contains {} blocks",
+            SECTION_VOLUME
+        );
+
+        let mut palette = Vec::new();
+        let palette_indices: Vec<usize> = blocks
+            .iter()
+            .map(|&block| match palette.iter().position(|&b| b == block) {
+                Some(index) => index,
+                None => {
+                    palette.push(block);
+                    palette.len() - 1
+                }
+            })
+            .collect();
+
+        let bits_per_index = bits_needed(palette.len());
+        let mut indices = vec![0u64; needed_u64s(bits_per_index)];
+        for (i, &index) in palette_indices.iter().enumerate() {
+            let (word, shift) = word_and_shift(bits_per_index, i);
+            indices[word] |= (index as u64) << shift;
+        }
+
+        Self {
+            palette,
+            bits_per_index,
+            indices,
+        }
+    }
+
+    /// Creates a section from its raw parts.
+    ///
+    /// Returns `None` if the parts are inconsistent. See [`ChunkSectionData::is_valid`].
+    pub fn from_raw_parts(
+        palette: Vec<u16>,
+        bits_per_index: u8,
+        indices: Vec<u64>,
+    ) -> Option<Self> {
+        let this = Self {
+            palette,
+            bits_per_index,
+            indices,
+        };
+        if this.is_valid() {
+            Some(this)
+        } else {
+            None
+        }
+    }
+
+    /// Determines whether the palette is nonempty,
+    /// `bits_per_index` is within `1..=16`, the number of
+    /// `u64`s matches `bits_per_index`, and every index
+    /// points into the palette.
+    pub fn is_valid(&self) -> bool {
+        if self.palette.is_empty()
+            || self.bits_per_index == 0
+            || self.bits_per_index > 16
+            || self.indices.len() != needed_u64s(self.bits_per_index)
+        {
+            return false;
+        }
+
+        (0..SECTION_VOLUME).all(|i| self.palette_index(i) < self.palette.len())
+    }
+
+    /// Gets the palette of vanilla block state IDs.
+    pub fn palette(&self) -> &[u16] {
+        &self.palette
+    }
+
+    /// Gets the number of bits used to store each palette index.
+    pub fn bits_per_index(&self) -> u8 {
+        self.bits_per_index
+    }
+
+    /// Gets the packed palette indices.
+    pub fn indices(&self) -> &[u64] {
+        &self.indices
+    }
+
+    /// Gets the block at the given coordinates within the section.
+    ///
+    /// Returns `None` if the coordinates are out of bounds.
+    pub fn block_at(&self, x: usize, y: usize, z: usize) -> Option<u16> {
+        let index = block_index(x, y, z)?;
+        self.palette.get(self.palette_index(index)).copied()
+    }
+
+    /// Returns an iterator over all blocks in YZX order.
+    pub fn blocks(&self) -> impl Iterator<Item = u16> + '_ {
+        self.palette_indices()
+            .map(move |index| self.palette.get(index).copied().unwrap_or_default())
+    }
+
+    /// Returns an iterator over the palette index
+    /// of each block in YZX order.
+    pub fn palette_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..SECTION_VOLUME).map(move |i| self.palette_index(i))
+    }
+
+    fn palette_index(&self, i: usize) -> usize {
+        let (word, shift) = word_and_shift(self.bits_per_index, i);
+        let mask = (1u64 << self.bits_per_index) - 1;
+        ((self.indices[word] >> shift) & mask) as usize
+    }
+}
+
+fn bits_needed(palette_len: usize) -> u8 {
+    let max_index = palette_len.saturating_sub(1) as u64;
+    ((64 - max_index.leading_zeros()) as u8).max(1)
+}
+
+fn needed_u64s(bits_per_index: u8) -> usize {
+    let per_u64 = 64 / bits_per_index as usize;
+    (SECTION_VOLUME + per_u64 - 1) / per_u64
+}
+
+fn word_and_shift(bits_per_index: u8, i: usize) -> (usize, u32) {
+    let per_u64 = 64 / bits_per_index as usize;
+    (
+        i / per_u64,
+        ((i % per_u64) * bits_per_index as usize) as u32,
+    )
+}
+
+/// Light levels of a chunk section, one byte per block
+/// in YZX order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionLight {
+    pub block_light: Vec<u8>,
+    pub sky_light: Vec<u8>,
+}
+
+impl SectionLight {
+    /// Gets the block light level at the given coordinates within the section.
+    pub fn block_light_at(&self, x: usize, y: usize, z: usize) -> Option<u8> {
+        self.block_light.get(block_index(x, y, z)?).copied()
+    }
+
+    /// Gets the sky light level at the given coordinates within the section.
+    pub fn sky_light_at(&self, x: usize, y: usize, z: usize) -> Option<u8> {
+        self.sky_light.get(block_index(x, y, z)?).copied()
+    }
+}
+
+/// The kinds of heightmaps stored for each chunk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum HeightmapKind {
+    MotionBlocking,
+    MotionBlockingNoLeaves,
+    LightBlocking,
+    OceanFloor,
+    WorldSurface,
+}
+
+impl HeightmapKind {
+    pub fn from_u32(x: u32) -> Option<Self> {
+        Some(match x {
+            0 => HeightmapKind::MotionBlocking,
+            1 => HeightmapKind::MotionBlockingNoLeaves,
+            2 => HeightmapKind::LightBlocking,
+            3 => HeightmapKind::OceanFloor,
+            4 => HeightmapKind::WorldSurface,
+            _ => return None,
+        })
+    }
+
+    pub fn to_u32(self) -> u32 {
+        self as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filled_section() {
+        let section = ChunkSectionData::filled(10);
+        assert!(section.is_valid());
+        assert_eq!(section.block_at(0, 0, 0), Some(10));
+        assert_eq!(section.block_at(15, 15, 15), Some(10));
+        assert_eq!(section.block_at(16, 0, 0), None);
+    }
+
+    #[test]
+    fn from_blocks_round_trip() {
+        let blocks: Vec<u16> = (0..SECTION_VOLUME).map(|i| (i % 37) as u16 * 3).collect();
+        let section = ChunkSectionData::from_blocks(&blocks);
+        assert!(section.is_valid());
+        assert_eq!(section.palette().len(), 37);
+        assert_eq!(section.bits_per_index(), 6);
+        assert_eq!(section.blocks().collect::<Vec<_>>(), blocks);
+        assert_eq!(section.block_at(1, 0, 0), Some(3));
+        assert_eq!(section.block_at(0, 1, 0), Some(blocks[256]));
+    }
+
+    #[test]
+    fn indices_do_not_span_words() {
+        let blocks: Vec<u16> = (0..SECTION_VOLUME).map(|i| (i % 5) as u16).collect();
+        let section = ChunkSectionData::from_blocks(&blocks);
+        // 3 bits per index: 21 indices per u64
+        assert_eq!(section.bits_per_index(), 3);
+        assert_eq!(section.indices().len(), (SECTION_VOLUME + 20) / 21);
+        assert_eq!(section.blocks().collect::<Vec<_>>(), blocks);
+    }
+
+    #[test]
+    fn invalid_raw_parts() {
+        assert!(ChunkSectionData::from_raw_parts(vec![], 1, vec![0; 64]).is_none());
+        assert!(ChunkSectionData::from_raw_parts(vec![1], 1, vec![0; 63]).is_none());
+        assert!(ChunkSectionData::from_raw_parts(vec![1], 0, vec![]).is_none());
+        // Index 1 is out of bounds for a palette with one entry
+        assert!(ChunkSectionData::from_raw_parts(vec![1], 1, vec![1; 64]).is_none());
+        assert!(ChunkSectionData::from_raw_parts(vec![1, 2], 1, vec![u64::MAX; 64]).is_some());
+    }
+
+    #[test]
+    fn heightmap_kind_round_trip() {
+        for kind in [
+            HeightmapKind::MotionBlocking,
+            HeightmapKind::MotionBlockingNoLeaves,
+            HeightmapKind::LightBlocking,
+            HeightmapKind::OceanFloor,
+            HeightmapKind::WorldSurface,
+        ] {
+            assert_eq!(HeightmapKind::from_u32(kind.to_u32()), Some(kind));
+        }
+        assert_eq!(HeightmapKind::from_u32(5), None);
+    }
+}
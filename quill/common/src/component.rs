@@ -206,6 +206,9 @@ host_component_enum! {
         FlyingAbilityEvent = 1028,
         BuildingAbilityEvent = 1029,
         InvulnerabilityEvent = 1030,
+        PluginDisableEvent = 1031,
+        ServerListPingEvent = 1032,
+        WorldId = 1033,
     }
 }
 
@@ -377,3 +380,5 @@ bincode_component_impl!(InstabreakEvent);
 bincode_component_impl!(FlyingAbilityEvent);
 bincode_component_impl!(BuildingAbilityEvent);
 bincode_component_impl!(InvulnerabilityEvent);
+bincode_component_impl!(PluginDisableEvent);
+bincode_component_impl!(ServerListPingEvent);
@@ -284,3 +284,20 @@ impl Sprinting {
     }
 }
 bincode_component_impl!(Sprinting);
+
+/// The world an entity is in.
+///
+/// Entities start out in the server's default world.
+/// This component should not be changed directly;
+/// teleport the entity into another world instead.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct WorldId(pub u32);
+
+bincode_component_impl!(WorldId);
+
+impl WorldId {
+    /// The server's default world, which is always loaded.
+    pub const DEFAULT: WorldId = WorldId(0);
+}
@@ -5,8 +5,12 @@ pub use change::{
 };
 pub use entity::{EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent};
 pub use interact_entity::InteractEntityEvent;
+pub use plugin::PluginDisableEvent;
+pub use status::{PlayerSample, ServerListPingEvent};
 
 mod block_interact;
 mod change;
 mod entity;
 mod interact_entity;
+mod plugin;
+mod status;
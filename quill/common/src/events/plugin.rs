@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Triggered when the server disables a plugin
+/// because it exceeded its resource limits or
+/// trapped too many times.
+///
+/// This is a global event.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginDisableEvent {
+    /// Identifier of the disabled plugin.
+    pub identifier: String,
+    /// Human-readable reason why the plugin was disabled.
+    pub reason: String,
+}
@@ -0,0 +1,46 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Triggered when a client pings the server from its server list.
+///
+/// The server responds with the contents of this event one tick
+/// after triggering it. To change the response, insert a modified
+/// copy of the event into its entity.
+///
+/// This is a global event.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServerListPingEvent {
+    /// IP address of the pinging client.
+    pub address: IpAddr,
+    /// Protocol version sent by the client, or `None`
+    /// for a legacy (pre-1.7) ping.
+    pub client_protocol_version: Option<i32>,
+
+    /// The message of the day.
+    pub motd: String,
+    /// Version name shown when the client's
+    /// protocol version is not supported.
+    pub version_name: String,
+    /// Protocol version the server advertises.
+    pub protocol_version: i32,
+    /// Number of players shown as online.
+    pub online_players: u32,
+    /// Maximum number of players shown.
+    pub max_players: u32,
+    /// Whether to hide the player counts and sample.
+    /// Clients show `???` instead.
+    pub hide_player_count: bool,
+    /// Players listed when hovering over the player count.
+    pub player_sample: Vec<PlayerSample>,
+    /// The server icon as a `data:image/png;base64,` URI.
+    pub favicon: Option<String>,
+}
+
+/// A player listed in a [`ServerListPingEvent`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: Uuid,
+}
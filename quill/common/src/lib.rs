@@ -2,12 +2,15 @@
 mod utils;
 #[macro_use]
 pub mod component;
+pub mod access;
 pub mod block;
+pub mod chunk;
 pub mod components;
 pub mod entities;
 pub mod entity;
 pub mod entity_init;
 pub mod events;
+pub mod world;
 
 use std::marker::PhantomData;
 
@@ -0,0 +1,105 @@
+//! Settings and errors for worlds created and loaded at runtime.
+
+use std::fmt::{self, Display};
+
+use libcraft_core::Dimension;
+use serde::{Deserialize, Serialize};
+
+/// The settings of a world to create or load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldSettings {
+    /// The generator for chunks that have not
+    /// been generated yet, such as `default` or `flat`.
+    pub generator: String,
+    pub seed: u64,
+    /// The dimension type of the world, which
+    /// determines e.g. the sky shown by clients.
+    pub dimension: Dimension,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        Self {
+            generator: "default".to_owned(),
+            seed: 0,
+            dimension: Dimension::Overworld,
+        }
+    }
+}
+
+/// Error returned when creating, loading or unloading a world fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorldError {
+    /// World names may only contain ASCII letters,
+    /// digits, `_` and `-`.
+    InvalidName(String),
+    /// A world with the name, ignoring case, is already loaded.
+    AlreadyLoaded(String),
+    /// A world with the name already exists on disk.
+    AlreadyExists(String),
+    /// No world with the name exists on disk.
+    NotFound(String),
+    UnknownGenerator(String),
+    /// The world is not loaded.
+    NotLoaded,
+    /// The default world cannot be unloaded.
+    DefaultWorld,
+}
+
+impl Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::InvalidName(name) => write!(f, "invalid world name {:?}", name),
+            WorldError::AlreadyLoaded(name) => write!(f, "world {:?} is already loaded", name),
+            WorldError::AlreadyExists(name) => write!(f, "world {:?} already exists", name),
+            WorldError::NotFound(name) => write!(f, "world {:?} does not exist", name),
+            WorldError::UnknownGenerator(generator) => {
+                write!(f, "unknown world generator {:?}", generator)
+            }
+            WorldError::NotLoaded => f.write_str("world is not loaded"),
+            WorldError::DefaultWorld => f.write_str("the default world cannot be unloaded"),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+/// Determines whether `name` is a valid world name.
+///
+/// World names are used as directory names,
+/// so they are restricted to ASCII letters,
+/// digits, `_` and `-`.
+pub fn is_valid_world_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_names() {
+        assert!(is_valid_world_name("world"));
+        assert!(is_valid_world_name("arena_2-b"));
+        assert!(!is_valid_world_name(""));
+        assert!(!is_valid_world_name("../world"));
+        assert!(!is_valid_world_name("my world"));
+    }
+
+    #[test]
+    fn settings_roundtrip() {
+        let settings = WorldSettings {
+            generator: "flat".to_owned(),
+            seed: 5,
+            dimension: Dimension::TheNether,
+        };
+        let bytes = bincode::serialize(&settings).unwrap();
+        assert_eq!(
+            bincode::deserialize::<WorldSettings>(&bytes).unwrap(),
+            settings
+        );
+    }
+}
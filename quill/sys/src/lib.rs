@@ -118,6 +118,13 @@ extern "C" {
     /// Does nothing if the entity does not exist or if it does not have the `Chat` component.
     pub fn entity_send_title(entity: EntityId, title_json_ptr: Pointer<u8>, title_len: u32);
 
+    /// Determines whether an entity has a permission node.
+    ///
+    /// `node_ptr` is a pointer to a UTF-8 permission node such as
+    /// `feather.build`. Returns `false` if the entity does not exist
+    /// or is not a player.
+    pub fn entity_has_permission(entity: EntityId, node_ptr: Pointer<u8>, node_len: u32) -> bool;
+
     /// Creates an empty entity builder.
     ///
     /// This builder is used for creating an ecs-entity
@@ -183,6 +190,99 @@ extern "C" {
     pub fn block_fill_chunk_section(chunk_x: i32, section_y: u32, chunk_z: i32, block: u16)
         -> bool;
 
+    /// Determines whether the given chunk is loaded.
+    pub fn chunk_is_loaded(chunk_x: i32, chunk_z: i32) -> bool;
+
+    /// Gets the positions of all loaded chunks.
+    ///
+    /// Sets `bytes_ptr` to a pointer to a `bincode`-serialized
+    /// `Vec<ChunkPosition>` and `bytes_len` to its length.
+    ///
+    /// The returned buffer is allocated within the plugin's
+    /// bump allocator. It will be freed automatically after
+    /// the plugin finishes executing the current system.
+    pub fn chunk_loaded_list(bytes_ptr: PointerMut<Pointer<u8>>, bytes_len: PointerMut<u32>);
+
+    /// Gets the blocks of a chunk section as a `bincode`-serialized
+    /// [`ChunkSectionData`](quill_common::chunk::ChunkSectionData).
+    ///
+    /// Returns `false` if the chunk is not loaded or
+    /// the section index is out of bounds. The returned
+    /// buffer is allocated within the plugin's bump allocator.
+    pub fn chunk_section_get(
+        chunk_x: i32,
+        section_y: u32,
+        chunk_z: i32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    ) -> bool;
+
+    /// Overwrites the blocks of a chunk section with a `bincode`-serialized
+    /// [`ChunkSectionData`](quill_common::chunk::ChunkSectionData).
+    ///
+    /// Returns `false` if the chunk is not loaded or
+    /// the section index is out of bounds.
+    pub fn chunk_section_set(
+        chunk_x: i32,
+        section_y: u32,
+        chunk_z: i32,
+        bytes_ptr: Pointer<u8>,
+        bytes_len: u32,
+    ) -> bool;
+
+    /// Gets the light levels of a chunk section as a `bincode`-serialized
+    /// [`SectionLight`](quill_common::chunk::SectionLight).
+    ///
+    /// Returns `false` if the chunk is not loaded or
+    /// the section index is out of bounds. The returned
+    /// buffer is allocated within the plugin's bump allocator.
+    pub fn chunk_section_light(
+        chunk_x: i32,
+        section_y: u32,
+        chunk_z: i32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    ) -> bool;
+
+    /// Gets the biomes of a chunk as a `bincode`-serialized
+    /// `Vec<u32>` of biome IDs, sampled in 4x4x4 block cells
+    /// in YZX order.
+    ///
+    /// Returns `false` if the chunk is not loaded. The returned
+    /// buffer is allocated within the plugin's bump allocator.
+    pub fn chunk_biomes(
+        chunk_x: i32,
+        chunk_z: i32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    ) -> bool;
+
+    /// Gets a heightmap of a chunk as a `bincode`-serialized `Vec<u16>`
+    /// in ZX order. `kind` is a [`HeightmapKind`](quill_common::chunk::HeightmapKind).
+    ///
+    /// Returns `false` if the chunk is not loaded. The returned
+    /// buffer is allocated within the plugin's bump allocator.
+    pub fn chunk_heightmap(
+        chunk_x: i32,
+        chunk_z: i32,
+        kind: u32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    ) -> bool;
+
+    /// Keeps the given chunk loaded until [`chunk_release`]
+    /// is called, loading it if needed.
+    ///
+    /// Returns `false` if this plugin already forces the
+    /// chunk to stay loaded.
+    pub fn chunk_force_load(chunk_x: i32, chunk_z: i32) -> bool;
+
+    /// Stops forcing the given chunk to stay loaded.
+    ///
+    /// Returns `false` if this plugin did not force the
+    /// chunk to stay loaded.
+    pub fn chunk_release(chunk_x: i32, chunk_z: i32) -> bool;
+
     /// Sends a custom packet to an entity.
     ///
     /// Does nothing if the entity does not have the `ClientId` component.
@@ -193,4 +293,119 @@ extern "C" {
         data_ptr: Pointer<u8>,
         data_len: u32,
     );
+
+    /// Gets the path to the plugin's data directory.
+    ///
+    /// Sets `path_ptr` to a pointer to the UTF-8 path
+    /// and `path_len` to its length in bytes.
+    ///
+    /// On WASM, the data directory is preopened as the WASI root,
+    /// so the path is always `/`. Native plugins receive the
+    /// path on the host filesystem.
+    ///
+    /// The returned string is allocated within the plugin's
+    /// bump allocator. It will be freed automatically after
+    /// the plugin finishes executing the current system.
+    pub fn plugin_data_dir(path_ptr: PointerMut<Pointer<u8>>, path_len: PointerMut<u32>);
+
+    /// Schedules a task.
+    ///
+    /// The task is run by calling the plugin's exported
+    /// `quill_run_system` method with the `task_data` pointer,
+    /// first after `delay` ticks and then every `period` ticks.
+    /// If `period` is zero, the task runs only once.
+    ///
+    /// After the task finishes or is cancelled, the host
+    /// calls the plugin's exported `quill_drop_task`
+    /// method with `task_data` so the plugin can free it.
+    ///
+    /// Returns the ID of the task.
+    pub fn scheduler_schedule(task_data: PointerMut<u8>, delay: u64, period: u64) -> u64;
+
+    /// Cancels a task scheduled by this plugin.
+    ///
+    /// Returns `false` if the task has already finished or been cancelled.
+    pub fn scheduler_cancel(task: u64) -> bool;
+
+    /// Determines whether a task scheduled by this
+    /// plugin is still going to run.
+    pub fn scheduler_is_scheduled(task: u64) -> bool;
+
+    /// Reloads the whitelist, ban lists and operator list from disk.
+    ///
+    /// Returns `false` if a file could not be read; the error is
+    /// logged by the host.
+    pub fn access_reload() -> bool;
+
+    /// Determines whether the whitelist is enforced.
+    pub fn access_whitelist_enabled() -> bool;
+
+    /// Gets one of the access lists.
+    ///
+    /// `list` is an `AccessListKind`. Sets `bytes_ptr` to a pointer
+    /// to a `bincode`-serialized `Vec` of the list's entries and
+    /// `bytes_len` to its length. Expired bans are omitted.
+    ///
+    /// The returned buffer is allocated within the plugin's
+    /// bump allocator. It will be freed automatically after
+    /// the plugin finishes executing the current system.
+    pub fn access_get(list: u32, bytes_ptr: PointerMut<Pointer<u8>>, bytes_len: PointerMut<u32>);
+
+    /// Applies a `bincode`-serialized `AccessUpdate`
+    /// and saves the changed list.
+    ///
+    /// Returns whether the lists changed.
+    pub fn access_update(bytes_ptr: Pointer<u8>, bytes_len: u32) -> bool;
+
+    /// Creates a world in the directory `name` and loads it.
+    ///
+    /// `settings_ptr` points to a `bincode`-serialized `WorldSettings`.
+    /// Sets `bytes_ptr` to a pointer to a `bincode`-serialized
+    /// `Result<WorldId, WorldError>` and `bytes_len` to its length.
+    ///
+    /// The returned buffer is allocated within the plugin's
+    /// bump allocator. It will be freed automatically after
+    /// the plugin finishes executing the current system.
+    pub fn world_create(
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        settings_ptr: Pointer<u8>,
+        settings_len: u32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    );
+
+    /// Loads the world saved in the directory `name`.
+    ///
+    /// Takes and returns the same data as [`world_create`].
+    pub fn world_load(
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        settings_ptr: Pointer<u8>,
+        settings_len: u32,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    );
+
+    /// Unloads a world, saving its chunks. Players in the
+    /// world are moved to the default world.
+    ///
+    /// Sets `bytes_ptr` to a pointer to a `bincode`-serialized
+    /// `Result<(), WorldError>` and `bytes_len` to its length.
+    pub fn world_unload(world: u32, bytes_ptr: PointerMut<Pointer<u8>>, bytes_len: PointerMut<u32>);
+
+    /// Finds the loaded world with the given name,
+    /// writing its ID to `world`.
+    ///
+    /// Returns `false` if no such world is loaded.
+    pub fn world_find(name_ptr: Pointer<u8>, name_len: u32, world: PointerMut<u32>) -> bool;
+
+    /// Moves an entity into another world.
+    ///
+    /// `position` points to the `Position` to
+    /// place the entity at in the new world.
+    ///
+    /// Returns `false` if the entity does not exist
+    /// or the world is not loaded.
+    pub fn entity_teleport_to_world(entity: EntityId, world: u32, position: Pointer<u8>) -> bool;
 }
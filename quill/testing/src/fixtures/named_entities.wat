@@ -0,0 +1,40 @@
+;; A minimal plugin for testing the harness itself.
+;;
+;; It registers one system, which spawns an entity
+;; with the custom name "fixture" each tick.
+(module
+  (import "quill_01" "register_system" (func $register_system (param i64 i64 i32)))
+  (import "quill_01" "entity_builder_new_empty" (func $builder_new (result i32)))
+  (import "quill_01" "entity_builder_add_component" (func $builder_add (param i32 i32 i64 i32)))
+  (import "quill_01" "entity_builder_finish" (func $builder_finish (param i32) (result i64)))
+
+  (memory (export "memory") 16)
+
+  ;; The name of the system.
+  (data (i32.const 16) "spawn_named_entity")
+  ;; `CustomName("fixture")` encoded with bincode.
+  (data (i32.const 64) "\07\00\00\00\00\00\00\00fixture")
+
+  ;; Memory is never freed, which is fine for short tests.
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "quill_allocate") (param $size i32) (param $align i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (i32.and
+        (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+        (i32.sub (i32.const 0) (local.get $align))))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+    (local.get $ptr))
+
+  (func (export "quill_deallocate") (param i32 i32 i32))
+
+  (func (export "quill_setup")
+    (call $register_system (i64.const 0) (i64.const 16) (i32.const 18)))
+
+  (func (export "quill_run_system") (param i32)
+    (local $builder i32)
+    (local.set $builder (call $builder_new))
+    ;; 1004 is `HostComponent::CustomName`.
+    (call $builder_add (local.get $builder) (i32.const 1004) (i64.const 64) (i32.const 15))
+    (drop (call $builder_finish (local.get $builder)))))
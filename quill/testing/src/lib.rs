@@ -0,0 +1,279 @@
+//! An in-process test harness for Quill plugins.
+//!
+//! A [`PluginTest`] runs a headless Feather server with your
+//! plugin loaded. Tests can connect fake players, inject
+//! packets and events, advance ticks, and then assert on
+//! the world state and on the packets sent to players.
+//!
+//! Run your tests with `cargo quill test`. It builds the
+//! plugin and then runs `cargo test` with the `QUILL_TEST_PLUGIN`
+//! environment variable set to the built plugin file,
+//! which [`PluginTest::new`] loads.
+//!
+//! # Example
+//! ```no_run
+//! use quill_testing::{PluginTest, ServerPlayPacket};
+//!
+//! let mut test = PluginTest::new().unwrap();
+//! let player = test.connect_player("caelunshun");
+//! test.tick();
+//!
+//! let packets = player.take_received();
+//! assert!(packets
+//!     .iter()
+//!     .any(|packet| matches!(packet, ServerPlayPacket::ChatMessage(_))));
+//! ```
+
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Context;
+use base::{anvil::level::SuperflatGeneratorOptions, BlockId, ValidBlockPosition};
+use common::{Game, World, Worlds};
+use ecs::{Entity, SystemExecutor};
+use feather_server::{NewPlayer, Options, Server};
+use flume::Sender;
+use plugin_host::{PluginId, PluginManager};
+use quill_common::Component;
+use worldgen::SuperflatWorldGenerator;
+
+mod player;
+
+pub use player::FakePlayer;
+pub use protocol::{ClientPlayPacket, ServerPlayPacket};
+
+/// Environment variable containing the path
+/// to the plugin file under test.
+pub const PLUGIN_ENV_VAR: &str = "QUILL_TEST_PLUGIN";
+
+/// A headless server running the plugin under test.
+pub struct PluginTest {
+    game: Game,
+    plugin: PluginId,
+    new_players: Sender<NewPlayer>,
+    dir: PathBuf,
+}
+
+impl PluginTest {
+    /// Starts a test with the plugin file given
+    /// by the `QUILL_TEST_PLUGIN` environment variable.
+    ///
+    /// `cargo quill test` sets this variable.
+    pub fn new() -> anyhow::Result<Self> {
+        let path = std::env::var_os(PLUGIN_ENV_VAR).with_context(|| {
+            format!(
+                "{} is not set. Run tests with `cargo quill test`",
+                PLUGIN_ENV_VAR
+            )
+        })?;
+        Self::with_plugin_file(path)
+    }
+
+    /// Starts a test with the plugin file at `path`.
+    pub fn with_plugin_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Self::with_plugin_bytes(&bytes)
+    }
+
+    /// Starts a test with the given plugin file contents.
+    pub fn with_plugin_bytes(plugin_file: &[u8]) -> anyhow::Result<Self> {
+        Self::with_options(plugin_file, Options::default())
+    }
+
+    /// Starts a test with the given plugin file contents
+    /// and server options.
+    pub fn with_options(plugin_file: &[u8], options: Options) -> anyhow::Result<Self> {
+        let dir = create_test_dir()?;
+
+        let mut game = Game::new();
+        game.worlds = Worlds::new(
+            "world",
+            World::with_gen_and_path(
+                Arc::new(SuperflatWorldGenerator::new(
+                    SuperflatGeneratorOptions::default(),
+                    0,
+                )?),
+                dir.join("world"),
+            ),
+        );
+
+        let (server, new_players) = Server::headless(options);
+        let mut systems = SystemExecutor::new();
+        common::register(&mut game, &mut systems);
+        server.link_with_game(&mut game, &mut systems);
+        game.system_executor = Rc::new(RefCell::new(systems));
+
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager.set_plugins_dir(dir.join("plugins"));
+        let plugin = plugin_manager.load(&mut game, plugin_file)?;
+        game.insert_resource(Rc::new(RefCell::new(plugin_manager)));
+
+        Ok(Self {
+            game,
+            plugin,
+            new_players,
+            dir,
+        })
+    }
+
+    /// Gets the `Game`.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Mutably gets the `Game`.
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    /// Gets the ID of the plugin under test.
+    pub fn plugin_id(&self) -> PluginId {
+        self.plugin
+    }
+
+    /// Determines whether the plugin has been disabled
+    /// because it exceeded its resource limits or faulted
+    /// too often.
+    pub fn is_plugin_disabled(&self) -> bool {
+        let plugin_manager = self
+            .game
+            .resources
+            .get::<Rc<RefCell<PluginManager>>>()
+            .expect("missing plugin manager");
+        let plugin_manager = plugin_manager.borrow();
+        plugin_manager
+            .plugin(self.plugin)
+            .map_or(true, |plugin| plugin.is_disabled())
+    }
+
+    /// Runs a single tick.
+    pub fn tick(&mut self) {
+        let systems = Rc::clone(&self.game.system_executor);
+        systems.borrow_mut().run(&mut self.game);
+        self.game.tick_count += 1;
+    }
+
+    /// Runs `n` ticks.
+    pub fn tick_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
+    /// Connects a fake player with the given username.
+    ///
+    /// The player joins the game on the next tick.
+    pub fn connect_player(&mut self, username: &str) -> FakePlayer {
+        let (player, new_player) = FakePlayer::new(username);
+        self.new_players
+            .send(new_player)
+            .expect("server dropped the new player channel");
+        player
+    }
+
+    /// Inserts an event visible to all systems during the next tick.
+    pub fn insert_event<T: Component>(&mut self, event: T) {
+        self.game.ecs.insert_event(event);
+    }
+
+    /// Inserts an event for `entity`, visible to all
+    /// systems during the next tick.
+    pub fn insert_entity_event<T: Component>(
+        &mut self,
+        entity: Entity,
+        event: T,
+    ) -> anyhow::Result<()> {
+        self.game.ecs.insert_entity_event(entity, event)?;
+        Ok(())
+    }
+
+    /// Gets the block at `pos`, or `None` if its chunk
+    /// is not loaded.
+    pub fn block(&self, pos: ValidBlockPosition) -> Option<BlockId> {
+        self.game.block(pos)
+    }
+
+    /// Sets the block at `pos`, returning `false`
+    /// if its chunk is not loaded.
+    pub fn set_block(&mut self, pos: ValidBlockPosition, block: BlockId) -> bool {
+        self.game.set_block(pos, block)
+    }
+
+    /// Gets the directory containing this test's
+    /// world and plugin data.
+    ///
+    /// It is deleted when the test is dropped.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for PluginTest {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn create_test_dir() -> anyhow::Result<PathBuf> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "quill-test-{}-{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create test directory {}", dir.display()))?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use quill_common::components::CustomName;
+    use quill_plugin_format::{PluginFile, PluginMetadata, PluginTarget};
+
+    use super::*;
+
+    /// Builds the plugin in `fixtures/named_entities.wat`.
+    fn named_entities_plugin() -> Vec<u8> {
+        let module = wat::parse_str(include_str!("fixtures/named_entities.wat"))
+            .expect("invalid fixture plugin");
+        let metadata = PluginMetadata {
+            name: "NamedEntities".to_owned(),
+            identifier: "named-entities".to_owned(),
+            version: "0.1.0".to_owned(),
+            api_version: "0.1.0".to_owned(),
+            description: None,
+            authors: Vec::new(),
+            target: PluginTarget::Wasm,
+        };
+        PluginFile::new(module, metadata).encode(1)
+    }
+
+    fn named_entities(test: &PluginTest) -> usize {
+        test.game()
+            .ecs
+            .query::<&CustomName>()
+            .iter()
+            .filter(|(_, name)| name.as_str() == "fixture")
+            .count()
+    }
+
+    #[test]
+    fn plugin_systems_run_each_tick() {
+        let mut test = PluginTest::with_plugin_bytes(&named_entities_plugin()).unwrap();
+        assert_eq!(named_entities(&test), 0);
+
+        test.tick_n(3);
+        assert!(!test.is_plugin_disabled());
+        assert_eq!(named_entities(&test), 3);
+    }
+}
@@ -0,0 +1,85 @@
+use std::net::Ipv4Addr;
+
+use common::Game;
+use ecs::Entity;
+use feather_server::NewPlayer;
+use flume::{Receiver, Sender};
+use protocol::{ClientPlayPacket, ProtocolVersion, ServerPlayPacket};
+use uuid::Uuid;
+
+/// A simulated client connected to a [`PluginTest`](crate::PluginTest).
+///
+/// Dropping the `FakePlayer` disconnects it.
+pub struct FakePlayer {
+    username: String,
+    uuid: Uuid,
+    packets_to_server: Sender<ClientPlayPacket>,
+    packets_from_server: Receiver<ServerPlayPacket>,
+}
+
+impl FakePlayer {
+    pub(crate) fn new(username: &str) -> (Self, NewPlayer) {
+        let uuid = Uuid::new_v4();
+        let (packets_to_server, received_packets) = flume::unbounded();
+        let (packets_to_send, packets_from_server) = flume::unbounded();
+
+        let new_player = NewPlayer {
+            uuid,
+            username: username.to_owned(),
+            profile: Vec::new(),
+            protocol_version: ProtocolVersion::LATEST,
+            ip: Ipv4Addr::LOCALHOST.into(),
+            received_packets,
+            packets_to_send,
+        };
+        let player = Self {
+            username: username.to_owned(),
+            uuid,
+            packets_to_server,
+            packets_from_server,
+        };
+        (player, new_player)
+    }
+
+    /// Gets the player's username.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Gets the player's UUID.
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Gets the player's entity, or `None` if
+    /// the player has not joined the game yet.
+    pub fn entity(&self, game: &Game) -> Option<Entity> {
+        game.ecs
+            .query::<&Uuid>()
+            .iter()
+            .find(|(_, &uuid)| uuid == self.uuid)
+            .map(|(entity, _)| entity)
+    }
+
+    /// Sends a packet to the server as if the
+    /// client had sent it.
+    ///
+    /// The server handles the packet on the next tick.
+    pub fn send(&self, packet: ClientPlayPacket) {
+        // The server drops its receiver when it
+        // disconnects the player, which tests
+        // observe through `is_disconnected`.
+        let _ = self.packets_to_server.send(packet);
+    }
+
+    /// Takes all packets the server has sent
+    /// to this player since the last call.
+    pub fn take_received(&self) -> Vec<ServerPlayPacket> {
+        self.packets_from_server.try_iter().collect()
+    }
+
+    /// Determines whether the server has disconnected this player.
+    pub fn is_disconnected(&self) -> bool {
+        self.packets_from_server.is_disconnected()
+    }
+}